@@ -0,0 +1,182 @@
+/* Copyright 2022-present University of Tuebingen, Chair of Communication Networks
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/*
+ * Steffen Lindner (steffen.lindner@uni-tuebingen.de)
+ */
+
+use crate::api::server::Error;
+use crate::core::archive::{with_archive, ArchiveRecord, ArchiveSummary};
+use crate::AppState;
+use axum::debug_handler;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Json, Response};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Debug, Deserialize)]
+pub struct ArchiveParams {
+    pub tag: Option<String>,
+    pub name: Option<String>,
+}
+
+fn not_found(id: &str) -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(Error::new(format!("Archived test {id} not found."))),
+    )
+        .into_response()
+}
+
+fn internal_error(e: std::io::Error) -> Response {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(Error::new(format!("Archive not accessible: {e}"))),
+    )
+        .into_response()
+}
+
+/// Method called on GET /archive
+/// Returns the summaries of all archived tests
+#[debug_handler]
+#[utoipa::path(
+    get,
+    path = "/api/archive",
+    params(
+        ("tag" = Option<String>, Query, description = "Only retrieve tests with this tag"),
+        ("name" = Option<String>, Query, description = "Only retrieve tests with this name")
+    ),
+    responses(
+    (status = 200,
+    description = "Returns the metadata and tags of the archived tests ordered by their start.",
+    body = Vec<ArchiveSummary>))
+)]
+pub async fn archive(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ArchiveParams>,
+) -> Response {
+    let summaries = match with_archive(&state, |archive| archive.list()).await {
+        Ok(summaries) => summaries,
+        Err(e) => return internal_error(e),
+    };
+
+    let summaries: Vec<ArchiveSummary> = summaries
+        .into_iter()
+        .filter(|r| params.tag.as_ref().is_none_or(|t| r.tags.contains(t)))
+        .filter(|r| {
+            params
+                .name
+                .as_ref()
+                .is_none_or(|n| r.metadata.name.as_ref() == Some(n))
+        })
+        .collect();
+
+    (StatusCode::OK, Json(summaries)).into_response()
+}
+
+/// Method called on GET /archive/{id}
+/// Returns an archived test
+#[debug_handler]
+#[utoipa::path(
+    get,
+    path = "/api/archive/{id}",
+    params(
+        ("id" = String, Path, description = "Id of the archived test")
+    ),
+    responses(
+    (status = 200,
+    description = "Returns the traffic generation, the final statistics, and the time statistics of the archived test.",
+    body = ArchiveRecord),
+    (status = 404,
+    description = "Returned when the test does not exist."))
+)]
+pub async fn archived_test(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> Response {
+    let result = {
+        let id = id.clone();
+        with_archive(&state, move |archive| archive.get(&id)).await
+    };
+
+    match result {
+        Ok(Some(record)) => (StatusCode::OK, Json(record)).into_response(),
+        Ok(None) => not_found(&id),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Method called on POST /archive/{id}/tags
+/// Replaces the tags of an archived test
+#[debug_handler]
+#[utoipa::path(
+    post,
+    path = "/api/archive/{id}/tags",
+    params(
+        ("id" = String, Path, description = "Id of the archived test")
+    ),
+    request_body = Vec<String>,
+    responses(
+    (status = 200,
+    description = "Returns the summary of the archived test with its new tags.",
+    body = ArchiveSummary),
+    (status = 404,
+    description = "Returned when the test does not exist."))
+)]
+pub async fn tag_archived_test(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(tags): Json<Vec<String>>,
+) -> Response {
+    let result = {
+        let id = id.clone();
+        with_archive(&state, move |archive| archive.set_tags(&id, tags)).await
+    };
+
+    match result {
+        Ok(Some(summary)) => (StatusCode::OK, Json(summary)).into_response(),
+        Ok(None) => not_found(&id),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// Method called on DELETE /archive/{id}
+/// Deletes an archived test
+#[debug_handler]
+#[utoipa::path(
+    delete,
+    path = "/api/archive/{id}",
+    params(
+        ("id" = String, Path, description = "Id of the archived test")
+    ),
+    responses(
+    (status = 200,
+    description = "The test has been deleted."),
+    (status = 404,
+    description = "Returned when the test does not exist."))
+)]
+pub async fn delete_archived_test(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Response {
+    let result = {
+        let id = id.clone();
+        with_archive(&state, move |archive| archive.delete(&id)).await
+    };
+
+    match result {
+        Ok(true) => StatusCode::OK.into_response(),
+        Ok(false) => not_found(&id),
+        Err(e) => internal_error(e),
+    }
+}
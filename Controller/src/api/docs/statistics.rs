@@ -1536,6 +1536,101 @@ lazy_static! {
             "176": 0,
             "60": 0
         },
+        "app_packet_loss": {
+            "136": {
+                "1": 0
+            }
+        },
+        "app_out_of_order": {
+            "136": {
+                "1": 0
+            }
+        },
+        "app_rtts": {
+            "136": {
+                "1": {
+                    "mean": 1040.6738382099827,
+                    "min": 1020,
+                    "max": 1060,
+                    "current": 1040,
+                    "jitter": 5.407541284205046,
+                    "n": 1162
+                }
+            }
+        },
+        "rtt_quantiles": {
+            "136": {
+                "p50": 1040.2,
+                "p90": 1050.6,
+                "p99": 1060.0,
+                "p999": 1060.0,
+                "n": 1162
+            }
+        },
+        "app_rtt_quantiles": {
+            "136": {
+                "1": {
+                    "p50": 1040.2,
+                    "p90": 1050.6,
+                    "p99": 1060.0,
+                    "p999": 1060.0,
+                    "n": 1162
+                }
+            }
+        },
+        "iat_quantiles": {},
+        "disruption": {
+            "136": {
+                "disruption_time": 0.0,
+                "outages": []
+            }
+        },
+        "app_disruption": {
+            "136": {
+                "1": {
+                    "disruption_time": 0.0,
+                    "outages": []
+                }
+            }
+        },
+        "loss_analysis": {
+            "136": {
+                "lost_packets": 0,
+                "loss_episodes": 0,
+                "min_burst_length": 0,
+                "mean_burst_length": 0.0,
+                "max_burst_length": 0,
+                "loss_probability": 0.0,
+                "recovery_probability": 1.0,
+                "duplicates": 0,
+                "reordered": 0,
+                "samples": 1162
+            }
+        },
+        "app_loss_analysis": {
+            "136": {
+                "1": {
+                    "lost_packets": 0,
+                    "loss_episodes": 0,
+                    "min_burst_length": 0,
+                    "mean_burst_length": 0.0,
+                    "max_burst_length": 0,
+                    "loss_probability": 0.0,
+                    "recovery_probability": 1.0,
+                    "duplicates": 0,
+                    "reordered": 0,
+                    "samples": 1162
+                }
+            }
+        },
+        "mean_rates": {
+            "136": {
+                "tx_l1": 99999843127.1,
+                "tx_l2": 98461384003.7,
+                "rx_l1": 99999843127.1,
+                "rx_l2": 98461384003.7
+            }
+        },
         "elapsed_time": 0
     })
     .to_string();
@@ -1880,8 +1975,93 @@ lazy_static! {
                 "4": 2510,
                 "5": 2510
               }
+            },
+            "app_tx_l2": {
+              "136": {
+                "1": {
+                  "0": 96131851671.92445,
+                  "1": 381696000000.1,
+                  "2": 381695998201.6,
+                  "3": 381696000599.8,
+                  "4": 381695999945.5,
+                  "5": 381696000981.5
+                }
+              }
+            },
+            "app_rx_l2": {
+              "144": {
+                "1": {
+                  "0": 96131850000.6221,
+                  "1": 381695998012.4,
+                  "2": 381695997500.3,
+                  "3": 381696000321.0,
+                  "4": 381695999820.7,
+                  "5": 381696000744.2
+                }
+              }
+            },
+            "app_packet_loss": {
+              "144": {
+                "1": {
+                  "0": 0,
+                  "1": 0,
+                  "2": 0,
+                  "3": 0,
+                  "4": 0,
+                  "5": 0
+                }
+              }
+            },
+            "app_out_of_order": {
+              "144": {
+                "1": {
+                  "0": 0,
+                  "1": 0,
+                  "2": 0,
+                  "3": 0,
+                  "4": 0,
+                  "5": 0
+                }
+              }
+            },
+            "app_rtt": {
+              "144": {
+                "1": {
+                  "0": 2507,
+                  "1": 2501,
+                  "2": 2519,
+                  "3": 2506,
+                  "4": 2510,
+                  "5": 2510
+                }
+              }
+            },
+            "disruption_time": {
+              "144": {
+                "0": 0.0,
+                "1": 0.0,
+                "2": 0.0,
+                "3": 0.0,
+                "4": 0.0,
+                "5": 0.0
+              }
+            },
+            "app_disruption_time": {
+              "144": {
+                "1": {
+                  "0": 0.0,
+                  "1": 0.0,
+                  "2": 0.0,
+                  "3": 0.0,
+                  "4": 0.0,
+                  "5": 0.0
+                }
+              }
+            },
+            "measurement_window": {
+              "start": "1",
+              "end": "5"
             }
-
     })
     .to_string();
 }
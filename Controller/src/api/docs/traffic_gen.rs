@@ -1,4 +1,5 @@
-use crate::core::statistics::HistogramConfig;
+use crate::core::statistics::{BinLayout, HistogramConfig};
+use crate::core::traffic_gen_core::feasibility::analyze_feasibility;
 use crate::core::traffic_gen_core::types::*;
 use lazy_static::lazy_static;
 use std::collections::HashMap;
@@ -80,11 +81,18 @@ lazy_static! {
                     min: 1000,
                     max: 2000,
                     num_bins: 100,
+                    layout: BinLayout::Linear,
+                    bin_edges: None,
+                    auto: None,
                     percentiles: Some(vec![0.25, 0.5, 0.75, 0.9]),
                 }
             )])
         )])),
         iat_histogram_config: None,
+        sla: None,
+        time_resolution: None,
+        warmup: None,
+        cooldown: None,
     };
     pub static ref EXAMPLE_GET_2: TrafficGenData = TrafficGenData {
         mode: GenerationMode::Cbr,
@@ -161,11 +169,18 @@ lazy_static! {
                     min: 1000,
                     max: 2000,
                     num_bins: 100,
+                    layout: BinLayout::Linear,
+                    bin_edges: None,
+                    auto: None,
                     percentiles: Some(vec![0.25, 0.5, 0.75, 0.9]),
                 }
             )])
         )])),
         iat_histogram_config: None,
+        sla: None,
+        time_resolution: None,
+        warmup: None,
+        cooldown: None,
     };
     pub static ref EXAMPLE_POST_1_RESPONSE: Vec<Stream> = vec![Stream {
         stream_id: 1,
@@ -188,6 +203,8 @@ lazy_static! {
         unit: Some(GenerationUnit::Gbps),
         pattern: None,
     }];
+    pub static ref EXAMPLE_POST_1_FEASIBILITY: FeasibilityReport =
+        analyze_feasibility(&EXAMPLE_POST_1_REQUEST, &HashMap::new(), false, 2);
     pub static ref EXAMPLE_POST_1_REQUEST: TrafficGenData = TrafficGenData {
         name: None,
         mode: GenerationMode::Cbr,
@@ -263,11 +280,18 @@ lazy_static! {
                     min: 1000,
                     max: 2000,
                     num_bins: 100,
+                    layout: BinLayout::Linear,
+                    bin_edges: None,
+                    auto: None,
                     percentiles: Some(vec![0.25, 0.5, 0.75, 0.9]),
                 }
             )])
         )])),
         iat_histogram_config: None,
+        sla: None,
+        time_resolution: None,
+        warmup: None,
+        cooldown: None,
     };
     pub static ref EXAMPLE_POST_2_REQUEST: TrafficGenData = TrafficGenData {
         name: None,
@@ -343,11 +367,18 @@ lazy_static! {
                     min: 1000,
                     max: 2000,
                     num_bins: 100,
+                    layout: BinLayout::Linear,
+                    bin_edges: None,
+                    auto: None,
                     percentiles: Some(vec![0.25, 0.5, 0.75, 0.9]),
                 }
             )])
         )])),
         iat_histogram_config: None,
+        sla: None,
+        time_resolution: None,
+        warmup: None,
+        cooldown: None,
     };
     pub static ref EXAMPLE_POST_2_RESPONSE: Vec<Stream> = vec![Stream {
         stream_id: 1,
@@ -431,6 +462,10 @@ lazy_static! {
         duration: None,
         rtt_histogram_config: None,
         iat_histogram_config: None,
+        sla: None,
+        time_resolution: None,
+        warmup: None,
+        cooldown: None,
     };
     pub static ref EXAMPLE_POST_3_RESPONSE: Vec<Stream> = vec![Stream {
         stream_id: 1,
@@ -531,11 +566,18 @@ lazy_static! {
                         min: 1000,
                         max: 2000,
                         num_bins: 100,
+                        layout: BinLayout::Linear,
+                        bin_edges: None,
+                        auto: None,
                         percentiles: Some(vec![0.25, 0.5, 0.75, 0.9]),
                     }
                 )])
             )])),
             iat_histogram_config: None,
+            sla: None,
+            time_resolution: None,
+            warmup: None,
+            cooldown: None,
             name: Some("Test 1".to_string())
         },
         TrafficGenData {
@@ -615,11 +657,18 @@ lazy_static! {
                         min: 1000,
                         max: 2000,
                         num_bins: 100,
+                        layout: BinLayout::Linear,
+                        bin_edges: None,
+                        auto: None,
                         percentiles: Some(vec![0.25, 0.5, 0.75, 0.9]),
                     }
                 )])
             )])),
             iat_histogram_config: None,
+            sla: None,
+            time_resolution: None,
+            warmup: None,
+            cooldown: None,
             name: Some("Another test".to_string())
         }
     ];
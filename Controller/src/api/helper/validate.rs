@@ -22,11 +22,12 @@ use log::warn;
 use std::collections::HashMap;
 
 use crate::api::server::Error;
-use crate::core::statistics::HistogramConfig;
+use crate::core::statistics::{BinLayout, HistogramConfig};
 use crate::core::traffic_gen_core::const_definitions::{
-    IAT_HISTOGRAM_TABLE_SIZE, MAX_ADDRESS_RANDOMIZATION_IPV6_TOFINO1,
+    IAT_HISTOGRAM_TABLE, IAT_HISTOGRAM_TABLE_SIZE, MAX_ADDRESS_RANDOMIZATION_IPV6_TOFINO1,
     MAX_ADDRESS_RANDOMIZATION_IPV6_TOFINO2, MAX_BUFFER_SIZE, MAX_NUM_MPLS_LABEL, MAX_NUM_SRV6_SIDS,
-    RTT_HISTOGRAM_TABLE, RTT_HISTOGRAM_TABLE_SIZE, TG_MAX_RATE, TG_MAX_RATE_TF2,
+    MONITORING_PACKET_INTERVAL, RTT_HISTOGRAM_TABLE, RTT_HISTOGRAM_TABLE_SIZE, TG_MAX_RATE,
+    TG_MAX_RATE_TF2,
 };
 use crate::core::traffic_gen_core::helper::{
     calculate_overhead, generate_front_panel_to_dev_port_mappings, mpps_to_gbps, range_to_ternary,
@@ -127,6 +128,37 @@ pub fn validate_request(
         }
     }
 
+    // Validate that the acceptance criteria refer to the ports and streams of the test
+    if let Some(sla) = &payload.sla {
+        sla.validate(payload).map_err(Error::new)?;
+    }
+
+    // The time statistics are updated with each monitoring packet
+    let min_time_resolution = MONITORING_PACKET_INTERVAL / 1_000_000;
+    if payload
+        .time_resolution
+        .is_some_and(|r| r < min_time_resolution)
+    {
+        return Err(Error::new(format!(
+            "Time resolution must be at least {min_time_resolution} ms."
+        )));
+    }
+
+    // The cool-down phase is at the end of the test
+    if payload.cooldown.is_some_and(|c| c > 0) && payload.duration.is_none_or(|d| d == 0) {
+        return Err(Error::new("A cool-down phase requires a test duration."));
+    }
+
+    if let Some(duration) = payload.duration.filter(|d| *d > 0) {
+        let excluded = payload.warmup.unwrap_or(0) as u64 + payload.cooldown.unwrap_or(0) as u64;
+
+        if excluded >= duration as u64 {
+            return Err(Error::new(
+                "Warm-up and cool-down phase must be shorter than the test duration.",
+            ));
+        }
+    }
+
     // Poisson traffic is only allowed to have a single stream
     if payload.mode == GenerationMode::Poisson && active_streams.len() != 1 {
         return Err(Error::new(
@@ -278,11 +310,9 @@ pub fn validate_request(
                         )));
                     }
                     // Validate IPv6 Address Randomization Mask size
-                    if stream.ip_version == Some(6) && setting.ipv6.is_some() {
-                        let ipv6_src_mask_int: u128 =
-                            setting.ipv6.as_ref().unwrap().ipv6_src_mask.into();
-                        let ipv6_dst_mask_int: u128 =
-                            setting.ipv6.as_ref().unwrap().ipv6_dst_mask.into();
+                    if let (Some(6), Some(ipv6)) = (stream.ip_version, setting.ipv6.as_ref()) {
+                        let ipv6_src_mask_int: u128 = ipv6.ipv6_src_mask.into();
+                        let ipv6_dst_mask_int: u128 = ipv6.ipv6_dst_mask.into();
 
                         // For tofino2 at most ::ff:ffff:ffff, for tofino1 ::ffff:ffff
                         let randomization_max = if is_tofino2 {
@@ -522,9 +552,9 @@ pub fn validate_histogram(
         t_name = format!(", Test: {name:?},");
     }
 
-    let max_table_size = match hist_type {
-        HistogramType::Rtt => RTT_HISTOGRAM_TABLE_SIZE,
-        HistogramType::Iat => IAT_HISTOGRAM_TABLE_SIZE,
+    let (table_name, max_table_size) = match hist_type {
+        HistogramType::Rtt => (RTT_HISTOGRAM_TABLE, RTT_HISTOGRAM_TABLE_SIZE),
+        HistogramType::Iat => (IAT_HISTOGRAM_TABLE, IAT_HISTOGRAM_TABLE_SIZE),
     };
 
     for (port, channel_map) in request.iter() {
@@ -534,17 +564,52 @@ pub fn validate_histogram(
                 Ok(p) => p,
                 Err(_) => return Err(Error::new(format!("Invalid port number: {port}"))),
             };
-            if config.min >= config.max {
-                return Err(Error::new(format!("Histogram config error {t_name} port {port}: Minimum value must be less than maximum value of range.")));
-            }
-            if config.num_bins > 500 {
-                return Err(Error::new(format!("Histogram config error {t_name} port {port}: Too many bins. 500 bins per port are supported at maximum.")));
-            }
-            if config.num_bins > (config.max - config.min) {
-                return Err(Error::new(format!("Histogram config error {t_name} port {port}: Too many bins for too less of range. Increase range, or decrease number of bins.")));
+            if let Some(calibration) = &config.auto {
+                if config.bin_edges.is_some() {
+                    return Err(Error::new(format!("Histogram config error {t_name} port {port}: Explicit bin edges can not be calibrated automatically.")));
+                }
+                if calibration.warmup == 0 {
+                    return Err(Error::new(format!("Histogram config error {t_name} port {port}: Warm-up duration must be positive for automatic calibration.")));
+                }
+                if !(0.0..=1.0).contains(&calibration.lower_percentile)
+                    || !(0.0..=1.0).contains(&calibration.upper_percentile)
+                    || calibration.lower_percentile >= calibration.upper_percentile
+                {
+                    return Err(Error::new(format!("Histogram config error {t_name} port {port}: Calibration percentiles must satisfy 0 <= lower_percentile < upper_percentile <= 1.")));
+                }
             }
-            if config.num_bins == 0 {
-                return Err(Error::new(format!("Histogram config error {t_name} port {port}: num_bins must be positive for histogram config.")));
+            if let Some(edges) = &config.bin_edges {
+                if edges.len() < 2 {
+                    return Err(Error::new(format!("Histogram config error {t_name} port {port}: At least two bin edges are required.")));
+                }
+                if edges.len() > 501 {
+                    return Err(Error::new(format!("Histogram config error {t_name} port {port}: Too many bins. 500 bins per port are supported at maximum.")));
+                }
+                if edges.windows(2).any(|e| e[0] >= e[1]) {
+                    return Err(Error::new(format!("Histogram config error {t_name} port {port}: Bin edges must be strictly increasing.")));
+                }
+            } else {
+                if config.min >= config.max {
+                    return Err(Error::new(format!("Histogram config error {t_name} port {port}: Minimum value must be less than maximum value of range.")));
+                }
+                if config.num_bins > 500 {
+                    return Err(Error::new(format!("Histogram config error {t_name} port {port}: Too many bins. 500 bins per port are supported at maximum.")));
+                }
+                if config.num_bins > (config.max - config.min) {
+                    return Err(Error::new(format!("Histogram config error {t_name} port {port}: Too many bins for too less of range. Increase range, or decrease number of bins.")));
+                }
+                if config.num_bins == 0 {
+                    return Err(Error::new(format!("Histogram config error {t_name} port {port}: num_bins must be positive for histogram config.")));
+                }
+                if config.layout == BinLayout::Logarithmic {
+                    if config.min == 0 {
+                        return Err(Error::new(format!("Histogram config error {t_name} port {port}: Minimum value must be at least 1 for logarithmic bins.")));
+                    }
+                    // the narrowest bins must have a width of at least 1
+                    if config.bin_edges().windows(2).any(|e| e[0] >= e[1]) {
+                        return Err(Error::new(format!("Histogram config error {t_name} port {port}: Too many bins for too less of range. Increase range, or decrease number of bins.")));
+                    }
+                }
             }
 
             if let Some(percentiles) = &config.percentiles {
@@ -562,17 +627,8 @@ pub fn validate_histogram(
                 }
             }
 
-            // Calculate bin width based on config params
-            let bin_width = config.get_bin_width();
-
-            for bin_index in 0..config.num_bins {
+            for (start, end) in config.bin_ranges() {
                 // For each bin, write table entries
-                let start = config.min + bin_index * bin_width;
-                let mut end = start + bin_width - 1;
-                if end > config.max {
-                    end = config.max;
-                }
-
                 let new_requests = range_to_ternary(start, end).len() as u32;
 
                 if let HistogramType::Iat = hist_type {
@@ -584,7 +640,9 @@ pub fn validate_histogram(
                 }
 
                 if num_requests > max_table_size {
-                    return Err(Error::new(format!("Number of table entries exceeds available space in table {RTT_HISTOGRAM_TABLE}")));
+                    return Err(Error::new(format!(
+                        "Number of table entries exceeds available space in table {table_name}"
+                    )));
                 }
             }
         }
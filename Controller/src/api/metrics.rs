@@ -0,0 +1,519 @@
+/* Copyright 2022-present University of Tuebingen, Chair of Communication Networks
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/*
+ * Steffen Lindner (steffen.lindner@uni-tuebingen.de)
+ */
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+
+use crate::api::statistics::{get_statistics, StatisticsApi};
+use crate::core::statistics::{Histogram, HistogramData};
+use crate::AppState;
+
+/// Content type of the Prometheus text exposition format
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Method called on GET /metrics
+/// Returns the statistics in the Prometheus text exposition format
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    responses(
+    (status = 200,
+    description = "Returns the statistics of the current and all previous tests in the Prometheus text exposition format. \
+    Metrics are labeled by front panel port, channel, app id, and test name. \
+    The RTT and IAT histograms are exposed as Prometheus histograms with the configured bins as buckets.",
+    content_type = "text/plain",
+    body = String))
+)]
+pub async fn metrics(State(state): State<Arc<AppState>>) -> Response {
+    let stats = get_statistics(&state).await;
+
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, CONTENT_TYPE)],
+        render_metrics(&stats),
+    )
+        .into_response()
+}
+
+/// A metric family, i.e., all samples of a metric with the same name.
+struct Family {
+    name: &'static str,
+    help: &'static str,
+    kind: &'static str,
+    samples: String,
+}
+
+impl Family {
+    fn new(name: &'static str, kind: &'static str, help: &'static str) -> Family {
+        Family {
+            name,
+            help,
+            kind,
+            samples: String::new(),
+        }
+    }
+
+    /// Adds the sample `value` of the metric with name suffix `suffix`, e.g., `_bucket` for histograms.
+    fn push(&mut self, suffix: &str, labels: &[(&str, String)], value: f64) {
+        let labels: Vec<String> = labels
+            .iter()
+            .map(|(k, v)| format!("{k}=\"{}\"", escape(v)))
+            .collect();
+
+        let _ = writeln!(
+            self.samples,
+            "{}{suffix}{{{}}} {}",
+            self.name,
+            labels.join(","),
+            format_value(value)
+        );
+    }
+
+    /// Adds a histogram sample with the bins of `histogram` as buckets.
+    /// The sum is estimated from the mean of the histogram.
+    /// Values outside the histogram range are not part of the histogram,
+    /// as it is unknown whether they were below or above the range.
+    fn push_histogram(
+        &mut self,
+        labels: &[(&str, String)],
+        histogram: &Histogram,
+        data: &HistogramData,
+    ) {
+        let edges = histogram.config.bin_edges();
+        let mut cumulative = 0u128;
+
+        for (bin, high) in edges.iter().skip(1).enumerate() {
+            cumulative += data
+                .data_bins
+                .get(&(bin as u32))
+                .map(|b| b.count)
+                .unwrap_or(0);
+
+            let mut bucket_labels = labels.to_vec();
+            bucket_labels.push(("le", high.to_string()));
+
+            self.push("_bucket", &bucket_labels, cumulative as f64);
+        }
+
+        let count = data.total_pkt_count as f64;
+        let mut bucket_labels = labels.to_vec();
+        bucket_labels.push(("le", "+Inf".to_owned()));
+
+        self.push("_bucket", &bucket_labels, count);
+        self.push("_sum", labels, data.mean * count);
+        self.push("_count", labels, count);
+    }
+}
+
+/// Escapes a label value according to the Prometheus text exposition format.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_infinite() {
+        if value > 0f64 { "+Inf" } else { "-Inf" }.to_owned()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Returns the entries of a map sorted by key.
+fn sorted<K: Ord + Copy, V>(map: &HashMap<K, V>) -> Vec<(K, &V)> {
+    let mut entries: Vec<(K, &V)> = map.iter().map(|(k, v)| (*k, v)).collect();
+    entries.sort_by_key(|(k, _)| *k);
+    entries
+}
+
+/// Calls `f` for each front panel port and channel of `map` with the corresponding labels.
+fn for_each_port<V>(
+    map: &HashMap<u32, HashMap<u8, V>>,
+    labels: &[(&str, String)],
+    mut f: impl FnMut(Vec<(&str, String)>, &V),
+) {
+    for (port, channels) in sorted(map) {
+        for (channel, value) in sorted(channels) {
+            let mut port_labels = labels.to_vec();
+            port_labels.push(("port", port.to_string()));
+            port_labels.push(("channel", channel.to_string()));
+
+            f(port_labels, value);
+        }
+    }
+}
+
+/// Renders the statistics of all tests in the Prometheus text exposition format.
+///
+/// Each test is labeled with its name. Unnamed tests are named by their position in `stats`
+/// if statistics of multiple tests are available.
+pub(crate) fn render_metrics(stats: &[StatisticsApi]) -> String {
+    let mut elapsed_time = Family::new(
+        "p4tg_elapsed_time_seconds",
+        "gauge",
+        "Elapsed time since the traffic generation has started.",
+    );
+    let mut rates = [
+        Family::new("p4tg_tx_rate_l1_bits_per_second", "gauge", "L1 send rate."),
+        Family::new("p4tg_tx_rate_l2_bits_per_second", "gauge", "L2 send rate."),
+        Family::new(
+            "p4tg_rx_rate_l1_bits_per_second",
+            "gauge",
+            "L1 receive rate.",
+        ),
+        Family::new(
+            "p4tg_rx_rate_l2_bits_per_second",
+            "gauge",
+            "L2 receive rate.",
+        ),
+    ];
+    let mut app_rates = [
+        Family::new(
+            "p4tg_app_tx_rate_l2_bits_per_second",
+            "gauge",
+            "L2 send rate per stream.",
+        ),
+        Family::new(
+            "p4tg_app_rx_rate_l2_bits_per_second",
+            "gauge",
+            "L2 receive rate per stream.",
+        ),
+    ];
+    let mut packet_loss = Family::new(
+        "p4tg_packet_loss_total",
+        "counter",
+        "Number of lost packets.",
+    );
+    let mut out_of_order = Family::new(
+        "p4tg_out_of_order_total",
+        "counter",
+        "Number of out of order packets.",
+    );
+    let mut rtt = [
+        Family::new("p4tg_rtt_mean_nanoseconds", "gauge", "Mean RTT."),
+        Family::new("p4tg_rtt_min_nanoseconds", "gauge", "Minimal RTT."),
+        Family::new("p4tg_rtt_max_nanoseconds", "gauge", "Maximal RTT."),
+        Family::new(
+            "p4tg_rtt_jitter_nanoseconds",
+            "gauge",
+            "Standard deviation of the RTT.",
+        ),
+    ];
+    let mut iat_mean = Family::new(
+        "p4tg_iat_mean_nanoseconds",
+        "gauge",
+        "Mean inter-arrival time.",
+    );
+    let mut iat_mae = Family::new(
+        "p4tg_iat_mae_nanoseconds",
+        "gauge",
+        "Mean absolute error of the inter-arrival time.",
+    );
+    let mut rtt_histogram = Family::new(
+        "p4tg_rtt_histogram_nanoseconds",
+        "histogram",
+        "RTT histogram.",
+    );
+    let mut iat_histogram = Family::new(
+        "p4tg_iat_histogram_nanoseconds",
+        "histogram",
+        "Inter-arrival time histogram.",
+    );
+    let mut rtt_histogram_missed = Family::new(
+        "p4tg_rtt_histogram_missed_total",
+        "counter",
+        "Number of RTTs outside the range of the RTT histogram.",
+    );
+    let mut iat_histogram_missed = Family::new(
+        "p4tg_iat_histogram_missed_total",
+        "counter",
+        "Number of inter-arrival times outside the range of the inter-arrival time histogram.",
+    );
+
+    for (index, s) in stats.iter().enumerate() {
+        let name = match &s.name {
+            Some(name) => name.clone(),
+            None if stats.len() > 1 => format!("Test {}", index + 1),
+            None => String::new(),
+        };
+        let labels = [("name", name)];
+
+        elapsed_time.push("", &labels, s.elapsed_time as f64);
+
+        for (family, map) in
+            rates
+                .iter_mut()
+                .zip([&s.tx_rate_l1, &s.tx_rate_l2, &s.rx_rate_l1, &s.rx_rate_l2])
+        {
+            for_each_port(map, &labels, |l, v| family.push("", &l, *v));
+        }
+
+        for (family, map) in app_rates.iter_mut().zip([&s.app_tx_l2, &s.app_rx_l2]) {
+            for_each_port(map, &labels, |l, apps| {
+                for (app_id, v) in sorted(apps) {
+                    let mut app_labels = l.clone();
+                    app_labels.push(("app_id", app_id.to_string()));
+
+                    family.push("", &app_labels, *v);
+                }
+            });
+        }
+
+        for_each_port(&s.packet_loss, &labels, |l, v| {
+            packet_loss.push("", &l, *v as f64)
+        });
+        for_each_port(&s.out_of_order, &labels, |l, v| {
+            out_of_order.push("", &l, *v as f64)
+        });
+
+        for_each_port(&s.rtts, &labels, |l, v| {
+            let [mean, min, max, jitter] = &mut rtt;
+
+            mean.push("", &l, v.mean);
+            min.push("", &l, v.min as f64);
+            max.push("", &l, v.max as f64);
+            jitter.push("", &l, v.jitter);
+        });
+
+        for_each_port(&s.iats, &labels, |l, v| {
+            for (direction, values) in [("tx", &v.tx), ("rx", &v.rx)] {
+                let mut iat_labels = l.clone();
+                iat_labels.push(("direction", direction.to_owned()));
+
+                iat_mean.push("", &iat_labels, values.mean as f64);
+                iat_mae.push("", &iat_labels, values.mae as f64);
+            }
+        });
+
+        for_each_port(&s.rtt_histogram, &labels, |l, h| {
+            rtt_histogram.push_histogram(&l, h, &h.data.rx);
+            rtt_histogram_missed.push("", &l, h.data.rx.missed_bin_count as f64);
+        });
+
+        for_each_port(&s.iat_histogram, &labels, |l, h| {
+            for (direction, data) in [("tx", &h.data.tx), ("rx", &h.data.rx)] {
+                let mut iat_labels = l.clone();
+                iat_labels.push(("direction", direction.to_owned()));
+
+                iat_histogram.push_histogram(&iat_labels, h, data);
+                iat_histogram_missed.push("", &iat_labels, data.missed_bin_count as f64);
+            }
+        });
+    }
+
+    let families = [elapsed_time]
+        .into_iter()
+        .chain(rates)
+        .chain(app_rates)
+        .chain([packet_loss, out_of_order])
+        .chain(rtt)
+        .chain([
+            iat_mean,
+            iat_mae,
+            rtt_histogram,
+            iat_histogram,
+            rtt_histogram_missed,
+            iat_histogram_missed,
+        ]);
+
+    let mut output = String::new();
+
+    for family in families.filter(|f| !f.samples.is_empty()) {
+        let _ = writeln!(output, "# HELP {} {}", family.name, family.help);
+        let _ = writeln!(output, "# TYPE {} {}", family.name, family.kind);
+        output.push_str(&family.samples);
+    }
+
+    output
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::core::statistics::{BinLayout, HistogramBinEntry, HistogramConfig, RTTStatistics};
+
+    pub(crate) fn statistics(name: Option<&str>) -> StatisticsApi {
+        StatisticsApi {
+            sample_mode: false,
+            frame_size: HashMap::new(),
+            tx_rate_l1: HashMap::from([(1, HashMap::from([(0, 100e9)]))]),
+            tx_rate_l2: HashMap::new(),
+            rx_rate_l1: HashMap::new(),
+            rx_rate_l2: HashMap::new(),
+            app_tx_l2: HashMap::from([(1, HashMap::from([(0, HashMap::from([(2, 1e9)]))]))]),
+            app_rx_l2: HashMap::new(),
+            frame_type_data: HashMap::new(),
+            iats: HashMap::new(),
+            rtts: HashMap::from([(
+                2,
+                HashMap::from([(
+                    0,
+                    RTTStatistics {
+                        mean: 1500f64,
+                        min: 1000,
+                        max: 2000,
+                        current: 1500,
+                        jitter: 10f64,
+                        n: 5,
+                    },
+                )]),
+            )]),
+            packet_loss: HashMap::from([(2, HashMap::from([(0, 3)]))]),
+            out_of_order: HashMap::new(),
+            app_packet_loss: HashMap::from([(2, HashMap::from([(0, HashMap::from([(2, 3)]))]))]),
+            app_out_of_order: HashMap::new(),
+            app_rtts: HashMap::new(),
+            rtt_quantiles: HashMap::new(),
+            app_rtt_quantiles: HashMap::new(),
+            iat_quantiles: HashMap::new(),
+            disruption: HashMap::new(),
+            app_disruption: HashMap::new(),
+            loss_analysis: HashMap::new(),
+            app_loss_analysis: HashMap::new(),
+            mean_rates: HashMap::new(),
+            elapsed_time: 10,
+            rtt_histogram: HashMap::from([(
+                2,
+                HashMap::from([(
+                    0,
+                    Histogram {
+                        config: HistogramConfig {
+                            num_bins: 2,
+                            min: 1000,
+                            max: 2000,
+                            layout: BinLayout::Linear,
+                            bin_edges: None,
+                            auto: None,
+                            percentiles: None,
+                        },
+                        data: crate::core::statistics::HistogramPacketPath {
+                            tx: Default::default(),
+                            rx: HistogramData {
+                                data_bins: HashMap::from([
+                                    (
+                                        0,
+                                        HistogramBinEntry {
+                                            count: 3,
+                                            probability: 0.6,
+                                        },
+                                    ),
+                                    (
+                                        1,
+                                        HistogramBinEntry {
+                                            count: 2,
+                                            probability: 0.4,
+                                        },
+                                    ),
+                                ]),
+                                mean: 1400f64,
+                                total_pkt_count: 5,
+                                missed_bin_count: 1,
+                                ..Default::default()
+                            },
+                        },
+                        calibration: None,
+                    },
+                )]),
+            )]),
+            app_rtt_histogram: HashMap::new(),
+            iat_histogram: HashMap::new(),
+            name: name.map(|n| n.to_owned()),
+            sla: None,
+        }
+    }
+
+    #[test]
+    fn statistics_are_rendered_in_text_format() {
+        let output = render_metrics(&[statistics(Some("Test \"A\""))]);
+
+        assert!(output.contains("# TYPE p4tg_tx_rate_l1_bits_per_second gauge\n"));
+        assert!(output.contains(
+            "p4tg_tx_rate_l1_bits_per_second{name=\"Test \\\"A\\\"\",port=\"1\",channel=\"0\"} 100000000000\n"
+        ));
+        assert!(output.contains(
+            "p4tg_app_tx_rate_l2_bits_per_second{name=\"Test \\\"A\\\"\",port=\"1\",channel=\"0\",app_id=\"2\"} 1000000000\n"
+        ));
+        assert!(output.contains("# TYPE p4tg_packet_loss_total counter\n"));
+        assert!(output.contains(
+            "p4tg_rtt_max_nanoseconds{name=\"Test \\\"A\\\"\",port=\"2\",channel=\"0\"} 2000\n"
+        ));
+
+        // families without samples are omitted
+        assert!(!output.contains("p4tg_rx_rate_l1_bits_per_second"));
+    }
+
+    #[test]
+    fn histograms_have_cumulative_buckets() {
+        let output = render_metrics(&[statistics(None), statistics(Some("B"))]);
+
+        assert!(output.contains("# TYPE p4tg_rtt_histogram_nanoseconds histogram\n"));
+
+        for line in [
+            "p4tg_rtt_histogram_nanoseconds_bucket{name=\"Test 1\",port=\"2\",channel=\"0\",le=\"1500\"} 3\n",
+            "p4tg_rtt_histogram_nanoseconds_bucket{name=\"Test 1\",port=\"2\",channel=\"0\",le=\"2000\"} 5\n",
+            "p4tg_rtt_histogram_nanoseconds_bucket{name=\"Test 1\",port=\"2\",channel=\"0\",le=\"+Inf\"} 5\n",
+            "p4tg_rtt_histogram_nanoseconds_sum{name=\"Test 1\",port=\"2\",channel=\"0\"} 7000\n",
+            "p4tg_rtt_histogram_nanoseconds_count{name=\"B\",port=\"2\",channel=\"0\"} 5\n",
+        ] {
+            assert!(output.contains(line), "{line}");
+        }
+
+        // HELP and TYPE are only emitted once per family
+        assert_eq!(
+            output
+                .matches("# TYPE p4tg_rtt_histogram_nanoseconds")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn samples_outside_the_histogram_range_are_reported_separately() {
+        let mut stats = statistics(None);
+        let data = &mut stats
+            .rtt_histogram
+            .get_mut(&2)
+            .and_then(|h| h.get_mut(&0))
+            .unwrap()
+            .data
+            .rx;
+        data.missed_bin_count = 4;
+
+        let output = render_metrics(&[stats]);
+
+        // +Inf, count and sum only cover the samples within the range
+        for line in [
+            "p4tg_rtt_histogram_nanoseconds_bucket{name=\"\",port=\"2\",channel=\"0\",le=\"2000\"} 5\n",
+            "p4tg_rtt_histogram_nanoseconds_bucket{name=\"\",port=\"2\",channel=\"0\",le=\"+Inf\"} 5\n",
+            "p4tg_rtt_histogram_nanoseconds_sum{name=\"\",port=\"2\",channel=\"0\"} 7000\n",
+            "p4tg_rtt_histogram_nanoseconds_count{name=\"\",port=\"2\",channel=\"0\"} 5\n",
+            "# TYPE p4tg_rtt_histogram_missed_total counter\n",
+            "p4tg_rtt_histogram_missed_total{name=\"\",port=\"2\",channel=\"0\"} 4\n",
+        ] {
+            assert!(output.contains(line), "{line}");
+        }
+    }
+}
@@ -16,28 +16,42 @@
 /*
  * Steffen Lindner (steffen.lindner@uni-tuebingen.de)
  */
+mod archive;
 mod histogram;
-mod online;
+pub(crate) mod online;
 mod ports;
 pub mod server;
 pub mod statistics;
 mod tables;
 pub mod traffic_gen;
 
-mod helper;
+pub(crate) mod helper;
+mod report;
 mod reset;
 mod restart;
+mod rfc2544;
+mod y1564;
 
 mod config;
-mod docs;
+pub(crate) mod docs;
+pub(crate) mod metrics;
 
+pub use archive::{archive, archived_test, delete_archived_test, tag_archived_test};
 pub use config::config;
+pub use metrics::metrics;
 pub use online::online;
 pub use ports::add_port;
 pub use ports::ports;
+pub use report::report;
 pub use reset::reset;
 pub use restart::restart;
+pub use rfc2544::{rfc2544, start_rfc2544, stop_rfc2544};
 pub use statistics::statistics;
 pub use traffic_gen::configure_traffic_gen;
+pub use traffic_gen::plan_traffic_gen;
 pub use traffic_gen::stop_traffic_gen;
 pub use traffic_gen::traffic_gen;
+pub use traffic_gen::traffic_gen_feasibility;
+pub use traffic_gen::traffic_gen_import;
+pub use traffic_gen::traffic_gen_pcap;
+pub use y1564::{start_y1564, stop_y1564, y1564};
@@ -19,6 +19,7 @@
 
 use crate::api::docs;
 use crate::api::server::Error;
+use crate::core::backend::SwitchBackend;
 use crate::core::traffic_gen_core::helper::{
     generate_front_panel_to_dev_port_mappings, resolve_front_panel_mode, sanitize_fec,
 };
@@ -111,10 +112,7 @@ pub struct ArpReply {
     ))
 )]
 pub async fn ports(State(state): State<Arc<AppState>>) -> Response {
-    let pm = &state.pm;
-    let switch = &state.switch;
-
-    match pm.get_ports(switch).await {
+    match state.switch.get_ports().await {
         Ok(ports) => Json(ports).into_response(),
         Err(err) => (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -140,8 +138,6 @@ pub async fn add_port(
     State(state): State<Arc<AppState>>,
     payload: Json<PortConfiguration>,
 ) -> Response {
-    let pm = &state.pm;
-
     let front_panel_dev_port_mappings =
         generate_front_panel_to_dev_port_mappings(&state.port_mapping, state.tofino2);
 
@@ -222,11 +218,10 @@ pub async fn add_port(
         req = req.loopback(Loopback::BF_LPBK_MAC_NEAR);
     }
 
-    match pm.update_port(&state.switch, &req).await {
+    match state.switch.update_port(&req).await {
         Ok(_) => {
             warn_on_mixed_breakout_rates(
-                pm,
-                &state.switch,
+                state.switch.as_ref(),
                 payload.front_panel_port,
                 payload.channel_count,
             )
@@ -243,8 +238,7 @@ pub async fn add_port(
 }
 
 async fn warn_on_mixed_breakout_rates(
-    pm: &rbfrt::util::PortManager,
-    switch: &rbfrt::SwitchConnection,
+    switch: &dyn SwitchBackend,
     front_panel_port: u32,
     channel_count: Option<u8>,
 ) {
@@ -256,7 +250,7 @@ async fn warn_on_mixed_breakout_rates(
         return;
     }
 
-    let Ok(ports) = pm.get_ports(switch).await else {
+    let Ok(ports) = switch.get_ports().await else {
         return;
     };
 
@@ -368,7 +362,12 @@ pub async fn arp_reply(State(state): State<Arc<AppState>>, payload: Json<ArpRepl
 
     match &state
         .arp_handler
-        .modify_arp(&state.switch, &target_mappings, payload.arp_reply, mac)
+        .modify_arp(
+            state.switch.as_ref(),
+            &target_mappings,
+            payload.arp_reply,
+            mac,
+        )
         .await
     {
         Ok(_) => {
@@ -0,0 +1,1032 @@
+/* Copyright 2022-present University of Tuebingen, Chair of Communication Networks
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/*
+ * Steffen Lindner (steffen.lindner@uni-tuebingen.de)
+ */
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write;
+use std::sync::Arc;
+
+use axum::extract::{Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Json, Response};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::api::server::Error;
+use crate::api::statistics::{
+    get_statistics, get_time_statistics, Params, StatisticsApi, TimeStatisticsApi,
+};
+use crate::core::statistics::{ElapsedTime, Histogram, HistogramData};
+use crate::core::traffic_gen_core::types::TrafficGenData;
+use crate::AppState;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    Csv,
+    Html,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ReportParams {
+    #[serde(default)]
+    pub format: ReportFormat,
+    pub test: Option<usize>,
+}
+
+/// Report of a single test.
+/// Rates are in bits per second, RTTs and IATs in nanoseconds.
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct TestReport {
+    /// Name of the test
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Elapsed time of the test in seconds. 0 if the test is not running.
+    pub elapsed_time: u32,
+    /// Traffic generation of the test
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub configuration: Option<TrafficGenData>,
+    pub ports: Vec<PortReport>,
+    pub streams: Vec<StreamReport>,
+    pub rtts: Vec<RttReport>,
+    pub iats: Vec<IatReport>,
+    pub histograms: Vec<HistogramReport>,
+    pub time_series: Vec<TimeSeriesReport>,
+}
+
+/// Rates and packet counters of a port
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct PortReport {
+    pub port: u32,
+    pub channel: u8,
+    pub tx_rate_l1: f64,
+    pub tx_rate_l2: f64,
+    pub rx_rate_l1: f64,
+    pub rx_rate_l2: f64,
+    pub tx_packets: u128,
+    pub rx_packets: u128,
+    pub packet_loss: u64,
+    pub out_of_order: u64,
+}
+
+/// L2 rates of a stream on a port
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct StreamReport {
+    pub port: u32,
+    pub channel: u8,
+    pub app_id: u32,
+    pub tx_rate_l2: f64,
+    pub rx_rate_l2: f64,
+}
+
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct RttReport {
+    pub port: u32,
+    pub channel: u8,
+    pub mean: f64,
+    pub min: u32,
+    pub max: u32,
+    pub jitter: f64,
+    pub n: u32,
+}
+
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct IatReport {
+    pub port: u32,
+    pub channel: u8,
+    /// Either `tx` or `rx`
+    pub direction: String,
+    pub mean: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub std: Option<f32>,
+    pub mae: f32,
+    pub n: u32,
+}
+
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct HistogramReport {
+    /// Either `rtt` or `iat`
+    pub kind: String,
+    pub port: u32,
+    pub channel: u8,
+    /// Either `tx` or `rx`
+    pub direction: String,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub total_pkt_count: u128,
+    pub missed_bin_count: u128,
+    pub bins: Vec<HistogramBinReport>,
+    /// Percentiles in percent and their values
+    pub percentiles: BTreeMap<u32, f64>,
+}
+
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct HistogramBinReport {
+    pub low: u32,
+    pub high: u32,
+    pub count: u128,
+    /// Probability of the bin in percent
+    pub probability: f64,
+}
+
+/// Time statistics of a port in a time slot of the test
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct TimeSeriesReport {
+    pub port: u32,
+    pub channel: u8,
+    /// Elapsed time of the test in seconds
+    pub time: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_rate_l1: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rx_rate_l1: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub packet_loss: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub out_of_order: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rtt: Option<u64>,
+}
+
+/// Method called on GET /report
+/// Returns a report of the current test and all previous tests of a multiple test run
+#[utoipa::path(
+    get,
+    path = "/api/report",
+    params(
+        ("format" = Option<String>, Query, description = "Format of the report, either `json` (default), `csv`, or `html`"),
+        ("test" = Option<usize>, Query, description = "Only report the test with this index as returned by `GET /api/statistics`")
+    ),
+    responses(
+    (status = 200,
+    description = "Returns one report per test with its configuration, per-port summary, per-stream rates, RTT and IAT statistics, histograms with percentiles, and time statistics. \
+    CSV reports consist of one table per section that starts with a line containing the title of the section.",
+    body = Vec<TestReport>),
+    (status = 400,
+    description = "Returned when the test does not exist."))
+)]
+pub async fn report(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ReportParams>,
+) -> Response {
+    let stats = get_statistics(&state).await;
+    let time_stats = get_time_statistics(
+        &state,
+        Params {
+            limit: None,
+            resolution: None,
+        },
+    )
+    .await;
+
+    // the current test is followed by the previous tests
+    let mut configurations = vec![state.traffic_generator.lock().await.traffic_gen_data()];
+    configurations.extend(
+        state
+            .multiple_tests
+            .collected_traffic_gen
+            .lock()
+            .await
+            .iter()
+            .cloned(),
+    );
+
+    let mut reports: Vec<TestReport> = stats
+        .iter()
+        .enumerate()
+        .map(|(i, s)| test_report(s, time_stats.get(i), configurations.get(i).cloned()))
+        .collect();
+
+    if let Some(test) = params.test {
+        if test >= reports.len() {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(Error::new(format!("Test {test} does not exist."))),
+            )
+                .into_response();
+        }
+
+        reports = vec![reports.swap_remove(test)];
+    }
+
+    match params.format {
+        ReportFormat::Json => (StatusCode::OK, Json(reports)).into_response(),
+        ReportFormat::Csv => (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+                (
+                    header::CONTENT_DISPOSITION,
+                    "attachment; filename=\"p4tg_report.csv\"",
+                ),
+            ],
+            render_csv(&reports),
+        )
+            .into_response(),
+        ReportFormat::Html => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "text/html; charset=utf-8")],
+            render_html(&reports),
+        )
+            .into_response(),
+    }
+}
+
+/// Returns the entries of a port map sorted by port and channel.
+fn ports<V>(map: &HashMap<u32, HashMap<u8, V>>) -> Vec<(u32, u8, &V)> {
+    let mut entries: Vec<(u32, u8, &V)> = map
+        .iter()
+        .flat_map(|(port, channels)| channels.iter().map(|(channel, v)| (*port, *channel, v)))
+        .collect();
+    entries.sort_by_key(|(port, channel, _)| (*port, *channel));
+    entries
+}
+
+fn value<V: Copy>(map: &HashMap<u32, HashMap<u8, V>>, port: u32, channel: u8) -> Option<V> {
+    map.get(&port).and_then(|c| c.get(&channel)).copied()
+}
+
+fn histogram_report(
+    kind: &str,
+    port: u32,
+    channel: u8,
+    direction: &str,
+    histogram: &Histogram,
+    data: &HistogramData,
+) -> HistogramReport {
+    let edges = histogram.config.bin_edges();
+
+    HistogramReport {
+        kind: kind.to_owned(),
+        port,
+        channel,
+        direction: direction.to_owned(),
+        mean: data.mean,
+        std_dev: data.std_dev,
+        total_pkt_count: data.total_pkt_count,
+        missed_bin_count: data.missed_bin_count,
+        bins: edges
+            .windows(2)
+            .enumerate()
+            .map(|(bin, edges)| {
+                let entry = data.data_bins.get(&(bin as u32));
+
+                HistogramBinReport {
+                    low: edges[0],
+                    high: edges[1],
+                    count: entry.map(|e| e.count).unwrap_or(0),
+                    probability: entry.map(|e| e.probability).unwrap_or(0f64),
+                }
+            })
+            .collect(),
+        percentiles: data.percentiles.iter().map(|(p, v)| (*p, *v)).collect(),
+    }
+}
+
+/// Creates the report of a test from its statistics.
+pub(crate) fn test_report(
+    stats: &StatisticsApi,
+    time_stats: Option<&TimeStatisticsApi>,
+    configuration: Option<TrafficGenData>,
+) -> TestReport {
+    // all ports with rates, counters, or frame sizes
+    let port_keys: BTreeSet<(u32, u8)> = [
+        &stats.tx_rate_l1,
+        &stats.tx_rate_l2,
+        &stats.rx_rate_l1,
+        &stats.rx_rate_l2,
+    ]
+    .into_iter()
+    .flat_map(|m| ports(m).into_iter().map(|(p, c, _)| (p, c)))
+    .chain(ports(&stats.frame_size).into_iter().map(|(p, c, _)| (p, c)))
+    .chain(
+        ports(&stats.packet_loss)
+            .into_iter()
+            .map(|(p, c, _)| (p, c)),
+    )
+    .collect();
+
+    let packets = |port: u32, channel: u8, rx: bool| -> u128 {
+        stats
+            .frame_size
+            .get(&port)
+            .and_then(|c| c.get(&channel))
+            .map(|r| {
+                if rx { &r.rx } else { &r.tx }
+                    .iter()
+                    .map(|v| v.packets)
+                    .sum()
+            })
+            .unwrap_or(0)
+    };
+
+    let port_reports = port_keys
+        .into_iter()
+        .map(|(port, channel)| PortReport {
+            port,
+            channel,
+            tx_rate_l1: value(&stats.tx_rate_l1, port, channel).unwrap_or_default(),
+            tx_rate_l2: value(&stats.tx_rate_l2, port, channel).unwrap_or_default(),
+            rx_rate_l1: value(&stats.rx_rate_l1, port, channel).unwrap_or_default(),
+            rx_rate_l2: value(&stats.rx_rate_l2, port, channel).unwrap_or_default(),
+            tx_packets: packets(port, channel, false),
+            rx_packets: packets(port, channel, true),
+            packet_loss: value(&stats.packet_loss, port, channel).unwrap_or_default(),
+            out_of_order: value(&stats.out_of_order, port, channel).unwrap_or_default(),
+        })
+        .collect();
+
+    let mut streams: BTreeMap<(u32, u8, u32), StreamReport> = BTreeMap::new();
+
+    for (rx, map) in [(false, &stats.app_tx_l2), (true, &stats.app_rx_l2)] {
+        for (port, channel, apps) in ports(map) {
+            for (app_id, rate) in apps {
+                let stream = streams
+                    .entry((port, channel, *app_id))
+                    .or_insert(StreamReport {
+                        port,
+                        channel,
+                        app_id: *app_id,
+                        tx_rate_l2: 0f64,
+                        rx_rate_l2: 0f64,
+                    });
+
+                if rx {
+                    stream.rx_rate_l2 = *rate;
+                } else {
+                    stream.tx_rate_l2 = *rate;
+                }
+            }
+        }
+    }
+
+    let rtts = ports(&stats.rtts)
+        .into_iter()
+        .map(|(port, channel, r)| RttReport {
+            port,
+            channel,
+            mean: r.mean,
+            min: r.min,
+            max: r.max,
+            jitter: r.jitter,
+            n: r.n,
+        })
+        .collect();
+
+    let iats = ports(&stats.iats)
+        .into_iter()
+        .flat_map(|(port, channel, i)| {
+            [("tx", &i.tx), ("rx", &i.rx)]
+                .into_iter()
+                .map(move |(direction, v)| IatReport {
+                    port,
+                    channel,
+                    direction: direction.to_owned(),
+                    mean: v.mean,
+                    std: v.std,
+                    mae: v.mae,
+                    n: v.n,
+                })
+        })
+        .collect();
+
+    let mut histograms = vec![];
+
+    for (port, channel, h) in ports(&stats.rtt_histogram) {
+        histograms.push(histogram_report("rtt", port, channel, "rx", h, &h.data.rx));
+    }
+
+    for (port, channel, h) in ports(&stats.iat_histogram) {
+        for (direction, data) in [("tx", &h.data.tx), ("rx", &h.data.rx)] {
+            histograms.push(histogram_report("iat", port, channel, direction, h, data));
+        }
+    }
+
+    TestReport {
+        name: stats.name.clone(),
+        elapsed_time: stats.elapsed_time,
+        configuration,
+        ports: port_reports,
+        streams: streams.into_values().collect(),
+        rtts,
+        iats,
+        histograms,
+        time_series: time_stats.map(time_series).unwrap_or_default(),
+    }
+}
+
+fn time_series(stats: &TimeStatisticsApi) -> Vec<TimeSeriesReport> {
+    let mut series: BTreeMap<(u32, u8, ElapsedTime), TimeSeriesReport> = BTreeMap::new();
+
+    fn collect<V: Copy>(
+        series: &mut BTreeMap<(u32, u8, ElapsedTime), TimeSeriesReport>,
+        map: &HashMap<u32, HashMap<u8, BTreeMap<ElapsedTime, V>>>,
+        set: impl Fn(&mut TimeSeriesReport, V),
+    ) {
+        for (port, channel, values) in ports(map) {
+            for (time, v) in values {
+                let entry = series
+                    .entry((port, channel, *time))
+                    .or_insert(TimeSeriesReport {
+                        port,
+                        channel,
+                        time: time.as_secs_f64(),
+                        tx_rate_l1: None,
+                        rx_rate_l1: None,
+                        packet_loss: None,
+                        out_of_order: None,
+                        rtt: None,
+                    });
+
+                set(entry, *v);
+            }
+        }
+    }
+
+    collect(&mut series, &stats.tx_rate_l1, |e, v| {
+        e.tx_rate_l1 = Some(v)
+    });
+    collect(&mut series, &stats.rx_rate_l1, |e, v| {
+        e.rx_rate_l1 = Some(v)
+    });
+    collect(&mut series, &stats.packet_loss, |e, v| {
+        e.packet_loss = Some(v)
+    });
+    collect(&mut series, &stats.out_of_order, |e, v| {
+        e.out_of_order = Some(v)
+    });
+    collect(&mut series, &stats.rtt, |e, v| e.rtt = Some(v));
+
+    series.into_values().collect()
+}
+
+/// A section of a CSV or HTML report
+struct Table {
+    title: String,
+    columns: &'static [&'static str],
+    rows: Vec<Vec<String>>,
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// Returns the sections of the CSV and HTML report of a test.
+fn tables(title: &str, report: &TestReport) -> Vec<Table> {
+    let mut tables = vec![];
+    let section = |name: &str| format!("{title}: {name}");
+
+    let mut configuration = vec![
+        vec!["Name".to_owned(), optional(report.name.clone())],
+        vec![
+            "Elapsed time (s)".to_owned(),
+            report.elapsed_time.to_string(),
+        ],
+    ];
+
+    if let Some(config) = &report.configuration {
+        configuration.push(vec!["Mode".to_owned(), format!("{:?}", config.mode)]);
+        configuration.push(vec!["Duration (s)".to_owned(), optional(config.duration)]);
+    }
+
+    tables.push(Table {
+        title: section("Configuration"),
+        columns: &["Setting", "Value"],
+        rows: configuration,
+    });
+
+    if let Some(config) = &report.configuration {
+        tables.push(Table {
+            title: section("Streams"),
+            columns: &[
+                "Stream ID",
+                "App ID",
+                "Frame size (B)",
+                "Encapsulation",
+                "Traffic rate",
+                "Unit",
+                "Burst",
+            ],
+            rows: config
+                .streams
+                .iter()
+                .map(|s| {
+                    vec![
+                        s.stream_id.to_string(),
+                        s.app_id.to_string(),
+                        s.frame_size.to_string(),
+                        format!("{:?}", s.encapsulation),
+                        s.traffic_rate.to_string(),
+                        optional(s.unit.map(|u| format!("{u:?}"))),
+                        s.burst.to_string(),
+                    ]
+                })
+                .collect(),
+        });
+
+        tables.push(Table {
+            title: section("Stream settings"),
+            columns: &["Port", "Channel", "Stream ID", "Active"],
+            rows: config
+                .stream_settings
+                .iter()
+                .map(|s| {
+                    vec![
+                        s.port.to_string(),
+                        s.channel.unwrap_or(0).to_string(),
+                        s.stream_id.to_string(),
+                        s.active.to_string(),
+                    ]
+                })
+                .collect(),
+        });
+
+        let mut mapping: Vec<(u32, u8, u32, u8)> = config
+            .port_tx_rx_mapping
+            .iter()
+            .flat_map(|(port, channels)| {
+                channels.iter().map(move |(channel, target)| {
+                    (
+                        port.parse().unwrap_or(0),
+                        channel.parse().unwrap_or(0),
+                        target.port,
+                        target.channel,
+                    )
+                })
+            })
+            .collect();
+        mapping.sort();
+
+        tables.push(Table {
+            title: section("Port mapping"),
+            columns: &["TX port", "TX channel", "RX port", "RX channel"],
+            rows: mapping
+                .into_iter()
+                .map(|(tx, tx_channel, rx, rx_channel)| {
+                    vec![
+                        tx.to_string(),
+                        tx_channel.to_string(),
+                        rx.to_string(),
+                        rx_channel.to_string(),
+                    ]
+                })
+                .collect(),
+        });
+    }
+
+    tables.push(Table {
+        title: section("Ports"),
+        columns: &[
+            "Port",
+            "Channel",
+            "TX rate L1 (bit/s)",
+            "TX rate L2 (bit/s)",
+            "RX rate L1 (bit/s)",
+            "RX rate L2 (bit/s)",
+            "TX packets",
+            "RX packets",
+            "Packet loss",
+            "Out of order",
+        ],
+        rows: report
+            .ports
+            .iter()
+            .map(|p| {
+                vec![
+                    p.port.to_string(),
+                    p.channel.to_string(),
+                    p.tx_rate_l1.to_string(),
+                    p.tx_rate_l2.to_string(),
+                    p.rx_rate_l1.to_string(),
+                    p.rx_rate_l2.to_string(),
+                    p.tx_packets.to_string(),
+                    p.rx_packets.to_string(),
+                    p.packet_loss.to_string(),
+                    p.out_of_order.to_string(),
+                ]
+            })
+            .collect(),
+    });
+
+    tables.push(Table {
+        title: section("Stream rates"),
+        columns: &[
+            "Port",
+            "Channel",
+            "App ID",
+            "TX rate L2 (bit/s)",
+            "RX rate L2 (bit/s)",
+        ],
+        rows: report
+            .streams
+            .iter()
+            .map(|s| {
+                vec![
+                    s.port.to_string(),
+                    s.channel.to_string(),
+                    s.app_id.to_string(),
+                    s.tx_rate_l2.to_string(),
+                    s.rx_rate_l2.to_string(),
+                ]
+            })
+            .collect(),
+    });
+
+    tables.push(Table {
+        title: section("RTT"),
+        columns: &[
+            "Port",
+            "Channel",
+            "Mean (ns)",
+            "Min (ns)",
+            "Max (ns)",
+            "Jitter (ns)",
+            "Samples",
+        ],
+        rows: report
+            .rtts
+            .iter()
+            .map(|r| {
+                vec![
+                    r.port.to_string(),
+                    r.channel.to_string(),
+                    r.mean.to_string(),
+                    r.min.to_string(),
+                    r.max.to_string(),
+                    r.jitter.to_string(),
+                    r.n.to_string(),
+                ]
+            })
+            .collect(),
+    });
+
+    tables.push(Table {
+        title: section("IAT"),
+        columns: &[
+            "Port",
+            "Channel",
+            "Direction",
+            "Mean (ns)",
+            "Std (ns)",
+            "MAE (ns)",
+            "Samples",
+        ],
+        rows: report
+            .iats
+            .iter()
+            .map(|i| {
+                vec![
+                    i.port.to_string(),
+                    i.channel.to_string(),
+                    i.direction.clone(),
+                    i.mean.to_string(),
+                    optional(i.std),
+                    i.mae.to_string(),
+                    i.n.to_string(),
+                ]
+            })
+            .collect(),
+    });
+
+    tables.push(Table {
+        title: section("Histograms"),
+        columns: &[
+            "Histogram",
+            "Port",
+            "Channel",
+            "Direction",
+            "Mean (ns)",
+            "Std (ns)",
+            "Packets",
+            "Missed packets",
+        ],
+        rows: report
+            .histograms
+            .iter()
+            .map(|h| {
+                vec![
+                    h.kind.clone(),
+                    h.port.to_string(),
+                    h.channel.to_string(),
+                    h.direction.clone(),
+                    h.mean.to_string(),
+                    h.std_dev.to_string(),
+                    h.total_pkt_count.to_string(),
+                    h.missed_bin_count.to_string(),
+                ]
+            })
+            .collect(),
+    });
+
+    let histogram_key = |h: &HistogramReport| {
+        vec![
+            h.kind.clone(),
+            h.port.to_string(),
+            h.channel.to_string(),
+            h.direction.clone(),
+        ]
+    };
+
+    tables.push(Table {
+        title: section("Histogram percentiles"),
+        columns: &[
+            "Histogram",
+            "Port",
+            "Channel",
+            "Direction",
+            "Percentile (%)",
+            "Value (ns)",
+        ],
+        rows: report
+            .histograms
+            .iter()
+            .flat_map(|h| {
+                h.percentiles.iter().map(move |(p, v)| {
+                    let mut row = histogram_key(h);
+                    row.extend([p.to_string(), v.to_string()]);
+                    row
+                })
+            })
+            .collect(),
+    });
+
+    tables.push(Table {
+        title: section("Histogram bins"),
+        columns: &[
+            "Histogram",
+            "Port",
+            "Channel",
+            "Direction",
+            "Low (ns)",
+            "High (ns)",
+            "Packets",
+            "Probability (%)",
+        ],
+        rows: report
+            .histograms
+            .iter()
+            .flat_map(|h| {
+                h.bins.iter().map(move |b| {
+                    let mut row = histogram_key(h);
+                    row.extend([
+                        b.low.to_string(),
+                        b.high.to_string(),
+                        b.count.to_string(),
+                        b.probability.to_string(),
+                    ]);
+                    row
+                })
+            })
+            .collect(),
+    });
+
+    tables.push(Table {
+        title: section("Time series"),
+        columns: &[
+            "Port",
+            "Channel",
+            "Time (s)",
+            "TX rate L1 (bit/s)",
+            "RX rate L1 (bit/s)",
+            "Packet loss",
+            "Out of order",
+            "RTT (ns)",
+        ],
+        rows: report
+            .time_series
+            .iter()
+            .map(|t| {
+                vec![
+                    t.port.to_string(),
+                    t.channel.to_string(),
+                    t.time.to_string(),
+                    optional(t.tx_rate_l1),
+                    optional(t.rx_rate_l1),
+                    optional(t.packet_loss),
+                    optional(t.out_of_order),
+                    optional(t.rtt),
+                ]
+            })
+            .collect(),
+    });
+
+    tables
+}
+
+/// Returns the title of each report.
+/// Unnamed tests are named by their position if multiple tests are reported.
+fn titles(reports: &[TestReport]) -> Vec<String> {
+    reports
+        .iter()
+        .enumerate()
+        .map(|(i, r)| match &r.name {
+            Some(name) => name.clone(),
+            None if reports.len() > 1 => format!("Test {}", i + 1),
+            None => "Test".to_owned(),
+        })
+        .collect()
+}
+
+/// Escapes a CSV field according to RFC 4180.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+fn csv_row<S: AsRef<str>>(output: &mut String, fields: &[S]) {
+    let fields: Vec<String> = fields.iter().map(|f| csv_field(f.as_ref())).collect();
+    let _ = writeln!(output, "{}", fields.join(","));
+}
+
+/// Renders the reports as CSV.
+/// Each section is a table that starts with its title and is separated from the next section by an empty line.
+pub(crate) fn render_csv(reports: &[TestReport]) -> String {
+    let mut output = String::new();
+
+    for (title, report) in titles(reports).iter().zip(reports) {
+        for table in tables(title, report) {
+            csv_row(&mut output, &[&table.title]);
+            csv_row(&mut output, table.columns);
+
+            for row in &table.rows {
+                csv_row(&mut output, row);
+            }
+
+            output.push('\n');
+        }
+    }
+
+    output
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Renders the reports as a self-contained HTML document.
+pub(crate) fn render_html(reports: &[TestReport]) -> String {
+    let mut output = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>P4TG report</title>\n\
+         <style>body{font-family:sans-serif}table{border-collapse:collapse;margin-bottom:1em}\
+         th,td{border:1px solid #ccc;padding:2px 8px;text-align:right}th{background:#eee}</style>\n\
+         </head>\n<body>\n",
+    );
+    let _ = writeln!(
+        output,
+        "<h1>P4TG report</h1>\n<p>P4TG version {}</p>",
+        env!("CARGO_PKG_VERSION")
+    );
+
+    for (title, report) in titles(reports).iter().zip(reports) {
+        let _ = writeln!(output, "<h2>{}</h2>", escape_html(title));
+
+        for table in tables(title, report) {
+            // the title of the test is already shown as heading
+            let section = table
+                .title
+                .strip_prefix(&format!("{title}: "))
+                .unwrap_or(&table.title);
+
+            let _ = writeln!(output, "<h3>{}</h3>\n<table>\n<tr>", escape_html(section));
+
+            for column in table.columns {
+                let _ = write!(output, "<th>{}</th>", escape_html(column));
+            }
+
+            output.push_str("</tr>\n");
+
+            for row in &table.rows {
+                output.push_str("<tr>");
+
+                for field in row {
+                    let _ = write!(output, "<td>{}</td>", escape_html(field));
+                }
+
+                output.push_str("</tr>\n");
+            }
+
+            output.push_str("</table>\n");
+        }
+    }
+
+    output.push_str("</body>\n</html>\n");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::docs::traffic_gen::EXAMPLE_POST_2_REQUEST;
+    use crate::core::backend::mock::tests::{call, mock_state, request};
+    use axum::body::to_bytes;
+    use axum::http::Method;
+
+    fn report(name: &str) -> TestReport {
+        TestReport {
+            name: Some(name.to_owned()),
+            elapsed_time: 10,
+            configuration: None,
+            ports: vec![PortReport {
+                port: 1,
+                channel: 0,
+                tx_rate_l1: 100e9,
+                tx_rate_l2: 80e9,
+                rx_rate_l1: 100e9,
+                rx_rate_l2: 80e9,
+                tx_packets: 1000,
+                rx_packets: 998,
+                packet_loss: 2,
+                out_of_order: 0,
+            }],
+            streams: vec![],
+            rtts: vec![],
+            iats: vec![],
+            histograms: vec![],
+            time_series: vec![],
+        }
+    }
+
+    #[test]
+    fn csv_sections_are_escaped() {
+        let output = render_csv(&[report("Test, \"A\"")]);
+
+        assert!(output.contains("\"Test, \"\"A\"\": Ports\"\n"));
+        assert!(output.contains("Port,Channel,TX rate L1 (bit/s),"));
+        assert!(output
+            .contains("\n1,0,100000000000,80000000000,100000000000,80000000000,1000,998,2,0\n"));
+    }
+
+    #[test]
+    fn html_is_escaped() {
+        let output = render_html(&[report("<A>")]);
+
+        assert!(output.contains("<h2>&lt;A&gt;</h2>"));
+        assert!(output.contains("<h3>Ports</h3>"));
+        assert!(!output.contains("<A>"));
+    }
+
+    #[tokio::test]
+    async fn report_is_generated_in_all_formats() {
+        let (_, state) = mock_state().await;
+
+        let (status, _) = call(
+            &state,
+            Method::POST,
+            "/trafficgen",
+            serde_json::to_string(&*EXAMPLE_POST_2_REQUEST).unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, report) = call(&state, Method::GET, "/report", String::new()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(report.as_array().unwrap().len(), 1);
+        assert_eq!(
+            report[0]["configuration"]["streams"]
+                .as_array()
+                .unwrap()
+                .len(),
+            EXAMPLE_POST_2_REQUEST.streams.len()
+        );
+
+        let (status, _) = call(&state, Method::GET, "/report?test=1", String::new()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        for (format, content_type, section) in [
+            ("csv", "text/csv; charset=utf-8", "Test: Ports\n"),
+            ("html", "text/html; charset=utf-8", "<h3>Ports</h3>"),
+        ] {
+            let uri = format!("/report?format={format}");
+            let response = request(&state, Method::GET, &uri, String::new()).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.headers()["content-type"], content_type);
+
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            assert!(String::from_utf8(body.to_vec()).unwrap().contains(section));
+        }
+    }
+}
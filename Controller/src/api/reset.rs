@@ -38,7 +38,7 @@ use std::sync::Arc;
     ))
 )]
 pub async fn reset(State(state): State<Arc<AppState>>) -> Response {
-    let switch = &state.switch;
+    let switch = state.switch.as_ref();
     let frame_size = state.frame_size_monitor.lock().await.on_reset(switch).await;
     let frame_type = state.frame_type_monitor.lock().await.on_reset(switch).await;
     let rate = state.rate_monitor.lock().await.on_reset(switch).await;
@@ -49,11 +49,17 @@ pub async fn reset(State(state): State<Arc<AppState>>) -> Response {
         .on_reset(switch)
         .await;
 
+    // Violations refer to the statistics that have been reset
+    let sla = state.traffic_generator.lock().await.sla.clone();
+    state.sla_monitor.lock().await.start(sla.as_ref());
+
     // Clear History statistics
     let mut stats_lock = state.multiple_tests.collected_statistics.lock().await;
     stats_lock.clear();
     let mut stats_lock = state.multiple_tests.collected_time_statistics.lock().await;
     stats_lock.clear();
+    let mut traffic_gen_lock = state.multiple_tests.collected_traffic_gen.lock().await;
+    traffic_gen_lock.clear();
 
     if frame_size.is_ok() && frame_type.is_ok() && rate.is_ok() && rtt_histogram.is_ok() {
         (
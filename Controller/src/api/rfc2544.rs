@@ -0,0 +1,130 @@
+/* Copyright 2022-present University of Tuebingen, Chair of Communication Networks
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/*
+ * Steffen Lindner (steffen.lindner@uni-tuebingen.de)
+ */
+
+use crate::api::helper::validate::validate_request;
+use crate::api::server::Error;
+use crate::core::rfc2544::{Rfc2544Config, Rfc2544Result};
+use crate::AppState;
+use axum::debug_handler;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Json, Response};
+use std::sync::Arc;
+
+/// Method called on GET /rfc2544
+/// Returns the state and results of the current or last RFC 2544 benchmark
+#[debug_handler]
+#[utoipa::path(
+    get,
+    path = "/api/rfc2544",
+    responses(
+    (status = 200,
+    description = "Returns the state and the throughput, latency, frame loss rate, and back-to-back tables of the current or last RFC 2544 benchmark.",
+    body = Rfc2544Result))
+)]
+pub async fn rfc2544(State(state): State<Arc<AppState>>) -> Response {
+    let result = state.rfc2544.result.lock().await.clone();
+
+    (StatusCode::OK, Json(result)).into_response()
+}
+
+/// Method called on POST /rfc2544
+/// Starts an RFC 2544 benchmark with the configured tests. Stops any running traffic generation.
+#[debug_handler]
+#[utoipa::path(
+    post,
+    path = "/api/rfc2544",
+    request_body = Rfc2544Config,
+    responses(
+    (status = 200,
+    description = "The benchmark has been started.",
+    body = Rfc2544Result),
+    (status = 400,
+    description = "Returned when the benchmark configuration is invalid."))
+)]
+pub async fn start_rfc2544(
+    State(state): State<Arc<AppState>>,
+    Json(config): Json<Rfc2544Config>,
+) -> Response {
+    if let Err(message) = config.validate() {
+        return (StatusCode::BAD_REQUEST, Json(Error::new(message))).into_response();
+    }
+
+    let is_tofino2 = state.traffic_generator.lock().await.is_tofino2;
+
+    // the traffic generation of the trials at 100% has the highest rate
+    for &frame_size in &config.frame_sizes {
+        if let Err(e) = validate_request(
+            &config.trial(frame_size, 100f64),
+            &state.port_mapping,
+            is_tofino2,
+        ) {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(Error::new(format!(
+                    "Invalid configuration for frame size {frame_size}: {}",
+                    e.message
+                ))),
+            )
+                .into_response();
+        }
+    }
+
+    // Cancel any running benchmark, test, and duration monitor task
+    state.rfc2544.task.lock().await.cancel_existing_task().await;
+    state.y1564.task.lock().await.cancel_existing_task().await;
+    state
+        .multiple_tests
+        .multiple_test_monitor_task
+        .lock()
+        .await
+        .cancel_existing_monitoring_task()
+        .await;
+    state
+        .monitor_task
+        .lock()
+        .await
+        .cancel_existing_monitoring_task()
+        .await;
+
+    state.rfc2544.task.lock().await.start(&state, config).await;
+
+    let result = state.rfc2544.result.lock().await.clone();
+
+    (StatusCode::OK, Json(result)).into_response()
+}
+
+/// Method called on DELETE /rfc2544
+/// Aborts the running RFC 2544 benchmark
+#[debug_handler]
+#[utoipa::path(
+    delete,
+    path = "/api/rfc2544",
+    responses(
+    (status = 200,
+    description = "Aborts the running benchmark and returns its results.",
+    body = Rfc2544Result))
+)]
+pub async fn stop_rfc2544(State(state): State<Arc<AppState>>) -> Response {
+    state.rfc2544.task.lock().await.cancel_existing_task().await;
+
+    let result = state.rfc2544.result.lock().await.clone();
+
+    (StatusCode::OK, Json(result)).into_response()
+}
@@ -20,6 +20,7 @@
 use std::env;
 use std::sync::Arc;
 
+use axum::extract::DefaultBodyLimit;
 use axum::http::Method;
 use axum::response::{IntoResponse, Response};
 use axum::routing::post;
@@ -34,31 +35,53 @@ use utoipa::{
 use utoipa_swagger_ui::SwaggerUi;
 
 use crate::api::{
-    add_port, config, configure_traffic_gen, online, ports, reset, restart, statistics,
-    stop_traffic_gen, traffic_gen,
+    add_port, archive, archived_test, config, configure_traffic_gen, delete_archived_test, metrics,
+    online, plan_traffic_gen, ports, report, reset, restart, rfc2544, start_rfc2544, start_y1564,
+    statistics, stop_rfc2544, stop_traffic_gen, stop_y1564, tag_archived_test, traffic_gen,
+    traffic_gen_feasibility, traffic_gen_import, traffic_gen_pcap, y1564,
 };
 use tower_http::cors::{Any, CorsLayer};
 
 use crate::api::helper::serve_static_files::{serve_index, static_path};
 use crate::api::histogram;
 use crate::api::ports::{arp_reply, PortConfiguration};
-use crate::api::statistics::time_statistics;
+use crate::api::statistics::{statistics_stream, time_statistics};
 use crate::api::tables;
 use crate::api::tables::tables;
 use crate::AppState;
 
 use crate::core::traffic_gen_core::types::*;
 
+/// Maximal size of pcap files on POST /trafficgen/import
+const PCAP_IMPORT_BODY_LIMIT: usize = 64 * 1024 * 1024;
+
 #[derive(OpenApi)]
 #[openapi(
     paths(
         traffic_gen::traffic_gen,
         traffic_gen::configure_traffic_gen,
         traffic_gen::stop_traffic_gen,
+        traffic_gen::plan_traffic_gen,
+        traffic_gen::traffic_gen_feasibility,
+        traffic_gen::traffic_gen_pcap,
+        traffic_gen::traffic_gen_import,
         tables::tables,
         statistics::statistics,
         statistics::time_statistics,
+        statistics::statistics_stream,
         restart::restart,
+        rfc2544::rfc2544,
+        rfc2544::start_rfc2544,
+        rfc2544::stop_rfc2544,
+        y1564::y1564,
+        metrics::metrics,
+        y1564::start_y1564,
+        y1564::stop_y1564,
+        archive::archive,
+        archive::archived_test,
+        archive::tag_archived_test,
+        archive::delete_archived_test,
+        report::report,
         reset::reset,
         ports::ports,
         ports::add_port,
@@ -81,6 +104,48 @@ use crate::core::traffic_gen_core::types::*;
         VxLAN,
         GtpU,
         MPLSHeader,
+        TrafficGenPlan,
+        PlannedTableEntry,
+        EntryOperation,
+        PcapImport,
+        PcapImportIssue,
+        FeasibilityReport,
+        StreamFeasibility,
+        PortFeasibility,
+        BufferUsage,
+        crate::core::rfc2544::Rfc2544Config,
+        crate::core::rfc2544::Rfc2544Result,
+        crate::core::rfc2544::Rfc2544Trial,
+        crate::core::rfc2544::ThroughputResult,
+        crate::core::rfc2544::Rfc2544Test,
+        crate::core::rfc2544::TrialCounters,
+        crate::core::rfc2544::Latency,
+        crate::core::rfc2544::LatencyResult,
+        crate::core::rfc2544::FrameLossResult,
+        crate::core::rfc2544::BackToBackResult,
+        crate::core::y1564::Y1564Config,
+        crate::core::y1564::Y1564Service,
+        crate::core::y1564::Y1564Criteria,
+        crate::core::y1564::Y1564Step,
+        crate::core::y1564::Y1564Result,
+        crate::core::y1564::Y1564ServiceReport,
+        crate::core::y1564::Y1564StepResult,
+        crate::core::statistics_stream::StatisticsEvent,
+        crate::core::archive::ArchiveRecord,
+        crate::core::archive::ArchiveSummary,
+        crate::core::archive::ArchiveMetadata,
+        crate::api::report::TestReport,
+        crate::core::sla::SlaConfig,
+        crate::core::sla::SlaThreshold,
+        crate::core::sla::SlaVerdict,
+        crate::core::sla::SlaViolation,
+        crate::core::sla::SlaMetric,
+        crate::core::disruption::DisruptionStatistics,
+        crate::core::disruption::Outage,
+        crate::core::loss_analysis::LossAnalysis,
+        crate::core::measurement_window::MeasurementWindow,
+        crate::core::measurement_window::MeanRates,
+        crate::core::y1564::ServiceMeasurement,
         tables::TableDescriptor,
         crate::core::statistics::Statistics,
         crate::core::statistics::TimeStatistics,
@@ -89,6 +154,8 @@ use crate::core::traffic_gen_core::types::*;
         crate::core::statistics::TypeCount,
         crate::core::statistics::IATStatistics,
         crate::core::statistics::RTTStatistics,
+        crate::core::statistics::Quantiles,
+        crate::core::statistics::IATQuantiles,
         crate::core::statistics::IATValues
         ),
     ),
@@ -143,12 +210,8 @@ pub fn generate_api_json() {
         .expect("Failed to write OpenAPI JSON");
 }
 
-pub async fn start_api_server(state: Arc<AppState>) {
-    let port = env::var("P4TG_PORT")
-        .unwrap_or("8000".to_owned())
-        .parse()
-        .unwrap_or(8000);
-
+/// Creates the router for the REST API that is served under `/api`.
+pub fn api_router(state: Arc<AppState>) -> Router {
     let cors = CorsLayer::new()
         // allow `GET` and `POST` when accessing the resource
         .allow_methods([Method::GET, Method::POST, Method::DELETE, Method::OPTIONS])
@@ -156,10 +219,10 @@ pub async fn start_api_server(state: Arc<AppState>) {
         .allow_origin(Any)
         .allow_headers(Any);
 
-    // Router for the REST API
-    let api_router = Router::new()
+    Router::new()
         .route("/online", get(online))
         .route("/statistics", get(statistics))
+        .route("/statistics/stream", get(statistics_stream))
         .route("/time_statistics", get(time_statistics))
         .route(
             "/trafficgen",
@@ -167,8 +230,27 @@ pub async fn start_api_server(state: Arc<AppState>) {
                 .post(configure_traffic_gen)
                 .delete(stop_traffic_gen),
         )
+        .route("/trafficgen/plan", post(plan_traffic_gen))
+        .route("/trafficgen/feasibility", post(traffic_gen_feasibility))
+        .route("/trafficgen/pcap", get(traffic_gen_pcap))
+        .route(
+            "/trafficgen/import",
+            post(traffic_gen_import).layer(DefaultBodyLimit::max(PCAP_IMPORT_BODY_LIMIT)),
+        )
+        .route("/report", get(report))
         .route("/reset", get(reset))
         .route("/restart", get(restart))
+        .route(
+            "/rfc2544",
+            get(rfc2544).post(start_rfc2544).delete(stop_rfc2544),
+        )
+        .route("/y1564", get(y1564).post(start_y1564).delete(stop_y1564))
+        .route("/archive", get(archive))
+        .route(
+            "/archive/{id}",
+            get(archived_test).delete(delete_archived_test),
+        )
+        .route("/archive/{id}/tags", post(tag_archived_test))
         .route("/ports", get(ports))
         .route("/ports", post(add_port))
         .route("/ports/arp", post(arp_reply))
@@ -176,12 +258,23 @@ pub async fn start_api_server(state: Arc<AppState>) {
         .route("/config", get(config))
         .route("/histogram", get(histogram::config))
         .layer(cors)
-        .with_state(Arc::clone(&state));
+        .with_state(state)
+}
+
+pub async fn start_api_server(state: Arc<AppState>) {
+    let port = env::var("P4TG_PORT")
+        .unwrap_or("8000".to_owned())
+        .parse()
+        .unwrap_or(8000);
+
+    // Router for the REST API
+    let api_router = api_router(Arc::clone(&state));
 
     // Router for the static configuration gui
     let app = Router::new()
         .merge(SwaggerUi::new("/api/docs").url("/api-docs/openapi.json", ApiDoc::openapi()))
         .nest_service("/api", api_router)
+        .route("/metrics", get(metrics).with_state(Arc::clone(&state)))
         .route("/", get(serve_index)) // create react routing endpoints
         .route("/home", get(serve_index))
         .route("/ports", get(serve_index))
@@ -17,10 +17,18 @@
  * Steffen Lindner (steffen.lindner@uni-tuebingen.de)
  */
 
+use crate::core::disruption::DisruptionStatistics;
+use crate::core::histogram_monitor::HistogramMonitor;
+use crate::core::loss_analysis::LossAnalysis;
+use crate::core::measurement_window::{MeanRates, MeasurementWindow};
+use crate::core::quantile_sketch::QuantileSketch;
+use crate::core::sla::SlaVerdict;
 use crate::core::statistics::{
-    Histogram, IATStatistics, IATValues, RTTStatistics, RangeCount, Statistics, TimeStatistics,
-    TypeCount,
+    ElapsedTime, Histogram, IATQuantiles, IATStatistics, IATValues, Quantiles, RTTStatistics,
+    RangeCount, Statistics, TimeStatistics, TypeCount,
 };
+use crate::core::statistics_stream::{subscribe, StatisticsEvent};
+use crate::core::traffic_gen_core::const_definitions::DEFAULT_TIME_RESOLUTION;
 use crate::core::traffic_gen_core::helper::{
     derive_fpch, filter_map_for_keys, generate_dev_port_to_front_panel_mappings, get_used_ports,
     remap_app_map, remap_port_map,
@@ -28,12 +36,16 @@ use crate::core::traffic_gen_core::helper::{
 use crate::AppState;
 use axum::extract::{Query, State};
 use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::response::{IntoResponse, Json, Response};
-use log::info;
+use futures_util::stream::{self, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::convert::Infallible;
+use std::hash::Hash;
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
 use utoipa::ToSchema;
 
 use crate::api::{docs, helper};
@@ -56,11 +68,26 @@ pub struct StatisticsApi {
     pub rtts: HashMap<u32, HashMap<u8, RTTStatistics>>,
     pub packet_loss: HashMap<u32, HashMap<u8, u64>>,
     pub out_of_order: HashMap<u32, HashMap<u8, u64>>,
+    pub app_packet_loss: HashMap<u32, HashMap<u8, HashMap<u32, u64>>>,
+    pub app_out_of_order: HashMap<u32, HashMap<u8, HashMap<u32, u64>>>,
+    pub app_rtts: HashMap<u32, HashMap<u8, HashMap<u32, RTTStatistics>>>,
+    pub rtt_quantiles: HashMap<u32, HashMap<u8, Quantiles>>,
+    pub app_rtt_quantiles: HashMap<u32, HashMap<u8, HashMap<u32, Quantiles>>>,
+    pub iat_quantiles: HashMap<u32, HashMap<u8, IATQuantiles>>,
+    pub disruption: HashMap<u32, HashMap<u8, DisruptionStatistics>>,
+    pub app_disruption: HashMap<u32, HashMap<u8, HashMap<u32, DisruptionStatistics>>>,
+    pub loss_analysis: HashMap<u32, HashMap<u8, LossAnalysis>>,
+    pub app_loss_analysis: HashMap<u32, HashMap<u8, HashMap<u32, LossAnalysis>>>,
+    pub mean_rates: HashMap<u32, HashMap<u8, MeanRates>>,
     pub elapsed_time: u32,
     pub rtt_histogram: HashMap<u32, HashMap<u8, Histogram>>,
+    pub app_rtt_histogram: HashMap<u32, HashMap<u8, HashMap<u32, Histogram>>>,
     pub iat_histogram: HashMap<u32, HashMap<u8, Histogram>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    /// Verdict of the acceptance criteria. Only present if the test has acceptance criteria.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sla: Option<SlaVerdict>,
 }
 
 impl StatisticsApi {
@@ -86,10 +113,23 @@ impl StatisticsApi {
             rtts: remap_port_map(&core.rtts, &dev_to_fpch),
             packet_loss: remap_port_map(&core.packet_loss, &dev_to_fpch),
             out_of_order: remap_port_map(&core.out_of_order, &dev_to_fpch),
+            app_packet_loss: remap_port_map(&core.app_packet_loss, &dev_to_fpch),
+            app_out_of_order: remap_port_map(&core.app_out_of_order, &dev_to_fpch),
+            app_rtts: remap_port_map(&core.app_rtts, &dev_to_fpch),
+            rtt_quantiles: remap_port_map(&core.rtt_quantiles, &dev_to_fpch),
+            app_rtt_quantiles: remap_port_map(&core.app_rtt_quantiles, &dev_to_fpch),
+            iat_quantiles: remap_port_map(&core.iat_quantiles, &dev_to_fpch),
+            disruption: remap_port_map(&core.disruption, &dev_to_fpch),
+            app_disruption: remap_port_map(&core.app_disruption, &dev_to_fpch),
+            loss_analysis: remap_port_map(&core.loss_analysis, &dev_to_fpch),
+            app_loss_analysis: remap_port_map(&core.app_loss_analysis, &dev_to_fpch),
+            mean_rates: remap_port_map(&core.mean_rates, &dev_to_fpch),
             elapsed_time: core.elapsed_time,
             rtt_histogram: remap_port_map(&core.rtt_histogram, &dev_to_fpch),
+            app_rtt_histogram: remap_port_map(&core.app_rtt_histogram, &dev_to_fpch),
             iat_histogram: remap_port_map(&core.iat_histogram, &dev_to_fpch),
             name: core.name.clone(),
+            sla: None,
         }
     }
 
@@ -108,7 +148,19 @@ impl StatisticsApi {
         filter_map_for_keys(&mut stats.rtts, &used_ports);
         filter_map_for_keys(&mut stats.packet_loss, &used_ports);
         filter_map_for_keys(&mut stats.out_of_order, &used_ports);
+        filter_map_for_keys(&mut stats.app_packet_loss, &used_ports);
+        filter_map_for_keys(&mut stats.app_out_of_order, &used_ports);
+        filter_map_for_keys(&mut stats.app_rtts, &used_ports);
+        filter_map_for_keys(&mut stats.rtt_quantiles, &used_ports);
+        filter_map_for_keys(&mut stats.app_rtt_quantiles, &used_ports);
+        filter_map_for_keys(&mut stats.iat_quantiles, &used_ports);
+        filter_map_for_keys(&mut stats.disruption, &used_ports);
+        filter_map_for_keys(&mut stats.app_disruption, &used_ports);
+        filter_map_for_keys(&mut stats.loss_analysis, &used_ports);
+        filter_map_for_keys(&mut stats.app_loss_analysis, &used_ports);
+        filter_map_for_keys(&mut stats.mean_rates, &used_ports);
         filter_map_for_keys(&mut stats.rtt_histogram, &used_ports);
+        filter_map_for_keys(&mut stats.app_rtt_histogram, &used_ports);
         filter_map_for_keys(&mut stats.iat_histogram, &used_ports);
 
         stats
@@ -118,13 +170,26 @@ impl StatisticsApi {
 // Those statistics are communicated via the API.
 // The structure is Port(u32)->Channel(u8)->Stats
 // Ports are front panel numbers
+#[allow(clippy::type_complexity)]
 #[derive(Serialize, Debug, Clone, ToSchema)]
 pub struct TimeStatisticsApi {
-    pub(crate) tx_rate_l1: HashMap<u32, HashMap<u8, BTreeMap<u32, f64>>>,
-    pub(crate) rx_rate_l1: HashMap<u32, HashMap<u8, BTreeMap<u32, f64>>>,
-    pub(crate) packet_loss: HashMap<u32, HashMap<u8, BTreeMap<u32, u64>>>,
-    pub(crate) out_of_order: HashMap<u32, HashMap<u8, BTreeMap<u32, u64>>>,
-    pub(crate) rtt: HashMap<u32, HashMap<u8, BTreeMap<u32, u64>>>,
+    pub(crate) tx_rate_l1: HashMap<u32, HashMap<u8, BTreeMap<ElapsedTime, f64>>>,
+    pub(crate) rx_rate_l1: HashMap<u32, HashMap<u8, BTreeMap<ElapsedTime, f64>>>,
+    pub(crate) packet_loss: HashMap<u32, HashMap<u8, BTreeMap<ElapsedTime, u64>>>,
+    pub(crate) out_of_order: HashMap<u32, HashMap<u8, BTreeMap<ElapsedTime, u64>>>,
+    pub(crate) rtt: HashMap<u32, HashMap<u8, BTreeMap<ElapsedTime, u64>>>,
+    pub(crate) app_tx_l2: HashMap<u32, HashMap<u8, HashMap<u32, BTreeMap<ElapsedTime, f64>>>>,
+    pub(crate) app_rx_l2: HashMap<u32, HashMap<u8, HashMap<u32, BTreeMap<ElapsedTime, f64>>>>,
+    pub(crate) app_packet_loss: HashMap<u32, HashMap<u8, HashMap<u32, BTreeMap<ElapsedTime, u64>>>>,
+    pub(crate) app_out_of_order:
+        HashMap<u32, HashMap<u8, HashMap<u32, BTreeMap<ElapsedTime, u64>>>>,
+    pub(crate) app_rtt: HashMap<u32, HashMap<u8, HashMap<u32, BTreeMap<ElapsedTime, u64>>>>,
+    pub(crate) disruption_time: HashMap<u32, HashMap<u8, BTreeMap<ElapsedTime, f64>>>,
+    pub(crate) app_disruption_time:
+        HashMap<u32, HashMap<u8, HashMap<u32, BTreeMap<ElapsedTime, f64>>>>,
+    /// Measurement window of the test. Only present if a warm-up or cool-down phase is configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) measurement_window: Option<MeasurementWindow>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) name: Option<String>,
 }
@@ -143,10 +208,38 @@ impl TimeStatisticsApi {
             packet_loss: remap_port_map(&core.packet_loss, &dev_to_fpch),
             out_of_order: remap_port_map(&core.out_of_order, &dev_to_fpch),
             rtt: remap_port_map(&core.rtt, &dev_to_fpch),
+            app_tx_l2: remap_port_map(&core.app_tx_l2, &dev_to_fpch),
+            app_rx_l2: remap_port_map(&core.app_rx_l2, &dev_to_fpch),
+            app_packet_loss: remap_port_map(&core.app_packet_loss, &dev_to_fpch),
+            app_out_of_order: remap_port_map(&core.app_out_of_order, &dev_to_fpch),
+            app_rtt: remap_port_map(&core.app_rtt, &dev_to_fpch),
+            disruption_time: remap_port_map(&core.disruption_time, &dev_to_fpch),
+            app_disruption_time: remap_port_map(&core.app_disruption_time, &dev_to_fpch),
+            measurement_window: core.measurement_window,
             name: core.name.clone(),
         }
     }
 
+    /// Returns the time statistics with the first element of each time slot of length `resolution` ms.
+    fn downsample(&self, resolution: u32) -> TimeStatisticsApi {
+        TimeStatisticsApi {
+            tx_rate_l1: downsample_streams(&self.tx_rate_l1, resolution),
+            rx_rate_l1: downsample_streams(&self.rx_rate_l1, resolution),
+            packet_loss: downsample_streams(&self.packet_loss, resolution),
+            out_of_order: downsample_streams(&self.out_of_order, resolution),
+            rtt: downsample_streams(&self.rtt, resolution),
+            app_tx_l2: downsample_channel_streams(&self.app_tx_l2, resolution),
+            app_rx_l2: downsample_channel_streams(&self.app_rx_l2, resolution),
+            app_packet_loss: downsample_channel_streams(&self.app_packet_loss, resolution),
+            app_out_of_order: downsample_channel_streams(&self.app_out_of_order, resolution),
+            app_rtt: downsample_channel_streams(&self.app_rtt, resolution),
+            disruption_time: downsample_streams(&self.disruption_time, resolution),
+            app_disruption_time: downsample_channel_streams(&self.app_disruption_time, resolution),
+            measurement_window: self.measurement_window,
+            name: self.name.clone(),
+        }
+    }
+
     /// Removes all statistics of unused ports.
     /// Used ports are provided in `used_ports`.
     fn filter_inactive_ports(
@@ -158,6 +251,13 @@ impl TimeStatisticsApi {
         filter_map_for_keys(&mut stats.packet_loss, &used_ports);
         filter_map_for_keys(&mut stats.out_of_order, &used_ports);
         filter_map_for_keys(&mut stats.rtt, &used_ports);
+        filter_map_for_keys(&mut stats.app_tx_l2, &used_ports);
+        filter_map_for_keys(&mut stats.app_rx_l2, &used_ports);
+        filter_map_for_keys(&mut stats.app_packet_loss, &used_ports);
+        filter_map_for_keys(&mut stats.app_out_of_order, &used_ports);
+        filter_map_for_keys(&mut stats.app_rtt, &used_ports);
+        filter_map_for_keys(&mut stats.disruption_time, &used_ports);
+        filter_map_for_keys(&mut stats.app_disruption_time, &used_ports);
 
         stats
     }
@@ -201,8 +301,20 @@ pub async fn get_statistics(state: &Arc<AppState>) -> Vec<StatisticsApi> {
         rtts: Default::default(),
         packet_loss: Default::default(),
         out_of_order: Default::default(),
+        app_packet_loss: Default::default(),
+        app_out_of_order: Default::default(),
+        app_rtts: Default::default(),
+        rtt_quantiles: Default::default(),
+        app_rtt_quantiles: Default::default(),
+        iat_quantiles: Default::default(),
+        disruption: Default::default(),
+        app_disruption: Default::default(),
+        loss_analysis: Default::default(),
+        app_loss_analysis: Default::default(),
+        mean_rates: Default::default(),
         elapsed_time: 0,
         rtt_histogram: Default::default(),
+        app_rtt_histogram: Default::default(),
         iat_histogram: Default::default(),
         name: None,
     };
@@ -225,26 +337,88 @@ pub async fn get_statistics(state: &Arc<AppState>) -> Vec<StatisticsApi> {
         stats.name = state.traffic_generator.lock().await.name.clone();
     }
 
-    let monitor_statistics = rate_monitor.lock().await.statistics.clone();
+    let (monitor_statistics, rtts, app_rtts) = {
+        let rate_monitor = rate_monitor.lock().await;
+        (
+            rate_monitor.statistics.clone(),
+            rate_monitor.rtt_storage.clone(),
+            rate_monitor.app_rtt_storage.clone(),
+        )
+    };
 
-    let rtts = rate_monitor.lock().await.rtt_storage.clone();
+    {
+        let rate_monitor = rate_monitor.lock().await;
+        stats.disruption = rate_monitor.disruption.port_statistics();
+        stats.app_disruption = rate_monitor.disruption.stream_statistics();
+        stats.loss_analysis = rate_monitor.loss_analysis.port_statistics();
+        stats.app_loss_analysis = rate_monitor.loss_analysis.stream_statistics();
+        stats.mean_rates = rate_monitor.window_counters.mean_rates();
+
+        // the RTT quantiles of a port are based on the RTTs of all its streams
+        for (port, per_app) in &rate_monitor.rtt_sketches {
+            let mut port_sketch = QuantileSketch::default();
+
+            for (app_id, sketch) in per_app {
+                port_sketch.merge(sketch);
+
+                if *app_id != 0 {
+                    stats
+                        .app_rtt_quantiles
+                        .entry(*port)
+                        .or_default()
+                        .insert(*app_id, sketch.quantiles());
+                }
+            }
+
+            stats.rtt_quantiles.insert(*port, port_sketch.quantiles());
+        }
 
-    let mut rtt_stats = HashMap::new();
+        for port in rate_monitor
+            .tx_iat_sketches
+            .keys()
+            .chain(rate_monitor.rx_iat_sketches.keys())
+        {
+            let quantiles = |sketches: &HashMap<u32, QuantileSketch>| {
+                sketches
+                    .get(port)
+                    .map(|s| s.quantiles())
+                    .unwrap_or_default()
+            };
 
-    for (port, rtt_samples) in &rtts {
-        let mean = helper::simple_stats::average(rtt_samples);
-        let std = helper::simple_stats::std(rtt_samples);
+            stats.iat_quantiles.insert(
+                *port,
+                IATQuantiles {
+                    tx: quantiles(&rate_monitor.tx_iat_sketches),
+                    rx: quantiles(&rate_monitor.rx_iat_sketches),
+                },
+            );
+        }
+    }
 
-        let stats = RTTStatistics {
-            mean,
-            min: *rtt_samples.iter().min().unwrap_or(&0) as u32,
-            max: *rtt_samples.iter().max().unwrap_or(&0) as u32,
-            current: *rtt_samples.iter().last().unwrap_or(&0) as u32,
-            jitter: std,
-            n: rtt_samples.len() as u32,
-        };
+    let rtt_stats = rtts
+        .iter()
+        .map(|(port, rtt_samples)| (*port, rtt_statistics(rtt_samples)))
+        .collect();
 
-        rtt_stats.insert(*port, stats);
+    // RTT statistics and histograms per stream
+    // The histograms use the RTT histogram config of the port
+    for (port, per_app) in &app_rtts {
+        let hist_config = stats.rtt_histogram.get(port).map(|h| h.config.clone());
+
+        for (app_id, rtt_samples) in per_app {
+            stats
+                .app_rtts
+                .entry(*port)
+                .or_default()
+                .insert(*app_id, rtt_statistics(rtt_samples));
+
+            if let Some(hist_config) = &hist_config {
+                stats.app_rtt_histogram.entry(*port).or_default().insert(
+                    *app_id,
+                    HistogramMonitor::histogram_from_samples(rtt_samples, hist_config),
+                );
+            }
+        }
     }
 
     let port_mapping = &state.port_mapping;
@@ -290,6 +464,8 @@ pub async fn get_statistics(state: &Arc<AppState>) -> Vec<StatisticsApi> {
     stats.app_rx_l2 = monitor_statistics.app_rx_l2.clone();
     stats.packet_loss = monitor_statistics.packet_loss.clone();
     stats.out_of_order = monitor_statistics.out_of_order.clone();
+    stats.app_packet_loss = monitor_statistics.app_packet_loss.clone();
+    stats.app_out_of_order = monitor_statistics.app_out_of_order.clone();
     stats.elapsed_time = {
         let experiment = state.experiment.lock().await;
         if experiment.running {
@@ -310,7 +486,8 @@ pub async fn get_statistics(state: &Arc<AppState>) -> Vec<StatisticsApi> {
 
     // Filter for inactive ports
     let used_ports: HashSet<u32> = get_used_ports(state).await;
-    let stats = StatisticsApi::filter_inactive_ports(stats, used_ports);
+    let mut stats = StatisticsApi::filter_inactive_ports(stats, used_ports);
+    stats.sla = state.sla_monitor.lock().await.verdict.clone();
 
     let mut all_stats = vec![stats];
     let previous_stats = state
@@ -324,16 +501,31 @@ pub async fn get_statistics(state: &Arc<AppState>) -> Vec<StatisticsApi> {
     all_stats
 }
 
+/// Calculates the RTT statistics from sampled RTTs.
+fn rtt_statistics(rtt_samples: &VecDeque<u64>) -> RTTStatistics {
+    RTTStatistics {
+        mean: helper::simple_stats::average(rtt_samples),
+        min: *rtt_samples.iter().min().unwrap_or(&0) as u32,
+        max: *rtt_samples.iter().max().unwrap_or(&0) as u32,
+        current: *rtt_samples.iter().last().unwrap_or(&0) as u32,
+        jitter: helper::simple_stats::std(rtt_samples),
+        n: rtt_samples.len() as u32,
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Params {
     pub limit: Option<usize>,
+    /// Resolution of the time series in ms
+    pub resolution: Option<u32>,
 }
 
 #[utoipa::path(
     get,
     path = "/api/time_statistics",
     params(
-        ("limit" = Option<usize>, Query, description = "Only retrieve the last *limit* entries")
+        ("limit" = Option<usize>, Query, description = "Only retrieve the last *limit* entries"),
+        ("resolution" = Option<u32>, Query, description = "Only retrieve one entry per *resolution* ms")
     ),
     responses(
         (status = 200,
@@ -342,7 +534,8 @@ pub struct Params {
         example = json!(*docs::statistics::EXAMPLE_GET_2)
         ))
 )]
-/// Returns the current statistics over time with one data point per second.
+/// Returns the current statistics over time.
+/// By default, there is one data point per time resolution of the test, i.e., per second if not configured otherwise.
 pub async fn time_statistics(
     State(state): State<Arc<AppState>>,
     Query(params): Query<Params>,
@@ -351,20 +544,89 @@ pub async fn time_statistics(
     (StatusCode::OK, Json(stats)).into_response()
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/statistics/stream",
+    responses(
+        (status = 200,
+        description = "Server-sent events stream of the statistics. \
+        The first event is a `snapshot` of the statistics and time statistics of all tests. \
+        Afterwards, `update` events contain JSON merge patches (RFC 7386) of the snapshot. \
+        `started`, `stopped`, `duration_elapsed`, and `next_test` events are sent on state transitions of the traffic generation.",
+        content_type = "text/event-stream",
+        body = StatisticsEvent
+        ))
+)]
+/// Streams the statistics and state transitions of the traffic generation as server-sent events.
+pub async fn statistics_stream(
+    State(state): State<Arc<AppState>>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let (receiver, statistics) = subscribe(&state).await;
+    let snapshot = StatisticsEvent::Snapshot { statistics };
+
+    let events = stream::unfold((receiver, state), |(mut receiver, state)| async move {
+        let event = match receiver.recv().await {
+            Ok(event) => event,
+            // the client missed updates and requires a new snapshot
+            Err(RecvError::Lagged(_)) => {
+                let (new_receiver, statistics) = subscribe(&state).await;
+                receiver = new_receiver;
+
+                StatisticsEvent::Snapshot { statistics }
+            }
+            Err(RecvError::Closed) => return None,
+        };
+
+        Some((event, (receiver, state)))
+    });
+
+    let sse_events = stream::once(async { snapshot }).chain(events).map(|event| {
+        Ok(Event::default()
+            .event(event.name())
+            .json_data(&event)
+            .unwrap_or_default())
+    });
+
+    Sse::new(sse_events).keep_alive(KeepAlive::default())
+}
+
 pub async fn get_time_statistics(state: &Arc<AppState>, params: Params) -> Vec<TimeStatisticsApi> {
-    let rate_monitor = &state.rate_monitor;
-    let stats = rate_monitor.lock().await.time_statistics.clone();
+    let mut all_time_stats = vec![current_time_statistics(state, &params, None).await];
 
-    let port_mapping = &state.port_mapping;
+    // previous tests are stored with the time resolution of their test
+    let time_resolutions: Vec<u32> = state
+        .multiple_tests
+        .collected_traffic_gen
+        .lock()
+        .await
+        .iter()
+        .map(|t| t.time_resolution.unwrap_or(DEFAULT_TIME_RESOLUTION))
+        .collect();
+    let previous_time_stats = state.multiple_tests.collected_time_statistics.lock().await;
+
+    all_time_stats.extend(previous_time_stats.iter().enumerate().map(|(i, stats)| {
+        let time_resolution = time_resolutions
+            .get(i)
+            .copied()
+            .unwrap_or(DEFAULT_TIME_RESOLUTION);
+        let elements = stats
+            .tx_rate_l1
+            .values()
+            .flat_map(|channels| channels.values())
+            .map(|series| series.len())
+            .max()
+            .unwrap_or(0);
+
+        stats.downsample(resolution(&params, elements, time_resolution))
+    }));
 
-    let limit = params.limit.unwrap_or(usize::MAX);
+    all_time_stats
+}
 
-    let elements = stats
-        .tx_rate_l1
-        .values()
-        .map(|series| series.len())
-        .max()
-        .unwrap_or(0);
+/// Returns the length of the time slots in ms of which the first element is returned.
+/// The slot length is given by the requested resolution and the limit of elements.
+fn resolution(params: &Params, elements: usize, time_resolution: u32) -> u32 {
+    let limit = params.limit.unwrap_or(usize::MAX).max(1);
 
     let step = {
         if limit < elements {
@@ -374,76 +636,52 @@ pub async fn get_time_statistics(state: &Arc<AppState>, params: Params) -> Vec<T
         }
     };
 
-    // get every ratio-nth element
-    let tx_rate_l1: HashMap<u32, BTreeMap<u32, f64>> = stats
-        .tx_rate_l1
-        .clone()
-        .into_iter()
-        .map(|v| {
-            (
-                v.0,
-                v.1.into_iter()
-                    .filter(|elem| elem.0 % (step as u32) == 0)
-                    .collect(),
-            )
-        })
-        .collect();
+    params
+        .resolution
+        .unwrap_or(time_resolution)
+        .max(time_resolution * step as u32)
+}
 
-    let rx_rate_l1: HashMap<u32, BTreeMap<u32, f64>> = stats
-        .rx_rate_l1
-        .clone()
-        .into_iter()
-        .map(|v| {
-            (
-                v.0,
-                v.1.into_iter()
-                    .filter(|elem| elem.0 % (step as u32) == 0)
-                    .collect(),
-            )
-        })
-        .collect();
+/// Returns the time statistics of the current test.
+/// If `since` is given, only the time slots at or after `since` are included.
+pub(crate) async fn current_time_statistics(
+    state: &Arc<AppState>,
+    params: &Params,
+    since: Option<ElapsedTime>,
+) -> TimeStatisticsApi {
+    let (stats, time_resolution) = {
+        let rate_monitor = state.rate_monitor.lock().await;
+        let stats = match since {
+            Some(since) => rate_monitor.time_statistics.since(since),
+            None => rate_monitor.time_statistics.clone(),
+        };
+        (stats, rate_monitor.time_resolution)
+    };
 
-    let packet_loss: HashMap<u32, BTreeMap<u32, u64>> = stats
-        .packet_loss
-        .clone()
-        .into_iter()
-        .map(|v| {
-            (
-                v.0,
-                v.1.into_iter()
-                    .filter(|elem| elem.0 % (step as u32) == 0)
-                    .collect(),
-            )
-        })
-        .collect();
+    let port_mapping = &state.port_mapping;
 
-    let out_of_order: HashMap<u32, BTreeMap<u32, u64>> = stats
-        .out_of_order
-        .clone()
-        .into_iter()
-        .map(|v| {
-            (
-                v.0,
-                v.1.into_iter()
-                    .filter(|elem| elem.0 % (step as u32) == 0)
-                    .collect(),
-            )
-        })
-        .collect();
+    let elements = stats
+        .tx_rate_l1
+        .values()
+        .map(|series| series.len())
+        .max()
+        .unwrap_or(0);
 
-    let rtt: HashMap<u32, BTreeMap<u32, u64>> = stats
-        .rtt
-        .clone()
-        .into_iter()
-        .map(|v| {
-            (
-                v.0,
-                v.1.into_iter()
-                    .filter(|elem| elem.0 % (step as u32) == 0)
-                    .collect(),
-            )
-        })
-        .collect();
+    // get the first element of each time slot
+    let resolution = resolution(params, elements, time_resolution);
+
+    let tx_rate_l1 = downsample_ports(&stats.tx_rate_l1, resolution);
+    let rx_rate_l1 = downsample_ports(&stats.rx_rate_l1, resolution);
+    let packet_loss = downsample_ports(&stats.packet_loss, resolution);
+    let out_of_order = downsample_ports(&stats.out_of_order, resolution);
+    let rtt = downsample_ports(&stats.rtt, resolution);
+    let app_tx_l2 = downsample_streams(&stats.app_tx_l2, resolution);
+    let app_rx_l2 = downsample_streams(&stats.app_rx_l2, resolution);
+    let app_packet_loss = downsample_streams(&stats.app_packet_loss, resolution);
+    let app_out_of_order = downsample_streams(&stats.app_out_of_order, resolution);
+    let app_rtt = downsample_streams(&stats.app_rtt, resolution);
+    let disruption_time = downsample_ports(&stats.disruption_time, resolution);
+    let app_disruption_time = downsample_streams(&stats.app_disruption_time, resolution);
 
     let name = state.traffic_generator.lock().await.name.clone();
     let new_time_stats = TimeStatistics {
@@ -452,6 +690,14 @@ pub async fn get_time_statistics(state: &Arc<AppState>, params: Params) -> Vec<T
         packet_loss,
         out_of_order,
         rtt,
+        app_tx_l2,
+        app_rx_l2,
+        app_packet_loss,
+        app_out_of_order,
+        app_rtt,
+        disruption_time,
+        app_disruption_time,
+        measurement_window: stats.measurement_window,
         name,
     };
 
@@ -464,15 +710,129 @@ pub async fn get_time_statistics(state: &Arc<AppState>, params: Params) -> Vec<T
 
     // Filter for inactive ports
     let used_ports: HashSet<u32> = get_used_ports(state).await;
-    let new_time_stats = TimeStatisticsApi::filter_inactive_ports(new_time_stats, used_ports);
+    TimeStatisticsApi::filter_inactive_ports(new_time_stats, used_ports)
+}
 
-    let mut all_time_stats = vec![new_time_stats];
-    let previous_time_stats = state
-        .multiple_tests
-        .collected_time_statistics
-        .lock()
-        .await
-        .clone();
-    all_time_stats.extend(previous_time_stats);
-    all_time_stats
+/// Gets the first element of each time slot of length `resolution` ms.
+fn downsample<V: Copy>(
+    series: &BTreeMap<ElapsedTime, V>,
+    resolution: u32,
+) -> BTreeMap<ElapsedTime, V> {
+    let mut downsampled = BTreeMap::new();
+    let mut last_slot = None;
+
+    for (time, value) in series {
+        let slot = time.slot(resolution);
+
+        if last_slot != Some(slot) {
+            downsampled.insert(*time, *value);
+            last_slot = Some(slot);
+        }
+    }
+
+    downsampled
+}
+
+/// Downsamples the time series per port.
+fn downsample_ports<K: Copy + Eq + Hash, V: Copy>(
+    series: &HashMap<K, BTreeMap<ElapsedTime, V>>,
+    resolution: u32,
+) -> HashMap<K, BTreeMap<ElapsedTime, V>> {
+    series
+        .iter()
+        .map(|(port, values)| (*port, downsample(values, resolution)))
+        .collect()
+}
+
+/// Downsamples the time series per port and stream, or per port and channel.
+fn downsample_streams<K: Copy + Eq + Hash, L: Copy + Eq + Hash, V: Copy>(
+    series: &HashMap<K, HashMap<L, BTreeMap<ElapsedTime, V>>>,
+    resolution: u32,
+) -> HashMap<K, HashMap<L, BTreeMap<ElapsedTime, V>>> {
+    series
+        .iter()
+        .map(|(port, per_app)| (*port, downsample_ports(per_app, resolution)))
+        .collect()
+}
+
+/// Downsamples the time series per port, channel and stream.
+#[allow(clippy::type_complexity)]
+fn downsample_channel_streams<V: Copy>(
+    series: &HashMap<u32, HashMap<u8, HashMap<u32, BTreeMap<ElapsedTime, V>>>>,
+    resolution: u32,
+) -> HashMap<u32, HashMap<u8, HashMap<u32, BTreeMap<ElapsedTime, V>>>> {
+    series
+        .iter()
+        .map(|(port, per_channel)| (*port, downsample_streams(per_channel, resolution)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::docs::traffic_gen::EXAMPLE_POST_2_REQUEST;
+    use crate::core::backend::mock::tests::{call, mock_state};
+    use crate::core::backend::SwitchBackend;
+    use crate::core::duration_monitor::DurationMonitorTask;
+    use axum::http::Method;
+
+    #[tokio::test]
+    async fn time_statistics_are_downsampled() {
+        let (switch, state) = mock_state().await;
+
+        let mut request = serde_json::to_value(&*EXAMPLE_POST_2_REQUEST).unwrap();
+        request["time_resolution"] = serde_json::json!(100);
+
+        let (status, _) = call(&state, Method::POST, "/trafficgen", request.to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        request["time_resolution"] = serde_json::json!(500);
+
+        let (status, _) = call(&state, Method::POST, "/trafficgen", request.to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(state.rate_monitor.lock().await.time_resolution, 500);
+
+        let port = switch.dev_port(1, 0).unwrap();
+        state
+            .rate_monitor
+            .lock()
+            .await
+            .time_statistics
+            .tx_rate_l1
+            .insert(
+                port,
+                (0..4).map(|t| (ElapsedTime(t * 500), t as f64)).collect(),
+            );
+
+        let (status, stats) = call(&state, Method::GET, "/time_statistics", String::new()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            stats[0]["tx_rate_l1"]["1"]["0"],
+            serde_json::json!({"0": 0.0, "0.5": 1.0, "1": 2.0, "1.5": 3.0})
+        );
+
+        let uri = "/time_statistics?resolution=1000";
+        let (status, stats) = call(&state, Method::GET, uri, String::new()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            stats[0]["tx_rate_l1"]["1"]["0"],
+            serde_json::json!({"0": 0.0, "1": 2.0})
+        );
+
+        // previous tests of multiple tests are downsampled as well
+        DurationMonitorTask::copy_stats_to_history(&state).await;
+
+        let (status, stats) = call(&state, Method::GET, uri, String::new()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            stats[1]["tx_rate_l1"]["1"]["0"],
+            serde_json::json!({"0": 0.0, "1": 2.0})
+        );
+
+        let (_, stats) = call(&state, Method::GET, "/time_statistics", String::new()).await;
+        assert_eq!(
+            stats[1]["tx_rate_l1"]["1"]["0"],
+            serde_json::json!({"0": 0.0, "0.5": 1.0, "1": 2.0, "1.5": 3.0})
+        );
+    }
 }
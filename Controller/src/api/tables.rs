@@ -102,7 +102,7 @@ pub async fn tables(State(state): State<Arc<AppState>>) -> Response {
 
     // read all table entries
     let all_entries = {
-        let switch = &state.switch;
+        let switch = state.switch.as_ref();
         let mut all_entries: Vec<(String, Vec<TableEntry>)> = vec![];
 
         for t in table_names {
@@ -18,28 +18,38 @@
  */
 
 use crate::api::helper::validate::{validate_multiple_test, validate_request};
+use crate::core::traffic_gen_core::const_definitions::DEFAULT_TIME_RESOLUTION;
+use crate::core::traffic_gen_core::feasibility::analyze_feasibility;
 use crate::core::traffic_gen_core::helper::{
     generate_front_panel_to_dev_port_mappings, translate_fp_channel_to_dev_port_mapping,
 };
+use crate::core::traffic_gen_core::pcap::{create_example_frames, import_pcap, write_pcap};
+use crate::error::P4TGError;
+use axum::body::Bytes;
 use axum::debug_handler;
 use axum::extract::{Query, State};
-use axum::http::StatusCode;
+use axum::http::{header, StatusCode};
 use axum::response::{IntoResponse, Json, Response};
 use log::info;
 use rbfrt::error::RBFRTError;
 use serde::Deserialize;
 use serde_json::json;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::SystemTime;
 
 use crate::api::server::Error;
+use crate::core::archive::{archive_current_test, is_archivable};
+use crate::core::measurement_window::MeasurementWindow;
+use crate::core::sla::SlaMonitor;
 use crate::core::statistics::{Histogram, HistogramPacketPath};
+use crate::core::statistics_stream::StatisticsEvent;
 use crate::AppState;
 
 use crate::api::docs::traffic_gen::{
-    EXAMPLE_GET_1, EXAMPLE_GET_2, EXAMPLE_POST_1_REQUEST, EXAMPLE_POST_1_RESPONSE,
-    EXAMPLE_POST_2_REQUEST, EXAMPLE_POST_3_REQUEST, EXAMPLE_POST_3_RESPONSE,
-    EXAMPLE_POST_4_REQUEST, EXAMPLE_POST_4_RESPONSE,
+    EXAMPLE_GET_1, EXAMPLE_GET_2, EXAMPLE_POST_1_FEASIBILITY, EXAMPLE_POST_1_REQUEST,
+    EXAMPLE_POST_1_RESPONSE, EXAMPLE_POST_2_REQUEST, EXAMPLE_POST_3_REQUEST,
+    EXAMPLE_POST_3_RESPONSE, EXAMPLE_POST_4_REQUEST, EXAMPLE_POST_4_RESPONSE,
 };
 use crate::core::traffic_gen_core::types::*;
 
@@ -76,18 +86,7 @@ pub async fn traffic_gen(State(state): State<Arc<AppState>>) -> Response {
         )
             .into_response()
     } else {
-        let tg_data = TrafficGenData {
-            mode: tg.mode,
-            stream_settings: tg.stream_settings.clone(),
-            streams: tg.streams.clone(),
-            port_tx_rx_mapping: tg.port_mapping.clone(),
-            duration: tg.duration,
-            rtt_histogram_config: Some(tg.rtt_histogram_config.clone()),
-            iat_histogram_config: Some(tg.iat_histogram_config.clone()),
-            name: tg.name.clone(),
-        };
-
-        (StatusCode::OK, Json(tg_data)).into_response()
+        (StatusCode::OK, Json(tg.traffic_gen_data())).into_response()
     }
 }
 
@@ -121,6 +120,10 @@ pub async fn configure_traffic_gen(
     State(state): State<Arc<AppState>>,
     payload: Json<TrafficGenTests>,
 ) -> Response {
+    // Cancel any running RFC 2544 benchmark or Y.1564 test
+    state.rfc2544.task.lock().await.cancel_existing_task().await;
+    state.y1564.task.lock().await.cancel_existing_task().await;
+
     // Cancel any existing duration monitor task
     state
         .monitor_task
@@ -141,6 +144,8 @@ pub async fn configure_traffic_gen(
     stats_lock.clear();
     let mut stats_lock = state.multiple_tests.collected_time_statistics.lock().await;
     stats_lock.clear();
+    let mut traffic_gen_lock = state.multiple_tests.collected_traffic_gen.lock().await;
+    traffic_gen_lock.clear();
 
     let port_mapping = &state.port_mapping;
 
@@ -196,10 +201,15 @@ pub async fn configure_traffic_gen(
     }
 }
 
-pub async fn start_single_test(
-    state: &Arc<AppState>,
-    payload: TrafficGenData,
-) -> Result<Vec<Stream>, RBFRTError> {
+/// Translates the front panel ports of a test into dev ports and filters the active streams.
+///
+/// # Returns
+///
+/// Returns the active streams, the active stream settings, and the mapping of TX dev port to RX dev port.
+pub(crate) fn active_test_configuration(
+    state: &AppState,
+    payload: &TrafficGenData,
+) -> (Vec<Stream>, Vec<StreamSetting>, HashMap<String, u32>) {
     let port_mapping = &state.port_mapping;
 
     let front_panel_dev_port_mappings =
@@ -237,6 +247,19 @@ pub async fn start_single_test(
         &front_panel_dev_port_mappings,
     );
 
+    (active_streams, active_stream_settings, tx_rx_port_mapping)
+}
+
+pub async fn start_single_test(
+    state: &Arc<AppState>,
+    payload: TrafficGenData,
+) -> Result<Vec<Stream>, RBFRTError> {
+    let front_panel_dev_port_mappings =
+        generate_front_panel_to_dev_port_mappings(&state.port_mapping, state.tofino2);
+
+    let (active_streams, active_stream_settings, tx_rx_port_mapping) =
+        active_test_configuration(state, &payload);
+
     // Clear RTT histogram config state and release lock when out of scope
     {
         let mut histogram_configs = state.rtt_histogram_monitor.lock().await;
@@ -266,6 +289,7 @@ pub async fn start_single_test(
                 Histogram {
                     config: config.clone(),
                     data: HistogramPacketPath::default(),
+                    calibration: None,
                 },
             );
 
@@ -280,6 +304,7 @@ pub async fn start_single_test(
                     Histogram {
                         config,
                         data: HistogramPacketPath::default(),
+                        calibration: None,
                     },
                 );
             }
@@ -318,6 +343,7 @@ pub async fn start_single_test(
                 Histogram {
                     config,
                     data: HistogramPacketPath::default(),
+                    calibration: None,
                 },
             );
         }
@@ -331,6 +357,16 @@ pub async fn start_single_test(
         }
     }
 
+    // Calculate the RTT percentiles that are required by the acceptance criteria
+    if let Some(sla) = &payload.sla {
+        let percentiles = sla.rtt_percentiles();
+        let histogram_monitor = &mut state.rtt_histogram_monitor.lock().await;
+
+        for histogram in histogram_monitor.histogram.values_mut() {
+            histogram.config.add_percentiles(&percentiles);
+        }
+    }
+
     let tg = &mut state.traffic_generator.lock().await;
 
     match tg
@@ -353,7 +389,27 @@ pub async fn start_single_test(
             tg.iat_histogram_config = payload.iat_histogram_config.unwrap_or_default();
             tg.mode = payload.mode;
             tg.duration = payload.duration;
-            tg.name = payload.name;
+            tg.name = payload.name.clone();
+            tg.sla = payload.sla.clone();
+            tg.time_resolution = payload.time_resolution;
+            tg.warmup = payload.warmup;
+            tg.cooldown = payload.cooldown;
+
+            state.sla_monitor.lock().await.start(payload.sla.as_ref());
+            {
+                let rate_monitor = &mut state.rate_monitor.lock().await;
+                rate_monitor.time_resolution =
+                    payload.time_resolution.unwrap_or(DEFAULT_TIME_RESOLUTION);
+                // the generated streams are the active streams with normalized frame sizes
+                rate_monitor
+                    .disruption
+                    .configure(&tx_rx_port_mapping, &streams);
+                rate_monitor.configure_window(MeasurementWindow::new(
+                    payload.warmup,
+                    payload.cooldown,
+                    payload.duration,
+                ));
+            }
 
             // experiment starts now
             // these values are used to show how long the experiment is running at the GUI
@@ -368,12 +424,201 @@ pub async fn start_single_test(
             }
 
             info!("Traffic generation started.");
+            state
+                .events
+                .emit(StatisticsEvent::Started { name: payload.name });
             Ok(streams)
         }
         Err(err) => Err(err),
     }
 }
 
+/// Method called on POST /trafficgen/plan
+/// Computes the table entries and multicast groups of the traffic generation in the POST body
+/// without configuring the switch.
+#[utoipa::path(
+    post,
+    path = "/api/trafficgen/plan",
+    request_body(
+        content = TrafficGenData,
+        examples(("Example 1" = (summary = "VxLAN 1024 (+50) byte @ 100 Gbps", value = json!(*EXAMPLE_POST_1_REQUEST))),
+                 ("Example 2" = (summary = "VLAN 64 (+4) byte @ 80 Gbps", value = json!(*EXAMPLE_POST_2_REQUEST))),
+                 ("Example 3" = (summary = "Poisson @ 30 Gbps", value = json!(*EXAMPLE_POST_3_REQUEST)))
+        )
+    ),
+    responses(
+    (status = 200,
+    description = "Returns the table entries, multicast groups, and buffer offsets that would be configured.",
+    body = TrafficGenPlan),
+    (status = 400,
+    description = "Returned when the traffic generation is invalid.")
+    )
+)]
+pub async fn plan_traffic_gen(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<TrafficGenData>,
+) -> Response {
+    let tg = state.traffic_generator.lock().await;
+
+    if let Err(e) = validate_request(&payload, &state.port_mapping, tg.is_tofino2) {
+        return (StatusCode::BAD_REQUEST, Json(e)).into_response();
+    }
+
+    let (active_streams, active_stream_settings, tx_rx_port_mapping) =
+        active_test_configuration(&state, &payload);
+
+    match tg
+        .plan_traffic_generation(
+            &state.port_mapping,
+            active_streams,
+            payload.mode,
+            &active_stream_settings,
+            &tx_rx_port_mapping,
+        )
+        .await
+    {
+        Ok(plan) => (StatusCode::OK, Json(plan)).into_response(),
+        Err(e) => {
+            let message = match e {
+                RBFRTError::GenericError { message } => message,
+                _ => e.to_string(),
+            };
+
+            (StatusCode::BAD_REQUEST, Json(json!({ "message": message }))).into_response()
+        }
+    }
+}
+
+/// Method called on POST /trafficgen/feasibility
+/// Analyzes if the traffic generation in the POST body can be generated as configured
+/// without configuring the switch.
+#[utoipa::path(
+    post,
+    path = "/api/trafficgen/feasibility",
+    request_body(
+        content = TrafficGenData,
+        examples(("Example 1" = (summary = "VxLAN 1024 (+50) byte @ 100 Gbps", value = json!(*EXAMPLE_POST_1_REQUEST))),
+                 ("Example 2" = (summary = "VLAN 64 (+4) byte @ 80 Gbps", value = json!(*EXAMPLE_POST_2_REQUEST))),
+                 ("Example 3" = (summary = "Poisson @ 30 Gbps", value = json!(*EXAMPLE_POST_3_REQUEST)))
+        )
+    ),
+    responses(
+    (status = 200,
+    description = "Returns the L1 load, predicted generation accuracy, and pipe split of each stream, the load of each port, and the packet buffer usage.",
+    body = FeasibilityReport,
+    example = json!(*EXAMPLE_POST_1_FEASIBILITY)),
+    (status = 400,
+    description = "Returned when the traffic generation is invalid.")
+    )
+)]
+pub async fn traffic_gen_feasibility(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<TrafficGenData>,
+) -> Response {
+    let is_tofino2 = state.traffic_generator.lock().await.is_tofino2;
+
+    if let Err(e) = validate_request(&payload, &state.port_mapping, is_tofino2) {
+        return (StatusCode::BAD_REQUEST, Json(e)).into_response();
+    }
+
+    (
+        StatusCode::OK,
+        Json(feasibility_report(&state, &payload).await),
+    )
+        .into_response()
+}
+
+/// Analyzes the feasibility of the traffic generation `payload` with the configured port speeds.
+async fn feasibility_report(state: &AppState, payload: &TrafficGenData) -> FeasibilityReport {
+    // (front panel port, channel) -> speed in Gbps
+    let port_speeds: HashMap<(u32, u8), f64> = state
+        .switch
+        .get_ports()
+        .await
+        .unwrap_or_default()
+        .iter()
+        .map(|p| (p.get_frontpanel_port(), p.get_speed().to_u32() as f64))
+        .collect();
+
+    let tg = state.traffic_generator.lock().await;
+
+    analyze_feasibility(payload, &port_speeds, tg.is_tofino2, tg.num_pipes)
+}
+
+/// Method called on GET /trafficgen/pcap
+/// Returns a pcap file with one example frame per active (stream, port) pair of the configured traffic generation.
+/// The headers of the frames are rewritten as done by the data plane.
+#[utoipa::path(
+    get,
+    path = "/api/trafficgen/pcap",
+    responses(
+    (status = 200,
+    description = "Returns the example frames as pcap file.",
+    content_type = "application/vnd.tcpdump.pcap"),
+    (status = 202, description = "Returned when no traffic generation is configured.", body = EmptyResponse),
+    (status = 400, description = "Returned when the stream settings are invalid.")
+    )
+)]
+pub async fn traffic_gen_pcap(State(state): State<Arc<AppState>>) -> Response {
+    let tg = state.traffic_generator.lock().await;
+
+    if tg.streams.is_empty() {
+        return (
+            StatusCode::ACCEPTED,
+            Json(EmptyResponse {
+                message: "No traffic generation configured.".to_string(),
+            }),
+        )
+            .into_response();
+    }
+
+    match create_example_frames(&tg.streams, &tg.stream_settings) {
+        Ok(frames) => (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, "application/vnd.tcpdump.pcap"),
+                (
+                    header::CONTENT_DISPOSITION,
+                    "attachment; filename=\"p4tg.pcap\"",
+                ),
+            ],
+            write_pcap(&frames),
+        )
+            .into_response(),
+        Err(P4TGError::Error { message }) => {
+            (StatusCode::BAD_REQUEST, Json(json!({ "message": message }))).into_response()
+        }
+    }
+}
+
+/// Method called on POST /trafficgen/import
+/// Creates a traffic generation from the frames of the pcap file in the request body.
+/// The traffic generation is returned and not started.
+#[utoipa::path(
+    post,
+    path = "/api/trafficgen/import",
+    params(
+        ("port" = Option<u32>, Query, description = "Front panel port on which the imported streams are sent. Defaults to 1."),
+        ("rx_port" = Option<u32>, Query, description = "Front panel port on which the imported streams are received. Defaults to port."),
+        ("traffic_rate" = Option<f32>, Query, description = "Traffic rate in Gbps of each imported stream. Defaults to 1.")
+    ),
+    request_body(content = Vec<u8>, content_type = "application/vnd.tcpdump.pcap"),
+    responses(
+    (status = 200,
+    description = "Returns the imported traffic generation and the headers that could not be represented.",
+    body = PcapImport),
+    (status = 400, description = "Returned when the pcap file is invalid.")
+    )
+)]
+pub async fn traffic_gen_import(Query(options): Query<PcapImportOptions>, body: Bytes) -> Response {
+    match import_pcap(&body, &options) {
+        Ok(import) => (StatusCode::OK, Json(import)).into_response(),
+        Err(P4TGError::Error { message }) => {
+            (StatusCode::BAD_REQUEST, Json(json!({ "message": message }))).into_response()
+        }
+    }
+}
+
 #[utoipa::path(
     delete,
     path = "/api/trafficgen",
@@ -389,8 +634,31 @@ pub async fn stop_traffic_gen(
     State(state): State<Arc<AppState>>,
     Query(params): Query<StopTrafficGenParams>,
 ) -> Response {
+    match stop_test(&state, params.skip.unwrap_or(false)).await {
+        Ok(_) => StatusCode::OK.into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(Error::new(format!("{err:#?}"))),
+        )
+            .into_response(),
+    }
+}
+
+/// Stops the current traffic generation and archives the test.
+///
+/// - `skip_current_test`: If true, only the current test is stopped and the next test of multiple tests is started.
+///   Otherwise, multiple tests, RFC 2544 benchmarks, and Y.1564 tests are cancelled as well.
+pub(crate) async fn stop_test(
+    state: &Arc<AppState>,
+    skip_current_test: bool,
+) -> Result<(), RBFRTError> {
     let tg = &state.traffic_generator;
-    let switch = &state.switch;
+
+    // the state of the test has to be checked before the tasks that run it are cancelled
+    let archive = is_archivable(state).await;
+
+    // the acceptance criteria are evaluated a last time on the final statistics
+    SlaMonitor::evaluate(state).await;
 
     // Cancel any existing duration monitor task
     state
@@ -400,8 +668,6 @@ pub async fn stop_traffic_gen(
         .cancel_existing_monitoring_task()
         .await;
 
-    let skip_current_test = params.skip.unwrap_or(false);
-
     if !skip_current_test {
         // Cancel the multiple test monitor task if skip is set to false
         state
@@ -411,18 +677,101 @@ pub async fn stop_traffic_gen(
             .await
             .cancel_existing_monitoring_task()
             .await;
+        // as well as any running RFC 2544 benchmark or Y.1564 test
+        state.rfc2544.task.lock().await.cancel_existing_task().await;
+        state.y1564.task.lock().await.cancel_existing_task().await;
+    }
+
+    let mut tg = tg.lock().await;
+
+    tg.stop_traffic_generation(state).await?;
+
+    info!("Traffic generation stopped.");
+    state.experiment.lock().await.running = false;
+    state.events.emit(StatisticsEvent::Stopped {
+        name: tg.name.clone(),
+    });
+
+    drop(tg);
+
+    if archive {
+        archive_current_test(state).await;
     }
 
-    match tg.lock().await.stop(switch).await {
-        Ok(_) => {
-            info!("Traffic generation stopped.");
-            state.experiment.lock().await.running = false;
-            StatusCode::OK.into_response()
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::docs::traffic_gen::EXAMPLE_POST_2_REQUEST;
+    use crate::core::backend::mock::tests::{call, mock_state};
+    use crate::core::traffic_gen_core::const_definitions::APP_CFG;
+    use axum::http::{Method, StatusCode};
+
+    #[tokio::test]
+    async fn plan_does_not_touch_switch() {
+        let (switch, state) = mock_state().await;
+        let num_operations = switch.operations().len();
+
+        let body = serde_json::to_string(&*EXAMPLE_POST_2_REQUEST).unwrap();
+        let (status, plan) = call(&state, Method::POST, "/trafficgen/plan", body).await;
+        assert_eq!(status, StatusCode::OK);
+
+        assert_eq!(switch.operations().len(), num_operations);
+        assert!(!state.traffic_generator.lock().await.running);
+
+        assert_eq!(plan["streams"][0]["app_id"], 1);
+        assert!(plan["streams"][0]["n_packets"].as_u64().unwrap() > 0);
+        assert!(plan["buffer_offsets"]["1"].as_u64().unwrap() % 16 == 0);
+
+        let entries = plan["entries"].as_array().unwrap();
+        let app_cfg = entries
+            .iter()
+            .find(|e| e["table"] == APP_CFG && e["match_keys"]["app_id"] == "0x01")
+            .unwrap();
+        assert_eq!(app_cfg["operation"], "update");
+        assert_eq!(app_cfg["action_data"]["app_enable"], "0x01");
+        assert!(entries.iter().any(|e| e["table"] == "$pre.mgid"));
+        assert!(entries
+            .iter()
+            .any(|e| e["action"] == "egress.header_replace.rewrite_vlan"));
+
+        // the plan contains the same entries that are written on POST /trafficgen
+        let body = serde_json::to_string(&*EXAMPLE_POST_2_REQUEST).unwrap();
+        call(&state, Method::POST, "/trafficgen", body).await;
+        for entry in entries.iter().filter(|e| e["table"] != APP_CFG) {
+            let table = entry["table"].as_str().unwrap();
+            assert!(!switch.entries(table).is_empty(), "{table} not written");
         }
-        Err(err) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(Error::new(format!("{err:#?}"))),
+    }
+
+    #[tokio::test]
+    async fn feasibility_is_reported() {
+        let (switch, state) = mock_state().await;
+        let num_operations = switch.operations().len();
+
+        let body = serde_json::to_string(&*EXAMPLE_POST_2_REQUEST).unwrap();
+        let (status, report) = call(
+            &state,
+            Method::POST,
+            "/trafficgen/feasibility",
+            body.clone(),
         )
-            .into_response(),
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(switch.operations().len(), num_operations);
+
+        assert_eq!(report["feasible"], true);
+        assert_eq!(report["streams"][0]["n_pipes"], 2);
+        assert_eq!(report["streams"][0]["l1_frame_size"], 64 + 4 + 20);
+        assert_eq!(report["ports"][0]["port"], 1);
+        assert_eq!(report["ports"][0]["capacity"], 100.0);
+        assert_eq!(report["ports"][0]["load"], 80.0);
+        assert!(report["buffer"]["used"].as_u64().unwrap() % 16 == 0);
+
+        // the response of POST /trafficgen is the list of configured streams
+        let (status, streams) = call(&state, Method::POST, "/trafficgen", body).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(streams[0]["n_packets"], report["streams"][0]["n_packets"]);
     }
 }
@@ -0,0 +1,131 @@
+/* Copyright 2022-present University of Tuebingen, Chair of Communication Networks
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/*
+ * Steffen Lindner (steffen.lindner@uni-tuebingen.de)
+ */
+
+use crate::api::helper::validate::validate_request;
+use crate::api::server::Error;
+use crate::core::y1564::{Y1564Config, Y1564Result};
+use crate::AppState;
+use axum::debug_handler;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Json, Response};
+use std::sync::Arc;
+
+/// Method called on GET /y1564
+/// Returns the state and report of the current or last Y.1564 service activation test
+#[debug_handler]
+#[utoipa::path(
+    get,
+    path = "/api/y1564",
+    responses(
+    (status = 200,
+    description = "Returns the state and the pass/fail report per service of the current or last Y.1564 service activation test.",
+    body = Y1564Result))
+)]
+pub async fn y1564(State(state): State<Arc<AppState>>) -> Response {
+    let result = state.y1564.result.lock().await.clone();
+
+    (StatusCode::OK, Json(result)).into_response()
+}
+
+/// Method called on POST /y1564
+/// Starts a Y.1564 service activation test. Stops any running traffic generation.
+#[debug_handler]
+#[utoipa::path(
+    post,
+    path = "/api/y1564",
+    request_body = Y1564Config,
+    responses(
+    (status = 200,
+    description = "The test has been started.",
+    body = Y1564Result),
+    (status = 400,
+    description = "Returned when the test configuration is invalid."))
+)]
+pub async fn start_y1564(
+    State(state): State<Arc<AppState>>,
+    Json(config): Json<Y1564Config>,
+) -> Response {
+    if let Err(message) = config.validate() {
+        return (StatusCode::BAD_REQUEST, Json(Error::new(message))).into_response();
+    }
+
+    let is_tofino2 = state.traffic_generator.lock().await.is_tofino2;
+
+    // the highest step of each service and the performance test with all services
+    let mut trials = vec![config.performance_trial()];
+
+    for service in &config.services {
+        if let Some((_, rate)) = config.ramp(service).last() {
+            trials.push(config.trial(&[(service.stream_id, *rate)], config.step_duration));
+        }
+    }
+
+    for trial in &trials {
+        if let Err(e) = validate_request(trial, &state.port_mapping, is_tofino2) {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(Error::new(format!("Invalid configuration: {}", e.message))),
+            )
+                .into_response();
+        }
+    }
+
+    // Cancel any running benchmark, test, and duration monitor task
+    state.y1564.task.lock().await.cancel_existing_task().await;
+    state.rfc2544.task.lock().await.cancel_existing_task().await;
+    state
+        .multiple_tests
+        .multiple_test_monitor_task
+        .lock()
+        .await
+        .cancel_existing_monitoring_task()
+        .await;
+    state
+        .monitor_task
+        .lock()
+        .await
+        .cancel_existing_monitoring_task()
+        .await;
+
+    state.y1564.task.lock().await.start(&state, config).await;
+
+    let result = state.y1564.result.lock().await.clone();
+
+    (StatusCode::OK, Json(result)).into_response()
+}
+
+/// Method called on DELETE /y1564
+/// Aborts the running Y.1564 service activation test
+#[debug_handler]
+#[utoipa::path(
+    delete,
+    path = "/api/y1564",
+    responses(
+    (status = 200,
+    description = "Aborts the running test and returns its report.",
+    body = Y1564Result))
+)]
+pub async fn stop_y1564(State(state): State<Arc<AppState>>) -> Response {
+    state.y1564.task.lock().await.cancel_existing_task().await;
+
+    let result = state.y1564.result.lock().await.clone();
+
+    (StatusCode::OK, Json(result)).into_response()
+}
@@ -0,0 +1,544 @@
+/* Copyright 2022-present University of Tuebingen, Chair of Communication Networks
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/*
+ * Steffen Lindner (steffen.lindner@uni-tuebingen.de)
+ */
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+use crate::api::online::Asic;
+use crate::api::statistics::{get_statistics, get_time_statistics, Params};
+use crate::core::traffic_gen_core::types::TrafficGenData;
+use crate::AppState;
+
+/// Metadata of an archived test
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct ArchiveMetadata {
+    /// Name of the test
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Start of the test in milliseconds since the UNIX epoch
+    pub start: u64,
+    /// Duration of the test in seconds
+    pub duration: f64,
+    /// Version of P4TG that executed the test
+    pub version: String,
+    /// ASIC on which the test was executed
+    pub asic: Asic,
+}
+
+/// A finished test that is persisted in the archive
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct ArchiveRecord {
+    /// Identifies the record in the archive
+    pub id: String,
+    /// Tags that are assigned to the record via the REST API
+    pub tags: Vec<String>,
+    pub metadata: ArchiveMetadata,
+    /// Traffic generation of the test
+    pub traffic_gen: TrafficGenData,
+    /// Final statistics of the test, including the RTT and IAT histograms, as returned by `GET /api/statistics`
+    #[schema(value_type = crate::api::statistics::StatisticsApi)]
+    pub statistics: Value,
+    /// Time statistics of the test as returned by `GET /api/time_statistics`
+    #[schema(value_type = crate::api::statistics::TimeStatisticsApi)]
+    pub time_statistics: Value,
+}
+
+/// Summary of an archived test without its traffic generation and statistics
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct ArchiveSummary {
+    pub id: String,
+    pub tags: Vec<String>,
+    pub metadata: ArchiveMetadata,
+}
+
+impl From<&ArchiveRecord> for ArchiveSummary {
+    fn from(record: &ArchiveRecord) -> Self {
+        ArchiveSummary {
+            id: record.id.clone(),
+            tags: record.tags.clone(),
+            metadata: record.metadata.clone(),
+        }
+    }
+}
+
+/// Persists finished tests as JSON files in a directory.
+/// Each record is stored in the file `<id>.json`, its summary in the file `<id>.summary.json`.
+/// The summaries are used to list the archive without reading the statistics of all records.
+pub struct Archive {
+    dir: PathBuf,
+}
+
+impl Archive {
+    pub fn new(dir: impl Into<PathBuf>) -> Archive {
+        Archive { dir: dir.into() }
+    }
+
+    fn path(&self, id: &str) -> Option<PathBuf> {
+        // ids are used as file names and must not escape the archive directory
+        let valid = !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+
+        valid.then(|| self.dir.join(format!("{id}.json")))
+    }
+
+    fn summary_path(&self, id: &str) -> Option<PathBuf> {
+        self.path(id)
+            .map(|path| path.with_extension("summary.json"))
+    }
+
+    fn write(&self, record: &ArchiveRecord) -> io::Result<()> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidInput, "Invalid id.");
+        let path = self.path(&record.id).ok_or_else(invalid)?;
+        let summary_path = self.summary_path(&record.id).ok_or_else(invalid)?;
+
+        fs::write(path, serde_json::to_vec(record)?)?;
+        fs::write(
+            summary_path,
+            serde_json::to_vec(&ArchiveSummary::from(record))?,
+        )
+    }
+
+    /// Stores a new record and returns its id.
+    /// The id is derived from the start of the test.
+    pub fn store(
+        &self,
+        metadata: ArchiveMetadata,
+        traffic_gen: TrafficGenData,
+        statistics: Value,
+        time_statistics: Value,
+    ) -> io::Result<String> {
+        fs::create_dir_all(&self.dir)?;
+
+        let mut id = metadata.start.to_string();
+        let mut n = 1;
+
+        while self.dir.join(format!("{id}.json")).exists() {
+            id = format!("{}-{n}", metadata.start);
+            n += 1;
+        }
+
+        self.write(&ArchiveRecord {
+            id: id.clone(),
+            tags: vec![],
+            metadata,
+            traffic_gen,
+            statistics,
+            time_statistics,
+        })?;
+
+        Ok(id)
+    }
+
+    /// Returns the record with `id`, or `None` if it does not exist.
+    pub fn get(&self, id: &str) -> io::Result<Option<ArchiveRecord>> {
+        let Some(path) = self.path(id) else {
+            return Ok(None);
+        };
+
+        match fs::read(path) {
+            Ok(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the summary of the record with `id`, or `None` if it does not exist.
+    /// The summary is created from the record if it is missing, e.g., for records of previous versions.
+    fn summary(&self, id: &str) -> io::Result<Option<ArchiveSummary>> {
+        let Some(path) = self.summary_path(id) else {
+            return Ok(None);
+        };
+
+        match fs::read(&path) {
+            Ok(data) => return Ok(Some(serde_json::from_slice(&data)?)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        let Some(record) = self.get(id)? else {
+            return Ok(None);
+        };
+        let summary = ArchiveSummary::from(&record);
+
+        if let Err(e) = serde_json::to_vec(&summary)
+            .map_err(io::Error::from)
+            .and_then(|data| fs::write(&path, data))
+        {
+            error!("Could not write archive summary {}: {e}", path.display());
+        }
+
+        Ok(Some(summary))
+    }
+
+    /// Returns the summaries of all records ordered by their start.
+    /// Files that are not valid records are skipped.
+    pub fn list(&self) -> io::Result<Vec<ArchiveSummary>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
+
+        let mut summaries = vec![];
+
+        for entry in entries {
+            let path = entry?.path();
+
+            // summaries are named <id>.summary.json and are skipped as ids do not contain dots
+            let Some(id) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".json"))
+                .filter(|id| !id.contains('.'))
+            else {
+                continue;
+            };
+
+            match self.summary(id) {
+                Ok(Some(summary)) => summaries.push(summary),
+                Ok(None) => {}
+                Err(e) => error!("Invalid archive record {}: {e}", path.display()),
+            }
+        }
+
+        summaries.sort_by(|a, b| {
+            a.metadata
+                .start
+                .cmp(&b.metadata.start)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+
+        Ok(summaries)
+    }
+
+    /// Replaces the tags of the record with `id`.
+    /// Returns the summary of the updated record, or `None` if it does not exist.
+    pub fn set_tags(&self, id: &str, tags: Vec<String>) -> io::Result<Option<ArchiveSummary>> {
+        let Some(mut record) = self.get(id)? else {
+            return Ok(None);
+        };
+
+        let mut tags = tags;
+        tags.sort();
+        tags.dedup();
+        record.tags = tags;
+
+        self.write(&record)?;
+
+        Ok(Some(ArchiveSummary::from(&record)))
+    }
+
+    /// Deletes the record with `id`.
+    /// Returns false if it does not exist.
+    pub fn delete(&self, id: &str) -> io::Result<bool> {
+        let (Some(path), Some(summary_path)) = (self.path(id), self.summary_path(id)) else {
+            return Ok(false);
+        };
+
+        match fs::remove_file(summary_path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+
+        match fs::remove_file(path) {
+            Ok(_) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+impl Archive {
+    /// Creates an empty archive in the temporary directory of the system.
+    /// Each call creates a new archive, so that tests do not interfere.
+    pub(crate) fn temporary(name: &str) -> Archive {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let dir = std::env::temp_dir().join(format!(
+            "p4tg-archive-{name}-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+
+        Archive::new(dir)
+    }
+}
+
+/// Runs `f` on the archive in a thread that may block.
+/// Archive accesses are serialized by the lock of the archive.
+pub async fn with_archive<T: Send + 'static>(
+    state: &Arc<AppState>,
+    f: impl FnOnce(&Archive) -> io::Result<T> + Send + 'static,
+) -> io::Result<T> {
+    let state = Arc::clone(state);
+
+    tokio::task::spawn_blocking(move || {
+        let archive = state.archive.lock().unwrap_or_else(|e| e.into_inner());
+
+        f(&archive)
+    })
+    .await
+    .map_err(io::Error::other)?
+}
+
+/// Returns true if a test is running that should be archived when it is finished.
+///
+/// Trials of RFC 2544 benchmarks and Y.1564 tests are not archived, their results are reported by their own endpoints.
+pub async fn is_archivable(state: &Arc<AppState>) -> bool {
+    state.experiment.lock().await.running
+        && !state.rfc2544.result.lock().await.running
+        && !state.y1564.result.lock().await.running
+}
+
+/// Stores the current test with its final statistics in the archive.
+pub async fn archive_current_test(state: &Arc<AppState>) {
+    let traffic_gen = state.traffic_generator.lock().await.traffic_gen_data();
+    let start = state.experiment.lock().await.start;
+
+    let statistics = get_statistics(state).await.into_iter().next();
+    let time_statistics = get_time_statistics(
+        state,
+        Params {
+            limit: None,
+            resolution: None,
+        },
+    )
+    .await
+    .into_iter()
+    .next();
+
+    let metadata = ArchiveMetadata {
+        name: traffic_gen.name.clone(),
+        start: start
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64,
+        duration: SystemTime::now()
+            .duration_since(start)
+            .unwrap_or_default()
+            .as_secs_f64(),
+        version: env!("CARGO_PKG_VERSION").to_owned(),
+        asic: if state.tofino2 {
+            Asic::Tofino2
+        } else {
+            Asic::Tofino1
+        },
+    };
+
+    let statistics = serde_json::to_value(statistics).unwrap_or_default();
+    let time_statistics = serde_json::to_value(time_statistics).unwrap_or_default();
+
+    let result = with_archive(state, move |archive| {
+        archive.store(metadata, traffic_gen, statistics, time_statistics)
+    })
+    .await;
+
+    match result {
+        Ok(id) => info!("Test archived as {id}."),
+        Err(e) => error!("Error while archiving test: {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::docs::traffic_gen::EXAMPLE_POST_2_REQUEST;
+    use crate::core::backend::mock::tests::{call, mock_state};
+    use crate::core::traffic_gen_core::types::GenerationMode;
+    use axum::http::{Method, StatusCode};
+    use std::collections::HashMap;
+
+    fn store(archive: &Archive, start: u64) -> String {
+        let metadata = ArchiveMetadata {
+            name: Some("Test".to_owned()),
+            start,
+            duration: 10f64,
+            version: "2.7.1".to_owned(),
+            asic: Asic::Tofino1,
+        };
+        let traffic_gen = TrafficGenData {
+            mode: GenerationMode::Cbr,
+            stream_settings: vec![],
+            streams: vec![],
+            port_tx_rx_mapping: HashMap::new(),
+            duration: Some(10),
+            rtt_histogram_config: None,
+            iat_histogram_config: None,
+            name: Some("Test".to_owned()),
+            sla: None,
+            time_resolution: None,
+            warmup: None,
+            cooldown: None,
+        };
+
+        archive
+            .store(metadata, traffic_gen, Value::Null, Value::Null)
+            .unwrap()
+    }
+
+    #[test]
+    fn records_are_stored_tagged_and_deleted() {
+        let archive = Archive::temporary("records");
+
+        let second = store(&archive, 2000);
+        let first = store(&archive, 1000);
+        let duplicate = store(&archive, 1000);
+
+        assert_eq!(duplicate, "1000-1");
+        assert_eq!(
+            archive
+                .list()
+                .unwrap()
+                .into_iter()
+                .map(|r| r.id)
+                .collect::<Vec<_>>(),
+            vec![first.clone(), duplicate, second]
+        );
+
+        let record = archive
+            .set_tags(&first, vec!["b".to_owned(), "a".to_owned(), "b".to_owned()])
+            .unwrap()
+            .unwrap();
+        assert_eq!(record.tags, vec!["a", "b"]);
+        assert_eq!(archive.get(&first).unwrap().unwrap().tags, vec!["a", "b"]);
+
+        assert!(archive.delete(&first).unwrap());
+        assert!(!archive.delete(&first).unwrap());
+        assert!(archive.get(&first).unwrap().is_none());
+
+        let _ = fs::remove_dir_all(&archive.dir);
+    }
+
+    #[test]
+    fn ids_cannot_escape_the_archive() {
+        let archive = Archive::temporary("escape");
+
+        assert!(archive.get("../config").unwrap().is_none());
+        assert!(!archive.delete("../config").unwrap());
+        assert!(archive.set_tags("..", vec![]).unwrap().is_none());
+    }
+
+    #[test]
+    fn records_are_listed_from_their_summaries() {
+        let archive = Archive::temporary("summaries");
+
+        let id = store(&archive, 1000);
+        let previous = store(&archive, 2000);
+
+        // the statistics of the records are not read
+        fs::write(archive.path(&id).unwrap(), "{}").unwrap();
+        // records of previous versions do not have a summary
+        fs::remove_file(archive.summary_path(&previous).unwrap()).unwrap();
+
+        let summaries = archive.list().unwrap();
+        assert_eq!(
+            summaries.iter().map(|s| &s.id).collect::<Vec<_>>(),
+            vec![&id, &previous]
+        );
+        assert_eq!(summaries[1].metadata.name.as_deref(), Some("Test"));
+        assert!(archive.summary_path(&previous).unwrap().exists());
+
+        assert!(archive.delete(&previous).unwrap());
+        assert!(!archive.summary_path(&previous).unwrap().exists());
+
+        let _ = fs::remove_dir_all(&archive.dir);
+    }
+
+    #[tokio::test]
+    async fn stopped_tests_are_archived() {
+        let (_, state) = mock_state().await;
+
+        let (status, _) = call(
+            &state,
+            Method::POST,
+            "/trafficgen",
+            serde_json::to_string(&*EXAMPLE_POST_2_REQUEST).unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, _) = call(&state, Method::DELETE, "/trafficgen", String::new()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, archive) = call(&state, Method::GET, "/archive", String::new()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(archive.as_array().unwrap().len(), 1);
+        assert_eq!(archive[0]["metadata"]["asic"], "Tofino1");
+
+        let id = archive[0]["id"].as_str().unwrap().to_owned();
+
+        let (status, record) = call(
+            &state,
+            Method::GET,
+            &format!("/archive/{id}"),
+            String::new(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            record["traffic_gen"]["streams"].as_array().unwrap().len(),
+            EXAMPLE_POST_2_REQUEST.streams.len()
+        );
+        assert!(record["statistics"]["rtt_histogram"].is_object());
+        assert!(record["time_statistics"]["tx_rate_l1"].is_object());
+
+        let (status, summary) = call(
+            &state,
+            Method::POST,
+            &format!("/archive/{id}/tags"),
+            r#"["baseline"]"#.to_owned(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(summary["tags"], serde_json::json!(["baseline"]));
+
+        let (_, archive) = call(&state, Method::GET, "/archive?tag=baseline", String::new()).await;
+        assert_eq!(archive.as_array().unwrap().len(), 1);
+        let (_, archive) = call(&state, Method::GET, "/archive?tag=other", String::new()).await;
+        assert_eq!(archive.as_array().unwrap().len(), 0);
+
+        let (status, _) = call(
+            &state,
+            Method::DELETE,
+            &format!("/archive/{id}"),
+            String::new(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call(
+            &state,
+            Method::GET,
+            &format!("/archive/{id}"),
+            String::new(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
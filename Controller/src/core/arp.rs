@@ -21,10 +21,11 @@ use log::info;
 use macaddr::MacAddr;
 use std::collections::HashMap;
 
+use crate::core::backend::SwitchBackend;
 use crate::PortMapping;
 use rbfrt::error::RBFRTError;
+use rbfrt::table;
 use rbfrt::table::MatchValue;
-use rbfrt::{table, SwitchConnection};
 
 const ARP_REPLY_TABLE: &str = "ingress.arp.arp_reply";
 
@@ -41,7 +42,7 @@ impl Arp {
 
     pub async fn init(
         &self,
-        switch: &SwitchConnection,
+        switch: &dyn SwitchBackend,
         port_mapping: &HashMap<u32, PortMapping>,
     ) -> Result<(), RBFRTError> {
         switch.clear_table(ARP_REPLY_TABLE).await?;
@@ -71,7 +72,7 @@ impl Arp {
 
     pub async fn modify_arp(
         &self,
-        switch: &SwitchConnection,
+        switch: &dyn SwitchBackend,
         ports: &[PortMapping],
         active: bool,
         mac: MacAddr,
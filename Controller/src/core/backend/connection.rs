@@ -0,0 +1,107 @@
+use async_trait::async_trait;
+use rbfrt::error::RBFRTError;
+use rbfrt::register::{self, Register};
+use rbfrt::table::{self, TableEntry};
+use rbfrt::util::{Digest, Port, PortManager};
+use rbfrt::SwitchConnection;
+
+use crate::core::backend::SwitchBackend;
+
+/// [SwitchBackend] that forwards all operations to a Tofino via BF Runtime.
+pub struct RbfrtBackend {
+    switch: SwitchConnection,
+    pm: PortManager,
+}
+
+impl RbfrtBackend {
+    /// Wraps an established `switch` connection and initializes its port manager.
+    pub async fn new(switch: SwitchConnection) -> RbfrtBackend {
+        let pm = PortManager::new(&switch).await;
+
+        RbfrtBackend { switch, pm }
+    }
+}
+
+#[async_trait]
+impl SwitchBackend for RbfrtBackend {
+    fn has_table(&self, name: &str) -> bool {
+        self.switch.has_table(name)
+    }
+
+    async fn execute_operation(&self, request: table::Request) -> Result<(), RBFRTError> {
+        self.switch.execute_operation(request).await
+    }
+
+    async fn get_table_entries(
+        &self,
+        request: table::Request,
+    ) -> Result<Vec<TableEntry>, RBFRTError> {
+        self.switch.get_table_entries(request).await
+    }
+
+    async fn write_table_entries(&self, requests: Vec<table::Request>) -> Result<(), RBFRTError> {
+        self.switch.write_table_entries(requests).await
+    }
+
+    async fn update_table_entries(&self, requests: Vec<table::Request>) -> Result<(), RBFRTError> {
+        self.switch.update_table_entries(requests).await
+    }
+
+    async fn delete_table_entries(&self, requests: Vec<table::Request>) -> Result<(), RBFRTError> {
+        self.switch.delete_table_entries(requests).await
+    }
+
+    async fn clear_tables(&self, names: Vec<&str>) -> Result<(), RBFRTError> {
+        self.switch.clear_tables(names).await
+    }
+
+    fn try_recv_digest(&self) -> Option<Digest> {
+        self.switch.digest_queue.try_recv().ok()
+    }
+
+    fn dev_port(&self, port: u32, channel: u8) -> Result<u32, RBFRTError> {
+        self.pm.dev_port(port, channel)
+    }
+
+    async fn get_ports(&self) -> Result<Vec<Port>, RBFRTError> {
+        self.pm.get_ports(&self.switch).await
+    }
+
+    async fn add_ports(&self, requests: &[Port]) -> Result<(), RBFRTError> {
+        self.pm.add_ports(&self.switch, requests).await
+    }
+
+    async fn update_port(&self, request: &Port) -> Result<(), RBFRTError> {
+        self.pm.update_port(&self.switch, request).await
+    }
+
+    async fn write_table_entry(&self, request: table::Request) -> Result<(), RBFRTError> {
+        self.switch.write_table_entry(request).await
+    }
+
+    async fn update_table_entry(&self, request: table::Request) -> Result<(), RBFRTError> {
+        self.switch.update_table_entry(request).await
+    }
+
+    async fn delete_table_entry(&self, request: table::Request) -> Result<(), RBFRTError> {
+        self.switch.delete_table_entry(request).await
+    }
+
+    async fn clear_table(&self, name: &str) -> Result<(), RBFRTError> {
+        self.switch.clear_table(name).await
+    }
+
+    async fn get_register_entries(
+        &self,
+        requests: Vec<register::Request>,
+    ) -> Result<Register, RBFRTError> {
+        self.switch.get_register_entries(requests).await
+    }
+
+    async fn write_register_entries(
+        &self,
+        requests: Vec<register::Request>,
+    ) -> Result<(), RBFRTError> {
+        self.switch.write_register_entries(requests).await
+    }
+}
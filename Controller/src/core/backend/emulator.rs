@@ -0,0 +1,1149 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rbfrt::table::{self, MatchValue, ToBytes};
+use rbfrt::util::Digest;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::core::backend::mock::{MockSwitch, SwitchOperation};
+use crate::core::backend::SwitchBackend;
+use crate::core::rate_monitor::{
+    IAT_METER_TABLE, IS_INGRESS_TABLE, MAE_IAT_REGISTER, MEAN_IAT_REGISTER, MONITOR_IAT_TABLE,
+    RATE_DIGEST_NAME, RTT_IAT_DIGEST_NAME,
+};
+use crate::core::traffic_gen_core::const_definitions::*;
+use crate::error::P4TGError;
+
+/// Rate of RTT and IAT digests per port that is allowed by the meters in the data plane.
+const DIGEST_RATE: f64 = 800.0;
+
+/// Maximal number of samples that are drawn per port and step to fill the histograms.
+const HISTOGRAM_SAMPLES: u64 = 100;
+
+/// Interval in which [DataPlaneEmulator::run] advances the emulation.
+const EMULATION_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Lost packets are counted in these registers. They are cleared on a reset of the statistics.
+const LOST_PACKETS_REGISTER: &str = "ingress.p4tg.lost_packets.reg_lo";
+
+/// Impairments and wiring that are applied by the [DataPlaneEmulator].
+#[derive(Debug, Clone)]
+pub struct EmulatorConfig {
+    /// Fraction of packets in the range of [0, 1] that is lost between TX and RX port.
+    pub loss: f64,
+    /// Fraction of the received packets in the range of [0, 1] that arrives out of order.
+    pub reorder: f64,
+    /// Round trip time of a packet in ns.
+    pub delay: u64,
+    /// Maximal additional, uniformly distributed round trip time in ns.
+    pub jitter: u64,
+    /// Mapping of front panel dev port on which packets leave the switch to the front panel dev port on
+    /// which they are received. Ports that are not contained are connected in loopback.
+    pub links: HashMap<u32, u32>,
+    /// Seed for the sampling of RTTs and Poisson IATs.
+    pub seed: u64,
+}
+
+impl Default for EmulatorConfig {
+    fn default() -> Self {
+        EmulatorConfig {
+            loss: 0.0,
+            reorder: 0.0,
+            delay: 2000,
+            jitter: 0,
+            links: HashMap::new(),
+            seed: 0,
+        }
+    }
+}
+
+impl EmulatorConfig {
+    /// Reads the configuration from the environment variables
+    /// `EMULATOR_LOSS`, `EMULATOR_REORDER`, `EMULATOR_DELAY`, `EMULATOR_JITTER`, `EMULATOR_SEED`, and `EMULATOR_LINKS`.
+    /// Variables that are not set keep their default value.
+    pub fn from_env(switch: &MockSwitch) -> Result<EmulatorConfig, P4TGError> {
+        Self::from_vars(switch, |name| std::env::var(name).ok())
+    }
+
+    /// Reads the configuration from the variables that are returned by `var`.
+    ///
+    /// `EMULATOR_LINKS` is a comma separated list of `tx:rx` front panel ports (channel 0), e.g., `1:2,2:1`.
+    /// Packets that leave the switch on front panel port `tx` are received on front panel port `rx`.
+    pub fn from_vars(
+        switch: &MockSwitch,
+        var: impl Fn(&str) -> Option<String>,
+    ) -> Result<EmulatorConfig, P4TGError> {
+        fn parse<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, P4TGError> {
+            value.trim().parse().map_err(|_| P4TGError::Error {
+                message: format!("Invalid value {value} for {name}."),
+            })
+        }
+
+        let mut config = EmulatorConfig::default();
+
+        if let Some(loss) = var("EMULATOR_LOSS") {
+            config.loss = parse("EMULATOR_LOSS", &loss)?;
+        }
+        if let Some(reorder) = var("EMULATOR_REORDER") {
+            config.reorder = parse("EMULATOR_REORDER", &reorder)?;
+        }
+        if let Some(delay) = var("EMULATOR_DELAY") {
+            config.delay = parse("EMULATOR_DELAY", &delay)?;
+        }
+        if let Some(jitter) = var("EMULATOR_JITTER") {
+            config.jitter = parse("EMULATOR_JITTER", &jitter)?;
+        }
+        if let Some(seed) = var("EMULATOR_SEED") {
+            config.seed = parse("EMULATOR_SEED", &seed)?;
+        }
+
+        for fraction in [config.loss, config.reorder] {
+            if !(0.0..=1.0).contains(&fraction) {
+                return Err(P4TGError::Error {
+                    message: "EMULATOR_LOSS and EMULATOR_REORDER must be in the range of [0, 1]."
+                        .to_owned(),
+                });
+            }
+        }
+
+        for link in var("EMULATOR_LINKS")
+            .unwrap_or_default()
+            .split(',')
+            .filter(|l| !l.trim().is_empty())
+        {
+            let Some((tx, rx)) = link.split_once(':') else {
+                return Err(P4TGError::Error {
+                    message: format!("Invalid link {link} in EMULATOR_LINKS. Expected tx:rx."),
+                });
+            };
+
+            let dev_port = |port: &str| {
+                switch
+                    .dev_port(parse("EMULATOR_LINKS", port)?, 0)
+                    .map_err(|_| P4TGError::Error {
+                        message: format!("Unknown front panel port {port} in EMULATOR_LINKS."),
+                    })
+            };
+
+            config.links.insert(dev_port(tx)?, dev_port(rx)?);
+        }
+
+        Ok(config)
+    }
+}
+
+/// A stream as it is generated by the emulated data plane on a single TX recirculation port.
+#[derive(Debug, Clone)]
+pub struct EmulatedStream {
+    pub app_id: u8,
+    /// TX recirculation port on which the stream is sent.
+    pub port: u32,
+    /// Packets per second on `port`.
+    pub pps: f64,
+    /// Length of a packet without FCS.
+    pub pkt_len: u32,
+    /// Indicates if packets are randomly dropped to get Poisson distributed IATs.
+    pub poisson: bool,
+}
+
+/// Sums of the IAT registers of a single port.
+#[derive(Default)]
+struct IatCounters {
+    n: u64,
+    sum: u64,
+    abs_deviation_sum: u64,
+}
+
+/// Data plane state that is kept between two steps of the emulation.
+struct EmulatorState {
+    /// Current data plane time in ns.
+    time: u64,
+    /// Time at which the next monitoring packet is generated.
+    next_monitoring: u64,
+    /// Number of switch operations that were already processed.
+    journal_offset: usize,
+    /// L1 and L2 byte counters per recirculation port.
+    bytes: HashMap<u32, (u64, u64)>,
+    /// L2 byte counters per monitoring index.
+    app_bytes: HashMap<u32, u64>,
+    /// Lost packets per monitoring index of the RX recirculation port and stream.
+    /// Out of order packets are also counted as lost.
+    lost: HashMap<u32, u64>,
+    out_of_order: HashMap<u32, u64>,
+    /// Expected sequence number of the next sampled packet per RX recirculation port and stream.
+    rx_seq: HashMap<(u32, u8), u32>,
+    iats: HashMap<u32, IatCounters>,
+    /// Fractional packets and digests that are carried over to the next step.
+    carry: HashMap<(&'static str, u32, u8), f64>,
+    rng: StdRng,
+}
+
+impl EmulatorState {
+    /// Adds `amount` to the carry identified by `key` and takes the integer part.
+    fn take(&mut self, key: (&'static str, u32, u8), amount: f64) -> u64 {
+        let carry = self.carry.entry(key).or_default();
+        *carry += amount;
+        let whole = carry.floor();
+        *carry -= whole;
+        whole as u64
+    }
+}
+
+/// Emulates the P4TG data plane on top of a [MockSwitch].
+///
+/// The emulator reads the app config, packet buffer, forwarding, multicast, header-replace, monitoring, and histogram
+/// tables that were written by the controller. From those, it computes the generated streams and synthesizes
+/// the rate and RTT/IAT digests, the IAT registers, and the histogram counters at the configured rates.
+/// Loss, reordering, and delay between TX and RX ports are applied according to the [EmulatorConfig].
+///
+/// The emulation is advanced explicitly with [DataPlaneEmulator::step] and is fully deterministic,
+/// or in real time with [DataPlaneEmulator::run].
+pub struct DataPlaneEmulator {
+    switch: Arc<MockSwitch>,
+    config: EmulatorConfig,
+    state: Mutex<EmulatorState>,
+}
+
+impl DataPlaneEmulator {
+    pub fn new(switch: Arc<MockSwitch>, config: EmulatorConfig) -> DataPlaneEmulator {
+        let rng = StdRng::seed_from_u64(config.seed);
+
+        DataPlaneEmulator {
+            switch,
+            config,
+            state: Mutex::new(EmulatorState {
+                time: 0,
+                next_monitoring: 0,
+                journal_offset: 0,
+                bytes: HashMap::new(),
+                app_bytes: HashMap::new(),
+                lost: HashMap::new(),
+                out_of_order: HashMap::new(),
+                rx_seq: HashMap::new(),
+                iats: HashMap::new(),
+                carry: HashMap::new(),
+                rng,
+            }),
+        }
+    }
+
+    /// Advances the emulation in real time.
+    /// This method runs in a thread.
+    pub async fn run(self: Arc<Self>) {
+        loop {
+            self.step(EMULATION_INTERVAL);
+            tokio::time::sleep(EMULATION_INTERVAL).await;
+        }
+    }
+
+    /// Returns the streams that are currently generated according to the programmed tables.
+    pub fn streams(&self) -> Vec<EmulatedStream> {
+        let (app_cfg, buffer_cfg) = if self.switch.is_tofino2() {
+            (APP_CFG_TF2, APP_BUFFER_CFG_TF2)
+        } else {
+            (APP_CFG, APP_BUFFER_CFG)
+        };
+
+        let buffer_offsets: Vec<u32> = self
+            .switch
+            .entries(buffer_cfg)
+            .iter()
+            .filter_map(|e| exact(e, "pkt_buffer_offset"))
+            .collect();
+        let forward_entries = self.switch.entries(STREAM_FORWARD_TABLE);
+
+        let mut streams = vec![];
+
+        for cfg in self.switch.entries(app_cfg) {
+            let Some(app_id) = exact(&cfg, "app_id") else {
+                continue;
+            };
+
+            // app id 0 is the monitoring packet
+            if app_id == 0 || !data(&cfg, "app_enable").is_some_and(|d| d.to_bool()) {
+                continue;
+            }
+
+            let (Some(pkt_len), Some(timer), Some(offset)) = (
+                data_u32(&cfg, "pkt_len"),
+                data_u32(&cfg, "timer_nanosec"),
+                data_u32(&cfg, "pkt_buffer_offset"),
+            ) else {
+                continue;
+            };
+
+            // the internal traffic generator only sends valid packets if they are in the packet buffer
+            if timer == 0 || !buffer_offsets.contains(&offset) {
+                continue;
+            }
+
+            let packets_per_batch = data_u32(&cfg, "packets_per_batch_cfg").unwrap_or(0) + 1;
+            let batch_count = data_u32(&cfg, "batch_count_cfg").unwrap_or(0) + 1;
+            let pps_per_pipe = (packets_per_batch * batch_count) as f64 * 1e9 / timer as f64;
+
+            // each generating pipe has a forwarding entry for the app
+            // the upper bound of the random value is used to drop packets for Poisson traffic
+            let app_entries: Vec<&table::Request> = forward_entries
+                .iter()
+                .filter(|e| exact(e, "hdr.pkt_gen.app_id") == Some(app_id))
+                .collect();
+
+            let Some(mcid) = app_entries.first().and_then(|e| data_u32(e, "mcid")) else {
+                continue;
+            };
+
+            let keep_probability = match app_entries[0].get_match_keys().get("ig_md.rand_value") {
+                Some(MatchValue::RangeValue { higher_bytes, .. }) => {
+                    (higher_bytes.to_u32() as f64 + 1.0) / (u16::MAX as f64 + 1.0)
+                }
+                _ => 1.0,
+            };
+
+            let pps = pps_per_pipe * app_entries.len() as f64 * keep_probability.min(1.0);
+
+            for port in self.multicast_ports(mcid) {
+                streams.push(EmulatedStream {
+                    app_id: app_id as u8,
+                    port,
+                    pps,
+                    pkt_len,
+                    poisson: keep_probability < 1.0,
+                });
+            }
+        }
+
+        streams
+    }
+
+    /// Advances the data plane by `elapsed`.
+    ///
+    /// Packets of all generated streams are counted on their TX and RX recirculation ports,
+    /// the IAT registers and histogram counters are updated, and the digests of the monitoring packets
+    /// and of the RTT/IAT meters are queued at the [MockSwitch].
+    pub fn step(&self, elapsed: Duration) {
+        let mut state = self.state.lock().unwrap();
+        let elapsed_ns = elapsed.as_nanos() as u64;
+        let elapsed_secs = elapsed.as_secs_f64();
+
+        self.process_resets(&mut state);
+
+        let streams = self.streams();
+        let forwarding = self.forwarding();
+        let app_indices = self.app_indices();
+        let stream_indices = self.stream_indices();
+        let monitor_iat = !self.switch.entries(MONITOR_IAT_TABLE).is_empty();
+        let rtt_ports: Vec<u32> = self
+            .switch
+            .entries(IS_INGRESS_TABLE)
+            .iter()
+            .filter_map(|e| exact(e, "ig_intr_md.ingress_port"))
+            .collect();
+
+        // packets per recirculation port, used for the IAT calculation
+        let mut port_pps: HashMap<u32, f64> = HashMap::new();
+        let mut port_packets: HashMap<u32, u64> = HashMap::new();
+        let mut poisson_ports: Vec<u32> = vec![];
+        // app ids of the streams that are received per RX recirculation port
+        let mut rx_app_ids: HashMap<u32, Vec<u8>> = HashMap::new();
+
+        for stream in &streams {
+            let packets = state.take(
+                ("tx", stream.port, stream.app_id),
+                stream.pps * elapsed_secs,
+            );
+            let l2 = stream.pkt_len as u64 + 4;
+
+            add_bytes(&mut state, stream.port, packets, l2);
+            if let Some(index) = app_indices.get(&(stream.port, stream.app_id)) {
+                *state.app_bytes.entry(*index).or_default() += packets * l2;
+            }
+
+            *port_pps.entry(stream.port).or_default() += stream.pps;
+            *port_packets.entry(stream.port).or_default() += packets;
+
+            // TX recirculation port -> front panel port -> link -> front panel port -> RX recirculation port
+            let rx_port = forwarding
+                .get(&stream.port)
+                .map(|out| self.config.links.get(out).unwrap_or(out))
+                .and_then(|rx| forwarding.get(rx))
+                .copied();
+
+            let Some(rx_port) = rx_port else {
+                continue;
+            };
+
+            let lost = state.take(
+                ("loss", rx_port, stream.app_id),
+                packets as f64 * self.config.loss,
+            );
+            let received = packets.saturating_sub(lost);
+            let out_of_order = state.take(
+                ("reorder", rx_port, stream.app_id),
+                received as f64 * self.config.reorder,
+            );
+
+            add_bytes(&mut state, rx_port, received, l2);
+            if let Some(index) = app_indices.get(&(rx_port, stream.app_id)) {
+                *state.app_bytes.entry(*index).or_default() += received * l2;
+            }
+            if let Some(index) = stream_indices
+                .get(&(rx_port, Some(stream.app_id)))
+                .or_else(|| stream_indices.get(&(rx_port, None)))
+            {
+                *state.lost.entry(*index).or_default() += lost + out_of_order;
+                *state.out_of_order.entry(*index).or_default() += out_of_order;
+            }
+
+            rx_app_ids.entry(rx_port).or_default().push(stream.app_id);
+
+            *port_pps.entry(rx_port).or_default() += stream.pps * (1.0 - self.config.loss);
+            *port_packets.entry(rx_port).or_default() += received;
+
+            if stream.poisson {
+                poisson_ports.extend([stream.port, rx_port]);
+            }
+        }
+
+        let mut ports: Vec<u32> = port_packets.keys().copied().collect();
+        ports.sort();
+
+        for port in ports {
+            let packets = port_packets[&port];
+            let pps = port_pps[&port];
+
+            if packets == 0 || pps <= 0.0 {
+                continue;
+            }
+
+            let mean_iat = 1e9 / pps;
+            let poisson = poisson_ports.contains(&port);
+
+            if monitor_iat {
+                // mean absolute deviation of exponential distributed IATs is 2/e * mean
+                let mae = if poisson {
+                    mean_iat * 2.0 / std::f64::consts::E
+                } else {
+                    0.0
+                };
+
+                let iat = state.iats.entry(port).or_default();
+                iat.n += packets;
+                iat.sum += (packets as f64 * mean_iat) as u64;
+                iat.abs_deviation_sum += (packets as f64 * mae) as u64;
+            }
+
+            let iat_samples = sample(&mut state.rng, packets, |rng| {
+                if poisson {
+                    (-(1.0 - rng.random::<f64>()).ln() * mean_iat) as u32
+                } else {
+                    mean_iat.round() as u32
+                }
+            });
+            self.count_histogram(IAT_HISTOGRAM_TABLE, "ig_md.iat", port, &iat_samples);
+
+            if rtt_ports.contains(&port) {
+                let (delay, jitter) = (self.config.delay, self.config.jitter);
+                let rtt_samples = sample(&mut state.rng, packets, |rng| {
+                    (delay + rng.random_range(0..=jitter)) as u32
+                });
+                self.count_histogram(RTT_HISTOGRAM_TABLE, "ig_md.rtt", port, &rtt_samples);
+
+                let digests = state
+                    .take(("rtt", port, 0), DIGEST_RATE * elapsed_secs)
+                    .min(packets);
+
+                // RTTs are measured on the received streams in turn
+                let app_ids = rx_app_ids.get(&port).cloned().unwrap_or(vec![0]);
+
+                // packets that are received in order between two sampled packets of a stream
+                let spacing = (packets / digests.max(1)) as u32;
+
+                for ((rtt, _), app_id) in rtt_samples
+                    .iter()
+                    .cycle()
+                    .zip(app_ids.iter().cycle())
+                    .take(digests as usize)
+                {
+                    let (seq, expected_seq) = self.sample_seq(&mut state, port, *app_id, spacing);
+
+                    self.switch.inject_digest(Digest {
+                        name: RTT_IAT_DIGEST_NAME.to_owned(),
+                        data: HashMap::from([
+                            ("port".to_owned(), port.to_bytes()),
+                            ("rtt".to_owned(), (*rtt as u64).to_bytes()),
+                            ("iat".to_owned(), (mean_iat.round() as u64).to_bytes()),
+                            ("app_id".to_owned(), (*app_id as u32).to_bytes()),
+                            ("seq".to_owned(), seq.to_bytes()),
+                            ("expected_seq".to_owned(), expected_seq.to_bytes()),
+                        ]),
+                    });
+                }
+            } else if self.iat_meter_active(port) {
+                // IAT samples of TX ports are only reported in sample mode
+                let digests = state
+                    .take(("iat", port, 0), DIGEST_RATE * elapsed_secs)
+                    .min(packets);
+
+                for (iat, _) in iat_samples.iter().cycle().take(digests as usize) {
+                    self.switch.inject_digest(Digest {
+                        name: RTT_IAT_DIGEST_NAME.to_owned(),
+                        data: HashMap::from([
+                            ("port".to_owned(), port.to_bytes()),
+                            ("rtt".to_owned(), 0u64.to_bytes()),
+                            ("iat".to_owned(), (*iat as u64).to_bytes()),
+                            ("app_id".to_owned(), 0u32.to_bytes()),
+                            ("seq".to_owned(), 0u32.to_bytes()),
+                            ("expected_seq".to_owned(), 0u32.to_bytes()),
+                        ]),
+                    });
+                }
+            }
+        }
+
+        if monitor_iat {
+            self.write_iat_registers(&state);
+        }
+
+        // monitoring packets
+        let end = state.time + elapsed_ns;
+
+        while state.next_monitoring <= end {
+            let time = state.next_monitoring;
+            self.send_monitoring_digests(&state, time);
+            state.next_monitoring += MONITORING_PACKET_INTERVAL as u64;
+        }
+
+        state.time = end;
+    }
+
+    /// Returns the sequence number and the expected sequence number of a sampled packet of stream `app_id`
+    /// on RX recirculation port `port`. Packets are lost independently of each other, so that the sampled
+    /// packet is preceded by a geometrically distributed loss burst. `spacing` packets are received in order
+    /// until the next sampled packet.
+    fn sample_seq(
+        &self,
+        state: &mut EmulatorState,
+        port: u32,
+        app_id: u8,
+        spacing: u32,
+    ) -> (u32, u32) {
+        let expected_seq = state.rx_seq.get(&(port, app_id)).copied().unwrap_or(0);
+
+        if expected_seq > 1 && state.rng.random::<f64>() < self.config.reorder {
+            // packet that was overtaken by the previous packet
+            return (expected_seq - 2, expected_seq);
+        }
+
+        let mut gap = 0u32;
+        while gap < 1000 && state.rng.random::<f64>() < self.config.loss {
+            gap += 1;
+        }
+
+        let seq = expected_seq.wrapping_add(gap);
+        state
+            .rx_seq
+            .insert((port, app_id), seq.wrapping_add(1).wrapping_add(spacing));
+
+        (seq, expected_seq)
+    }
+
+    /// Resets the data plane state if the controller cleared the corresponding registers.
+    fn process_resets(&self, state: &mut EmulatorState) {
+        let operations = self.switch.operations_since(state.journal_offset);
+        state.journal_offset += operations.len();
+
+        for operation in operations {
+            if let SwitchOperation::Clear(name) = operation {
+                match name.as_str() {
+                    LOST_PACKETS_REGISTER => {
+                        state.lost.clear();
+                        state.out_of_order.clear();
+                        state.rx_seq.clear();
+                    }
+                    MEAN_IAT_REGISTER => state.iats.clear(),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Returns the dev ports of the multicast group `mcid`.
+    fn multicast_ports(&self, mcid: u32) -> Vec<u32> {
+        let node_ids: Vec<u32> = self
+            .switch
+            .entries("$pre.mgid")
+            .iter()
+            .filter(|e| exact(e, "$MGID") == Some(mcid))
+            .flat_map(|e| repeated_u32(e, "$MULTICAST_NODE_ID"))
+            .collect();
+
+        self.switch
+            .entries("$pre.node")
+            .iter()
+            .filter(|e| exact(e, "$MULTICAST_NODE_ID").is_some_and(|id| node_ids.contains(&id)))
+            .flat_map(|e| repeated_u32(e, "$DEV_PORT"))
+            .collect()
+    }
+
+    /// Returns the mapping of ingress port to egress port of the default forwarding table.
+    fn forwarding(&self) -> HashMap<u32, u32> {
+        self.switch
+            .entries(DEFAULT_FORWARD_TABLE)
+            .iter()
+            .filter_map(|e| Some((exact(e, "ig_intr_md.ingress_port")?, data_u32(e, "e_port")?)))
+            .collect()
+    }
+
+    /// Returns the mapping of (recirculation port, app_id) to the index that is used for stream rate monitoring.
+    fn app_indices(&self) -> HashMap<(u32, u8), u32> {
+        self.switch
+            .entries(MONITORING_EGRESS_TABLE)
+            .iter()
+            .filter_map(|e| {
+                Some((
+                    (
+                        exact(e, "eg_intr_md.egress_port")?,
+                        exact(e, "hdr.path.app_id")? as u8,
+                    ),
+                    data_u32(e, "idx")?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Returns the mapping of (RX recirculation port, app_id) to the index at which lost and out of order packets are tracked.
+    /// The fallback index of an RX recirculation port for unknown app ids has no app id.
+    fn stream_indices(&self) -> HashMap<(u32, Option<u8>), u32> {
+        let streams = self.switch.entries(MONITORING_INGRESS_TABLE);
+        let fallbacks = self.switch.entries(MONITORING_INGRESS_PORT_TABLE);
+
+        streams
+            .iter()
+            .chain(&fallbacks)
+            .filter_map(|e| {
+                Some((
+                    (
+                        exact(e, "ig_intr_md.ingress_port")?,
+                        exact(e, "hdr.path.app_id").map(|app_id| app_id as u8),
+                    ),
+                    data_u32(e, "idx")?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Checks if the IAT meter of `port` lets digests pass.
+    fn iat_meter_active(&self, port: u32) -> bool {
+        self.switch
+            .entries(IAT_METER_TABLE)
+            .iter()
+            .filter(|e| exact(e, "$METER_INDEX") == Some(port))
+            .any(|e| data_u32(e, "$METER_SPEC_CIR_KBPS").is_some_and(|cir| cir > 0))
+    }
+
+    /// Counts the `samples` in the first matching bin of the histogram table of `port`.
+    /// Samples that do not match any bin are counted in the missed bin.
+    fn count_histogram(&self, table: &str, field: &str, port: u32, samples: &[(u32, u64)]) {
+        let entries: Vec<table::Request> = self
+            .switch
+            .entries(table)
+            .into_iter()
+            .filter(|e| exact(e, "ig_md.ig_port") == Some(port))
+            .collect();
+
+        if entries.is_empty() {
+            return;
+        }
+
+        for (value, packets) in samples {
+            let matches = |e: &&table::Request| match e.get_match_keys().get(field) {
+                Some(MatchValue::Ternary { value: v, mask }) => {
+                    value & mask.to_u32() == v.to_u32() & mask.to_u32()
+                }
+                _ => false,
+            };
+
+            let entry = entries
+                .iter()
+                .filter(|e| e.has_action() && e.get_action_name().ends_with("count_histogram_bin"))
+                .find(matches)
+                .or_else(|| {
+                    entries
+                        .iter()
+                        .filter(|e| {
+                            e.has_action() && e.get_action_name().ends_with("count_missed_bin")
+                        })
+                        .find(matches)
+                });
+
+            if let Some(entry) = entry {
+                self.switch
+                    .add_to_counter(table, entry.get_match_keys(), *packets, 0);
+            }
+        }
+    }
+
+    /// Writes the IAT sums into the mean IAT and MAE IAT registers.
+    /// Each register holds a value per pipe. Only the pipe of the port is set.
+    fn write_iat_registers(&self, state: &EmulatorState) {
+        let num_pipes = self.switch.num_pipes() as usize;
+
+        for (port, iat) in &state.iats {
+            let pipe = (*port >> 7) as usize;
+
+            let per_pipe = |value: u64| {
+                let mut values = vec![0u64; num_pipes.max(pipe + 1)];
+                values[pipe] = value;
+                values
+            };
+
+            for (register, sum) in [
+                (MEAN_IAT_REGISTER, iat.sum),
+                (MAE_IAT_REGISTER, iat.abs_deviation_sum),
+            ] {
+                self.switch.set_entry(
+                    table::Request::new(register)
+                        .match_key("$REGISTER_INDEX", MatchValue::exact(*port))
+                        .action_data_repeated(&format!("{register}.sum"), per_pipe(sum))
+                        .action_data_repeated(&format!("{register}.n"), per_pipe(iat.n)),
+                );
+            }
+        }
+    }
+
+    /// Creates the digests of a monitoring packet that traverses all recirculation ports and monitoring indices.
+    fn send_monitoring_digests(&self, state: &EmulatorState, time: u64) {
+        let app_cfg = if self.switch.is_tofino2() {
+            APP_CFG_TF2
+        } else {
+            APP_CFG
+        };
+
+        let monitoring_enabled = self.switch.entries(app_cfg).iter().any(|e| {
+            exact(e, "app_id") == Some(0) && data(e, "app_enable").is_some_and(|d| d.to_bool())
+        });
+
+        if !monitoring_enabled {
+            return;
+        }
+
+        // index with which the packet is received -> (port, next index)
+        let mut hops: BTreeMap<u32, (u32, u32)> = BTreeMap::new();
+
+        for entry in self.switch.entries(MONITORING_FORWARD_TABLE) {
+            if entry.has_action()
+                && entry.get_action_name() == "ingress.p4tg.make_digest_and_forward"
+            {
+                if let (Some(port), Some(index), Some(next_index)) = (
+                    exact(&entry, "ig_intr_md.ingress_port"),
+                    exact(&entry, "hdr.monitor.index"),
+                    data_u32(&entry, "index"),
+                ) {
+                    hops.insert(index, (port, next_index));
+                }
+            }
+        }
+
+        // the counters of the monitoring index with which the packet was received are reported
+        // together with the next index of the packet
+        for (index, (port, next_index)) in hops {
+            let (l1, l2) = state.bytes.get(&port).copied().unwrap_or_default();
+
+            self.switch.inject_digest(Digest {
+                name: RATE_DIGEST_NAME.to_owned(),
+                data: HashMap::from([
+                    ("port".to_owned(), port.to_bytes()),
+                    ("tstmp".to_owned(), time.to_bytes()),
+                    ("byte_counter_l1".to_owned(), l1.to_bytes()),
+                    ("byte_counter_l2".to_owned(), l2.to_bytes()),
+                    (
+                        "app_counter".to_owned(),
+                        state
+                            .app_bytes
+                            .get(&index)
+                            .copied()
+                            .unwrap_or_default()
+                            .to_bytes(),
+                    ),
+                    ("index".to_owned(), next_index.to_bytes()),
+                    (
+                        "packet_loss".to_owned(),
+                        state
+                            .lost
+                            .get(&index)
+                            .copied()
+                            .unwrap_or_default()
+                            .to_bytes(),
+                    ),
+                    (
+                        "out_of_order".to_owned(),
+                        state
+                            .out_of_order
+                            .get(&index)
+                            .copied()
+                            .unwrap_or_default()
+                            .to_bytes(),
+                    ),
+                ]),
+            });
+        }
+    }
+}
+
+/// Adds `packets` with an L2 size of `l2` bytes to the byte counters of `port`.
+/// The L1 size additionally contains preamble and inter frame gap.
+fn add_bytes(state: &mut EmulatorState, port: u32, packets: u64, l2: u64) {
+    let counter = state.bytes.entry(port).or_default();
+    counter.0 += packets * (l2 + 20);
+    counter.1 += packets * l2;
+}
+
+/// Draws up to [HISTOGRAM_SAMPLES] values and distributes `packets` equally among them.
+fn sample(
+    rng: &mut StdRng,
+    packets: u64,
+    mut value: impl FnMut(&mut StdRng) -> u32,
+) -> Vec<(u32, u64)> {
+    let num_samples = packets.min(HISTOGRAM_SAMPLES);
+
+    (0..num_samples)
+        .map(|i| {
+            let weight = packets / num_samples + u64::from(i < packets % num_samples);
+            (value(rng), weight)
+        })
+        .collect()
+}
+
+fn exact(request: &table::Request, key: &str) -> Option<u32> {
+    match request.get_match_keys().get(key) {
+        Some(MatchValue::ExactValue { bytes }) => Some(bytes.to_u32()),
+        _ => None,
+    }
+}
+
+fn data<'a>(request: &'a table::Request, key: &str) -> Option<&'a Vec<u8>> {
+    request
+        .get_action_data()
+        .iter()
+        .find(|a| a.get_key() == key)
+        .map(|a| a.get_data())
+}
+
+fn data_u32(request: &table::Request, key: &str) -> Option<u32> {
+    data(request, key).map(|d| d.to_u32())
+}
+
+fn repeated_u32(request: &table::Request, key: &str) -> Vec<u32> {
+    request
+        .get_action_data_repeated()
+        .iter()
+        .filter(|a| a.get_key() == key)
+        .flat_map(|a| a.get_data().iter().map(|d| d.to_u32()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::docs::traffic_gen::EXAMPLE_POST_2_REQUEST;
+    use crate::api::server::api_router;
+    use crate::core::archive::Archive;
+    use crate::core::{Config, RateMonitor};
+    use crate::{init_state, AppState};
+    use axum::body::Body;
+    use axum::http::{Method, Request, StatusCode};
+    use tower::ServiceExt;
+
+    /// Creates the controller state on a mock switch and starts the digest monitoring.
+    async fn monitored_switch() -> (Arc<MockSwitch>, Arc<AppState>) {
+        let switch = Arc::new(MockSwitch::new(32, 2, false));
+        let (state, index_mapping) = init_state(
+            switch.clone(),
+            Config::default_tofino(false),
+            32,
+            false,
+            false,
+            false,
+            Archive::temporary("emulator"),
+        )
+        .await
+        .unwrap();
+
+        let monitoring_state = Arc::clone(&state);
+        tokio::spawn(async move {
+            RateMonitor::monitor_digests(monitoring_state, &index_mapping, false).await;
+        });
+
+        (switch, state)
+    }
+
+    /// Starts 80 Gbps VLAN traffic with 64 byte frames on port 1 in loopback.
+    async fn start_traffic(state: &Arc<AppState>) {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/trafficgen")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                serde_json::to_string(&*EXAMPLE_POST_2_REQUEST).unwrap(),
+            ))
+            .unwrap();
+        let response = api_router(Arc::clone(state))
+            .oneshot(request)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn emulated_traffic_is_monitored() {
+        let (switch, state) = monitored_switch().await;
+        start_traffic(&state).await;
+
+        let emulator = DataPlaneEmulator::new(
+            switch.clone(),
+            EmulatorConfig {
+                loss: 0.01,
+                reorder: 0.001,
+                delay: 5000,
+                jitter: 1000,
+                seed: 7,
+                ..Default::default()
+            },
+        );
+
+        let port = switch.dev_port(1, 0).unwrap();
+        let mapping = state.port_mapping[&port];
+        let streams = emulator.streams();
+
+        // the emulator sends the traffic back to the TX port
+        state.rate_monitor.lock().await.disruption.configure(
+            &HashMap::from([(format!("{port}"), port)]),
+            &EXAMPLE_POST_2_REQUEST.streams,
+        );
+        assert_eq!(streams.len(), 1);
+        assert_eq!(streams[0].port, mapping.tx_recirculation);
+
+        for _ in 0..30 {
+            emulator.step(Duration::from_millis(100));
+        }
+
+        // wait until all digests are processed
+        for _ in 0..100 {
+            if switch.num_pending_digests() == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let rate_monitor = state.rate_monitor.lock().await;
+        let statistics = &rate_monitor.statistics;
+        let expected_rate = streams[0].pps * (streams[0].pkt_len + 24) as f64 * 8.0;
+        assert!((expected_rate - 80e9).abs() / 80e9 < 0.01);
+
+        let tx_rate = statistics.tx_rate_l1[&port];
+        let rx_rate = statistics.rx_rate_l1[&port];
+        assert!((tx_rate - expected_rate).abs() / expected_rate < 0.001);
+        assert!((rx_rate - 0.99 * expected_rate).abs() / expected_rate < 0.001);
+
+        // packet loss does not contain reordered packets
+        let packets = streams[0].pps * 3.0;
+        let loss = statistics.packet_loss[&port] as f64;
+        let out_of_order = statistics.out_of_order[&port] as f64;
+        assert!((loss - 0.01 * packets).abs() / packets < 0.001);
+        assert!((out_of_order - 0.00099 * packets).abs() / packets < 0.001);
+
+        // the only stream has app id 1
+        assert_eq!(statistics.app_packet_loss[&port][&1], loss as u64);
+        assert_eq!(statistics.app_out_of_order[&port][&1], out_of_order as u64);
+
+        // stream rates are recorded over time
+        let time_statistics = &rate_monitor.time_statistics;
+        let app_tx_l2 = &time_statistics.app_tx_l2[&port][&1];
+        let app_rx_l2 = &time_statistics.app_rx_l2[&port][&1];
+        assert!(!app_tx_l2.is_empty() && !app_rx_l2.is_empty());
+        assert_eq!(
+            app_tx_l2.values().last(),
+            statistics.app_tx_l2[&port].get(&1)
+        );
+
+        // the disruption time is the time it takes to send the lost packets
+        let disruption = rate_monitor.disruption.port_statistics()[&port].clone();
+        let app_disruption = &rate_monitor.disruption.stream_statistics()[&port][&1];
+        assert!((disruption.disruption_time - loss / streams[0].pps).abs() < 0.002);
+        assert!((app_disruption.disruption_time - disruption.disruption_time).abs() < 0.002);
+        assert_eq!(disruption.outages.len(), 1);
+        assert!(disruption.outages[0].ongoing);
+        assert!(!time_statistics.disruption_time[&port].is_empty());
+
+        // independent losses have a short mean burst length
+        let loss_analysis = &rate_monitor.loss_analysis.stream_statistics()[&port][&1];
+        assert_eq!(loss_analysis.lost_packets, loss as u64);
+        assert_eq!(
+            loss_analysis.samples as usize,
+            rate_monitor.rtt_storage[&port].len()
+        );
+        assert_eq!(loss_analysis.duplicates, 0);
+        assert!((loss_analysis.loss_probability - 0.01).abs() < 0.005);
+        assert!((1.0..1.1).contains(&loss_analysis.mean_burst_length));
+        assert!(loss_analysis.loss_episodes > 0);
+
+        let rtts = &rate_monitor.rtt_storage[&port];
+        assert!(!rtts.is_empty());
+        assert!(rtts.iter().all(|rtt| (5000..=6000).contains(rtt)));
+        assert_eq!(rate_monitor.app_rtt_storage[&port][&1].len(), rtts.len());
+
+        // RTTs are uniformly distributed in [5000, 6000] ns
+        let p50 = rate_monitor.rtt_sketches[&port][&1].quantile(0.5).unwrap();
+        assert!((p50 - 5500.0).abs() / 5500.0 < 0.01);
+
+        // constant bit rate traffic has a constant IAT
+        let iat = switch
+            .entries(MEAN_IAT_REGISTER)
+            .into_iter()
+            .find(|e| exact(e, "$REGISTER_INDEX") == Some(mapping.tx_recirculation))
+            .unwrap();
+        let sum: u64 = repeated_u32(&iat, &format!("{MEAN_IAT_REGISTER}.sum"))
+            .iter()
+            .map(|x| *x as u64)
+            .sum();
+        let n: u64 = repeated_u32(&iat, &format!("{MEAN_IAT_REGISTER}.n"))
+            .iter()
+            .map(|x| *x as u64)
+            .sum();
+        assert!((sum as f64 / n as f64 - 1e9 / streams[0].pps).abs() < 0.1);
+    }
+
+    #[tokio::test]
+    async fn emulator_is_configured_from_variables() {
+        let (switch, state) = monitored_switch().await;
+        let port = switch.dev_port(1, 0).unwrap();
+
+        let vars = HashMap::from([
+            ("EMULATOR_LOSS", "0.05"),
+            ("EMULATOR_DELAY", "10000"),
+            ("EMULATOR_JITTER", "500"),
+            ("EMULATOR_SEED", "3"),
+            ("EMULATOR_LINKS", "2:3, 3:2"),
+        ]);
+        let config =
+            EmulatorConfig::from_vars(&switch, |name| vars.get(name).map(|v| (*v).to_owned()))
+                .unwrap();
+        assert_eq!(config.reorder, 0.0);
+        assert_eq!(
+            config.links,
+            HashMap::from([
+                (
+                    switch.dev_port(2, 0).unwrap(),
+                    switch.dev_port(3, 0).unwrap()
+                ),
+                (
+                    switch.dev_port(3, 0).unwrap(),
+                    switch.dev_port(2, 0).unwrap()
+                ),
+            ])
+        );
+
+        for (name, value) in [
+            ("EMULATOR_LOSS", "1.5"),
+            ("EMULATOR_DELAY", "fast"),
+            ("EMULATOR_LINKS", "1-2"),
+            ("EMULATOR_LINKS", "1:99"),
+        ] {
+            let invalid =
+                EmulatorConfig::from_vars(&switch, |n| (n == name).then(|| value.to_owned()));
+            assert!(invalid.is_err(), "{name}={value}");
+        }
+
+        start_traffic(&state).await;
+
+        let emulator = DataPlaneEmulator::new(switch.clone(), config);
+        for _ in 0..20 {
+            emulator.step(Duration::from_millis(100));
+        }
+
+        // wait until the monitoring packets with the final loss counters are processed
+        let lost: u64 = emulator.state.lock().unwrap().lost.values().sum();
+        for _ in 0..200 {
+            let rate_monitor = state.rate_monitor.lock().await;
+            if rate_monitor.statistics.packet_loss.get(&port) == Some(&lost) {
+                break;
+            }
+            drop(rate_monitor);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let rate_monitor = state.rate_monitor.lock().await;
+        let statistics = &rate_monitor.statistics;
+        let packets = emulator.streams()[0].pps * 2.0;
+        assert_eq!(statistics.packet_loss[&port], lost);
+        assert!((lost as f64 - 0.05 * packets).abs() / packets < 0.001);
+        assert_eq!(statistics.out_of_order[&port], 0);
+
+        let rtts = &rate_monitor.rtt_storage[&port];
+        assert!(!rtts.is_empty());
+        assert!(rtts.iter().all(|rtt| (10000..=10500).contains(rtt)));
+    }
+
+    #[tokio::test]
+    async fn packet_loss_of_unknown_app_ids_is_reported_per_port() {
+        let (switch, state) = monitored_switch().await;
+        let port = switch.dev_port(1, 0).unwrap();
+        let mapping = state.port_mapping[&port];
+
+        start_traffic(&state).await;
+
+        // the app id of the stream is unknown on the RX port
+        switch
+            .delete_table_entry(
+                table::Request::new(MONITORING_INGRESS_TABLE)
+                    .match_key(
+                        "ig_intr_md.ingress_port",
+                        MatchValue::exact(mapping.rx_recirculation),
+                    )
+                    .match_key("hdr.path.app_id", MatchValue::exact(1u8)),
+            )
+            .await
+            .unwrap();
+
+        let emulator = DataPlaneEmulator::new(
+            switch.clone(),
+            EmulatorConfig {
+                loss: 0.01,
+                seed: 5,
+                ..Default::default()
+            },
+        );
+        for _ in 0..20 {
+            emulator.step(Duration::from_millis(100));
+        }
+
+        // wait until the monitoring packets with the final loss counters are processed
+        let lost: u64 = emulator.state.lock().unwrap().lost.values().sum();
+        for _ in 0..200 {
+            let rate_monitor = state.rate_monitor.lock().await;
+            if rate_monitor.statistics.packet_loss.get(&port) == Some(&lost) {
+                break;
+            }
+            drop(rate_monitor);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let rate_monitor = state.rate_monitor.lock().await;
+        let statistics = &rate_monitor.statistics;
+        let packets = emulator.streams()[0].pps * 2.0;
+        assert!((lost as f64 - 0.01 * packets).abs() / packets < 0.001);
+
+        // the lost packets are contained in the port counters but not in the counters of the stream
+        assert_eq!(statistics.packet_loss[&port], lost);
+        assert_eq!(rate_monitor.unmatched_counters[&port], (lost, 0));
+        assert_eq!(
+            statistics.app_packet_loss[&port]
+                .get(&1)
+                .copied()
+                .unwrap_or_default(),
+            0
+        );
+    }
+}
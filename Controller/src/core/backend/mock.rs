@@ -0,0 +1,543 @@
+// Parts of the mock are only used to inspect the switch state in tests.
+#![allow(dead_code)]
+
+use async_trait::async_trait;
+use rbfrt::error::RBFRTError;
+use rbfrt::table::{self, ActionData, MatchValue, TableEntry};
+use rbfrt::util::{Digest, Port};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Mutex;
+
+use crate::core::backend::SwitchBackend;
+use crate::core::traffic_gen_core::const_definitions::{
+    DEVICE_CONFIGURATION, DEVICE_CONFIGURATION_TF2,
+};
+
+/// Counter fields that are attached to every table entry that is read from the mock.
+/// The monitors expect them on all counter tables.
+const COUNTER_FIELDS: [&str; 2] = ["$COUNTER_SPEC_PKTS", "$COUNTER_SPEC_BYTES"];
+
+/// A single operation that was sent to the [MockSwitch].
+#[derive(Debug, Clone)]
+pub enum SwitchOperation {
+    Write(table::Request),
+    Update(table::Request),
+    Delete(table::Request),
+    Clear(String),
+    Execute(table::Request),
+}
+
+/// Programmed state of a single table.
+#[derive(Default)]
+struct MockTable {
+    entries: Vec<table::Request>,
+    default_entry: Option<table::Request>,
+    /// Packet and byte counters of the entries, indexed by the match keys
+    counters: HashMap<BTreeMap<String, String>, (u64, u64)>,
+}
+
+/// In-memory [SwitchBackend] that is used when no Tofino is available.
+///
+/// Table and register writes are stored and served on subsequent reads.
+/// Every operation is additionally recorded in a journal that can be inspected via [MockSwitch::operations].
+/// Digests are not generated by the mock itself but can be injected with [MockSwitch::inject_digest].
+pub struct MockSwitch {
+    is_tofino2: bool,
+    num_pipes: u32,
+    /// Mapping of `front panel port/channel` to dev port
+    dev_ports: HashMap<(u32, u8), u32>,
+    tables: Mutex<HashMap<String, MockTable>>,
+    journal: Mutex<Vec<SwitchOperation>>,
+    digests: Mutex<VecDeque<Digest>>,
+    ports: Mutex<Vec<Port>>,
+}
+
+impl MockSwitch {
+    /// Creates a mock switch with `num_ports` front panel ports that are equally distributed over `num_pipes` pipes.
+    ///
+    /// The device configuration table is initialized with `num_pipes` so that the controller detects
+    /// the same number of pipes as on hardware.
+    pub fn new(num_ports: u32, num_pipes: u32, is_tofino2: bool) -> MockSwitch {
+        let num_pipes = num_pipes.max(1);
+        let ports_per_pipe = num_ports.div_ceil(num_pipes).max(1);
+
+        // Channels are encoded in the 2 (Tofino1) or 3 (Tofino2) least-significant bits of the dev port.
+        // On Tofino2, the first 8 dev ports of a pipe are not used for front panel ports.
+        let (stride, max_channel, offset) = if is_tofino2 { (8, 8, 8) } else { (4, 4, 0) };
+
+        let mut dev_ports = HashMap::new();
+
+        for port in 1..=num_ports {
+            let pipe = (port - 1) / ports_per_pipe;
+            let local = (port - 1) % ports_per_pipe;
+
+            for channel in 0..max_channel {
+                dev_ports.insert(
+                    (port, channel as u8),
+                    (pipe << 7) + offset + local * stride + channel,
+                );
+            }
+        }
+
+        let device_configuration = if is_tofino2 {
+            DEVICE_CONFIGURATION_TF2
+        } else {
+            DEVICE_CONFIGURATION
+        };
+
+        let mut tables: HashMap<String, MockTable> = HashMap::new();
+        tables
+            .entry(device_configuration.to_owned())
+            .or_default()
+            .default_entry = Some(
+            table::Request::new(device_configuration)
+                .default(true)
+                .action_data("num_pipes", num_pipes),
+        );
+
+        MockSwitch {
+            is_tofino2,
+            num_pipes,
+            dev_ports,
+            tables: Mutex::new(tables),
+            journal: Mutex::new(vec![]),
+            digests: Mutex::new(VecDeque::new()),
+            ports: Mutex::new(vec![]),
+        }
+    }
+
+    pub fn is_tofino2(&self) -> bool {
+        self.is_tofino2
+    }
+
+    pub fn num_pipes(&self) -> u32 {
+        self.num_pipes
+    }
+
+    /// Queues a digest that is returned by the next call of [SwitchBackend::try_recv_digest].
+    pub fn inject_digest(&self, digest: Digest) {
+        self.digests.lock().unwrap().push_back(digest);
+    }
+
+    /// Number of injected digests that were not yet received.
+    pub fn num_pending_digests(&self) -> usize {
+        self.digests.lock().unwrap().len()
+    }
+
+    /// Returns all operations that were sent to the switch in the order they were received.
+    pub fn operations(&self) -> Vec<SwitchOperation> {
+        self.journal.lock().unwrap().clone()
+    }
+
+    /// Returns the currently programmed entries of table `name`.
+    pub fn entries(&self, name: &str) -> Vec<table::Request> {
+        self.tables
+            .lock()
+            .unwrap()
+            .get(name)
+            .map(|t| t.entries.clone())
+            .unwrap_or_default()
+    }
+
+    /// Returns the currently programmed default entry of table `name`.
+    pub fn default_entry(&self, name: &str) -> Option<table::Request> {
+        self.tables
+            .lock()
+            .unwrap()
+            .get(name)
+            .and_then(|t| t.default_entry.clone())
+    }
+
+    /// Number of operations that were sent to the switch so far.
+    pub fn num_operations(&self) -> usize {
+        self.journal.lock().unwrap().len()
+    }
+
+    /// Returns all operations that were sent to the switch after the first `offset` operations.
+    pub fn operations_since(&self, offset: usize) -> Vec<SwitchOperation> {
+        self.journal
+            .lock()
+            .unwrap()
+            .iter()
+            .skip(offset)
+            .cloned()
+            .collect()
+    }
+
+    /// Writes or replaces an entry without recording it as operation.
+    /// This is used to reflect state changes of the data plane itself, e.g., register values.
+    pub fn set_entry(&self, request: table::Request) {
+        self.upsert(request);
+    }
+
+    /// Increases the counters of the entry of table `name` with the specified `match_keys`.
+    pub fn add_to_counter(
+        &self,
+        name: &str,
+        match_keys: &HashMap<String, MatchValue>,
+        packets: u64,
+        bytes: u64,
+    ) {
+        let mut tables = self.tables.lock().unwrap();
+        let counter = tables
+            .entry(name.to_owned())
+            .or_default()
+            .counters
+            .entry(Self::match_key_repr(match_keys))
+            .or_default();
+
+        counter.0 += packets;
+        counter.1 += bytes;
+    }
+
+    fn record(&self, operation: SwitchOperation) {
+        self.journal.lock().unwrap().push(operation);
+    }
+
+    /// [MatchValue] does not implement `PartialEq`, therefore match keys are compared by their debug representation.
+    fn match_key_repr(keys: &HashMap<String, MatchValue>) -> BTreeMap<String, String> {
+        keys.iter()
+            .map(|(name, value)| (name.clone(), format!("{value:?}")))
+            .collect()
+    }
+
+    /// Writes or replaces the entry with the same match keys.
+    fn upsert(&self, request: table::Request) {
+        let mut tables = self.tables.lock().unwrap();
+        let table = tables.entry(request.table_name.clone()).or_default();
+
+        if request.is_default() {
+            table.default_entry = Some(request);
+            return;
+        }
+
+        let key = Self::match_key_repr(request.get_match_keys());
+
+        match table
+            .entries
+            .iter_mut()
+            .find(|e| Self::match_key_repr(e.get_match_keys()) == key)
+        {
+            Some(entry) => *entry = request,
+            None => table.entries.push(request),
+        }
+    }
+
+    fn to_table_entry(table: &MockTable, request: &table::Request) -> TableEntry {
+        let mut action_data: Vec<ActionData> = request.get_action_data().clone();
+
+        // Repeated action data is returned as one field per element
+        for repeated in request.get_action_data_repeated() {
+            for data in repeated.get_data() {
+                action_data.push(ActionData::new(repeated.get_key(), data.clone()));
+            }
+        }
+
+        if !request.get_match_keys().contains_key("$REGISTER_INDEX") {
+            let (packets, bytes) = table
+                .counters
+                .get(&Self::match_key_repr(request.get_match_keys()))
+                .copied()
+                .unwrap_or_default();
+
+            for (field, value) in COUNTER_FIELDS.into_iter().zip([packets, bytes]) {
+                if !action_data.iter().any(|a| a.get_key() == field) {
+                    action_data.push(ActionData::new(field, value));
+                }
+            }
+        }
+
+        TableEntry {
+            table_id: 0,
+            table_name: request.table_name.clone(),
+            match_keys: request.get_match_keys().clone(),
+            default_entry: request.is_default(),
+            action: if request.has_action() {
+                request.get_action_name().to_owned()
+            } else {
+                String::new()
+            },
+            action_data,
+        }
+    }
+}
+
+#[async_trait]
+impl SwitchBackend for MockSwitch {
+    fn has_table(&self, name: &str) -> bool {
+        name.starts_with("tf2.") == self.is_tofino2 || !name.starts_with("tf")
+    }
+
+    async fn execute_operation(&self, request: table::Request) -> Result<(), RBFRTError> {
+        self.record(SwitchOperation::Execute(request));
+        Ok(())
+    }
+
+    async fn get_table_entries(
+        &self,
+        request: table::Request,
+    ) -> Result<Vec<TableEntry>, RBFRTError> {
+        let tables = self.tables.lock().unwrap();
+
+        let Some(table) = tables.get(&request.table_name) else {
+            return Ok(vec![]);
+        };
+
+        if request.is_default() {
+            return Ok(table
+                .default_entry
+                .iter()
+                .map(|e| Self::to_table_entry(table, e))
+                .collect());
+        }
+
+        let filter = Self::match_key_repr(request.get_match_keys());
+
+        Ok(table
+            .entries
+            .iter()
+            .filter(|e| {
+                let keys = Self::match_key_repr(e.get_match_keys());
+                filter
+                    .iter()
+                    .all(|(name, value)| keys.get(name) == Some(value))
+            })
+            .map(|e| Self::to_table_entry(table, e))
+            .collect())
+    }
+
+    async fn write_table_entries(&self, requests: Vec<table::Request>) -> Result<(), RBFRTError> {
+        for request in requests {
+            self.record(SwitchOperation::Write(request.clone()));
+            self.upsert(request);
+        }
+
+        Ok(())
+    }
+
+    async fn update_table_entries(&self, requests: Vec<table::Request>) -> Result<(), RBFRTError> {
+        for request in requests {
+            self.record(SwitchOperation::Update(request.clone()));
+            self.upsert(request);
+        }
+
+        Ok(())
+    }
+
+    async fn delete_table_entries(&self, requests: Vec<table::Request>) -> Result<(), RBFRTError> {
+        for request in requests {
+            self.record(SwitchOperation::Delete(request.clone()));
+
+            let key = Self::match_key_repr(request.get_match_keys());
+
+            if let Some(table) = self.tables.lock().unwrap().get_mut(&request.table_name) {
+                table
+                    .entries
+                    .retain(|e| Self::match_key_repr(e.get_match_keys()) != key);
+                table.counters.remove(&key);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn clear_tables(&self, names: Vec<&str>) -> Result<(), RBFRTError> {
+        for name in names {
+            self.record(SwitchOperation::Clear(name.to_owned()));
+
+            if name == "$PORT" {
+                self.ports.lock().unwrap().clear();
+            }
+
+            if let Some(table) = self.tables.lock().unwrap().get_mut(name) {
+                table.entries.clear();
+                table.counters.clear();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn try_recv_digest(&self) -> Option<Digest> {
+        self.digests.lock().unwrap().pop_front()
+    }
+
+    fn dev_port(&self, port: u32, channel: u8) -> Result<u32, RBFRTError> {
+        self.dev_ports
+            .get(&(port, channel))
+            .copied()
+            .ok_or(RBFRTError::PortNotFound {
+                name: format!("{port}/{channel}"),
+            })
+    }
+
+    async fn get_ports(&self) -> Result<Vec<Port>, RBFRTError> {
+        Ok(self.ports.lock().unwrap().clone())
+    }
+
+    async fn add_ports(&self, requests: &[Port]) -> Result<(), RBFRTError> {
+        for request in requests {
+            self.dev_port(
+                request.get_frontpanel_port().0,
+                request.get_frontpanel_port().1,
+            )?;
+        }
+
+        self.ports.lock().unwrap().extend_from_slice(requests);
+
+        Ok(())
+    }
+
+    async fn update_port(&self, request: &Port) -> Result<(), RBFRTError> {
+        let (port, channel) = request.get_frontpanel_port();
+        self.dev_port(port, channel)?;
+
+        let mut ports = self.ports.lock().unwrap();
+        ports.retain(|p| p.get_frontpanel_port() != (port, channel));
+        ports.push(request.clone());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::api::docs::traffic_gen::EXAMPLE_POST_2_REQUEST;
+    use crate::api::server::api_router;
+    use crate::core::archive::Archive;
+    use crate::core::rate_monitor::IS_INGRESS_TABLE;
+    use crate::core::traffic_gen_core::const_definitions::APP_CFG;
+    use crate::core::Config;
+    use crate::{init_state, AppState};
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Method, Request, StatusCode};
+    use axum::response::Response;
+    use rbfrt::table::ToBytes;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    /// Creates the controller state on a Tofino1 [MockSwitch] with 32 ports.
+    pub(crate) async fn mock_state() -> (Arc<MockSwitch>, Arc<AppState>) {
+        let switch = Arc::new(MockSwitch::new(32, 2, false));
+        let (state, _) = init_state(
+            switch.clone(),
+            Config::default_tofino(false),
+            32,
+            false,
+            false,
+            false,
+            Archive::temporary("mock"),
+        )
+        .await
+        .unwrap();
+
+        (switch, state)
+    }
+
+    /// Sends a request with a JSON `body` to the REST API.
+    pub(crate) async fn request(
+        state: &Arc<AppState>,
+        method: Method,
+        uri: &str,
+        body: String,
+    ) -> Response {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap();
+
+        api_router(Arc::clone(state))
+            .oneshot(request)
+            .await
+            .unwrap()
+    }
+
+    /// Sends a request with a JSON `body` to the REST API and returns the status and the JSON response.
+    pub(crate) async fn call(
+        state: &Arc<AppState>,
+        method: Method,
+        uri: &str,
+        body: String,
+    ) -> (StatusCode, serde_json::Value) {
+        let response = request(state, method, uri, body).await;
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    /// Waits until the traffic generation of a test that runs in a task is started.
+    pub(crate) async fn wait_for_traffic_gen(state: &AppState) {
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while !state.traffic_generator.lock().await.running {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("traffic generation not started");
+    }
+
+    fn app_enabled(switch: &MockSwitch, app_id: u8) -> bool {
+        switch
+            .entries(APP_CFG)
+            .iter()
+            .filter(|e| {
+                format!("{:?}", e.get_match_keys().get("app_id"))
+                    == format!("{:?}", Some(MatchValue::exact(app_id)))
+            })
+            .any(|e| {
+                e.get_action_data()
+                    .iter()
+                    .any(|a| a.get_key() == "app_enable" && a.get_data() == &true.to_bytes())
+            })
+    }
+
+    #[tokio::test]
+    async fn traffic_generation_is_programmed_via_rest_api() {
+        let (switch, state) = mock_state().await;
+
+        // monitoring application is always active
+        assert!(app_enabled(&switch, 0));
+        assert!(!app_enabled(&switch, 1));
+
+        let body = serde_json::to_string(&*EXAMPLE_POST_2_REQUEST).unwrap();
+        let (status, _) = call(&state, Method::POST, "/trafficgen", body).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(state.traffic_generator.lock().await.running);
+        assert!(app_enabled(&switch, 1));
+        assert!(!switch.entries(IS_INGRESS_TABLE).is_empty());
+
+        let (status, _) = call(&state, Method::GET, "/trafficgen", String::new()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, _) = call(&state, Method::DELETE, "/trafficgen", String::new()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(!state.traffic_generator.lock().await.running);
+        assert!(!app_enabled(&switch, 1));
+
+        // RTT monitoring is disabled when the test stops
+        assert!(switch.entries(IS_INGRESS_TABLE).is_empty());
+
+        assert!(switch
+            .operations()
+            .iter()
+            .any(|op| matches!(op, SwitchOperation::Update(r) if r.table_name == APP_CFG)));
+    }
+
+    #[tokio::test]
+    async fn injected_digests_are_received() {
+        let switch = MockSwitch::new(32, 2, false);
+        assert!(switch.try_recv_digest().is_none());
+
+        switch.inject_digest(Digest {
+            name: "digest".to_owned(),
+            data: HashMap::new(),
+        });
+
+        assert_eq!(switch.try_recv_digest().unwrap().name, "digest");
+        assert!(switch.try_recv_digest().is_none());
+    }
+}
@@ -0,0 +1,132 @@
+use async_trait::async_trait;
+use rbfrt::error::RBFRTError;
+use rbfrt::register::{self, Register};
+use rbfrt::table::{self, MatchValue, TableEntry};
+use rbfrt::util::{Digest, Port};
+
+mod connection;
+pub mod emulator;
+pub mod mock;
+pub mod trace;
+
+pub use connection::RbfrtBackend;
+pub use emulator::{DataPlaneEmulator, EmulatorConfig};
+pub use mock::MockSwitch;
+pub use trace::{RecordingBackend, ReplayBackend};
+
+/// Abstraction of the switch operations that are used by the controller.
+///
+/// All subsystems (traffic generator, monitors, ARP, port configuration) talk to the
+/// data plane through this trait. [RbfrtBackend] forwards the calls to a Tofino via
+/// BF Runtime, [MockSwitch] keeps the programmed state in memory.
+#[async_trait]
+pub trait SwitchBackend: Send + Sync {
+    /// Checks if the P4 program has a table with the specified `name`.
+    fn has_table(&self, name: &str) -> bool;
+
+    /// Executes a table operation, e.g., synchronization of counters or registers.
+    async fn execute_operation(&self, request: table::Request) -> Result<(), RBFRTError>;
+
+    /// Retrieves the entries of a single table.
+    async fn get_table_entries(
+        &self,
+        request: table::Request,
+    ) -> Result<Vec<TableEntry>, RBFRTError>;
+
+    /// Writes multiple table entries.
+    async fn write_table_entries(&self, requests: Vec<table::Request>) -> Result<(), RBFRTError>;
+
+    /// Updates multiple table entries.
+    async fn update_table_entries(&self, requests: Vec<table::Request>) -> Result<(), RBFRTError>;
+
+    /// Deletes multiple table entries.
+    async fn delete_table_entries(&self, requests: Vec<table::Request>) -> Result<(), RBFRTError>;
+
+    /// Deletes all entries of the tables with the specified `names`.
+    async fn clear_tables(&self, names: Vec<&str>) -> Result<(), RBFRTError>;
+
+    /// Returns the next received digest, if any.
+    fn try_recv_digest(&self) -> Option<Digest>;
+
+    /// Maps the front panel `port` and `channel` to the switch internal dev port.
+    fn dev_port(&self, port: u32, channel: u8) -> Result<u32, RBFRTError>;
+
+    /// Returns a list of all configured ports.
+    async fn get_ports(&self) -> Result<Vec<Port>, RBFRTError>;
+
+    /// Configures all provided [Ports](Port).
+    async fn add_ports(&self, requests: &[Port]) -> Result<(), RBFRTError>;
+
+    /// Deletes the already configured [Port] and adds it with the new configuration.
+    async fn update_port(&self, request: &Port) -> Result<(), RBFRTError>;
+
+    /// Writes a single table entry.
+    async fn write_table_entry(&self, request: table::Request) -> Result<(), RBFRTError> {
+        self.write_table_entries(vec![request]).await
+    }
+
+    /// Updates a single table entry.
+    async fn update_table_entry(&self, request: table::Request) -> Result<(), RBFRTError> {
+        self.update_table_entries(vec![request]).await
+    }
+
+    /// Deletes a single table entry.
+    async fn delete_table_entry(&self, request: table::Request) -> Result<(), RBFRTError> {
+        self.delete_table_entries(vec![request]).await
+    }
+
+    /// Deletes all entries of the table with the specified `name`.
+    async fn clear_table(&self, name: &str) -> Result<(), RBFRTError> {
+        self.clear_tables(vec![name]).await
+    }
+
+    /// Reads the values of multiple indices of a single register.
+    async fn get_register_entries(
+        &self,
+        requests: Vec<register::Request>,
+    ) -> Result<Register, RBFRTError> {
+        let name = match requests.first() {
+            Some(request) => request.get_name().to_owned(),
+            None => return Err(RBFRTError::RequestEmpty {}),
+        };
+
+        let mut entries = vec![];
+
+        for request in &requests {
+            let mut req = table::Request::new(request.get_name());
+
+            if let Some(index) = request.get_index() {
+                req = req.match_key("$REGISTER_INDEX", MatchValue::exact(*index));
+            }
+
+            entries.extend(self.get_table_entries(req).await?);
+        }
+
+        Ok(Register::parse_register_entries(entries, &name))
+    }
+
+    /// Writes values into multiple registers or indices.
+    async fn write_register_entries(
+        &self,
+        requests: Vec<register::Request>,
+    ) -> Result<(), RBFRTError> {
+        let mut write_requests = vec![];
+
+        for request in &requests {
+            let Some(index) = request.get_index() else {
+                return Err(RBFRTError::MissingRegisterIndex);
+            };
+
+            let mut req = table::Request::new(request.get_name())
+                .match_key("$REGISTER_INDEX", MatchValue::exact(*index));
+
+            for (name, value) in request.get_data() {
+                req = req.action_data(name, value.clone());
+            }
+
+            write_requests.push(req);
+        }
+
+        self.write_table_entries(write_requests).await
+    }
+}
@@ -0,0 +1,1029 @@
+use async_trait::async_trait;
+use log::warn;
+use rbfrt::error::RBFRTError;
+use rbfrt::register::{self, Register};
+use rbfrt::table::{self, ActionData, MatchValue, TableEntry, TableOperation, ToBytes};
+use rbfrt::util::{Digest, Port};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Instant;
+
+use crate::core::backend::SwitchBackend;
+
+/// Number of the oldest, not yet replayed records in which a call is searched first.
+const REPLAY_WINDOW: usize = 64;
+
+/// A single line of a switch trace.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceRecord {
+    /// Time in ns since the start of the recording.
+    pub time: u64,
+    #[serde(flatten)]
+    pub event: TraceEvent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TraceEvent {
+    /// A call of the [SwitchBackend] together with its response.
+    Request {
+        call: TraceCall,
+        response: TraceResponse,
+    },
+    /// A digest that was received from the switch.
+    Digest { digest: Digest },
+}
+
+/// A call of the [SwitchBackend] with its arguments.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum TraceCall {
+    HasTable { name: String },
+    ExecuteOperation { request: TraceTableRequest },
+    GetTableEntries { request: TraceTableRequest },
+    WriteTableEntries { requests: Vec<TraceTableRequest> },
+    UpdateTableEntries { requests: Vec<TraceTableRequest> },
+    DeleteTableEntries { requests: Vec<TraceTableRequest> },
+    ClearTables { names: Vec<String> },
+    DevPort { port: u32, channel: u8 },
+    GetPorts,
+    AddPorts { ports: Vec<serde_json::Value> },
+    UpdatePort { port: serde_json::Value },
+    GetRegisterEntries { requests: Vec<TraceRegisterRequest> },
+    WriteRegisterEntries { requests: Vec<TraceRegisterRequest> },
+}
+
+impl TraceCall {
+    /// Checks if both calls use the same method on the same tables or registers.
+    fn same_target(&self, other: &TraceCall) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+            && self.targets() == other.targets()
+    }
+
+    /// Names of the tables or registers that are accessed by the call.
+    fn targets(&self) -> Vec<&str> {
+        match self {
+            TraceCall::HasTable { name } => vec![name],
+            TraceCall::ExecuteOperation { request } | TraceCall::GetTableEntries { request } => {
+                vec![&request.table]
+            }
+            TraceCall::WriteTableEntries { requests }
+            | TraceCall::UpdateTableEntries { requests }
+            | TraceCall::DeleteTableEntries { requests } => {
+                requests.iter().map(|r| r.table.as_str()).collect()
+            }
+            TraceCall::ClearTables { names } => names.iter().map(|n| n.as_str()).collect(),
+            TraceCall::GetRegisterEntries { requests }
+            | TraceCall::WriteRegisterEntries { requests } => {
+                requests.iter().map(|r| r.name.as_str()).collect()
+            }
+            TraceCall::DevPort { .. }
+            | TraceCall::GetPorts
+            | TraceCall::AddPorts { .. }
+            | TraceCall::UpdatePort { .. } => vec![],
+        }
+    }
+}
+
+/// The response of the switch to a [TraceCall].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "result", content = "value", rename_all = "snake_case")]
+pub enum TraceResponse {
+    Ok,
+    Bool(bool),
+    DevPort(u32),
+    TableEntries(Vec<TraceTableEntry>),
+    /// Register entries in the same representation as they are read from the table.
+    Register(Vec<TraceTableEntry>),
+    Ports(Vec<serde_json::Value>),
+    Error(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceMatchValue {
+    Exact { value: Vec<u8> },
+    Range { lower: Vec<u8>, higher: Vec<u8> },
+    Lpm { value: Vec<u8>, prefix_length: i32 },
+    Ternary { value: Vec<u8>, mask: Vec<u8> },
+}
+
+/// Serializable representation of a [table::Request].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceTableRequest {
+    pub table: String,
+    pub match_keys: BTreeMap<String, TraceMatchValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    pub action_data: Vec<(String, Vec<u8>)>,
+    pub action_data_repeated: Vec<(String, Vec<Vec<u8>>)>,
+    pub default_entry: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pipe: Option<u32>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default)]
+    pub operation: String,
+}
+
+/// Serializable representation of a [TableEntry].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceTableEntry {
+    pub table_id: u32,
+    pub table: String,
+    pub match_keys: BTreeMap<String, TraceMatchValue>,
+    pub default_entry: bool,
+    pub action: String,
+    pub action_data: Vec<(String, Vec<u8>)>,
+}
+
+/// Serializable representation of a [register::Request].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceRegisterRequest {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<u32>,
+    pub data: BTreeMap<String, Vec<u8>>,
+}
+
+impl From<&MatchValue> for TraceMatchValue {
+    fn from(value: &MatchValue) -> Self {
+        match value {
+            MatchValue::ExactValue { bytes } => TraceMatchValue::Exact {
+                value: bytes.clone(),
+            },
+            MatchValue::RangeValue {
+                lower_bytes,
+                higher_bytes,
+            } => TraceMatchValue::Range {
+                lower: lower_bytes.clone(),
+                higher: higher_bytes.clone(),
+            },
+            MatchValue::LPM {
+                bytes,
+                prefix_length,
+            } => TraceMatchValue::Lpm {
+                value: bytes.clone(),
+                prefix_length: *prefix_length,
+            },
+            MatchValue::Ternary { value, mask } => TraceMatchValue::Ternary {
+                value: value.clone(),
+                mask: mask.clone(),
+            },
+        }
+    }
+}
+
+impl From<&TraceMatchValue> for MatchValue {
+    fn from(value: &TraceMatchValue) -> Self {
+        match value {
+            TraceMatchValue::Exact { value } => MatchValue::exact(value.clone()),
+            TraceMatchValue::Range { lower, higher } => {
+                MatchValue::range(lower.clone(), higher.clone())
+            }
+            TraceMatchValue::Lpm {
+                value,
+                prefix_length,
+            } => MatchValue::lpm(value.clone(), *prefix_length),
+            TraceMatchValue::Ternary { value, mask } => {
+                MatchValue::ternary(value.clone(), mask.clone())
+            }
+        }
+    }
+}
+
+impl From<&table::Request> for TraceTableRequest {
+    fn from(request: &table::Request) -> Self {
+        TraceTableRequest {
+            table: request.table_name.clone(),
+            match_keys: request
+                .get_match_keys()
+                .iter()
+                .map(|(k, v)| (k.clone(), v.into()))
+                .collect(),
+            action: request
+                .has_action()
+                .then(|| request.get_action_name().to_owned()),
+            action_data: request
+                .get_action_data()
+                .iter()
+                .map(|a| (a.get_key().to_owned(), a.get_data().clone()))
+                .collect(),
+            action_data_repeated: request
+                .get_action_data_repeated()
+                .iter()
+                .map(|a| (a.get_key().to_owned(), a.get_data().clone()))
+                .collect(),
+            default_entry: request.is_default(),
+            pipe: request.get_pipe(),
+            operation: request.get_operation().get_string(),
+        }
+    }
+}
+
+impl From<&TraceTableRequest> for table::Request {
+    fn from(request: &TraceTableRequest) -> Self {
+        let mut req = table::Request::new(&request.table)
+            .match_keys(
+                request
+                    .match_keys
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.into()))
+                    .collect(),
+            )
+            .default(request.default_entry);
+
+        if let Some(action) = &request.action {
+            req = req.action(action);
+        }
+
+        for (key, data) in &request.action_data {
+            req = req.action_data(key, data.clone());
+        }
+
+        for (key, data) in &request.action_data_repeated {
+            req = req.action_data_repeated(key, data.clone());
+        }
+
+        if let Some(pipe) = request.pipe {
+            req = req.pipe(pipe);
+        }
+
+        match request.operation.as_str() {
+            "SyncCounters" => req.operation(TableOperation::SyncCounters),
+            "SyncRegisters" => req.operation(TableOperation::SyncRegister),
+            _ => req,
+        }
+    }
+}
+
+impl From<&TableEntry> for TraceTableEntry {
+    fn from(entry: &TableEntry) -> Self {
+        TraceTableEntry {
+            table_id: entry.table_id,
+            table: entry.table_name.clone(),
+            match_keys: entry
+                .match_keys
+                .iter()
+                .map(|(k, v)| (k.clone(), v.into()))
+                .collect(),
+            default_entry: entry.default_entry,
+            action: entry.action.clone(),
+            action_data: entry
+                .action_data
+                .iter()
+                .map(|a| (a.get_key().to_owned(), a.get_data().clone()))
+                .collect(),
+        }
+    }
+}
+
+impl From<&TraceTableEntry> for TableEntry {
+    fn from(entry: &TraceTableEntry) -> Self {
+        TableEntry {
+            table_id: entry.table_id,
+            table_name: entry.table.clone(),
+            match_keys: entry
+                .match_keys
+                .iter()
+                .map(|(k, v)| (k.clone(), v.into()))
+                .collect(),
+            default_entry: entry.default_entry,
+            action: entry.action.clone(),
+            action_data: entry
+                .action_data
+                .iter()
+                .map(|(k, v)| ActionData::new(k, v.clone()))
+                .collect(),
+        }
+    }
+}
+
+impl From<&register::Request> for TraceRegisterRequest {
+    fn from(request: &register::Request) -> Self {
+        TraceRegisterRequest {
+            name: request.get_name().to_owned(),
+            index: *request.get_index(),
+            data: request
+                .get_data()
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+        }
+    }
+}
+
+/// Converts a [Register] into one table entry per index.
+/// Values of multiple pipes are stored as repeated action data, as they are read from the switch.
+fn register_to_trace(register: &Register) -> Vec<TraceTableEntry> {
+    let mut entries: Vec<TraceTableEntry> = register
+        .entries()
+        .iter()
+        .map(|(index, entry)| TraceTableEntry {
+            table_id: 0,
+            table: register.name().to_owned(),
+            match_keys: BTreeMap::from([(
+                "$REGISTER_INDEX".to_owned(),
+                TraceMatchValue::Exact {
+                    value: index.to_bytes(),
+                },
+            )]),
+            default_entry: false,
+            action: String::new(),
+            action_data: entry
+                .get_data()
+                .iter()
+                .collect::<BTreeMap<_, _>>()
+                .into_iter()
+                .flat_map(|(key, values)| values.iter().map(|v| (key.clone(), v.clone())))
+                .collect(),
+        })
+        .collect();
+
+    entries.sort_by_key(|e| match e.match_keys.get("$REGISTER_INDEX") {
+        Some(TraceMatchValue::Exact { value }) => value.to_u32(),
+        _ => 0,
+    });
+
+    entries
+}
+
+/// [Port] serializes its dev port as `pid` but deserializes it from `dev_port`.
+fn port_from_trace(value: &serde_json::Value) -> Option<Port> {
+    let mut value = value.clone();
+
+    if let Some(object) = value.as_object_mut() {
+        if let Some(pid) = object.remove("pid") {
+            object.insert("dev_port".to_owned(), pid);
+        }
+    }
+
+    serde_json::from_value(value).ok()
+}
+
+fn port_to_trace(port: &Port) -> serde_json::Value {
+    serde_json::to_value(port).unwrap_or_default()
+}
+
+fn response<T>(
+    result: &Result<T, RBFRTError>,
+    map: impl FnOnce(&T) -> TraceResponse,
+) -> TraceResponse {
+    match result {
+        Ok(value) => map(value),
+        Err(err) => TraceResponse::Error(err.to_string()),
+    }
+}
+
+/// [SwitchBackend] that forwards all operations to another backend and writes them,
+/// together with their responses and all received digests, into a trace file.
+///
+/// The trace contains one JSON encoded [TraceRecord] per line and can be replayed with the [ReplayBackend].
+/// The records are written by a separate thread so that switch operations are not delayed by the file system.
+pub struct RecordingBackend {
+    inner: Arc<dyn SwitchBackend>,
+    trace: mpsc::Sender<TraceMessage>,
+    start: Instant,
+}
+
+/// Message to the thread that writes the trace file.
+enum TraceMessage {
+    Record(TraceRecord),
+    /// Flushes the trace and acknowledges it on the sender.
+    #[cfg(test)]
+    Flush(mpsc::Sender<()>),
+}
+
+impl RecordingBackend {
+    /// Records all operations on `inner` into the file at `path`. An existing file is overwritten.
+    pub fn create(
+        inner: Arc<dyn SwitchBackend>,
+        path: impl AsRef<Path>,
+    ) -> std::io::Result<RecordingBackend> {
+        let mut trace = BufWriter::new(File::create(path)?);
+        let (sender, receiver) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            while let Ok(message) = receiver.recv() {
+                // all pending records are written before the trace is flushed
+                // the trace is complete as soon as the switch is idle, e.g., if the controller crashes
+                for message in std::iter::once(message).chain(receiver.try_iter()) {
+                    match message {
+                        TraceMessage::Record(record) => {
+                            let result = serde_json::to_writer(&mut trace, &record)
+                                .map_err(std::io::Error::from)
+                                .and_then(|_| trace.write_all(b"\n"));
+
+                            if let Err(err) = result {
+                                warn!("Unable to write switch trace: {err}");
+                            }
+                        }
+                        #[cfg(test)]
+                        TraceMessage::Flush(ack) => {
+                            let _ = trace.flush();
+                            let _ = ack.send(());
+                        }
+                    }
+                }
+
+                if let Err(err) = trace.flush() {
+                    warn!("Unable to write switch trace: {err}");
+                }
+            }
+        });
+
+        Ok(RecordingBackend {
+            inner,
+            trace: sender,
+            start: Instant::now(),
+        })
+    }
+
+    fn record(&self, event: TraceEvent) {
+        let record = TraceRecord {
+            time: self.start.elapsed().as_nanos() as u64,
+            event,
+        };
+
+        if self.trace.send(TraceMessage::Record(record)).is_err() {
+            warn!("Unable to write switch trace: writer stopped");
+        }
+    }
+
+    /// Waits until all records so far are written to the trace file.
+    #[cfg(test)]
+    fn flush(&self) {
+        let (ack, done) = mpsc::channel();
+
+        if self.trace.send(TraceMessage::Flush(ack)).is_ok() {
+            let _ = done.recv();
+        }
+    }
+
+    fn record_request(&self, call: TraceCall, response: TraceResponse) {
+        self.record(TraceEvent::Request { call, response });
+    }
+}
+
+#[async_trait]
+impl SwitchBackend for RecordingBackend {
+    fn has_table(&self, name: &str) -> bool {
+        let result = self.inner.has_table(name);
+        self.record_request(
+            TraceCall::HasTable {
+                name: name.to_owned(),
+            },
+            TraceResponse::Bool(result),
+        );
+        result
+    }
+
+    async fn execute_operation(&self, request: table::Request) -> Result<(), RBFRTError> {
+        let call = TraceCall::ExecuteOperation {
+            request: (&request).into(),
+        };
+        let result = self.inner.execute_operation(request).await;
+        self.record_request(call, response(&result, |_| TraceResponse::Ok));
+        result
+    }
+
+    async fn get_table_entries(
+        &self,
+        request: table::Request,
+    ) -> Result<Vec<TableEntry>, RBFRTError> {
+        let call = TraceCall::GetTableEntries {
+            request: (&request).into(),
+        };
+        let result = self.inner.get_table_entries(request).await;
+        self.record_request(
+            call,
+            response(&result, |entries| {
+                TraceResponse::TableEntries(entries.iter().map(|e| e.into()).collect())
+            }),
+        );
+        result
+    }
+
+    async fn write_table_entries(&self, requests: Vec<table::Request>) -> Result<(), RBFRTError> {
+        let call = TraceCall::WriteTableEntries {
+            requests: requests.iter().map(|r| r.into()).collect(),
+        };
+        let result = self.inner.write_table_entries(requests).await;
+        self.record_request(call, response(&result, |_| TraceResponse::Ok));
+        result
+    }
+
+    async fn update_table_entries(&self, requests: Vec<table::Request>) -> Result<(), RBFRTError> {
+        let call = TraceCall::UpdateTableEntries {
+            requests: requests.iter().map(|r| r.into()).collect(),
+        };
+        let result = self.inner.update_table_entries(requests).await;
+        self.record_request(call, response(&result, |_| TraceResponse::Ok));
+        result
+    }
+
+    async fn delete_table_entries(&self, requests: Vec<table::Request>) -> Result<(), RBFRTError> {
+        let call = TraceCall::DeleteTableEntries {
+            requests: requests.iter().map(|r| r.into()).collect(),
+        };
+        let result = self.inner.delete_table_entries(requests).await;
+        self.record_request(call, response(&result, |_| TraceResponse::Ok));
+        result
+    }
+
+    async fn clear_tables(&self, names: Vec<&str>) -> Result<(), RBFRTError> {
+        let call = TraceCall::ClearTables {
+            names: names.iter().map(|n| (*n).to_owned()).collect(),
+        };
+        let result = self.inner.clear_tables(names).await;
+        self.record_request(call, response(&result, |_| TraceResponse::Ok));
+        result
+    }
+
+    fn try_recv_digest(&self) -> Option<Digest> {
+        let digest = self.inner.try_recv_digest();
+
+        if let Some(digest) = &digest {
+            self.record(TraceEvent::Digest {
+                digest: digest.clone(),
+            });
+        }
+
+        digest
+    }
+
+    fn dev_port(&self, port: u32, channel: u8) -> Result<u32, RBFRTError> {
+        let result = self.inner.dev_port(port, channel);
+        self.record_request(
+            TraceCall::DevPort { port, channel },
+            response(&result, |p| TraceResponse::DevPort(*p)),
+        );
+        result
+    }
+
+    async fn get_ports(&self) -> Result<Vec<Port>, RBFRTError> {
+        let result = self.inner.get_ports().await;
+        self.record_request(
+            TraceCall::GetPorts,
+            response(&result, |ports| {
+                TraceResponse::Ports(ports.iter().map(port_to_trace).collect())
+            }),
+        );
+        result
+    }
+
+    async fn add_ports(&self, requests: &[Port]) -> Result<(), RBFRTError> {
+        let result = self.inner.add_ports(requests).await;
+        self.record_request(
+            TraceCall::AddPorts {
+                ports: requests.iter().map(port_to_trace).collect(),
+            },
+            response(&result, |_| TraceResponse::Ok),
+        );
+        result
+    }
+
+    async fn update_port(&self, request: &Port) -> Result<(), RBFRTError> {
+        let result = self.inner.update_port(request).await;
+        self.record_request(
+            TraceCall::UpdatePort {
+                port: port_to_trace(request),
+            },
+            response(&result, |_| TraceResponse::Ok),
+        );
+        result
+    }
+
+    async fn write_table_entry(&self, request: table::Request) -> Result<(), RBFRTError> {
+        let call = TraceCall::WriteTableEntries {
+            requests: vec![(&request).into()],
+        };
+        let result = self.inner.write_table_entry(request).await;
+        self.record_request(call, response(&result, |_| TraceResponse::Ok));
+        result
+    }
+
+    async fn update_table_entry(&self, request: table::Request) -> Result<(), RBFRTError> {
+        let call = TraceCall::UpdateTableEntries {
+            requests: vec![(&request).into()],
+        };
+        let result = self.inner.update_table_entry(request).await;
+        self.record_request(call, response(&result, |_| TraceResponse::Ok));
+        result
+    }
+
+    async fn delete_table_entry(&self, request: table::Request) -> Result<(), RBFRTError> {
+        let call = TraceCall::DeleteTableEntries {
+            requests: vec![(&request).into()],
+        };
+        let result = self.inner.delete_table_entry(request).await;
+        self.record_request(call, response(&result, |_| TraceResponse::Ok));
+        result
+    }
+
+    async fn clear_table(&self, name: &str) -> Result<(), RBFRTError> {
+        let result = self.inner.clear_table(name).await;
+        self.record_request(
+            TraceCall::ClearTables {
+                names: vec![name.to_owned()],
+            },
+            response(&result, |_| TraceResponse::Ok),
+        );
+        result
+    }
+
+    async fn get_register_entries(
+        &self,
+        requests: Vec<register::Request>,
+    ) -> Result<Register, RBFRTError> {
+        let call = TraceCall::GetRegisterEntries {
+            requests: requests.iter().map(|r| r.into()).collect(),
+        };
+        let result = self.inner.get_register_entries(requests).await;
+        self.record_request(
+            call,
+            response(&result, |register| {
+                TraceResponse::Register(register_to_trace(register))
+            }),
+        );
+        result
+    }
+
+    async fn write_register_entries(
+        &self,
+        requests: Vec<register::Request>,
+    ) -> Result<(), RBFRTError> {
+        let call = TraceCall::WriteRegisterEntries {
+            requests: requests.iter().map(|r| r.into()).collect(),
+        };
+        let result = self.inner.write_register_entries(requests).await;
+        self.record_request(call, response(&result, |_| TraceResponse::Ok));
+        result
+    }
+}
+
+struct ReplayState {
+    /// Recorded requests that were not replayed yet.
+    requests: VecDeque<(TraceCall, TraceResponse)>,
+    /// Recorded digests with their time of reception.
+    digests: VecDeque<(u64, Digest)>,
+}
+
+/// [SwitchBackend] that answers all operations from a trace that was written by the [RecordingBackend].
+///
+/// Each call is answered with the response of the oldest, not yet replayed record of the same call.
+/// If the controller diverges from the trace, e.g., due to randomized packet contents, the oldest record
+/// of the same method on the same tables is used instead. Both are first searched in the oldest [REPLAY_WINDOW] records,
+/// so that repeated calls do not consume records far ahead of the current position in the trace.
+/// Digests are returned with the same timing as they were recorded.
+pub struct ReplayBackend {
+    state: Mutex<ReplayState>,
+    start: Instant,
+}
+
+impl ReplayBackend {
+    /// Loads the trace from the file at `path`.
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<ReplayBackend> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut records = vec![];
+
+        for line in reader.lines() {
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            records.push(serde_json::from_str::<TraceRecord>(&line)?);
+        }
+
+        Ok(ReplayBackend::new(records))
+    }
+
+    pub fn new(records: Vec<TraceRecord>) -> ReplayBackend {
+        let mut requests = VecDeque::new();
+        let mut digests = VecDeque::new();
+
+        for record in records {
+            match record.event {
+                TraceEvent::Request { call, response } => requests.push_back((call, response)),
+                TraceEvent::Digest { digest } => digests.push_back((record.time, digest)),
+            }
+        }
+
+        ReplayBackend {
+            state: Mutex::new(ReplayState { requests, digests }),
+            start: Instant::now(),
+        }
+    }
+
+    /// Takes the recorded response of `call` from the trace.
+    fn replay(&self, call: TraceCall) -> Result<TraceResponse, RBFRTError> {
+        let mut state = self.state.lock().unwrap();
+
+        let find = |limit: usize| {
+            let window = || state.requests.iter().take(limit);
+
+            window().position(|(c, _)| *c == call).or_else(|| {
+                let position = window().position(|(c, _)| c.same_target(&call));
+
+                if position.is_some() {
+                    warn!("Request diverges from the switch trace: {call:?}");
+                }
+
+                position
+            })
+        };
+
+        let position = find(REPLAY_WINDOW).or_else(|| find(usize::MAX));
+
+        let Some((_, response)) = position.and_then(|p| state.requests.remove(p)) else {
+            return Err(RBFRTError::GRPCError {
+                message: "Request is not contained in the switch trace".to_owned(),
+                details: format!("{call:?}"),
+            });
+        };
+
+        match response {
+            TraceResponse::Error(message) => Err(RBFRTError::GRPCError {
+                message,
+                details: "Replayed from switch trace".to_owned(),
+            }),
+            response => Ok(response),
+        }
+    }
+
+    fn replay_ok(&self, call: TraceCall) -> Result<(), RBFRTError> {
+        self.replay(call).map(|_| ())
+    }
+}
+
+/// Error that is returned if a recorded response does not fit to the call.
+fn unexpected_response(response: TraceResponse) -> RBFRTError {
+    RBFRTError::GRPCError {
+        message: "Unexpected response in switch trace".to_owned(),
+        details: format!("{response:?}"),
+    }
+}
+
+#[async_trait]
+impl SwitchBackend for ReplayBackend {
+    fn has_table(&self, name: &str) -> bool {
+        matches!(
+            self.replay(TraceCall::HasTable {
+                name: name.to_owned()
+            }),
+            Ok(TraceResponse::Bool(true))
+        )
+    }
+
+    async fn execute_operation(&self, request: table::Request) -> Result<(), RBFRTError> {
+        self.replay_ok(TraceCall::ExecuteOperation {
+            request: (&request).into(),
+        })
+    }
+
+    async fn get_table_entries(
+        &self,
+        request: table::Request,
+    ) -> Result<Vec<TableEntry>, RBFRTError> {
+        match self.replay(TraceCall::GetTableEntries {
+            request: (&request).into(),
+        })? {
+            TraceResponse::TableEntries(entries) => Ok(entries.iter().map(|e| e.into()).collect()),
+            response => Err(unexpected_response(response)),
+        }
+    }
+
+    async fn write_table_entries(&self, requests: Vec<table::Request>) -> Result<(), RBFRTError> {
+        self.replay_ok(TraceCall::WriteTableEntries {
+            requests: requests.iter().map(|r| r.into()).collect(),
+        })
+    }
+
+    async fn update_table_entries(&self, requests: Vec<table::Request>) -> Result<(), RBFRTError> {
+        self.replay_ok(TraceCall::UpdateTableEntries {
+            requests: requests.iter().map(|r| r.into()).collect(),
+        })
+    }
+
+    async fn delete_table_entries(&self, requests: Vec<table::Request>) -> Result<(), RBFRTError> {
+        self.replay_ok(TraceCall::DeleteTableEntries {
+            requests: requests.iter().map(|r| r.into()).collect(),
+        })
+    }
+
+    async fn clear_tables(&self, names: Vec<&str>) -> Result<(), RBFRTError> {
+        self.replay_ok(TraceCall::ClearTables {
+            names: names.iter().map(|n| (*n).to_owned()).collect(),
+        })
+    }
+
+    fn try_recv_digest(&self) -> Option<Digest> {
+        let elapsed = self.start.elapsed().as_nanos() as u64;
+        let mut state = self.state.lock().unwrap();
+
+        match state.digests.front() {
+            Some((time, _)) if *time <= elapsed => state.digests.pop_front().map(|(_, d)| d),
+            _ => None,
+        }
+    }
+
+    fn dev_port(&self, port: u32, channel: u8) -> Result<u32, RBFRTError> {
+        match self.replay(TraceCall::DevPort { port, channel })? {
+            TraceResponse::DevPort(dev_port) => Ok(dev_port),
+            response => Err(unexpected_response(response)),
+        }
+    }
+
+    async fn get_ports(&self) -> Result<Vec<Port>, RBFRTError> {
+        match self.replay(TraceCall::GetPorts)? {
+            TraceResponse::Ports(ports) => Ok(ports.iter().filter_map(port_from_trace).collect()),
+            response => Err(unexpected_response(response)),
+        }
+    }
+
+    async fn add_ports(&self, requests: &[Port]) -> Result<(), RBFRTError> {
+        self.replay_ok(TraceCall::AddPorts {
+            ports: requests.iter().map(port_to_trace).collect(),
+        })
+    }
+
+    async fn update_port(&self, request: &Port) -> Result<(), RBFRTError> {
+        self.replay_ok(TraceCall::UpdatePort {
+            port: port_to_trace(request),
+        })
+    }
+
+    async fn get_register_entries(
+        &self,
+        requests: Vec<register::Request>,
+    ) -> Result<Register, RBFRTError> {
+        let name = match requests.first() {
+            Some(request) => request.get_name().to_owned(),
+            None => return Err(RBFRTError::RequestEmpty {}),
+        };
+
+        match self.replay(TraceCall::GetRegisterEntries {
+            requests: requests.iter().map(|r| r.into()).collect(),
+        })? {
+            TraceResponse::Register(entries) => Ok(Register::parse_register_entries(
+                entries.iter().map(|e| e.into()).collect(),
+                &name,
+            )),
+            response => Err(unexpected_response(response)),
+        }
+    }
+
+    async fn write_register_entries(
+        &self,
+        requests: Vec<register::Request>,
+    ) -> Result<(), RBFRTError> {
+        self.replay_ok(TraceCall::WriteRegisterEntries {
+            requests: requests.iter().map(|r| r.into()).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::docs::traffic_gen::EXAMPLE_POST_2_REQUEST;
+    use crate::api::server::api_router;
+    use crate::core::archive::Archive;
+    use crate::core::backend::MockSwitch;
+    use crate::core::Config;
+    use crate::{init_state, AppState};
+    use axum::body::Body;
+    use axum::http::{Method, Request, StatusCode};
+    use std::collections::HashMap;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    async fn start_traffic_gen(state: &Arc<AppState>) -> StatusCode {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/trafficgen")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                serde_json::to_string(&*EXAMPLE_POST_2_REQUEST).unwrap(),
+            ))
+            .unwrap();
+
+        api_router(Arc::clone(state))
+            .oneshot(request)
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn recorded_session_is_replayed() {
+        let path = std::env::temp_dir().join(format!("p4tg-trace-{}.jsonl", std::process::id()));
+
+        let mock = Arc::new(MockSwitch::new(32, 2, false));
+        let recorder = Arc::new(RecordingBackend::create(mock.clone(), &path).unwrap());
+        let (state, _) = init_state(
+            recorder.clone(),
+            Config::default_tofino(false),
+            32,
+            false,
+            false,
+            false,
+            Archive::temporary("trace-record"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(start_traffic_gen(&state).await, StatusCode::OK);
+
+        mock.inject_digest(Digest {
+            name: "digest".to_owned(),
+            data: HashMap::from([("port".to_owned(), 5u32.to_bytes())]),
+        });
+        assert!(recorder.try_recv_digest().is_some());
+
+        let mock_ports = mock.get_ports().await.unwrap();
+        recorder.flush();
+
+        let replay = Arc::new(ReplayBackend::open(&path).unwrap());
+        // digests are replayed with their recorded timing
+        let digests = ReplayBackend::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let (state, _) = init_state(
+            replay.clone(),
+            Config::default_tofino(false),
+            32,
+            false,
+            false,
+            false,
+            Archive::temporary("trace-replay"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(start_traffic_gen(&state).await, StatusCode::OK);
+        assert!(state.traffic_generator.lock().await.running);
+
+        // requests that are not part of the trace fail
+        assert!(replay.dev_port(1000, 0).is_err());
+        assert!(replay.state.lock().unwrap().requests.is_empty());
+
+        // ports keep their dev port
+        let ports = port_from_trace(&port_to_trace(&mock_ports[0])).unwrap();
+        assert_eq!(ports.get_dev_port(), mock_ports[0].get_dev_port());
+
+        let mut digest = None;
+        for _ in 0..100 {
+            digest = digests.try_recv_digest();
+            if digest.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        let digest = digest.unwrap();
+        assert_eq!(digest.name, "digest");
+        assert_eq!(digest.data["port"].to_u32(), 5);
+    }
+
+    #[test]
+    fn replay_prefers_records_close_to_the_current_position() {
+        let request = |call: TraceCall, response: TraceResponse| TraceRecord {
+            time: 0,
+            event: TraceEvent::Request { call, response },
+        };
+
+        let mut records = vec![request(
+            TraceCall::DevPort {
+                port: 1,
+                channel: 0,
+            },
+            TraceResponse::DevPort(10),
+        )];
+        records.extend((0..2 * REPLAY_WINDOW).map(|_| {
+            request(
+                TraceCall::HasTable {
+                    name: "table".to_owned(),
+                },
+                TraceResponse::Bool(true),
+            )
+        }));
+        records.push(request(
+            TraceCall::DevPort {
+                port: 2,
+                channel: 0,
+            },
+            TraceResponse::DevPort(20),
+        ));
+
+        let replay = ReplayBackend::new(records);
+
+        // the diverging call at the head of the trace is used instead of the same call far ahead
+        assert_eq!(replay.dev_port(2, 0).unwrap(), 10);
+        // records outside of the window are still found
+        assert_eq!(replay.dev_port(2, 0).unwrap(), 20);
+        assert!(replay.dev_port(2, 0).is_err());
+    }
+}
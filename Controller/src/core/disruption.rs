@@ -0,0 +1,276 @@
+/* Copyright 2022-present University of Tuebingen, Chair of Communication Networks
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/*
+ * Steffen Lindner (steffen.lindner@uni-tuebingen.de)
+ */
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::core::statistics::{ElapsedTime, RateMonitorStatistics};
+use crate::core::traffic_gen_core::const_definitions::MONITORING_PACKET_INTERVAL;
+use crate::core::traffic_gen_core::helper::calculate_overhead;
+use crate::core::traffic_gen_core::types::Stream;
+
+/// Disruption of the traffic received on a port or of a stream, e.g., during a failover of the DUT.
+#[derive(Serialize, Debug, Clone, Default, ToSchema)]
+pub struct DisruptionStatistics {
+    /// Total disruption time in seconds, i.e., the lost packets divided by the TX packet rate.
+    pub(crate) disruption_time: f64,
+    /// Outage episodes, i.e., periods with consecutive packet loss.
+    pub(crate) outages: Vec<Outage>,
+}
+
+/// An outage episode. An episode ends if no packets are lost for one monitoring interval.
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct Outage {
+    /// Estimated start of the outage in seconds since the start of the test.
+    pub(crate) start: f64,
+    /// Duration of the outage in seconds, i.e., the lost packets divided by the TX packet rate.
+    pub(crate) duration: f64,
+    /// Number of packets lost during the outage.
+    pub(crate) lost_packets: u64,
+    /// Indicates whether the outage is still ongoing.
+    pub(crate) ongoing: bool,
+}
+
+/// Tracks the disruption of a single port or stream based on its cumulative packet loss counter.
+#[derive(Debug, Clone, Default)]
+struct DisruptionTracker {
+    statistics: DisruptionStatistics,
+    last_loss: u64,
+    /// Time at which packets have been lost the last time
+    last_loss_time: Option<ElapsedTime>,
+}
+
+impl DisruptionTracker {
+    /// Updates the tracker with the cumulative packet loss `loss` at time `time`.
+    /// `packet_rate` is the TX packet rate of the traffic in packets per second.
+    fn update(&mut self, loss: u64, packet_rate: f64, time: ElapsedTime) {
+        // the loss counters have been reset
+        if loss < self.last_loss {
+            self.last_loss = loss;
+        }
+
+        // lost packets are accounted as soon as the TX packet rate is known
+        if packet_rate <= 0f64 {
+            return;
+        }
+
+        let lost = loss - self.last_loss;
+        self.last_loss = loss;
+
+        if lost == 0 {
+            let episode_ended = self.last_loss_time.is_some_and(|t| {
+                time.0.saturating_sub(t.0) >= MONITORING_PACKET_INTERVAL / 1_000_000
+            });
+
+            if episode_ended {
+                if let Some(outage) = self.statistics.outages.last_mut() {
+                    outage.ongoing = false;
+                }
+                self.last_loss_time = None;
+            }
+
+            return;
+        }
+
+        let disruption = lost as f64 / packet_rate;
+        self.statistics.disruption_time += disruption;
+
+        match self.statistics.outages.last_mut() {
+            Some(outage) if outage.ongoing => {
+                outage.duration += disruption;
+                outage.lost_packets += lost;
+            }
+            _ => self.statistics.outages.push(Outage {
+                // the packets have been lost until now
+                start: (time.as_secs_f64() - disruption).max(0f64),
+                duration: disruption,
+                lost_packets: lost,
+                ongoing: true,
+            }),
+        }
+
+        self.last_loss_time = Some(time);
+    }
+}
+
+/// Calculates the disruption time and detects outage episodes per RX port and stream
+/// from the packet loss counters that are reported by the monitoring packets.
+#[derive(Debug, Clone, Default)]
+pub struct DisruptionMonitor {
+    /// TX ports whose traffic is received on an RX port
+    tx_ports: HashMap<u32, Vec<u32>>,
+    /// L2 frame size in bytes per app id
+    frame_sizes: HashMap<u32, u32>,
+    ports: HashMap<u32, DisruptionTracker>,
+    streams: HashMap<u32, HashMap<u32, DisruptionTracker>>,
+}
+
+impl DisruptionMonitor {
+    /// Configures the monitor for a new test and resets all trackers.
+    ///
+    /// - `tx_rx_port_mapping`: Mapping of TX dev ports to RX dev ports
+    /// - `streams`: Active streams of the test
+    pub fn configure(&mut self, tx_rx_port_mapping: &HashMap<String, u32>, streams: &[Stream]) {
+        self.tx_ports.clear();
+        for (tx, rx) in tx_rx_port_mapping {
+            if let Ok(tx) = tx.parse() {
+                self.tx_ports.entry(*rx).or_default().push(tx);
+            }
+        }
+
+        self.frame_sizes = streams
+            .iter()
+            .map(|s| (s.app_id as u32, s.frame_size + calculate_overhead(s)))
+            .collect();
+
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        self.ports.clear();
+        self.streams.clear();
+    }
+
+    /// Updates the disruption of RX port `port` and of stream `app_id` on that port
+    /// with the packet loss in `statistics` at time `time`.
+    /// The packet loss of the port is the sum over all streams of the port.
+    /// The TX packet rates are calculated from the rates in `statistics`.
+    pub fn record(
+        &mut self,
+        statistics: &RateMonitorStatistics,
+        port: u32,
+        app_id: u32,
+        time: ElapsedTime,
+    ) {
+        let tx_ports = self.tx_ports.get(&port).cloned().unwrap_or_default();
+
+        // each packet has 20 bytes of L1 overhead
+        let port_packet_rate: f64 = tx_ports
+            .iter()
+            .map(|tx| {
+                let l1 = statistics.tx_rate_l1.get(tx).copied().unwrap_or_default();
+                let l2 = statistics.tx_rate_l2.get(tx).copied().unwrap_or_default();
+                (l1 - l2).max(0f64) / (20f64 * 8f64)
+            })
+            .sum();
+
+        let stream_packet_rate: f64 = match self.frame_sizes.get(&app_id) {
+            Some(&frame_size) if frame_size > 0 => tx_ports
+                .iter()
+                .filter_map(|tx| statistics.app_tx_l2.get(tx)?.get(&app_id))
+                .map(|rate| rate / (frame_size as f64 * 8f64))
+                .sum(),
+            _ => 0f64,
+        };
+
+        if let Some(per_app) = statistics.app_packet_loss.get(&port) {
+            self.ports.entry(port).or_default().update(
+                per_app.values().sum(),
+                port_packet_rate,
+                time,
+            );
+        }
+
+        if let Some(loss) = statistics
+            .app_packet_loss
+            .get(&port)
+            .and_then(|l| l.get(&app_id))
+        {
+            self.streams
+                .entry(port)
+                .or_default()
+                .entry(app_id)
+                .or_default()
+                .update(*loss, stream_packet_rate, time);
+        }
+    }
+
+    /// Returns the disruption statistics per RX port.
+    pub fn port_statistics(&self) -> HashMap<u32, DisruptionStatistics> {
+        self.ports
+            .iter()
+            .map(|(port, tracker)| (*port, tracker.statistics.clone()))
+            .collect()
+    }
+
+    /// Returns the disruption statistics per RX port and stream.
+    pub fn stream_statistics(&self) -> HashMap<u32, HashMap<u32, DisruptionStatistics>> {
+        self.streams
+            .iter()
+            .map(|(port, per_app)| {
+                let per_app = per_app
+                    .iter()
+                    .map(|(app_id, tracker)| (*app_id, tracker.statistics.clone()))
+                    .collect();
+                (*port, per_app)
+            })
+            .collect()
+    }
+
+    /// Returns the disruption time of RX port `port` and of stream `app_id` on that port.
+    pub fn disruption_time(&self, port: u32, app_id: u32) -> (Option<f64>, Option<f64>) {
+        (
+            self.ports.get(&port).map(|t| t.statistics.disruption_time),
+            self.streams
+                .get(&port)
+                .and_then(|s| s.get(&app_id))
+                .map(|t| t.statistics.disruption_time),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outage_episodes_are_detected() {
+        let mut tracker = DisruptionTracker::default();
+
+        // 1 Mpps, no loss until 2 s
+        tracker.update(0, 1e6, ElapsedTime(1500));
+        tracker.update(0, 1e6, ElapsedTime(2000));
+        // link is down from 2.3 s to 3.1 s
+        tracker.update(200_000, 1e6, ElapsedTime(2500));
+        tracker.update(700_000, 1e6, ElapsedTime(3000));
+        tracker.update(800_000, 1e6, ElapsedTime(3500));
+
+        let outage = &tracker.statistics.outages[0];
+        assert!(outage.ongoing);
+        assert!((outage.start - 2.3).abs() < 1e-9);
+
+        // no loss for one monitoring interval
+        tracker.update(800_000, 1e6, ElapsedTime(4000));
+        assert!(!tracker.statistics.outages[0].ongoing);
+        tracker.update(800_000, 1e6, ElapsedTime(4500));
+
+        // second outage of 50 ms
+        tracker.update(850_000, 1e6, ElapsedTime(5000));
+
+        let statistics = &tracker.statistics;
+        assert!((statistics.disruption_time - 0.85).abs() < 1e-9);
+        assert_eq!(statistics.outages.len(), 2);
+        assert!(!statistics.outages[0].ongoing);
+        assert_eq!(statistics.outages[0].lost_packets, 800_000);
+        assert!((statistics.outages[0].duration - 0.8).abs() < 1e-9);
+        assert!((statistics.outages[1].start - 4.95).abs() < 1e-9);
+    }
+}
@@ -28,6 +28,9 @@ use std::sync::Arc;
 use tokio::{task::JoinHandle, time::Instant};
 use tokio_util::sync::CancellationToken;
 
+use super::archive::{archive_current_test, is_archivable};
+use super::sla::SlaMonitor;
+use super::statistics_stream::StatisticsEvent;
 use super::traffic_gen_core::types::TrafficGenData;
 use crate::api::traffic_gen::start_single_test;
 
@@ -40,14 +43,14 @@ impl DurationMonitorTask {
     /// Monitors the duration of a test and regularly checks if the test has been aborted or if a cancellation token was sent. Stops traffic generation after duration exceeded.
     ///
     /// - `state`: App state that holds DurationMonitor
-    /// - `duration_secs`: Duration to wait in seconds
+    /// - `duration`: Duration to wait
     /// - `cancel_token`: The CancellationToken for this task
     async fn monitor_test_duration(
         state: Arc<AppState>,
-        duration_secs: u32,
+        duration: Duration,
         cancel_token: CancellationToken,
     ) {
-        let deadline = Instant::now() + Duration::from_secs_f64(duration_secs as f64);
+        let deadline = Instant::now() + duration;
         let mut interval = tokio::time::interval(Duration::from_millis(100));
 
         loop {
@@ -81,14 +84,36 @@ impl DurationMonitorTask {
         };
 
         if running {
+            // a cancelled monitor task is stopped by its caller that reports the state transition and archives the test
+            let elapsed = !cancel_token.is_cancelled();
+            let archive = elapsed && is_archivable(&state).await;
+
+            if elapsed {
+                // the acceptance criteria are evaluated a last time on the final statistics
+                SlaMonitor::evaluate(&state).await;
+            }
+
             // Perform the shutdown
             let tg = &state.traffic_generator;
-            let switch = &state.switch;
 
-            match tg.lock().await.stop(switch).await {
+            let mut tg = tg.lock().await;
+
+            match tg.stop_traffic_generation(&state).await {
                 Ok(_) => {
                     info!("Traffic generation stopped after duration.");
                     state.experiment.lock().await.running = false;
+
+                    if elapsed {
+                        state.events.emit(StatisticsEvent::DurationElapsed {
+                            name: tg.name.clone(),
+                        });
+                    }
+
+                    drop(tg);
+
+                    if archive {
+                        archive_current_test(&state).await;
+                    }
                 }
                 Err(e) => {
                     error!("Error while stopping traffic generation: {e}");
@@ -102,12 +127,22 @@ impl DurationMonitorTask {
     /// - `state`: App state that holds DurationMonitor
     /// - `duration_secs`: Duration to wait in seconds
     pub async fn start(&mut self, state: &Arc<AppState>, duration_secs: u32) {
+        self.start_with_duration(state, Duration::from_secs(duration_secs as u64))
+            .await;
+    }
+
+    /// Starts a duration monitor task that waits for `duration` and stops traffic generation afterwards.
+    /// In contrast to [DurationMonitorTask::start], the duration may be shorter than a second.
+    ///
+    /// - `state`: App state that holds DurationMonitor
+    /// - `duration`: Duration to wait
+    pub async fn start_with_duration(&mut self, state: &Arc<AppState>, duration: Duration) {
         let state_clone = state.clone();
         let cancel_token = CancellationToken::new();
         let cancel_token_clone = cancel_token.clone();
 
         let handle = tokio::spawn(async move {
-            Self::monitor_test_duration(state_clone, duration_secs, cancel_token_clone).await
+            Self::monitor_test_duration(state_clone, duration, cancel_token_clone).await
         });
 
         self.handle = Some(handle);
@@ -136,6 +171,12 @@ impl DurationMonitorTask {
                 // Start the test
                 let idx = idx + 1;
 
+                state_clone.events.emit(StatisticsEvent::NextTest {
+                    index: idx,
+                    total: num_tests,
+                    name: traffic_gen_data.name.clone(),
+                });
+
                 let _ = start_single_test(&state_clone, traffic_gen_data.clone()).await;
 
                 loop {
@@ -179,9 +220,20 @@ impl DurationMonitorTask {
         let stats = get_statistics(state).await[0].clone();
         let mut stats_lock = state.multiple_tests.collected_statistics.lock().await;
         stats_lock.push(stats);
-        let time_stats = get_time_statistics(state, Params { limit: None }).await[0].clone();
+        let time_stats = get_time_statistics(
+            state,
+            Params {
+                limit: None,
+                resolution: None,
+            },
+        )
+        .await[0]
+            .clone();
         let mut time_stats_lock = state.multiple_tests.collected_time_statistics.lock().await;
         time_stats_lock.push(time_stats);
+        let traffic_gen = state.traffic_generator.lock().await.traffic_gen_data();
+        let mut traffic_gen_lock = state.multiple_tests.collected_traffic_gen.lock().await;
+        traffic_gen_lock.push(traffic_gen);
     }
 
     /// Check if a duration monitor task is running and cancels it using its CancellationToken
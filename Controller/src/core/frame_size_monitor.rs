@@ -17,11 +17,12 @@
  * Steffen Lindner (steffen.lindner@uni-tuebingen.de)
  */
 
+use crate::core::backend::SwitchBackend;
 use crate::{AppState, PortMapping};
 use async_trait::async_trait;
 use rbfrt::error::RBFRTError;
+use rbfrt::table;
 use rbfrt::table::{MatchValue, ToBytes};
-use rbfrt::{table, SwitchConnection};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
@@ -29,6 +30,7 @@ use std::time::Duration;
 use crate::core::traffic_gen_core::types::GenerationMode;
 use log::{info, warn};
 
+use crate::core::measurement_window;
 use crate::core::statistics::{FrameSizeStatistics, RangeCount, RangeCountValue};
 use crate::core::traffic_gen_core::event::TrafficGenEvent;
 
@@ -71,7 +73,7 @@ impl FrameSizeMonitor {
 
     /// Configures the [frame size monitor table](FRAME_SIZE_MONITOR) in the egress pipeline.
     /// It first clears the table, then rewrites it.
-    pub async fn configure(&self, switch: &SwitchConnection) -> Result<(), RBFRTError> {
+    pub async fn configure(&self, switch: &dyn SwitchBackend) -> Result<(), RBFRTError> {
         info!("Configure table {FRAME_SIZE_MONITOR}.");
 
         // First clear table and then rewrite it
@@ -123,7 +125,7 @@ impl FrameSizeMonitor {
                 .operation(table::TableOperation::SyncCounters);
 
             let entries = {
-                let switch = &state.switch;
+                let switch = state.switch.as_ref();
 
                 // sync counters
                 if switch.execute_operation(sync).await.is_err() {
@@ -194,7 +196,8 @@ impl FrameSizeMonitor {
                 }
             }
 
-            {
+            // the statistics are not updated after the measurement window
+            if !measurement_window::has_ended(&state).await {
                 let frame_size_state = &mut state.frame_size_monitor.lock().await;
                 frame_size_state.statistics = stats;
             }
@@ -204,7 +207,7 @@ impl FrameSizeMonitor {
     }
 
     /// Clear the [frame monitor table](FRAME_SIZE_MONITOR).
-    pub async fn clear(&self, switch: &SwitchConnection) -> Result<(), RBFRTError> {
+    pub async fn clear(&self, switch: &dyn SwitchBackend) -> Result<(), RBFRTError> {
         switch.clear_table(FRAME_SIZE_MONITOR).await?;
 
         Ok(())
@@ -215,18 +218,18 @@ impl FrameSizeMonitor {
 impl TrafficGenEvent for FrameSizeMonitor {
     async fn on_start(
         &mut self,
-        switch: &SwitchConnection,
+        switch: &dyn SwitchBackend,
         _mode: &GenerationMode,
     ) -> Result<(), RBFRTError> {
         self.configure(switch).await?;
         Ok(())
     }
 
-    async fn on_stop(&self, _switch: &SwitchConnection) -> Result<(), RBFRTError> {
+    async fn on_stop(&self, _switch: &dyn SwitchBackend) -> Result<(), RBFRTError> {
         Ok(())
     }
 
-    async fn on_reset(&mut self, switch: &SwitchConnection) -> Result<(), RBFRTError> {
+    async fn on_reset(&mut self, switch: &dyn SwitchBackend) -> Result<(), RBFRTError> {
         self.configure(switch).await?;
         Ok(())
     }
@@ -17,16 +17,18 @@
  * Steffen Lindner (steffen.lindner@uni-tuebingen.de)
  */
 
+use crate::core::backend::SwitchBackend;
 use crate::{AppState, PortMapping};
 use async_trait::async_trait;
 use rbfrt::error::RBFRTError;
+use rbfrt::table;
 use rbfrt::table::{MatchValue, ToBytes};
-use rbfrt::{table, SwitchConnection};
 use std::collections::HashMap;
 use std::net::{Ipv4Addr, Ipv6Addr};
 use std::sync::Arc;
 use std::time::Duration;
 
+use crate::core::measurement_window;
 use crate::core::statistics::{FrameTypeStatistics, TypeCount};
 use crate::core::traffic_gen_core::event::TrafficGenEvent;
 use crate::core::traffic_gen_core::types::GenerationMode;
@@ -84,7 +86,7 @@ impl FrameTypeMonitor {
 
     /// Configures the frame type monitor table in the ingress pipeline.
     /// It first clears the table, then rewrites it.
-    pub async fn configure(&self, switch: &SwitchConnection) -> Result<(), RBFRTError> {
+    pub async fn configure(&self, switch: &dyn SwitchBackend) -> Result<(), RBFRTError> {
         // First clear table and then rewrite it
         self.clear(switch).await?;
 
@@ -263,7 +265,7 @@ impl FrameTypeMonitor {
 
                 let entries = {
                     // sync counters
-                    let switch = &state.switch;
+                    let switch = state.switch.as_ref();
 
                     if switch.execute_operation(sync).await.is_err() {
                         warn! {"Encountered error while synchronizing {t}."};
@@ -335,7 +337,8 @@ impl FrameTypeMonitor {
                 }
             }
 
-            {
+            // the statistics are not updated after the measurement window
+            if !measurement_window::has_ended(&state).await {
                 let frame_type_state = &mut state.frame_type_monitor.lock().await;
                 frame_type_state.statistics = stats;
             }
@@ -345,7 +348,7 @@ impl FrameTypeMonitor {
     }
 
     /// Clear the frame type table
-    pub async fn clear(&self, switch: &SwitchConnection) -> Result<(), RBFRTError> {
+    pub async fn clear(&self, switch: &dyn SwitchBackend) -> Result<(), RBFRTError> {
         switch
             .clear_tables(vec![FRAME_TYPE_MONITOR, ETHERNET_TYPE_MONITOR])
             .await?;
@@ -358,7 +361,7 @@ impl FrameTypeMonitor {
 impl TrafficGenEvent for FrameTypeMonitor {
     async fn on_start(
         &mut self,
-        switch: &SwitchConnection,
+        switch: &dyn SwitchBackend,
         _mode: &GenerationMode,
     ) -> Result<(), RBFRTError> {
         self.configure(switch).await?;
@@ -366,11 +369,11 @@ impl TrafficGenEvent for FrameTypeMonitor {
         Ok(())
     }
 
-    async fn on_stop(&self, _switch: &SwitchConnection) -> Result<(), RBFRTError> {
+    async fn on_stop(&self, _switch: &dyn SwitchBackend) -> Result<(), RBFRTError> {
         Ok(())
     }
 
-    async fn on_reset(&mut self, switch: &SwitchConnection) -> Result<(), RBFRTError> {
+    async fn on_reset(&mut self, switch: &dyn SwitchBackend) -> Result<(), RBFRTError> {
         self.configure(switch).await?;
         Ok(())
     }
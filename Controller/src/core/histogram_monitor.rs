@@ -5,11 +5,14 @@ use log::{info, warn};
 use rbfrt::{
     error::RBFRTError,
     table::{self, MatchValue, Request, TableEntry, ToBytes},
-    SwitchConnection,
 };
 
+use crate::api::helper::validate::validate_histogram;
 use crate::core::{
-    statistics::{HistogramConfig, HistogramData},
+    backend::SwitchBackend,
+    measurement_window,
+    quantile_sketch::QuantileSketch,
+    statistics::{BinLayout, CalibratedRange, HistogramConfig, HistogramData, DEFAULT_PERCENTILES},
     traffic_gen_core::{
         const_definitions::{IAT_HISTOGRAM_TABLE, RTT_HISTOGRAM_TABLE},
         helper::range_to_ternary,
@@ -19,7 +22,7 @@ use crate::core::{
 use crate::{AppState, PortMapping};
 
 use super::{
-    statistics::{Histogram, HistogramBinEntry},
+    statistics::{Histogram, HistogramBinEntry, HistogramPacketPath},
     traffic_gen_core::{event::TrafficGenEvent, types::GenerationMode},
 };
 
@@ -44,7 +47,7 @@ impl HistogramMonitor {
 
     pub async fn init_rtt_histogram_table(
         &mut self,
-        switch: &SwitchConnection,
+        switch: &dyn SwitchBackend,
     ) -> Result<(), RBFRTError> {
         let table_name = match self.hist_type {
             HistogramType::Rtt => RTT_HISTOGRAM_TABLE,
@@ -53,70 +56,248 @@ impl HistogramMonitor {
 
         switch.clear_table(table_name).await?;
 
+        let requests: Vec<Request> = self
+            .histogram
+            .iter()
+            .flat_map(|(port, hist)| self.histogram_table_entries(*port, &hist.config))
+            .collect();
+
+        let number_requests = requests.len();
+
+        if !requests.is_empty() {
+            switch.write_table_entries(requests).await?;
+            info!("Configured table {table_name} with {number_requests} entries.");
+        }
+
+        Ok(())
+    }
+
+    /// Builds the table entries of the histogram of `port` with config `hist_config`.
+    fn histogram_table_entries(&self, port: u32, hist_config: &HistogramConfig) -> Vec<Request> {
         let mut requests = vec![];
 
-        for (port, hist) in self.histogram.iter() {
-            let hist_config = &hist.config;
+        let Some(mapping) = self.port_mapping.get(&port) else {
+            return requests;
+        };
 
-            // Calculate bin width based on config params
-            let bin_width = hist_config.get_bin_width();
+        for (bin_index, (start, end)) in hist_config.bin_ranges().into_iter().enumerate() {
+            let bin_index = bin_index as u32;
+
+            // For each bin, write table entries
+            let ternary_entries = range_to_ternary(start, end);
+
+            requests.extend(self.build_ternary_table_entries(
+                ternary_entries.clone(),
+                mapping.rx_recirculation,
+                bin_index,
+            ));
+            if let HistogramType::Iat = self.hist_type {
+                requests.extend(self.build_ternary_table_entries(
+                    ternary_entries,
+                    mapping.tx_recirculation,
+                    bin_index,
+                ));
+            }
+        }
 
-            for bin_index in 0..hist_config.num_bins {
-                // For each bin, write table entries
-                let start = hist_config.min + bin_index * bin_width;
-                let mut end = start + bin_width - 1;
-                if end > hist_config.max {
-                    end = hist_config.max;
-                }
+        // Wildcard match on RTT per port. This entry catches outliers of the histogram
+        let req = match self.hist_type {
+            HistogramType::Rtt => vec![Request::new(RTT_HISTOGRAM_TABLE)
+                .match_key("ig_md.ig_port", MatchValue::exact(mapping.rx_recirculation))
+                .match_key("ig_md.rtt", MatchValue::ternary(0, 0))
+                .action("ingress.p4tg.rtt.count_missed_bin")],
+            HistogramType::Iat => vec![
+                Request::new(IAT_HISTOGRAM_TABLE)
+                    .match_key("ig_md.ig_port", MatchValue::exact(mapping.rx_recirculation))
+                    .match_key("ig_md.iat", MatchValue::ternary(0, 0))
+                    .action("ingress.p4tg.iat.count_missed_bin"),
+                Request::new(IAT_HISTOGRAM_TABLE)
+                    .match_key("ig_md.ig_port", MatchValue::exact(mapping.tx_recirculation))
+                    .match_key("ig_md.iat", MatchValue::ternary(0, 0))
+                    .action("ingress.p4tg.iat.count_missed_bin"),
+            ],
+        };
+        requests.extend(req);
+
+        requests
+    }
+
+    /// Derives the range of a histogram from the samples of the warm-up phase.
+    /// The range covers the configured percentiles of the samples and is widened such that each bin has a width of at least 1.
+    /// Returns None if the histogram is not calibrated automatically or no samples are available.
+    pub(crate) fn calibrated_range(
+        hist_config: &HistogramConfig,
+        sketch: &QuantileSketch,
+    ) -> Option<CalibratedRange> {
+        let calibration = hist_config.auto.as_ref()?;
 
-                if let Some(mapping) = self.port_mapping.get(port) {
-                    let ternary_entries = range_to_ternary(start, end);
-
-                    requests.extend(self.build_ternary_table_entries(
-                        ternary_entries.clone(),
-                        mapping.rx_recirculation,
-                        bin_index,
-                    ));
-                    if let HistogramType::Iat = self.hist_type {
-                        requests.extend(self.build_ternary_table_entries(
-                            ternary_entries,
-                            mapping.tx_recirculation,
-                            bin_index,
-                        ));
+        let lower = sketch.quantile(calibration.lower_percentile)?;
+        let upper = sketch.quantile(calibration.upper_percentile)?;
+
+        let mut min = (lower.floor() as u32).min(u32::MAX - hist_config.num_bins);
+        if hist_config.layout == BinLayout::Logarithmic {
+            min = min.max(1);
+        }
+        let mut max = (upper.ceil() as u32).max(min + hist_config.num_bins);
+
+        // the narrowest logarithmic bins must have a width of at least 1
+        let mut config = HistogramConfig {
+            min,
+            max,
+            ..hist_config.clone()
+        };
+        while max < u32::MAX && config.bin_edges().windows(2).any(|e| e[0] >= e[1]) {
+            max = max.saturating_mul(2);
+            config.max = max;
+        }
+
+        Some(CalibratedRange {
+            min,
+            max,
+            samples: sketch.quantiles().n,
+        })
+    }
+
+    /// Calibrates the range of all histograms that are configured with automatic calibration and whose warm-up phase is over.
+    /// The table entries of a calibrated histogram are replaced, i.e., its data restarts with the calibrated range.
+    pub(crate) async fn calibrate_histograms(state: &AppState, hist_type: &HistogramType) {
+        let elapsed = {
+            let experiment = state.experiment.lock().await;
+            experiment.start.elapsed().unwrap_or_default()
+        };
+
+        let monitor = match hist_type {
+            HistogramType::Rtt => &state.rtt_histogram_monitor,
+            HistogramType::Iat => &state.iat_histogram_monitor,
+        };
+
+        let pending: Vec<u32> = monitor
+            .lock()
+            .await
+            .histogram
+            .iter()
+            .filter(|(_, hist)| {
+                hist.calibration.is_none()
+                    && hist
+                        .config
+                        .auto
+                        .as_ref()
+                        .is_some_and(|c| elapsed >= Duration::from_millis(c.warmup as u64))
+            })
+            .map(|(port, _)| *port)
+            .collect();
+
+        if pending.is_empty() {
+            return;
+        }
+
+        // Samples of the warm-up phase per port
+        let sketches: HashMap<u32, QuantileSketch> = {
+            let rate_monitor = state.rate_monitor.lock().await;
+
+            pending
+                .iter()
+                .map(|port| {
+                    let mut sketch = QuantileSketch::default();
+                    match hist_type {
+                        HistogramType::Rtt => {
+                            for app_sketch in rate_monitor
+                                .rtt_sketches
+                                .get(port)
+                                .into_iter()
+                                .flat_map(|s| s.values())
+                            {
+                                sketch.merge(app_sketch);
+                            }
+                        }
+                        HistogramType::Iat => {
+                            for iat_sketch in [
+                                rate_monitor.tx_iat_sketches.get(port),
+                                rate_monitor.rx_iat_sketches.get(port),
+                            ]
+                            .into_iter()
+                            .flatten()
+                            {
+                                sketch.merge(iat_sketch);
+                            }
+                        }
                     }
-                }
+                    (*port, sketch)
+                })
+                .collect()
+        };
+
+        let switch = state.switch.as_ref();
+        let mut histogram_monitor = monitor.lock().await;
+
+        for port in pending {
+            let Some(hist) = histogram_monitor.histogram.get(&port) else {
+                continue;
+            };
+
+            // no samples yet, e.g., IAT samples are only collected in sample mode
+            let Some(range) = sketches
+                .get(&port)
+                .and_then(|s| Self::calibrated_range(&hist.config, s))
+            else {
+                continue;
+            };
+
+            let old_config = hist.config.clone();
+            let config = HistogramConfig {
+                min: range.min,
+                max: range.max,
+                ..old_config.clone()
+            };
+
+            // the calibrated range may require more table entries than the configured range
+            let configs = histogram_monitor
+                .histogram
+                .iter()
+                .map(|(p, h)| {
+                    let config = if *p == port { &config } else { &h.config };
+                    (
+                        format!("{p}"),
+                        HashMap::from([("0".to_owned(), config.clone())]),
+                    )
+                })
+                .collect();
+
+            if let Err(e) = validate_histogram(&configs, None, hist_type.clone()) {
+                warn!(
+                    "Calibrated histogram of port {port} is invalid, keeping the configured range. Error: {}",
+                    e.message
+                );
+                continue;
             }
 
-            // Wildcard match on RTT per port. This entry catches outliers of the histogram
-            if let Some(mapping) = self.port_mapping.get(port) {
-                let req = match self.hist_type {
-                    HistogramType::Rtt => vec![Request::new(RTT_HISTOGRAM_TABLE)
-                        .match_key("ig_md.ig_port", MatchValue::exact(mapping.rx_recirculation))
-                        .match_key("ig_md.rtt", MatchValue::ternary(0, 0))
-                        .action("ingress.p4tg.rtt.count_missed_bin")],
-                    HistogramType::Iat => vec![
-                        Request::new(IAT_HISTOGRAM_TABLE)
-                            .match_key("ig_md.ig_port", MatchValue::exact(mapping.rx_recirculation))
-                            .match_key("ig_md.iat", MatchValue::ternary(0, 0))
-                            .action("ingress.p4tg.iat.count_missed_bin"),
-                        Request::new(IAT_HISTOGRAM_TABLE)
-                            .match_key("ig_md.ig_port", MatchValue::exact(mapping.tx_recirculation))
-                            .match_key("ig_md.iat", MatchValue::ternary(0, 0))
-                            .action("ingress.p4tg.iat.count_missed_bin"),
-                    ],
-                };
-                requests.extend(req);
+            let old_entries = histogram_monitor.histogram_table_entries(port, &old_config);
+            let new_entries = histogram_monitor.histogram_table_entries(port, &config);
+
+            if let Err(e) = switch.delete_table_entries(old_entries.clone()).await {
+                warn!("Error while deleting histogram entries of port {port}: {e:#?}");
+                continue;
             }
-        }
 
-        let number_requests = requests.len();
+            if let Err(e) = switch.write_table_entries(new_entries).await {
+                warn!("Calibrated histogram of port {port} could not be configured, keeping the configured range. Error: {e:#?}");
+                if switch.write_table_entries(old_entries).await.is_err() {
+                    warn!("Error while restoring histogram entries of port {port}.");
+                }
+                continue;
+            }
 
-        if !requests.is_empty() {
-            switch.write_table_entries(requests).await?;
-            info!("Configured table {table_name} with {number_requests} entries.");
-        }
+            info!(
+                "Calibrated {hist_type:?} histogram of port {port} to range [{}, {}] from {} samples.",
+                range.min, range.max, range.samples
+            );
 
-        Ok(())
+            if let Some(hist) = histogram_monitor.histogram.get_mut(&port) {
+                hist.config = config;
+                hist.calibration = Some(range);
+                hist.data = HistogramPacketPath::default();
+            }
+        }
     }
 
     async fn aggregate_histogram_data(
@@ -130,12 +311,6 @@ impl HistogramMonitor {
             HistogramType::Iat => "ingress.p4tg.iat.count_missed_bin",
         };
 
-        let mut bins_data = HashMap::new();
-        // Used to calculate the mean RTT based on the histogram
-        let mut running_sum: f64 = 0.0;
-        let mut running_sum_square: f64 = 0.0;
-        let mut total_pkt_count = 0;
-
         // Filter all TableEntries for the current port
         let hist_entries: Vec<&table::TableEntry> = table_data
             .iter()
@@ -149,16 +324,51 @@ impl HistogramMonitor {
             })
             .collect();
 
-        for b in 0..hist_config.num_bins {
-            // Filter all TableEntries for the current bin_index and calculate sum
-            let pkt_bin_count: u128 = hist_entries
-                .iter()
-                .filter(|t| {
-                    t.has_action_data("bin_index")
-                        && t.get_action_data("bin_index").unwrap().as_u32() == b
-                })
-                .map(|e| e.get_action_data("$COUNTER_SPEC_PKTS").unwrap().as_u128())
-                .sum();
+        // Filter all TableEntries for the current bin_index and calculate sum
+        let num_bins = hist_config.bin_ranges().len() as u32;
+        let bin_counts: Vec<u128> = (0..num_bins)
+            .map(|b| {
+                hist_entries
+                    .iter()
+                    .filter(|t| {
+                        t.has_action_data("bin_index")
+                            && t.get_action_data("bin_index").unwrap().as_u32() == b
+                    })
+                    .map(|e| e.get_action_data("$COUNTER_SPEC_PKTS").unwrap().as_u128())
+                    .sum()
+            })
+            .collect();
+
+        // Get entry for this port with missed bin action
+        let missed_bin_count = hist_entries
+            .iter()
+            .filter(|t| t.get_action_name() == action_name)
+            .map(|e| e.get_action_data("$COUNTER_SPEC_PKTS").unwrap().as_u128())
+            .sum();
+
+        Self::histogram_data(&bin_counts, missed_bin_count, hist_config)
+    }
+
+    /// Calculates the histogram data from the number of packets per bin.
+    ///
+    /// - `bin_counts`: Number of packets per bin index
+    /// - `missed_bin_count`: Number of packets that did not match any bin
+    /// - `hist_config`: Config of the histogram
+    pub(crate) fn histogram_data(
+        bin_counts: &[u128],
+        missed_bin_count: u128,
+        hist_config: &HistogramConfig,
+    ) -> HistogramData {
+        let mut bins_data = HashMap::new();
+        // Used to calculate the mean RTT based on the histogram
+        let mut running_sum: f64 = 0.0;
+        let mut running_sum_square: f64 = 0.0;
+        let mut total_pkt_count = 0;
+        let edges = hist_config.bin_edges();
+
+        for (b, &pkt_bin_count) in bin_counts.iter().enumerate() {
+            let b = b as u32;
+
             // Insert bin count. Probabilities will be updated later
             bins_data.insert(
                 b,
@@ -168,27 +378,21 @@ impl HistogramMonitor {
                 },
             );
 
-            let bin_middle_value: f64 = hist_config.min as f64
-                + b as f64 * hist_config.get_bin_width() as f64
-                + (hist_config.get_bin_width() as f64 / 2f64);
+            let bin_middle_value: f64 = match (edges.get(b as usize), edges.get(b as usize + 1)) {
+                (Some(&low), Some(&high)) => (low as f64 + high as f64) / 2f64,
+                _ => 0f64,
+            };
 
             running_sum += bin_middle_value * pkt_bin_count as f64;
             running_sum_square += bin_middle_value.powi(2) * pkt_bin_count as f64;
             total_pkt_count += pkt_bin_count;
         }
 
-        // Get entry for this port with missed bin action
-        let missed_bin_count = hist_entries
-            .iter()
-            .filter(|t| t.get_action_name() == action_name)
-            .map(|e| e.get_action_data("$COUNTER_SPEC_PKTS").unwrap().as_u128())
-            .sum();
-
         // Calculate percentiles
         let percentiles = hist_config
             .percentiles
             .clone()
-            .unwrap_or(vec![0.25, 0.5, 0.75, 0.9]);
+            .unwrap_or(DEFAULT_PERCENTILES.to_vec());
 
         let percentile_results =
             Self::estimate_percentiles_from_bins(&bins_data, percentiles, hist_config);
@@ -215,6 +419,35 @@ impl HistogramMonitor {
         }
     }
 
+    /// Estimates an RTT histogram from sampled RTTs, e.g., the RTTs of a single stream.
+    /// The samples are counted in the bins of `hist_config` as done by the data plane.
+    pub(crate) fn histogram_from_samples<'a>(
+        samples: impl IntoIterator<Item = &'a u64>,
+        hist_config: &HistogramConfig,
+    ) -> Histogram {
+        let edges = hist_config.bin_edges();
+        let mut bin_counts = vec![0u128; edges.len().saturating_sub(1)];
+        let mut missed_bin_count = 0;
+
+        for &sample in samples {
+            let bin = HistogramConfig::bin_index(&edges, sample);
+
+            match bin.and_then(|b| bin_counts.get_mut(b as usize)) {
+                Some(count) => *count += 1,
+                None => missed_bin_count += 1,
+            }
+        }
+
+        Histogram {
+            config: hist_config.clone(),
+            data: HistogramPacketPath {
+                tx: HistogramData::default(),
+                rx: Self::histogram_data(&bin_counts, missed_bin_count, hist_config),
+            },
+            calibration: None,
+        }
+    }
+
     /// Fetches histogram data for the Configuration GUI.
     ///
     /// - `state`: App state that holds the switch connection.
@@ -231,7 +464,12 @@ impl HistogramMonitor {
             };
 
             if running {
-                let switch = &state.switch;
+                Self::calibrate_histograms(&state, &hist_type).await;
+            }
+
+            // the histograms are not updated after the measurement window
+            if running && !measurement_window::has_ended(&state).await {
+                let switch = state.switch.as_ref();
                 // Sync Histogram counters
                 {
                     let sync =
@@ -339,17 +577,22 @@ impl HistogramMonitor {
         mut percentiles: Vec<f64>, // e.g. [0.25, 0.5, 0.75, 0.9]
         cfg: &HistogramConfig,
     ) -> HashMap<u32, f64> {
-        let min = cfg.min as f64;
-        let num_bins = cfg.num_bins as usize;
+        let mut edges: Vec<f64> = cfg.bin_edges().into_iter().map(|e| e as f64).collect();
+        let num_bins = edges.len().saturating_sub(1);
         if num_bins == 0 {
             return HashMap::new();
         }
 
-        // Use floating bin width to match the frontend
-        let bin_w = (cfg.max as f64 - cfg.min as f64) / cfg.num_bins as f64;
+        if cfg.bin_edges.is_none() && cfg.layout == BinLayout::Linear {
+            // Use floating bin width to match the frontend
+            let bin_w = (cfg.max as f64 - cfg.min as f64) / cfg.num_bins as f64;
+            edges = (0..=num_bins)
+                .map(|i| cfg.min as f64 + i as f64 * bin_w)
+                .collect();
+        }
 
         // Sorted bins by index (missing bins treated as count=0)
-        let sorted: Vec<(u32, u128)> = (0..cfg.num_bins)
+        let sorted: Vec<(u32, u128)> = (0..num_bins as u32)
             .map(|i| {
                 let c = bins_data.get(&i).map(|e| e.count).unwrap_or(0);
                 (i, c)
@@ -383,7 +626,8 @@ impl HistogramMonitor {
                 } else {
                     0.5 // empty bin; fall back to mid
                 };
-                let value = min + ((i as f64) + t) * bin_w;
+                let (low, high) = (edges[i as usize], edges[i as usize + 1]);
+                let value = low + t * (high - low);
                 out.insert((p * 100.0).round() as u32, value);
                 p_idx += 1;
             }
@@ -416,7 +660,7 @@ impl HistogramMonitor {
 impl TrafficGenEvent for HistogramMonitor {
     async fn on_start(
         &mut self,
-        switch: &SwitchConnection,
+        switch: &dyn SwitchBackend,
         _mode: &GenerationMode,
     ) -> Result<(), RBFRTError> {
         // Reconfigures the histogram table and deletes all statistics.
@@ -426,15 +670,257 @@ impl TrafficGenEvent for HistogramMonitor {
         Ok(())
     }
 
-    async fn on_stop(&self, _switch: &SwitchConnection) -> Result<(), RBFRTError> {
+    async fn on_stop(&self, _switch: &dyn SwitchBackend) -> Result<(), RBFRTError> {
         Ok(())
     }
 
     /// Reset the state.
-    async fn on_reset(&mut self, _switch: &SwitchConnection) -> Result<(), RBFRTError> {
+    async fn on_reset(&mut self, _switch: &dyn SwitchBackend) -> Result<(), RBFRTError> {
         // Deletes all statistics, keeps the configuration
         self.clear_data();
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::docs::traffic_gen::EXAMPLE_POST_2_REQUEST;
+    use crate::core::backend::mock::tests::{call, mock_state};
+    use crate::core::statistics::HistogramCalibration;
+    use crate::core::traffic_gen_core::const_definitions::RTT_HISTOGRAM_TABLE;
+    use axum::http::{Method, StatusCode};
+    use std::time::SystemTime;
+
+    #[test]
+    fn percentiles_are_estimated_from_non_uniform_bins() {
+        let config = HistogramConfig {
+            num_bins: 3,
+            min: 10,
+            max: 10000,
+            layout: BinLayout::Logarithmic,
+            bin_edges: None,
+            auto: None,
+            percentiles: Some(vec![0.5, 0.9]),
+        };
+        assert_eq!(config.bin_edges(), vec![10, 100, 1000, 10000]);
+
+        // 50 samples in [10, 100), 50 samples in [100, 1000), 10 samples are missed
+        let samples: Vec<u64> = (0..50)
+            .map(|i| 10 + i)
+            .chain((0..50).map(|i| 100 + 10 * i))
+            .chain((0..10).map(|_| 10000))
+            .collect();
+
+        let histogram = HistogramMonitor::histogram_from_samples(&samples, &config);
+        let data = &histogram.data.rx;
+        assert_eq!(data.data_bins[&0].count, 50);
+        assert_eq!(data.data_bins[&1].count, 50);
+        assert_eq!(data.data_bins[&2].count, 0);
+        assert_eq!(data.missed_bin_count, 10);
+
+        // percentiles are interpolated linearly within the bins
+        assert_eq!(data.percentiles[&50], 100.0);
+        assert_eq!(data.percentiles[&90], 100.0 + 0.8 * 900.0);
+    }
+
+    #[test]
+    fn calibrated_range_covers_percentile_span() {
+        let mut config = HistogramConfig {
+            num_bins: 100,
+            auto: Some(HistogramCalibration {
+                warmup: 1000,
+                lower_percentile: 0.1,
+                upper_percentile: 0.9,
+            }),
+            ..Default::default()
+        };
+
+        let mut sketch = QuantileSketch::default();
+        assert!(HistogramMonitor::calibrated_range(&config, &sketch).is_none());
+
+        for rtt in 1..=10_000u64 {
+            sketch.add(10_000 + rtt);
+        }
+
+        let range = HistogramMonitor::calibrated_range(&config, &sketch).unwrap();
+        assert_eq!(range.samples, 10_000);
+        assert!(range.min.abs_diff(11_000) <= 60);
+        assert!(range.max.abs_diff(19_000) <= 100);
+
+        // narrow spans are widened to one unit per bin
+        config.num_bins = 500;
+        config.layout = BinLayout::Logarithmic;
+        let range = HistogramMonitor::calibrated_range(&config, &sketch).unwrap();
+        assert!(range.max - range.min >= 500);
+        let calibrated = HistogramConfig {
+            min: range.min,
+            max: range.max,
+            ..config
+        };
+        assert!(calibrated.bin_edges().windows(2).all(|e| e[0] < e[1]));
+    }
+
+    #[tokio::test]
+    async fn histograms_support_explicit_bin_edges() {
+        let (switch, state) = mock_state().await;
+
+        let mut request = serde_json::to_value(&*EXAMPLE_POST_2_REQUEST).unwrap();
+        let config = &mut request["rtt_histogram_config"]["2"]["0"];
+        config["bin_edges"] = serde_json::json!([1000, 900]);
+
+        let (status, _) = call(&state, Method::POST, "/trafficgen", request.to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let config = &mut request["rtt_histogram_config"]["2"]["0"];
+        config["bin_edges"] = serde_json::Value::Null;
+        config["layout"] = serde_json::json!("logarithmic");
+        config["min"] = serde_json::json!(0);
+
+        let (status, _) = call(&state, Method::POST, "/trafficgen", request.to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let config = &mut request["rtt_histogram_config"]["2"]["0"];
+        config["bin_edges"] = serde_json::json!([1000, 1100, 1500, 3000]);
+
+        let (status, _) = call(&state, Method::POST, "/trafficgen", request.to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // each bin is decomposed into ternary entries
+        let entries = switch
+            .entries(RTT_HISTOGRAM_TABLE)
+            .into_iter()
+            .filter(|e| e.has_action() && e.get_action_name().ends_with("count_histogram_bin"))
+            .count();
+        let expected: usize = [(1000, 1099), (1100, 1499), (1500, 2999)]
+            .into_iter()
+            .map(|(start, end)| range_to_ternary(start, end).len())
+            .sum();
+        assert_eq!(entries, expected);
+    }
+
+    #[tokio::test]
+    async fn histogram_range_is_calibrated_after_warmup() {
+        let (switch, state) = mock_state().await;
+
+        let mut request = serde_json::to_value(&*EXAMPLE_POST_2_REQUEST).unwrap();
+        let config = &mut request["rtt_histogram_config"]["2"]["0"];
+        config["auto"] = serde_json::json!({"lower_percentile": 0.5, "upper_percentile": 0.4});
+
+        let (status, _) = call(&state, Method::POST, "/trafficgen", request.to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let config = &mut request["rtt_histogram_config"]["2"]["0"];
+        config["auto"] = serde_json::json!({"warmup": 1});
+
+        let (status, _) = call(&state, Method::POST, "/trafficgen", request.to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let port = *state
+            .rtt_histogram_monitor
+            .lock()
+            .await
+            .histogram
+            .iter()
+            .find(|(_, h)| h.config.auto.is_some())
+            .unwrap()
+            .0;
+
+        // RTTs between 2 and 4 us during the warm-up
+        {
+            let rate_monitor = &mut state.rate_monitor.lock().await;
+            let sketch = rate_monitor
+                .rtt_sketches
+                .entry(port)
+                .or_default()
+                .entry(1)
+                .or_default();
+            for rtt in 2000..4000 {
+                sketch.add(rtt);
+            }
+        }
+
+        // the warm-up phase is over
+        state.experiment.lock().await.start = SystemTime::now() - Duration::from_secs(1);
+        HistogramMonitor::calibrate_histograms(&state, &HistogramType::Rtt).await;
+
+        let hist = state.rtt_histogram_monitor.lock().await.histogram[&port].clone();
+        let calibration = hist.calibration.unwrap();
+        assert_eq!(calibration.samples, 2000);
+        assert!(calibration.min.abs_diff(2020) <= 20);
+        assert!(calibration.max.abs_diff(3980) <= 40);
+        assert_eq!(hist.config.min, calibration.min);
+
+        // the table contains the entries of the calibrated range only
+        let entries = switch
+            .entries(RTT_HISTOGRAM_TABLE)
+            .into_iter()
+            .filter(|e| e.has_action() && e.get_action_name().ends_with("count_histogram_bin"))
+            .count();
+        let expected: usize = hist
+            .config
+            .bin_ranges()
+            .into_iter()
+            .map(|(start, end)| range_to_ternary(start, end).len())
+            .sum();
+        assert_eq!(entries, expected);
+    }
+
+    #[tokio::test]
+    async fn calibrated_range_is_validated() {
+        let (switch, state) = mock_state().await;
+
+        let mut request = serde_json::to_value(&*EXAMPLE_POST_2_REQUEST).unwrap();
+        let config = &mut request["rtt_histogram_config"]["2"]["0"];
+        config["min"] = serde_json::json!(0);
+        config["max"] = serde_json::json!(500);
+        config["num_bins"] = serde_json::json!(500);
+        config["auto"] = serde_json::json!({"warmup": 1});
+
+        let (status, _) = call(&state, Method::POST, "/trafficgen", request.to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let port = *state
+            .rtt_histogram_monitor
+            .lock()
+            .await
+            .histogram
+            .iter()
+            .find(|(_, h)| h.config.auto.is_some())
+            .unwrap()
+            .0;
+
+        // 500 wide bins with unaligned edges exceed the capacity of the histogram table
+        {
+            let rate_monitor = &mut state.rate_monitor.lock().await;
+            let sketch = rate_monitor
+                .rtt_sketches
+                .entry(port)
+                .or_default()
+                .entry(1)
+                .or_default();
+            for i in 0..2000 {
+                sketch.add(1_000_003 + i * 493_001);
+            }
+        }
+
+        let entries = || {
+            switch
+                .entries(RTT_HISTOGRAM_TABLE)
+                .into_iter()
+                .filter(|e| e.has_action() && e.get_action_name().ends_with("count_histogram_bin"))
+                .count()
+        };
+        let configured_entries = entries();
+
+        state.experiment.lock().await.start = SystemTime::now() - Duration::from_secs(1);
+        HistogramMonitor::calibrate_histograms(&state, &HistogramType::Rtt).await;
+
+        // the configured range is kept
+        let hist = state.rtt_histogram_monitor.lock().await.histogram[&port].clone();
+        assert!(hist.calibration.is_none());
+        assert_eq!((hist.config.min, hist.config.max), (0, 500));
+        assert_eq!(entries(), configured_entries);
+    }
+}
@@ -0,0 +1,268 @@
+/* Copyright 2022-present University of Tuebingen, Chair of Communication Networks
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/*
+ * Steffen Lindner (steffen.lindner@uni-tuebingen.de)
+ */
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Sequence numbers that differ by more than half of the sequence number space are considered as overflow.
+/// Same as in the data plane.
+const SEQ_OVERFLOW: u32 = 1 << 31;
+
+/// Characterization of the packet loss of a port or stream.
+///
+/// The burst lengths and the Gilbert-Elliott parameters are estimated from the sequence numbers of the
+/// sampled packets. A packet whose sequence number is larger than the expected one was preceded by a loss burst
+/// of the length of the difference. The number of lost packets is given by the packet loss counters.
+#[derive(Serialize, Debug, Clone, Default, ToSchema)]
+pub struct LossAnalysis {
+    /// Number of lost packets.
+    pub(crate) lost_packets: u64,
+    /// Estimated number of loss episodes, i.e., bursts of consecutively lost packets.
+    pub(crate) loss_episodes: u64,
+    /// Minimal length of the sampled loss bursts in packets.
+    pub(crate) min_burst_length: u64,
+    /// Mean length of the sampled loss bursts in packets.
+    pub(crate) mean_burst_length: f64,
+    /// Maximal length of the sampled loss bursts in packets.
+    pub(crate) max_burst_length: u64,
+    /// Estimated probability that a packet is lost after a received packet (good -> bad state).
+    pub(crate) loss_probability: f64,
+    /// Estimated probability that a packet is received after a lost packet (bad -> good state).
+    pub(crate) recovery_probability: f64,
+    /// Number of sampled packets with the sequence number of the previously received packet.
+    pub(crate) duplicates: u64,
+    /// Number of sampled packets that arrived after a packet with a higher sequence number.
+    pub(crate) reordered: u64,
+    /// Number of sampled packets.
+    pub(crate) samples: u64,
+}
+
+/// Accumulates the sampled sequence numbers and the loss counters of a single port or stream.
+#[derive(Debug, Clone, Default)]
+struct LossTracker {
+    last_loss: u64,
+    lost_packets: u64,
+    /// Number of monitoring intervals in which packets have been lost
+    loss_intervals: u64,
+    /// Sampled packets that were received in order, i.e., with at least the expected sequence number
+    in_order: u64,
+    bursts: u64,
+    burst_sum: u64,
+    min_burst: u64,
+    max_burst: u64,
+    duplicates: u64,
+    reordered: u64,
+}
+
+impl LossTracker {
+    /// Adds the cumulative packet loss `loss` of the current monitoring interval.
+    fn record_loss(&mut self, loss: u64) {
+        // the loss counters have been reset
+        if loss < self.last_loss {
+            self.last_loss = loss;
+        }
+
+        let lost = loss - self.last_loss;
+        self.last_loss = loss;
+
+        if lost > 0 {
+            self.lost_packets += lost;
+            self.loss_intervals += 1;
+        }
+    }
+
+    /// Adds a sampled packet with sequence number `seq`.
+    /// `expected_seq` is the sequence number that was expected by the data plane for this packet.
+    fn record_sample(&mut self, seq: u32, expected_seq: u32) {
+        let gap = seq.wrapping_sub(expected_seq);
+
+        if gap < SEQ_OVERFLOW {
+            self.in_order += 1;
+
+            if gap > 0 {
+                let gap = gap as u64;
+                self.min_burst = if self.bursts == 0 {
+                    gap
+                } else {
+                    self.min_burst.min(gap)
+                };
+                self.max_burst = self.max_burst.max(gap);
+                self.burst_sum += gap;
+                self.bursts += 1;
+            }
+        } else if seq.wrapping_add(1) == expected_seq {
+            self.duplicates += 1;
+        } else {
+            self.reordered += 1;
+        }
+    }
+
+    fn analysis(&self) -> LossAnalysis {
+        let mean_burst_length = if self.bursts > 0 {
+            self.burst_sum as f64 / self.bursts as f64
+        } else {
+            0f64
+        };
+
+        // each monitoring interval with lost packets contains at least one episode
+        let loss_episodes = if mean_burst_length > 0f64 {
+            ((self.lost_packets as f64 / mean_burst_length).round() as u64).max(self.loss_intervals)
+        } else {
+            self.loss_intervals
+        };
+
+        // each burst starts after a received packet
+        let loss_probability = if self.in_order > 0 {
+            self.bursts as f64 / self.in_order as f64
+        } else {
+            0f64
+        };
+
+        let recovery_probability = if mean_burst_length > 0f64 {
+            1f64 / mean_burst_length
+        } else {
+            1f64
+        };
+
+        LossAnalysis {
+            lost_packets: self.lost_packets,
+            loss_episodes,
+            min_burst_length: self.min_burst,
+            mean_burst_length,
+            max_burst_length: self.max_burst,
+            loss_probability,
+            recovery_probability,
+            duplicates: self.duplicates,
+            reordered: self.reordered,
+            samples: self.in_order + self.duplicates + self.reordered,
+        }
+    }
+}
+
+/// Characterizes the packet loss per RX port and stream from the sampled sequence numbers
+/// and the packet loss counters that are reported by the monitoring packets.
+#[derive(Debug, Clone, Default)]
+pub struct LossAnalyzer {
+    ports: HashMap<u32, LossTracker>,
+    streams: HashMap<u32, HashMap<u32, LossTracker>>,
+}
+
+impl LossAnalyzer {
+    pub fn reset(&mut self) {
+        self.ports.clear();
+        self.streams.clear();
+    }
+
+    /// Updates the lost packets of RX port `port` and of stream `app_id` on that port.
+    ///
+    /// - `port_loss`: Cumulative packet loss of the port
+    /// - `app_loss`: Cumulative packet loss of the stream
+    pub fn record_loss(&mut self, port: u32, app_id: u32, port_loss: u64, app_loss: u64) {
+        self.ports.entry(port).or_default().record_loss(port_loss);
+        self.streams
+            .entry(port)
+            .or_default()
+            .entry(app_id)
+            .or_default()
+            .record_loss(app_loss);
+    }
+
+    /// Adds a sampled packet of stream `app_id` that was received on RX port `port`.
+    pub fn record_sample(&mut self, port: u32, app_id: u32, seq: u32, expected_seq: u32) {
+        self.ports
+            .entry(port)
+            .or_default()
+            .record_sample(seq, expected_seq);
+        self.streams
+            .entry(port)
+            .or_default()
+            .entry(app_id)
+            .or_default()
+            .record_sample(seq, expected_seq);
+    }
+
+    /// Returns the loss analysis per RX port.
+    pub fn port_statistics(&self) -> HashMap<u32, LossAnalysis> {
+        self.ports
+            .iter()
+            .map(|(port, tracker)| (*port, tracker.analysis()))
+            .collect()
+    }
+
+    /// Returns the loss analysis per RX port and stream.
+    pub fn stream_statistics(&self) -> HashMap<u32, HashMap<u32, LossAnalysis>> {
+        self.streams
+            .iter()
+            .map(|(port, per_app)| {
+                let per_app = per_app
+                    .iter()
+                    .map(|(app_id, tracker)| (*app_id, tracker.analysis()))
+                    .collect();
+                (*port, per_app)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sampled_sequence_numbers_are_classified() {
+        let mut tracker = LossTracker::default();
+
+        // 8 packets in order, 2 of them after a burst of 1 and 3 lost packets
+        for (seq, expected) in [
+            (0, 0),
+            (1, 1),
+            (3, 2),
+            (4, 4),
+            (5, 5),
+            (9, 6),
+            (10, 10),
+            (11, 11),
+        ] {
+            tracker.record_sample(seq, expected);
+        }
+        // duplicate of the last received packet and a late packet
+        tracker.record_sample(11, 12);
+        tracker.record_sample(7, 12);
+        // burst across the sequence number overflow
+        tracker.record_sample(1, u32::MAX - 1);
+
+        tracker.record_loss(4);
+        tracker.record_loss(4);
+        tracker.record_loss(7);
+
+        let analysis = tracker.analysis();
+        assert_eq!(analysis.samples, 11);
+        assert_eq!(analysis.duplicates, 1);
+        assert_eq!(analysis.reordered, 1);
+        assert_eq!(analysis.min_burst_length, 1);
+        assert_eq!(analysis.max_burst_length, 3);
+        assert_eq!(analysis.mean_burst_length, 7f64 / 3f64);
+        assert_eq!(analysis.loss_probability, 3f64 / 9f64);
+        assert_eq!(analysis.recovery_probability, 3f64 / 7f64);
+        assert_eq!(analysis.lost_packets, 7);
+        assert_eq!(analysis.loss_episodes, 3);
+    }
+}
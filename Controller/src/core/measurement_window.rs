@@ -0,0 +1,364 @@
+/* Copyright 2022-present University of Tuebingen, Chair of Communication Networks
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/*
+ * Steffen Lindner (steffen.lindner@uni-tuebingen.de)
+ */
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use log::{info, warn};
+use rbfrt::error::RBFRTError;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::core::rate_monitor::{MAE_IAT_REGISTER, MEAN_IAT_REGISTER, TSTMP_MASK};
+use crate::core::statistics::ElapsedTime;
+use crate::core::traffic_gen_core::event::TrafficGenEvent;
+use crate::AppState;
+
+/// Measurement window of a test.
+/// Loss, RTT, IAT, histogram and mean rate statistics only accumulate inside the window,
+/// i.e., the warm-up phase at the start and the cool-down phase at the end of the test are excluded.
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, ToSchema)]
+pub struct MeasurementWindow {
+    /// Start of the window in seconds since the start of the test, i.e., the end of the warm-up phase.
+    pub(crate) start: ElapsedTime,
+    /// End of the window in seconds since the start of the test, i.e., the start of the cool-down phase.
+    /// Not present if the window lasts until the test is stopped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) end: Option<ElapsedTime>,
+}
+
+impl MeasurementWindow {
+    /// Creates the measurement window of a test.
+    ///
+    /// - `warmup`: Duration of the warm-up phase in seconds
+    /// - `cooldown`: Duration of the cool-down phase in seconds
+    /// - `duration`: Duration of the test in seconds. The cool-down phase is ignored without a duration.
+    pub fn new(
+        warmup: Option<u32>,
+        cooldown: Option<u32>,
+        duration: Option<u32>,
+    ) -> MeasurementWindow {
+        let end = match (cooldown, duration) {
+            (Some(cooldown), Some(duration)) if cooldown > 0 && duration > 0 => Some(ElapsedTime(
+                duration.saturating_sub(cooldown).saturating_mul(1000),
+            )),
+            _ => None,
+        };
+
+        MeasurementWindow {
+            start: ElapsedTime(warmup.unwrap_or(0).saturating_mul(1000)),
+            end,
+        }
+    }
+
+    /// Indicates whether the window excludes a part of the test.
+    pub fn is_limited(&self) -> bool {
+        self.start.0 > 0 || self.end.is_some()
+    }
+
+    pub fn has_started(&self, time: ElapsedTime) -> bool {
+        time >= self.start
+    }
+
+    pub fn has_ended(&self, time: ElapsedTime) -> bool {
+        self.end.is_some_and(|end| time >= end)
+    }
+
+    pub fn contains(&self, time: ElapsedTime) -> bool {
+        self.has_started(time) && !self.has_ended(time)
+    }
+}
+
+/// Mean rates of a port inside the measurement window in bits per second.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, ToSchema)]
+pub struct MeanRates {
+    pub(crate) tx_l1: f64,
+    pub(crate) tx_l2: f64,
+    pub(crate) rx_l1: f64,
+    pub(crate) rx_l2: f64,
+}
+
+/// Byte counters of a port at a data plane timestamp.
+#[derive(Debug, Clone, Copy)]
+struct ByteCounter {
+    l1: u64,
+    l2: u64,
+    tstmp: u64,
+}
+
+/// Snapshots of the cumulative data plane counters at the boundaries of the measurement window.
+#[derive(Debug, Clone, Default)]
+pub struct WindowCounters {
+    /// Byte counters per TX port at the start of the window and at the latest update inside the window
+    tx_bytes: HashMap<u32, (ByteCounter, ByteCounter)>,
+    /// Byte counters per RX port at the start of the window and at the latest update inside the window
+    rx_bytes: HashMap<u32, (ByteCounter, ByteCounter)>,
+    /// Lost and out of order packets per RX port and stream before the start of the window
+    baseline: HashMap<u32, HashMap<u32, (u64, u64)>>,
+}
+
+impl WindowCounters {
+    pub fn reset(&mut self) {
+        self.tx_bytes.clear();
+        self.rx_bytes.clear();
+        self.baseline.clear();
+    }
+
+    /// Updates the byte counters of a port with the values of a monitoring packet inside the window.
+    pub fn record_bytes(&mut self, port: u32, is_tx: bool, l1: u64, l2: u64, tstmp: u64) {
+        let counters = if is_tx {
+            &mut self.tx_bytes
+        } else {
+            &mut self.rx_bytes
+        };
+        let counter = ByteCounter { l1, l2, tstmp };
+
+        match counters.get_mut(&port) {
+            Some((first, last)) if l1 >= first.l1 && l2 >= first.l2 => *last = counter,
+            // first update inside the window or the byte counters have been reset
+            _ => {
+                counters.insert(port, (counter, counter));
+            }
+        }
+    }
+
+    /// Stores the lost and out of order packets of a stream before the start of the window.
+    pub fn record_baseline(&mut self, port: u32, app_id: u32, packet_loss: u64, out_of_order: u64) {
+        self.baseline
+            .entry(port)
+            .or_default()
+            .insert(app_id, (packet_loss, out_of_order));
+    }
+
+    /// Returns the lost and out of order packets of a stream since the start of the window.
+    pub fn since_start(
+        &self,
+        port: u32,
+        app_id: u32,
+        packet_loss: u64,
+        out_of_order: u64,
+    ) -> (u64, u64) {
+        let (loss_start, out_of_order_start) = self
+            .baseline
+            .get(&port)
+            .and_then(|b| b.get(&app_id))
+            .copied()
+            .unwrap_or_default();
+
+        (
+            packet_loss.saturating_sub(loss_start),
+            out_of_order.saturating_sub(out_of_order_start),
+        )
+    }
+
+    /// Returns the mean rates per port inside the window.
+    pub fn mean_rates(&self) -> HashMap<u32, MeanRates> {
+        let mut mean_rates: HashMap<u32, MeanRates> = HashMap::new();
+
+        for (port, counters) in &self.tx_bytes {
+            let (l1, l2) = Self::rate(counters);
+            let rates = mean_rates.entry(*port).or_default();
+            rates.tx_l1 = l1;
+            rates.tx_l2 = l2;
+        }
+
+        for (port, counters) in &self.rx_bytes {
+            let (l1, l2) = Self::rate(counters);
+            let rates = mean_rates.entry(*port).or_default();
+            rates.rx_l1 = l1;
+            rates.rx_l2 = l2;
+        }
+
+        mean_rates
+    }
+
+    /// Returns the L1 and L2 rate between two byte counters.
+    fn rate((first, last): &(ByteCounter, ByteCounter)) -> (f64, f64) {
+        // catch overflow of the 48 bit timestamp
+        let time_diff = last.tstmp.wrapping_sub(first.tstmp) & TSTMP_MASK;
+
+        if time_diff == 0 {
+            return (0f64, 0f64);
+        }
+
+        let rate = |bytes: u64| 8f64 * bytes as f64 / time_diff as f64 * 1e9;
+
+        (rate(last.l1 - first.l1), rate(last.l2 - first.l2))
+    }
+}
+
+/// Indicates whether the measurement window of the current test has ended, i.e., statistics must not be updated anymore.
+/// The window of a stopped test has ended if it has a cool-down phase.
+pub(crate) async fn has_ended(state: &AppState) -> bool {
+    let (running, start) = {
+        let experiment = state.experiment.lock().await;
+        (experiment.running, experiment.start)
+    };
+
+    let window = state.rate_monitor.lock().await.window;
+
+    if window.end.is_none() {
+        return false;
+    }
+
+    !running || window.has_ended(elapsed(start))
+}
+
+fn elapsed(start: SystemTime) -> ElapsedTime {
+    ElapsedTime(start.elapsed().unwrap_or_default().as_millis() as u32)
+}
+
+/// Resets the data plane counters at the start of the measurement window.
+/// The packet loss and the byte counters of the monitoring packets are snapshotted by the [RateMonitor](crate::core::RateMonitor) instead.
+async fn reset_counters(state: &AppState) -> Result<(), RBFRTError> {
+    let switch = state.switch.as_ref();
+
+    state
+        .frame_size_monitor
+        .lock()
+        .await
+        .on_reset(switch)
+        .await?;
+    state
+        .frame_type_monitor
+        .lock()
+        .await
+        .on_reset(switch)
+        .await?;
+
+    for histogram_monitor in [&state.rtt_histogram_monitor, &state.iat_histogram_monitor] {
+        let mut histogram_monitor = histogram_monitor.lock().await;
+        histogram_monitor.init_rtt_histogram_table(switch).await?;
+        histogram_monitor.on_reset(switch).await?;
+    }
+
+    switch
+        .clear_tables(vec![MEAN_IAT_REGISTER, MAE_IAT_REGISTER])
+        .await?;
+    state.rate_monitor.lock().await.statistics.iats.clear();
+
+    Ok(())
+}
+
+/// Resets the data plane counters at the start of the measurement window of each test.
+/// This method runs in a thread.
+pub async fn monitor_window(state: Arc<AppState>) {
+    // start of the test whose measurement window has already started
+    let mut started: Option<SystemTime> = None;
+
+    loop {
+        let (running, start) = {
+            let experiment = state.experiment.lock().await;
+            (experiment.running, experiment.start)
+        };
+
+        if running && started != Some(start) {
+            let window = state.rate_monitor.lock().await.window;
+
+            if window.has_started(elapsed(start)) {
+                started = Some(start);
+
+                if window.start.0 > 0 {
+                    match reset_counters(&state).await {
+                        Ok(_) => info!(
+                            "Measurement window started after {} s.",
+                            window.start.as_secs_f64()
+                        ),
+                        Err(e) => warn!("Error while resetting the counters at the start of the measurement window: {e:#?}"),
+                    }
+                }
+            }
+        }
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::docs::traffic_gen::EXAMPLE_POST_2_REQUEST;
+    use crate::core::backend::mock::tests::{call, mock_state};
+    use axum::http::{Method, StatusCode};
+
+    #[test]
+    fn counters_are_relative_to_the_window() {
+        let window = MeasurementWindow::new(Some(2), Some(3), Some(10));
+        assert_eq!(window.start, ElapsedTime(2000));
+        assert_eq!(window.end, Some(ElapsedTime(7000)));
+        assert!(!window.contains(ElapsedTime(1999)));
+        assert!(window.contains(ElapsedTime(2000)));
+        assert!(window.has_ended(ElapsedTime(7000)));
+        // the cool-down phase requires a duration
+        assert!(!MeasurementWindow::new(None, Some(3), None).is_limited());
+
+        let mut counters = WindowCounters::default();
+        counters.record_baseline(1, 1, 100, 5);
+        assert_eq!(counters.since_start(1, 1, 150, 5), (50, 0));
+        assert_eq!(counters.since_start(1, 2, 150, 5), (150, 5));
+
+        // 1 Gbps L1 and 0.8 Gbps L2 on TX port 1 for 2 s
+        counters.record_bytes(1, true, 1_000, 800, 5_000_000_000);
+        counters.record_bytes(1, true, 250_001_000, 200_000_800, 7_000_000_000);
+        let rates = &counters.mean_rates()[&1];
+        assert_eq!(rates.tx_l1, 1e9);
+        assert_eq!(rates.tx_l2, 0.8e9);
+        assert_eq!(rates.rx_l1, 0f64);
+    }
+
+    #[tokio::test]
+    async fn time_statistics_mark_the_measurement_window() {
+        let (_, state) = mock_state().await;
+
+        let mut request = serde_json::to_value(&*EXAMPLE_POST_2_REQUEST).unwrap();
+        request["duration"] = serde_json::Value::Null;
+        request["cooldown"] = serde_json::json!(3);
+
+        let (status, _) = call(&state, Method::POST, "/trafficgen", request.to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        request["duration"] = serde_json::json!(5);
+        request["warmup"] = serde_json::json!(2);
+
+        let (status, _) = call(&state, Method::POST, "/trafficgen", request.to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        request["duration"] = serde_json::json!(10);
+
+        let (status, _) = call(&state, Method::POST, "/trafficgen", request.to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, stats) = call(&state, Method::GET, "/time_statistics", String::new()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            stats[0]["measurement_window"],
+            serde_json::json!({"start": "2", "end": "7"})
+        );
+
+        // the window is part of the test configuration
+        let (_, traffic_gen) = call(&state, Method::GET, "/trafficgen", String::new()).await;
+        assert_eq!(traffic_gen["warmup"], 2);
+        assert_eq!(traffic_gen["cooldown"], 3);
+
+        let (status, stats) = call(&state, Method::GET, "/statistics", String::new()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(stats[0]["mean_rates"].is_object());
+    }
+}
@@ -29,9 +29,14 @@ pub use duration_monitor::DurationMonitorTask;
 pub mod histogram_monitor;
 pub use histogram_monitor::HistogramMonitor;
 
+pub mod archive;
 mod rate_monitor;
+pub mod rfc2544;
+pub mod sla;
 pub mod statistics;
+pub mod statistics_stream;
 pub mod traffic_gen;
+pub mod y1564;
 
 mod multicast;
 pub use multicast::create_simple_multicast_group;
@@ -42,6 +47,10 @@ pub use rate_monitor::RateMonitor;
 
 mod arp;
 pub mod config;
+pub mod disruption;
+pub mod loss_analysis;
+pub mod measurement_window;
+pub mod quantile_sketch;
 pub mod traffic_gen_core;
 
 pub use config::Config;
@@ -53,3 +62,5 @@ pub use patterns::build_pattern_generation_entries;
 
 pub mod ports;
 pub use ports::configure_ports;
+
+pub mod backend;
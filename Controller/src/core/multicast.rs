@@ -17,9 +17,10 @@
  * Steffen Lindner (steffen.lindner@uni-tuebingen.de)
  */
 
+use crate::core::backend::SwitchBackend;
 use rbfrt::error::RBFRTError;
+use rbfrt::table;
 use rbfrt::table::MatchValue;
-use rbfrt::{table, SwitchConnection};
 
 /// Table for multicast groups
 const MULTICAST_TABLE: &str = "$pre.mgid";
@@ -36,7 +37,7 @@ const MULTICAST_NODE_TABLE: &str = "$pre.node";
 ///   This is used as identifier in the data plane.
 /// * `ports`: List of dev ports for the multicast group
 pub async fn create_simple_multicast_group(
-    switch: &SwitchConnection,
+    switch: &dyn SwitchBackend,
     mid: u16,
     ports: &[u32],
 ) -> Result<(), RBFRTError> {
@@ -68,7 +69,7 @@ pub async fn create_simple_multicast_group(
 /// * `mid`: Multicast group identifier.
 ///   This is used as identifier in the data plane.
 pub async fn delete_simple_multicast_group(
-    switch: &SwitchConnection,
+    switch: &dyn SwitchBackend,
     mid: u16,
 ) -> Result<(), RBFRTError> {
     let req = table::Request::new(MULTICAST_TABLE).match_key("$MGID", MatchValue::exact(mid));
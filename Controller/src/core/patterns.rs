@@ -171,6 +171,7 @@ fn flashcrowd_factor(
 ///  3. Splits [0..period_pkts) into NUM_SAMPLES segments
 ///  4. For each segment, computes a sine amplitude factor
 ///  5. Decomposes segment ranges into LPM prefixes and creates table entries
+#[allow(clippy::too_many_arguments)]
 pub fn build_pattern_generation_entries(
     app_id: u8,
     pattern_config: GenerationPatternConfig,
@@ -1,9 +1,8 @@
+use crate::core::backend::SwitchBackend;
 use log::{info, warn};
 use macaddr::MacAddr;
 use rbfrt::error::RBFRTError;
-use rbfrt::util::PortManager;
 use rbfrt::util::{AutoNegotiation, Loopback, Port};
-use rbfrt::SwitchConnection;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::str::FromStr;
@@ -211,7 +210,7 @@ pub fn configure_recirculation_ports(
 
 fn build_tg_recirc_mapping(
     config: &Config,
-    pm: &PortManager,
+    switch: &dyn SwitchBackend,
     auto_tg_ports: &[u32],
     manual_tg_ports: &[u32],
     recirc_ports_per_tg_choice: &HashMap<u32, RecirculationPair>,
@@ -235,9 +234,9 @@ fn build_tg_recirc_mapping(
             .expect("internal: missing recirc choice");
 
         for &ch in &layout.front_panel.channels {
-            let tx_dev = pm.dev_port(choice.tx_port, ch)?;
-            let rx_dev = pm.dev_port(choice.rx_port, ch)?;
-            let dev_port = pm.dev_port(*tg_port, ch)?;
+            let tx_dev = switch.dev_port(choice.tx_port, ch)?;
+            let rx_dev = switch.dev_port(choice.rx_port, ch)?;
+            let dev_port = switch.dev_port(*tg_port, ch)?;
             let mac = config
                 .get_mac_state(*tg_port, Some(ch))
                 .as_deref()
@@ -270,8 +269,7 @@ fn build_tg_recirc_mapping(
 }
 
 pub async fn configure_ports(
-    switch: &mut SwitchConnection,
-    pm: &PortManager,
+    switch: &dyn SwitchBackend,
     config: &mut Config,
     recirculation_ports: &[u32],
     port_mapping: &mut HashMap<u32, PortMapping>,
@@ -302,7 +300,7 @@ pub async fn configure_ports(
     port_requests.extend(recirculation_port_requests);
 
     // Push to hardware
-    pm.add_ports(switch, &port_requests).await?;
+    switch.add_ports(&port_requests).await?;
     info!("Ports of device configured.");
 
     // --- Build mapping (TG dev_port(+ch) -> recirc dev_ports(+ch)) ---
@@ -310,7 +308,7 @@ pub async fn configure_ports(
 
     match build_tg_recirc_mapping(
         config,
-        pm,
+        switch,
         &tg_port_config.auto_tg_ports,
         &tg_port_config.manual_tg_ports,
         &recirc_ports_per_tg_choice,
@@ -0,0 +1,163 @@
+/* Copyright 2022-present University of Tuebingen, Chair of Communication Networks
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/*
+ * Steffen Lindner (steffen.lindner@uni-tuebingen.de)
+ */
+
+use std::collections::BTreeMap;
+
+use crate::core::statistics::Quantiles;
+
+/// Maximal relative error of the estimated quantiles.
+const RELATIVE_ACCURACY: f64 = 0.005;
+
+/// Streaming sketch that estimates quantiles of non-negative values with a bounded relative error.
+///
+/// Values are counted in logarithmically sized buckets, i.e., bucket `i` contains the values in
+/// (gamma^(i-1), gamma^i] with gamma = (1 + [RELATIVE_ACCURACY]) / (1 - [RELATIVE_ACCURACY]).
+/// The memory is bounded by the range of the values and not by their number.
+/// Sketches are mergeable, i.e., the sketch of several sketches is the sum of their bucket counts.
+#[derive(Debug, Clone, Default)]
+pub struct QuantileSketch {
+    buckets: BTreeMap<i32, u64>,
+    zero_count: u64,
+    count: u64,
+    min: u64,
+    max: u64,
+}
+
+impl QuantileSketch {
+    fn gamma() -> f64 {
+        (1f64 + RELATIVE_ACCURACY) / (1f64 - RELATIVE_ACCURACY)
+    }
+
+    fn bucket(value: u64) -> i32 {
+        ((value as f64).ln() / Self::gamma().ln()).ceil() as i32
+    }
+
+    /// Representative value of bucket `index` that has a relative error of at most [RELATIVE_ACCURACY]
+    /// to all values of the bucket.
+    fn bucket_value(index: i32) -> f64 {
+        let gamma = Self::gamma();
+        2f64 * gamma.powi(index) / (gamma + 1f64)
+    }
+
+    pub fn add(&mut self, value: u64) {
+        if value == 0 {
+            self.zero_count += 1;
+        } else {
+            *self.buckets.entry(Self::bucket(value)).or_default() += 1;
+        }
+
+        self.min = if self.count == 0 {
+            value
+        } else {
+            self.min.min(value)
+        };
+        self.max = self.max.max(value);
+        self.count += 1;
+    }
+
+    /// Adds all values of `other` to this sketch.
+    pub fn merge(&mut self, other: &QuantileSketch) {
+        if other.count == 0 {
+            return;
+        }
+
+        for (index, count) in &other.buckets {
+            *self.buckets.entry(*index).or_default() += count;
+        }
+
+        self.zero_count += other.zero_count;
+        self.min = if self.count == 0 {
+            other.min
+        } else {
+            self.min.min(other.min)
+        };
+        self.max = self.max.max(other.max);
+        self.count += other.count;
+    }
+
+    /// Returns the estimated `q`-quantile with `q` in [0, 1], or None if no values have been added.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let rank = (q.clamp(0f64, 1f64) * (self.count - 1) as f64).floor() as u64;
+
+        if rank < self.zero_count {
+            return Some(0f64);
+        }
+
+        let mut seen = self.zero_count;
+        let mut value = self.max as f64;
+
+        for (index, count) in &self.buckets {
+            seen += count;
+
+            if seen > rank {
+                value = Self::bucket_value(*index);
+                break;
+            }
+        }
+
+        // the exact extreme values are known
+        Some(value.clamp(self.min as f64, self.max as f64))
+    }
+
+    /// Returns the commonly reported quantiles of the sketch.
+    pub fn quantiles(&self) -> Quantiles {
+        Quantiles {
+            p50: self.quantile(0.5).unwrap_or_default(),
+            p90: self.quantile(0.9).unwrap_or_default(),
+            p99: self.quantile(0.99).unwrap_or_default(),
+            p999: self.quantile(0.999).unwrap_or_default(),
+            n: self.count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantiles_have_bounded_relative_error() {
+        let mut sketch = QuantileSketch::default();
+        let mut other = QuantileSketch::default();
+
+        // 1..=100000 ns, split over two sketches
+        for value in 1..=100_000u64 {
+            if value % 2 == 0 {
+                sketch.add(value);
+            } else {
+                other.add(value);
+            }
+        }
+        sketch.merge(&other);
+
+        for (q, expected) in [(0.5, 50_000f64), (0.9, 90_000f64), (0.999, 99_900f64)] {
+            let estimate = sketch.quantile(q).unwrap();
+            assert!((estimate - expected).abs() / expected <= RELATIVE_ACCURACY + 1e-4);
+        }
+
+        assert_eq!(sketch.quantile(0.0), Some(1f64));
+        assert_eq!(sketch.quantile(1.0), Some(100_000f64));
+        assert_eq!(sketch.quantiles().n, 100_000);
+        assert_eq!(QuantileSketch::default().quantile(0.5), None);
+    }
+}
@@ -20,38 +20,50 @@
 use std::cmp::max;
 use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
+use crate::core::backend::SwitchBackend;
 use async_trait::async_trait;
 use log::{info, warn};
 use rbfrt::error::RBFRTError;
 use rbfrt::register::Register;
 use rbfrt::table::{MatchValue, ToBytes};
-use rbfrt::{register, table, SwitchConnection};
+use rbfrt::{register, table};
 use tokio::time::sleep;
 
-use crate::core::statistics::{IATStatistics, RateMonitorStatistics, TimeStatistics};
+use crate::core::disruption::DisruptionMonitor;
+use crate::core::loss_analysis::LossAnalyzer;
+use crate::core::measurement_window::{self, MeasurementWindow, WindowCounters};
+use crate::core::quantile_sketch::QuantileSketch;
+use crate::core::statistics::{ElapsedTime, IATStatistics, RateMonitorStatistics, TimeStatistics};
+use crate::core::traffic_gen_core::const_definitions::DEFAULT_TIME_RESOLUTION;
 use crate::core::traffic_gen_core::event::TrafficGenEvent;
 use crate::core::traffic_gen_core::types::GenerationMode;
 use crate::core::traffic_gen_core::types::MonitoringMapping;
 use crate::{AppState, PortMapping};
 
 /// Table that checks if a packet was received on an ingress port
-const IS_INGRESS_TABLE: &str = "ingress.p4tg.is_ingress";
-const MEAN_IAT_REGISTER: &str = "ingress.p4tg.iat.mean_iat";
-const MAE_IAT_REGISTER: &str = "ingress.p4tg.iat.mae_iat";
+pub(crate) const IS_INGRESS_TABLE: &str = "ingress.p4tg.is_ingress";
+pub(crate) const MEAN_IAT_REGISTER: &str = "ingress.p4tg.iat.mean_iat";
+pub(crate) const MAE_IAT_REGISTER: &str = "ingress.p4tg.iat.mae_iat";
 const CURRENT_MEAN_IAT_REGISTER: &str = "ingress.p4tg.iat.current_mean_iat";
 
 /// Controls whether IATs should be monitored
-const MONITOR_IAT_TABLE: &str = "ingress.p4tg.monitor_iat";
+pub(crate) const MONITOR_IAT_TABLE: &str = "ingress.p4tg.monitor_iat";
 const RTT_METER_TABLE: &str = "ingress.p4tg.rtt.digest_rate";
-const IAT_METER_TABLE: &str = "ingress.p4tg.iat.digest_rate";
-const RATE_DIGEST_NAME: &str = "pipe.SwitchIngressDeparser.digest";
-const RTT_IAT_DIGEST_NAME: &str = "pipe.SwitchIngressDeparser.digest_2";
+pub(crate) const IAT_METER_TABLE: &str = "ingress.p4tg.iat.digest_rate";
+pub(crate) const RATE_DIGEST_NAME: &str = "pipe.SwitchIngressDeparser.digest";
+pub(crate) const RTT_IAT_DIGEST_NAME: &str = "pipe.SwitchIngressDeparser.digest_2";
 
 /// Number of RTTs that should be stored
 const RTT_STORAGE: usize = 50000;
 
+/// IAT samples of the first 3 seconds are excluded in sample mode if no warm-up is configured
+const IAT_SAMPLE_WARMUP: u32 = 3000;
+
+/// Data plane timestamps are 48 bit wide
+pub(crate) const TSTMP_MASK: u64 = (1 << 48) - 1;
+
 /// This module handles the initialization of the `egress.frame_size_monitor` table
 /// that counts the different frame sizes that are received/sent
 pub struct RateMonitor {
@@ -59,12 +71,79 @@ pub struct RateMonitor {
     pub statistics: RateMonitorStatistics,
     pub time_statistics: TimeStatistics,
     pub rtt_storage: HashMap<u32, VecDeque<u64>>,
+    /// Sampled RTTs per port and stream
+    pub app_rtt_storage: HashMap<u32, HashMap<u32, VecDeque<u64>>>,
     pub tx_iat_storage: HashMap<u32, VecDeque<u64>>,
     pub rx_iat_storage: HashMap<u32, VecDeque<u64>>,
+    /// Quantile sketches of all RTTs per port and stream. RTTs of unknown streams have app id 0.
+    pub rtt_sketches: HashMap<u32, HashMap<u32, QuantileSketch>>,
+    /// Quantile sketches of all sampled IATs per port
+    pub tx_iat_sketches: HashMap<u32, QuantileSketch>,
+    pub rx_iat_sketches: HashMap<u32, QuantileSketch>,
+    /// Resolution of the time statistics in ms
+    pub time_resolution: u32,
+    /// Disruption time and outage episodes per RX port and stream
+    pub disruption: DisruptionMonitor,
+    /// Loss characterization per RX port and stream
+    pub loss_analysis: LossAnalyzer,
+    /// Measurement window of the current test
+    pub window: MeasurementWindow,
+    /// Snapshots of the data plane counters at the boundaries of the measurement window
+    pub window_counters: WindowCounters,
+    /// Lost and out of order packets per RX port of P4TG traffic with an unknown app id.
+    /// They are contained in the port counters, but in the counters of no stream.
+    pub unmatched_counters: HashMap<u32, (u64, u64)>,
     #[allow(dead_code)]
     running: bool,
 }
 
+/// Converts the data plane timestamps of the monitoring packets into the elapsed time of the current test.
+/// The data plane time is aligned with the start of the test at the first timestamp of the test.
+#[derive(Default)]
+struct DataPlaneClock {
+    /// Start of the current test. None if no test is running.
+    test_start: Option<SystemTime>,
+    /// First data plane timestamp of the test in ns and the elapsed time of the test at that timestamp in ns
+    start: Option<(u64, u64)>,
+    /// Elapsed time of the latest data plane timestamp
+    last: Option<ElapsedTime>,
+}
+
+impl DataPlaneClock {
+    /// Aligns the clock with the data plane time again if a new test has started.
+    fn sync(&mut self, test_start: Option<SystemTime>) {
+        if self.test_start != test_start {
+            self.test_start = test_start;
+            self.start = None;
+            self.last = None;
+        }
+    }
+
+    /// Returns the elapsed time of the test at data plane timestamp `tstmp`.
+    /// `wall_elapsed` is the elapsed time of the test based on the wall clock.
+    fn elapsed(&mut self, tstmp: u64, wall_elapsed: Duration) -> ElapsedTime {
+        let (start_tstmp, start_elapsed) = *self
+            .start
+            .get_or_insert((tstmp, wall_elapsed.as_nanos() as u64));
+
+        // catch overflow of the 48 bit timestamp
+        // timestamps slightly before the first timestamp are counted as the first timestamp
+        let diff = tstmp.wrapping_sub(start_tstmp) & TSTMP_MASK;
+        let diff = if diff > TSTMP_MASK / 2 { 0 } else { diff };
+
+        let elapsed = ElapsedTime(((start_elapsed + diff) / 1_000_000) as u32);
+        self.last = Some(elapsed);
+        elapsed
+    }
+
+    /// Returns the elapsed time of the latest data plane timestamp.
+    /// Falls back to `wall_elapsed` if no data plane timestamp was received yet.
+    fn now(&self, wall_elapsed: Duration) -> ElapsedTime {
+        self.last
+            .unwrap_or(ElapsedTime(wall_elapsed.as_millis() as u32))
+    }
+}
+
 #[derive(Clone, Debug)]
 struct DataRate {
     byte_count_l1: u64,
@@ -99,13 +178,26 @@ impl RateMonitor {
             statistics: RateMonitorStatistics::default(),
             time_statistics: TimeStatistics::default(),
             rtt_storage: Default::default(),
+            app_rtt_storage: Default::default(),
             tx_iat_storage: Default::default(),
             rx_iat_storage: Default::default(),
+            rtt_sketches: Default::default(),
+            tx_iat_sketches: Default::default(),
+            rx_iat_sketches: Default::default(),
+            time_resolution: DEFAULT_TIME_RESOLUTION,
+            disruption: DisruptionMonitor::default(),
+            loss_analysis: LossAnalyzer::default(),
+            window: MeasurementWindow::default(),
+            window_counters: WindowCounters::default(),
+            unmatched_counters: HashMap::new(),
             running: true,
         }
     }
 
-    pub async fn init_monitoring_rules(&self, switch: &SwitchConnection) -> Result<(), RBFRTError> {
+    pub async fn init_monitoring_rules(
+        &self,
+        switch: &dyn SwitchBackend,
+    ) -> Result<(), RBFRTError> {
         // clear table
         let delete_request = table::Request::new(IS_INGRESS_TABLE);
         switch.delete_table_entry(delete_request).await?;
@@ -128,7 +220,7 @@ impl RateMonitor {
         Ok(())
     }
 
-    pub async fn init_rtt_meter(&self, switch: &SwitchConnection) -> Result<(), RBFRTError> {
+    pub async fn init_rtt_meter(&self, switch: &dyn SwitchBackend) -> Result<(), RBFRTError> {
         let target_pps = 800f32;
         let packet_size = (64 + 20) as f32;
 
@@ -177,7 +269,7 @@ impl RateMonitor {
     /// This is used to limit the number of digests that are created.
     pub async fn init_iat_meter(
         &self,
-        switch: &SwitchConnection,
+        switch: &dyn SwitchBackend,
         sample_mode: bool,
     ) -> Result<(), RBFRTError> {
         let target_pps = 800f32;
@@ -257,8 +349,14 @@ impl RateMonitor {
         }
 
         loop {
+            // the IAT statistics are not updated after the measurement window
+            if measurement_window::has_ended(&state).await {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                continue;
+            }
+
             let mean_iat_register = {
-                let switch = &state.switch;
+                let switch = state.switch.as_ref();
                 let sync = table::Request::new(MEAN_IAT_REGISTER)
                     .operation(table::TableOperation::SyncRegister);
 
@@ -279,7 +377,7 @@ impl RateMonitor {
             };
 
             let mae_iat_register = {
-                let switch = &state.switch;
+                let switch = state.switch.as_ref();
                 let sync = table::Request::new(MAE_IAT_REGISTER)
                     .operation(table::TableOperation::SyncRegister);
 
@@ -404,7 +502,7 @@ impl RateMonitor {
 
             // write register updates
             {
-                let switch = &state.switch;
+                let switch = state.switch.as_ref();
                 if switch
                     .write_register_entries(update_requests)
                     .await
@@ -449,6 +547,183 @@ impl RateMonitor {
         }
     }
 
+    /// Configures the measurement window of a new test and deletes the snapshots of the previous test.
+    pub fn configure_window(&mut self, window: MeasurementWindow) {
+        self.window = window;
+        self.window_counters.reset();
+        self.time_statistics.measurement_window = window.is_limited().then_some(window);
+    }
+
+    /// Indicates whether statistics should be accumulated at time `time`.
+    /// `time` is None if no test is running. Statistics of a limited window are not updated after the test.
+    fn in_window(&self, time: Option<ElapsedTime>) -> bool {
+        match time {
+            Some(time) => self.window.contains(time),
+            None => !self.window.is_limited(),
+        }
+    }
+
+    /// Stores the lost and out of order packets of a stream on an RX port.
+    /// If `elapsed_time` is set, the values are also added to the time statistics.
+    /// The counters are relative to the start of the measurement window and are not updated after the window.
+    /// `now` is the time of the counters, None if no test is running.
+    fn record_stream_counters(
+        &mut self,
+        port: u32,
+        app_id: u32,
+        counters: (u64, u64),
+        now: Option<ElapsedTime>,
+        elapsed_time: Option<ElapsedTime>,
+    ) {
+        let Some((packet_loss, out_of_order)) = self.window_counters(port, app_id, counters, now)
+        else {
+            return;
+        };
+
+        self.statistics
+            .app_packet_loss
+            .entry(port)
+            .or_default()
+            .insert(app_id, packet_loss);
+        self.statistics
+            .app_out_of_order
+            .entry(port)
+            .or_default()
+            .insert(app_id, out_of_order);
+
+        if let Some(elapsed_time) = elapsed_time {
+            insert_stream_value(
+                &mut self.time_statistics.app_packet_loss,
+                port,
+                app_id,
+                elapsed_time,
+                packet_loss,
+            );
+            insert_stream_value(
+                &mut self.time_statistics.app_out_of_order,
+                port,
+                app_id,
+                elapsed_time,
+                out_of_order,
+            );
+        }
+    }
+
+    /// Stores the lost and out of order packets of P4TG traffic with an unknown app id on an RX port.
+    /// `now` is the time of the counters, None if no test is running.
+    fn record_unmatched_counters(
+        &mut self,
+        port: u32,
+        counters: (u64, u64),
+        now: Option<ElapsedTime>,
+    ) {
+        // app id 0 is not used by any stream
+        if let Some(counters) = self.window_counters(port, 0, counters, now) {
+            self.unmatched_counters.insert(port, counters);
+        }
+    }
+
+    /// Returns the data plane counters of a stream relative to the start of the measurement window.
+    /// Returns None if the counters are after the window and must not be updated anymore.
+    fn window_counters(
+        &mut self,
+        port: u32,
+        app_id: u32,
+        (packet_loss, out_of_order): (u64, u64),
+        now: Option<ElapsedTime>,
+    ) -> Option<(u64, u64)> {
+        if now.is_some_and(|now| !self.window.has_started(now)) {
+            self.window_counters
+                .record_baseline(port, app_id, packet_loss, out_of_order);
+        }
+
+        if !self.in_window(now) && now.is_none_or(|now| self.window.has_started(now)) {
+            return None;
+        }
+
+        Some(
+            self.window_counters
+                .since_start(port, app_id, packet_loss, out_of_order),
+        )
+    }
+
+    /// Returns the lost and out of order packets of an RX port.
+    /// These are the sum over all streams of the port and the traffic with an unknown app id.
+    fn port_counters(&self, port: u32) -> (u64, u64) {
+        let (unmatched_loss, unmatched_out_of_order) = self
+            .unmatched_counters
+            .get(&port)
+            .copied()
+            .unwrap_or_default();
+
+        (
+            self.statistics
+                .app_packet_loss
+                .get(&port)
+                .map_or(0, |loss| loss.values().sum())
+                + unmatched_loss,
+            self.statistics
+                .app_out_of_order
+                .get(&port)
+                .map_or(0, |out_of_order| out_of_order.values().sum())
+                + unmatched_out_of_order,
+        )
+    }
+
+    /// Updates the disruption of a stream on an RX port with its current packet loss at time `time`.
+    /// If `elapsed_time` is set, the disruption times are also added to the time statistics.
+    fn record_disruption(
+        &mut self,
+        port: u32,
+        app_id: u32,
+        time: ElapsedTime,
+        elapsed_time: Option<ElapsedTime>,
+    ) {
+        self.disruption.record(&self.statistics, port, app_id, time);
+
+        if let Some(elapsed_time) = elapsed_time {
+            let (port_disruption, app_disruption) = self.disruption.disruption_time(port, app_id);
+
+            if let Some(disruption) = port_disruption {
+                let values = self
+                    .time_statistics
+                    .disruption_time
+                    .entry(port)
+                    .or_default();
+                values.insert(elapsed_time, disruption);
+                values.retain(|key, _| *key <= elapsed_time);
+            }
+
+            if let Some(disruption) = app_disruption {
+                insert_stream_value(
+                    &mut self.time_statistics.app_disruption_time,
+                    port,
+                    app_id,
+                    elapsed_time,
+                    disruption,
+                );
+            }
+        }
+    }
+
+    /// Stores an RTT that was measured on a stream on an RX port.
+    fn record_stream_rtt(&mut self, port: u32, app_id: u32, rtt: u64, elapsed_time: ElapsedTime) {
+        self.app_rtt_storage
+            .entry(port)
+            .or_default()
+            .entry(app_id)
+            .or_insert(VecDeque::with_capacity(RTT_STORAGE))
+            .push_back(rtt);
+
+        insert_stream_value(
+            &mut self.time_statistics.app_rtt,
+            port,
+            app_id,
+            elapsed_time,
+            rtt,
+        );
+    }
+
     /// Monitors the digests that are received from the switch.
     /// This method runs in a thread.
     pub async fn monitor_digests(
@@ -487,6 +762,14 @@ impl RateMonitor {
                 rate_monitor.statistics.rx_rate_l2.insert(*port, 0.0);
                 rate_monitor.statistics.packet_loss.insert(*port, 0);
                 rate_monitor.statistics.out_of_order.insert(*port, 0);
+                rate_monitor
+                    .statistics
+                    .app_packet_loss
+                    .insert(*port, HashMap::new());
+                rate_monitor
+                    .statistics
+                    .app_out_of_order
+                    .insert(*port, HashMap::new());
                 tx_reverse_mapping.insert(mapping.tx_recirculation, *port);
                 rx_reverse_mapping.insert(mapping.rx_recirculation, *port);
                 last_tx.insert(*port, DataRate::new(0, 0, 0, 0.0, 0.0));
@@ -525,26 +808,26 @@ impl RateMonitor {
             last_app_rx.insert(*index, DataRate::new(0, 0, 0, 0.0, 0.0));
         }
 
+        let mut clock = DataPlaneClock::default();
+
         // listen on the channel that receives digests
         loop {
-            match state.switch.digest_queue.try_recv() {
-                Ok(digest) => {
-                    let (elapsed_time, running) = {
+            match state.switch.try_recv_digest() {
+                Some(digest) => {
+                    let (wall_elapsed, running) = {
                         let exp = state.experiment.lock().await;
 
                         if exp.running {
-                            (
-                                exp.start
-                                    .elapsed()
-                                    .unwrap_or(Duration::from_secs(0))
-                                    .as_secs() as u32,
-                                true,
-                            )
+                            clock.sync(Some(exp.start));
+                            (exp.start.elapsed().unwrap_or(Duration::from_secs(0)), true)
                         } else {
-                            (0, false)
+                            clock.sync(None);
+                            (Duration::from_secs(0), false)
                         }
                     };
 
+                    let time_resolution = state.rate_monitor.lock().await.time_resolution;
+
                     if digest.name == RATE_DIGEST_NAME {
                         let data = &digest.data;
 
@@ -560,6 +843,8 @@ impl RateMonitor {
                         }
 
                         let time = data.get("tstmp").unwrap().to_u64();
+                        let now = clock.elapsed(time, wall_elapsed);
+                        let elapsed_time = now.slot(time_resolution);
 
                         let l1_byte = data.get("byte_counter_l1").unwrap().to_u64();
                         let l2_byte = data.get("byte_counter_l2").unwrap().to_u64();
@@ -588,6 +873,90 @@ impl RateMonitor {
                             rx_reverse_mapping.get(&port).unwrap()
                         };
 
+                        // lost and out of order packets are tracked per stream in the data plane
+                        // the digest reports the counters of the index before app_index
+                        let counter_app_id = app_index
+                            .checked_sub(1)
+                            .and_then(|index| index_mapping.get(&index))
+                            .map(|mapping| mapping.app_id as u32);
+
+                        // traffic with an unknown app id is tracked at the first index of the TX recirculation port
+                        if is_tx && counter_app_id == Some(1) {
+                            state.rate_monitor.lock().await.record_unmatched_counters(
+                                *port,
+                                (packet_loss, out_of_order),
+                                running.then_some(now),
+                            );
+                        }
+
+                        let stream_app_id = counter_app_id.filter(|_| !is_tx);
+
+                        let (packet_loss, out_of_order) = match stream_app_id {
+                            Some(app_id) => {
+                                let rate_monitor = &mut state.rate_monitor.lock().await;
+
+                                rate_monitor.record_stream_counters(
+                                    *port,
+                                    app_id,
+                                    (packet_loss, out_of_order),
+                                    running.then_some(now),
+                                    running.then_some(elapsed_time),
+                                );
+
+                                let (port_loss, _) = rate_monitor.port_counters(*port);
+                                rate_monitor.loss_analysis.record_loss(
+                                    *port,
+                                    app_id,
+                                    port_loss,
+                                    packet_loss,
+                                );
+
+                                if running {
+                                    rate_monitor.record_disruption(
+                                        *port,
+                                        app_id,
+                                        now,
+                                        Some(elapsed_time),
+                                    );
+                                }
+
+                                rate_monitor.port_counters(*port)
+                            }
+                            None => {
+                                let rate_monitor = state.rate_monitor.lock().await;
+
+                                // the port counters of a limited measurement window are only updated per stream
+                                if !is_tx && rate_monitor.window.is_limited() {
+                                    let statistics = &rate_monitor.statistics;
+                                    (
+                                        statistics
+                                            .packet_loss
+                                            .get(port)
+                                            .copied()
+                                            .unwrap_or_default(),
+                                        statistics
+                                            .out_of_order
+                                            .get(port)
+                                            .copied()
+                                            .unwrap_or_default(),
+                                    )
+                                } else {
+                                    (packet_loss, out_of_order)
+                                }
+                            }
+                        };
+
+                        // byte counters for the mean rates inside the measurement window
+                        if running {
+                            let rate_monitor = &mut state.rate_monitor.lock().await;
+
+                            if rate_monitor.window.contains(now) {
+                                rate_monitor
+                                    .window_counters
+                                    .record_bytes(*port, is_tx, l1_byte, l2_byte, time);
+                            }
+                        }
+
                         let last = last_update.get(port).unwrap();
 
                         let index_port_app_mapping = index_mapping.get(&app_index);
@@ -746,26 +1115,34 @@ impl RateMonitor {
                                     RateMonitor::calculate_rate((0, app_byte, time), last_app);
                                 let mapping = index_mapping.get(&app_index).unwrap();
 
-                                if is_tx {
-                                    state
-                                        .rate_monitor
-                                        .lock()
-                                        .await
-                                        .statistics
-                                        .app_tx_l2
-                                        .get_mut(port)
-                                        .unwrap()
-                                        .insert(mapping.app_id as u32, new_app_rate.rate_l2);
+                                let mut rate_monitor = state.rate_monitor.lock().await;
+                                let rate_monitor = &mut *rate_monitor;
+                                let (app_rates, app_time_statistics) = if is_tx {
+                                    (
+                                        &mut rate_monitor.statistics.app_tx_l2,
+                                        &mut rate_monitor.time_statistics.app_tx_l2,
+                                    )
                                 } else {
-                                    state
-                                        .rate_monitor
-                                        .lock()
-                                        .await
-                                        .statistics
-                                        .app_rx_l2
-                                        .get_mut(port)
-                                        .unwrap()
-                                        .insert(mapping.app_id as u32, new_app_rate.rate_l2);
+                                    (
+                                        &mut rate_monitor.statistics.app_rx_l2,
+                                        &mut rate_monitor.time_statistics.app_rx_l2,
+                                    )
+                                };
+
+                                app_rates
+                                    .get_mut(port)
+                                    .unwrap()
+                                    .insert(mapping.app_id as u32, new_app_rate.rate_l2);
+
+                                // time statistics
+                                if running {
+                                    insert_stream_value(
+                                        app_time_statistics,
+                                        *port,
+                                        mapping.app_id as u32,
+                                        elapsed_time,
+                                        new_app_rate.rate_l2,
+                                    );
                                 }
 
                                 last_update_app.insert(app_index, new_app_rate);
@@ -782,11 +1159,21 @@ impl RateMonitor {
                         let port = data.get("port").unwrap().to_u32();
 
                         let rtt = data.get("rtt").unwrap().to_u64();
+                        // app id of the stream the RTT was measured on, 0 if unknown
+                        let app_id = data.get("app_id").map(|a| a.to_u32()).unwrap_or(0);
+                        // RTT digests do not contain a timestamp
+                        let now = clock.now(wall_elapsed);
+                        let elapsed_time = now.slot(time_resolution);
 
                         // catch timestamp overflow
                         if rtt > 0
                             && rtt < (u32::MAX / 2) as u64
                             && rx_reverse_mapping.contains_key(&port)
+                            && state
+                                .rate_monitor
+                                .lock()
+                                .await
+                                .in_window(running.then_some(now))
                         {
                             let port = rx_reverse_mapping.get(&port).unwrap();
 
@@ -798,6 +1185,16 @@ impl RateMonitor {
                                 .entry(*port)
                                 .or_insert(VecDeque::with_capacity(RTT_STORAGE))
                                 .push_back(rtt);
+                            state
+                                .rate_monitor
+                                .lock()
+                                .await
+                                .rtt_sketches
+                                .entry(*port)
+                                .or_default()
+                                .entry(app_id)
+                                .or_default()
+                                .add(rtt);
                             state
                                 .rate_monitor
                                 .lock()
@@ -818,51 +1215,81 @@ impl RateMonitor {
                                 .entry(*port)
                                 .or_default()
                                 .retain(|key, _| *key <= elapsed_time);
+
+                            if app_id != 0 {
+                                let rate_monitor = &mut state.rate_monitor.lock().await;
+                                rate_monitor.record_stream_rtt(*port, app_id, rtt, elapsed_time);
+
+                                // sequence number of the sampled packet and the expected sequence number
+                                if let (Some(seq), Some(expected_seq)) =
+                                    (data.get("seq"), data.get("expected_seq"))
+                                {
+                                    rate_monitor.loss_analysis.record_sample(
+                                        *port,
+                                        app_id,
+                                        seq.to_u32(),
+                                        expected_seq.to_u32(),
+                                    );
+                                }
+                            }
                         }
 
                         if sample_mode {
+                            let window = state.rate_monitor.lock().await.window;
                             let experiment = state.experiment.lock().await;
 
-                            if experiment.running
-                                && experiment
-                                    .start
-                                    .elapsed()
-                                    .is_ok_and(|x| x > Duration::from_secs(3))
-                            {
+                            let warmup = if window.start.0 > 0 {
+                                window.start
+                            } else {
+                                ElapsedTime(IAT_SAMPLE_WARMUP)
+                            };
+
+                            if experiment.running && now >= warmup {
                                 let iat = data.get("iat").unwrap().to_u64();
 
-                                if iat > 0 && iat < (u32::MAX / 2) as u64 {
+                                // samples after the measurement window are ignored
+                                if iat > 0 && iat < (u32::MAX / 2) as u64 && !window.has_ended(now)
+                                {
                                     // catch overflow
                                     if rx_reverse_mapping.contains_key(&port) {
                                         let port = rx_reverse_mapping.get(&port).unwrap();
-                                        state
-                                            .rate_monitor
-                                            .lock()
-                                            .await
+                                        let rate_monitor = &mut state.rate_monitor.lock().await;
+                                        rate_monitor
                                             .rx_iat_storage
                                             .entry(*port)
                                             .or_insert(VecDeque::with_capacity(RTT_STORAGE))
                                             .push_back(iat);
+                                        rate_monitor
+                                            .rx_iat_sketches
+                                            .entry(*port)
+                                            .or_default()
+                                            .add(iat);
                                     } else if tx_reverse_mapping.contains_key(&port) {
                                         let port = tx_reverse_mapping.get(&port).unwrap();
-                                        state
-                                            .rate_monitor
-                                            .lock()
-                                            .await
+                                        let rate_monitor = &mut state.rate_monitor.lock().await;
+                                        rate_monitor
                                             .tx_iat_storage
                                             .entry(*port)
                                             .or_insert(VecDeque::with_capacity(RTT_STORAGE))
                                             .push_back(iat);
+                                        rate_monitor
+                                            .tx_iat_sketches
+                                            .entry(*port)
+                                            .or_default()
+                                            .add(iat);
                                     }
                                 }
                             } else {
-                                state.rate_monitor.lock().await.tx_iat_storage.clear();
-                                state.rate_monitor.lock().await.rx_iat_storage.clear();
+                                let rate_monitor = &mut state.rate_monitor.lock().await;
+                                rate_monitor.tx_iat_storage.clear();
+                                rate_monitor.rx_iat_storage.clear();
+                                rate_monitor.tx_iat_sketches.clear();
+                                rate_monitor.rx_iat_sketches.clear();
                             }
                         }
                     }
                 }
-                Err(_) => {
+                None => {
                     // Sleep if there’s nothing to process. If we do not do this, the CPU load is very high.
                     sleep(Duration::from_millis(400)).await; // Sleep for 400ms before trying again
                 }
@@ -872,11 +1299,26 @@ impl RateMonitor {
     }
 }
 
+/// Inserts `value` at `elapsed_time` into the time series of stream `app_id` on `port`.
+fn insert_stream_value<V>(
+    series: &mut HashMap<u32, HashMap<u32, BTreeMap<ElapsedTime, V>>>,
+    port: u32,
+    app_id: u32,
+    elapsed_time: ElapsedTime,
+    value: V,
+) {
+    let values = series.entry(port).or_default().entry(app_id).or_default();
+
+    values.insert(elapsed_time, value);
+    // remove potential old data
+    values.retain(|key, _| *key <= elapsed_time);
+}
+
 #[async_trait]
 impl TrafficGenEvent for RateMonitor {
     async fn on_start(
         &mut self,
-        switch: &SwitchConnection,
+        switch: &dyn SwitchBackend,
         mode: &GenerationMode,
     ) -> Result<(), RBFRTError> {
         switch
@@ -885,6 +1327,8 @@ impl TrafficGenEvent for RateMonitor {
 
         self.time_statistics.tx_rate_l1.clear();
         self.time_statistics.rx_rate_l1.clear();
+        self.time_statistics.app_tx_l2.clear();
+        self.time_statistics.app_rx_l2.clear();
 
         // allow iat generation
         let req = table::Request::new(MONITOR_IAT_TABLE)
@@ -902,7 +1346,7 @@ impl TrafficGenEvent for RateMonitor {
         Ok(())
     }
 
-    async fn on_stop(&self, switch: &SwitchConnection) -> Result<(), RBFRTError> {
+    async fn on_stop(&self, switch: &dyn SwitchBackend) -> Result<(), RBFRTError> {
         // disable iat generation
         switch
             .clear_tables(vec![MONITOR_IAT_TABLE, IS_INGRESS_TABLE])
@@ -912,15 +1356,30 @@ impl TrafficGenEvent for RateMonitor {
     }
 
     /// Reset the state.
-    async fn on_reset(&mut self, switch: &SwitchConnection) -> Result<(), RBFRTError> {
+    async fn on_reset(&mut self, switch: &dyn SwitchBackend) -> Result<(), RBFRTError> {
         self.rtt_storage.clear();
         self.tx_iat_storage.clear();
         self.rx_iat_storage.clear();
+        self.rtt_sketches.clear();
+        self.tx_iat_sketches.clear();
+        self.rx_iat_sketches.clear();
         self.time_statistics.tx_rate_l1.clear();
         self.time_statistics.rx_rate_l1.clear();
         self.time_statistics.packet_loss.clear();
         self.time_statistics.out_of_order.clear();
         self.time_statistics.rtt.clear();
+        self.app_rtt_storage.clear();
+        self.time_statistics.app_tx_l2.clear();
+        self.time_statistics.app_rx_l2.clear();
+        self.time_statistics.app_packet_loss.clear();
+        self.time_statistics.app_out_of_order.clear();
+        self.time_statistics.app_rtt.clear();
+        self.time_statistics.disruption_time.clear();
+        self.time_statistics.app_disruption_time.clear();
+        self.disruption.reset();
+        self.loss_analysis.reset();
+        self.window_counters.reset();
+        self.unmatched_counters.clear();
 
         let monitoring_registers = vec![
             "ingress.p4tg.rx_seq",
@@ -0,0 +1,1313 @@
+/* Copyright 2022-present University of Tuebingen, Chair of Communication Networks
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/*
+ * Steffen Lindner (steffen.lindner@uni-tuebingen.de)
+ */
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use utoipa::ToSchema;
+
+use crate::api::helper::simple_stats;
+use crate::api::traffic_gen::{active_test_configuration, start_single_test};
+use crate::core::statistics_stream::StatisticsEvent;
+use crate::core::traffic_gen_core::helper::{calculate_overhead, normalize_stream};
+use crate::core::traffic_gen_core::types::{GenerationMode, Stream, StreamSetting, TrafficGenData};
+use crate::AppState;
+
+/// Tests of RFC 2544 that are supported by P4TG.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, ToSchema)]
+pub enum Rfc2544Test {
+    /// Highest rate without loss (RFC 2544, Section 26.1)
+    Throughput,
+    /// Latency at the throughput rate (RFC 2544, Section 26.2)
+    Latency,
+    /// Frame loss at decreasing rates (RFC 2544, Section 26.3)
+    FrameLoss,
+    /// Longest burst at the configured rates without loss (RFC 2544, Section 26.4)
+    BackToBack,
+}
+
+/// Configuration of an RFC 2544 benchmark.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct Rfc2544Config {
+    /// Traffic generation that is benchmarked.
+    /// The frame size of all streams is set to each of the `frame_sizes`,
+    /// and the traffic rates of all streams are scaled during the search.
+    /// The configured traffic rates correspond to 100%, i.e., the rate of the back-to-back bursts.
+    pub traffic_gen: TrafficGenData,
+    /// Tests that are run for each frame size.
+    /// The latency test is run at the throughput rate and therefore requires the throughput test.
+    #[serde(default = "Rfc2544Config::default_tests")]
+    pub tests: Vec<Rfc2544Test>,
+    /// Frame sizes (bytes) that are benchmarked.
+    #[serde(default = "Rfc2544Config::default_frame_sizes")]
+    pub frame_sizes: Vec<u32>,
+    /// Accepted frame loss in percent of the sent frames.
+    #[serde(default)]
+    pub loss_tolerance: f64,
+    /// The search stops if the highest rate without loss is known up to this resolution.
+    /// In percent of the configured traffic rates, or of the `back_to_back_duration` for the back-to-back test.
+    #[serde(default = "Rfc2544Config::default_resolution")]
+    pub resolution: f64,
+    /// Duration of a trial in seconds.
+    #[serde(default = "Rfc2544Config::default_trial_duration")]
+    pub trial_duration: u32,
+    /// Time in seconds after a trial before the loss counters are read.
+    #[serde(default = "Rfc2544Config::default_wait_time")]
+    pub wait_time: u32,
+    /// Number of latency trials per frame size. The results are averaged.
+    #[serde(default = "Rfc2544Config::default_latency_trials")]
+    pub latency_trials: u32,
+    /// Rate decrement of the frame loss test in percent of the configured traffic rates.
+    #[serde(default = "Rfc2544Config::default_frame_loss_step")]
+    pub frame_loss_step: f64,
+    /// Longest burst duration in ms of the back-to-back test.
+    #[serde(default = "Rfc2544Config::default_back_to_back_duration")]
+    pub back_to_back_duration: u32,
+    /// Number of back-to-back searches per frame size. The results are averaged.
+    #[serde(default = "Rfc2544Config::default_back_to_back_trials")]
+    pub back_to_back_trials: u32,
+}
+
+impl Rfc2544Config {
+    fn default_tests() -> Vec<Rfc2544Test> {
+        vec![Rfc2544Test::Throughput]
+    }
+
+    fn default_frame_sizes() -> Vec<u32> {
+        vec![64, 128, 256, 512, 1024, 1280, 1518]
+    }
+
+    fn default_resolution() -> f64 {
+        0.5
+    }
+
+    fn default_trial_duration() -> u32 {
+        60
+    }
+
+    fn default_wait_time() -> u32 {
+        2
+    }
+
+    fn default_latency_trials() -> u32 {
+        20
+    }
+
+    fn default_frame_loss_step() -> f64 {
+        10f64
+    }
+
+    fn default_back_to_back_duration() -> u32 {
+        2000
+    }
+
+    fn default_back_to_back_trials() -> u32 {
+        50
+    }
+
+    /// Validates the search parameters of the benchmark.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.tests.is_empty() {
+            return Err("No tests provided.".to_owned());
+        }
+
+        if self.frame_sizes.is_empty() {
+            return Err("No frame sizes provided.".to_owned());
+        }
+
+        if self.traffic_gen.mode != GenerationMode::Cbr
+            && self.traffic_gen.mode != GenerationMode::Mpps
+        {
+            return Err("RFC 2544 benchmarks require CBR or Mpps generation mode.".to_owned());
+        }
+
+        if !(0f64..100f64).contains(&self.loss_tolerance) {
+            return Err("Loss tolerance must be in [0, 100).".to_owned());
+        }
+
+        if !(self.resolution > 0f64 && self.resolution <= 100f64) {
+            return Err("Resolution must be in (0, 100].".to_owned());
+        }
+
+        if self.trial_duration == 0 {
+            return Err("Trial duration must be at least 1 second.".to_owned());
+        }
+
+        if self.latency_trials == 0 || self.back_to_back_trials == 0 {
+            return Err("At least one latency and back-to-back trial is required.".to_owned());
+        }
+
+        if !(self.frame_loss_step > 0f64 && self.frame_loss_step <= 100f64) {
+            return Err("Frame loss step must be in (0, 100].".to_owned());
+        }
+
+        if self.back_to_back_duration == 0 {
+            return Err("Back-to-back duration must be at least 1 ms.".to_owned());
+        }
+
+        Ok(())
+    }
+
+    /// Returns the traffic generation of a trial, i.e., the frame size of all streams is set to `frame_size`,
+    /// and the traffic rates are scaled to `rate` percent.
+    pub fn trial(&self, frame_size: u32, rate: f64) -> TrafficGenData {
+        let mut traffic_gen = self.traffic_gen.clone();
+
+        for s in traffic_gen.streams.iter_mut() {
+            s.frame_size = frame_size;
+            s.traffic_rate = (s.traffic_rate as f64 * rate / 100f64) as f32;
+        }
+
+        traffic_gen.duration = Some(self.trial_duration);
+        // trials are evaluated by the benchmark itself
+        traffic_gen.sla = None;
+        traffic_gen.warmup = None;
+        traffic_gen.cooldown = None;
+
+        traffic_gen
+    }
+
+    fn runs(&self, test: Rfc2544Test) -> bool {
+        self.tests.contains(&test)
+    }
+}
+
+/// Binary search of the highest value (percent of a maximum) that passes a trial,
+/// e.g., the highest rate without loss.
+#[derive(Debug, Clone)]
+pub(crate) struct BinarySearch {
+    /// Highest value that passed
+    low: f64,
+    /// Lowest value that failed
+    high: f64,
+    resolution: f64,
+    next: Option<f64>,
+}
+
+impl BinarySearch {
+    pub(crate) fn new(resolution: f64) -> BinarySearch {
+        BinarySearch {
+            low: 0f64,
+            high: 100f64,
+            resolution,
+            next: Some(100f64),
+        }
+    }
+
+    /// Value of the next trial. `None` if the search is done.
+    pub(crate) fn next_value(&self) -> Option<f64> {
+        self.next
+    }
+
+    /// Records the result of the trial at `value`.
+    pub(crate) fn record(&mut self, value: f64, passed: bool) {
+        if passed {
+            self.low = value;
+        } else {
+            self.high = value;
+        }
+
+        self.next = if self.low >= 100f64 || self.high - self.low <= self.resolution {
+            None
+        } else {
+            Some((self.low + self.high) / 2f64)
+        };
+    }
+
+    /// Highest value that passed
+    pub(crate) fn result(&self) -> f64 {
+        self.low
+    }
+}
+
+/// Rates of the frame loss test. Starts at 100% and decreases the rate by `step`
+/// until two successive trials are without loss (RFC 2544, Section 26.3).
+#[derive(Debug, Clone)]
+pub(crate) struct FrameLossSteps {
+    step: f64,
+    next: Option<f64>,
+    trials_without_loss: u32,
+}
+
+impl FrameLossSteps {
+    pub(crate) fn new(step: f64) -> FrameLossSteps {
+        FrameLossSteps {
+            step,
+            next: Some(100f64),
+            trials_without_loss: 0,
+        }
+    }
+
+    /// Rate of the next trial. `None` if the test is done.
+    pub(crate) fn next_rate(&self) -> Option<f64> {
+        self.next
+    }
+
+    /// Records the lost frames of the trial at `rate`.
+    pub(crate) fn record(&mut self, rate: f64, lost_frames: u64) {
+        if lost_frames == 0 {
+            self.trials_without_loss += 1;
+        } else {
+            self.trials_without_loss = 0;
+        }
+
+        let next = rate - self.step;
+
+        self.next = if self.trials_without_loss >= 2 || next <= 0f64 {
+            None
+        } else {
+            Some(next)
+        };
+    }
+}
+
+/// Packet counters of a trial.
+#[derive(Serialize, Debug, Clone, Copy, Default, ToSchema)]
+pub struct TrialCounters {
+    /// Number of frames sent on the TX ports
+    pub tx_frames: u64,
+    /// Number of frames received on the RX ports
+    pub rx_frames: u64,
+    /// Number of lost frames, i.e., the sent frames that were not received
+    pub lost_frames: u64,
+}
+
+impl TrialCounters {
+    /// Frame loss in percent of the sent frames. 100% if no frames were sent.
+    pub fn loss(&self) -> f64 {
+        if self.tx_frames == 0 {
+            100f64
+        } else {
+            100f64 * self.lost_frames as f64 / self.tx_frames as f64
+        }
+    }
+}
+
+/// Latency of a trial, calculated from the RTT samples and RTT histograms of the RX ports.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, ToSchema)]
+pub struct Latency {
+    /// Mean RTT in ns
+    pub mean: f64,
+    /// Minimal RTT in ns
+    pub min: u64,
+    /// Maximal RTT in ns
+    pub max: u64,
+    /// Standard deviation of the RTT in ns
+    pub jitter: f64,
+    /// Number of RTT samples
+    pub n: usize,
+    /// Percentiles (ns) of the RTT histograms, averaged over the RX ports.
+    /// The key is the percentile multiplied by 100.
+    pub percentiles: BTreeMap<u32, f64>,
+}
+
+/// Measurements of a trial.
+#[derive(Default)]
+pub(crate) struct TrialMeasurement {
+    pub counters: TrialCounters,
+    pub latency: Latency,
+}
+
+/// A single trial of an RFC 2544 benchmark.
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct Rfc2544Trial {
+    pub test: Rfc2544Test,
+    pub frame_size: u32,
+    /// Rate in percent of the configured traffic rates
+    pub rate: f64,
+    /// Burst duration in ms of a back-to-back trial
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub burst_duration: Option<f64>,
+    #[serde(flatten)]
+    pub counters: TrialCounters,
+    /// Frame loss in percent
+    pub loss: f64,
+    /// Indicates if the loss is within the loss tolerance
+    pub passed: bool,
+}
+
+/// Entry of the RFC 2544 throughput table.
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct ThroughputResult {
+    pub frame_size: u32,
+    /// Highest rate without loss in percent of the configured traffic rates
+    pub rate: f64,
+    /// Highest L1 rate (Gbps) without loss, summed over all streams and TX ports
+    pub rate_l1: f64,
+    /// Highest packet rate (Mpps) without loss, summed over all streams and TX ports
+    pub packet_rate: f64,
+    /// Number of trials of the search
+    pub trials: usize,
+}
+
+/// Entry of the RFC 2544 latency table.
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct LatencyResult {
+    pub frame_size: u32,
+    /// Throughput rate in percent of the configured traffic rates
+    pub rate: f64,
+    /// Latency averaged over all trials. `min` and `max` are the extremes of all trials.
+    #[serde(flatten)]
+    pub latency: Latency,
+    /// Number of trials
+    pub trials: usize,
+}
+
+/// Entry of the RFC 2544 frame loss rate table.
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct FrameLossResult {
+    pub frame_size: u32,
+    /// Rate in percent of the configured traffic rates
+    pub rate: f64,
+    /// Offered L1 rate (Gbps), summed over all streams and TX ports
+    pub rate_l1: f64,
+    #[serde(flatten)]
+    pub counters: TrialCounters,
+    /// Frame loss in percent
+    pub loss: f64,
+}
+
+/// Entry of the RFC 2544 back-to-back table.
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct BackToBackResult {
+    pub frame_size: u32,
+    /// Longest burst (frames) without loss, averaged over all searches
+    pub burst_frames: f64,
+    /// Duration (ms) of the longest burst without loss, averaged over all searches
+    pub burst_duration: f64,
+    /// Shortest longest burst (frames) without loss of all searches
+    pub min_burst_frames: u64,
+    /// Longest burst (frames) without loss of all searches
+    pub max_burst_frames: u64,
+    /// Number of searches
+    pub trials: usize,
+}
+
+/// State and results of the RFC 2544 benchmark.
+#[derive(Serialize, Debug, Clone, Default, ToSchema)]
+pub struct Rfc2544Result {
+    /// Indicates if the benchmark is running
+    pub running: bool,
+    /// Indicates if the benchmark was aborted
+    pub aborted: bool,
+    /// Error that aborted the benchmark
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Throughput table. One entry per completed frame size.
+    pub throughput: Vec<ThroughputResult>,
+    /// Latency table. One entry per completed frame size.
+    pub latency: Vec<LatencyResult>,
+    /// Frame loss rate table. One entry per frame size and rate.
+    pub frame_loss: Vec<FrameLossResult>,
+    /// Back-to-back table. One entry per completed frame size.
+    pub back_to_back: Vec<BackToBackResult>,
+    /// All trials in the order they were run
+    pub trials: Vec<Rfc2544Trial>,
+}
+
+/// Stores the RFC 2544 benchmark task and its results
+pub struct Rfc2544 {
+    pub(crate) task: Mutex<Rfc2544Task>,
+    pub(crate) result: Mutex<Rfc2544Result>,
+}
+
+#[derive(Default)]
+pub struct Rfc2544Task {
+    pub handle: Option<JoinHandle<()>>,
+    pub cancel_token: Option<CancellationToken>,
+}
+
+/// Runs the tests of a benchmark and stores the results in the app state.
+/// All methods return `Ok(None)` if the benchmark was cancelled.
+struct Runner<'a> {
+    state: &'a Arc<AppState>,
+    config: &'a Rfc2544Config,
+    cancel_token: &'a CancellationToken,
+}
+
+impl Rfc2544Task {
+    /// Starts a task that runs the RFC 2544 benchmark `config`.
+    /// The results are stored in the app state.
+    ///
+    /// - `state`: App state that holds the results
+    /// - `config`: Validated benchmark configuration
+    pub async fn start(&mut self, state: &Arc<AppState>, config: Rfc2544Config) {
+        let cancel_token = CancellationToken::new();
+        let cancel_token_clone = cancel_token.clone();
+        let state_clone = state.clone();
+
+        *state.rfc2544.result.lock().await = Rfc2544Result {
+            running: true,
+            ..Default::default()
+        };
+
+        let handle = tokio::spawn(async move {
+            let runner = Runner {
+                state: &state_clone,
+                config: &config,
+                cancel_token: &cancel_token_clone,
+            };
+            let result = runner.run().await;
+
+            if cancel_token_clone.is_cancelled() {
+                stop_trial(&state_clone).await;
+            }
+
+            let mut state_result = state_clone.rfc2544.result.lock().await;
+            state_result.running = false;
+            state_result.aborted = cancel_token_clone.is_cancelled();
+
+            if let Err(message) = result {
+                error!("RFC 2544 benchmark failed: {message}");
+                state_result.error = Some(message);
+            }
+        });
+
+        self.handle = Some(handle);
+        self.cancel_token = Some(cancel_token);
+    }
+
+    /// Cancels the benchmark task if it is running and waits until it is stopped.
+    pub async fn cancel_existing_task(&mut self) {
+        if let Some(token) = self.cancel_token.take() {
+            token.cancel();
+            info!("RFC 2544 task cancelled.")
+        }
+
+        if let Some(handle) = self.handle.take() {
+            if let Err(e) = handle.await {
+                error!("RFC 2544 task join error: {e}");
+            }
+        }
+    }
+}
+
+impl Runner<'_> {
+    async fn run(&self) -> Result<Option<()>, String> {
+        for &frame_size in &self.config.frame_sizes {
+            if self.config.runs(Rfc2544Test::Throughput) || self.config.runs(Rfc2544Test::Latency) {
+                let Some(throughput) = self.throughput(frame_size).await? else {
+                    return Ok(None);
+                };
+
+                if self.config.runs(Rfc2544Test::Latency)
+                    && self.latency(frame_size, throughput).await?.is_none()
+                {
+                    return Ok(None);
+                }
+            }
+
+            if self.config.runs(Rfc2544Test::FrameLoss)
+                && self.frame_loss(frame_size).await?.is_none()
+            {
+                return Ok(None);
+            }
+
+            if self.config.runs(Rfc2544Test::BackToBack)
+                && self.back_to_back(frame_size).await?.is_none()
+            {
+                return Ok(None);
+            }
+        }
+
+        Ok(Some(()))
+    }
+
+    /// Runs a trial and stores it in the results.
+    ///
+    /// - `rate`: Rate in percent of the configured traffic rates
+    /// - `burst_duration`: Duration of the trial in ms. The trial duration of the configuration is used if not set.
+    async fn trial(
+        &self,
+        test: Rfc2544Test,
+        frame_size: u32,
+        rate: f64,
+        burst_duration: Option<f64>,
+    ) -> Result<Option<TrialMeasurement>, String> {
+        let mut payload = self.config.trial(frame_size, rate);
+
+        let length = burst_duration.map(|ms| {
+            // the duration monitor of the trial is started with the burst duration instead
+            payload.duration = None;
+            Duration::from_secs_f64(ms / 1000f64)
+        });
+
+        info!("RFC 2544 {test:?} trial with {frame_size} byte frames at {rate}%.");
+
+        let Some(data) = run_trial(
+            self.state,
+            payload,
+            length,
+            self.config.wait_time,
+            self.cancel_token,
+        )
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        let measurement = data.measure();
+
+        let loss = measurement.counters.loss();
+
+        self.state
+            .rfc2544
+            .result
+            .lock()
+            .await
+            .trials
+            .push(Rfc2544Trial {
+                test,
+                frame_size,
+                rate,
+                burst_duration,
+                counters: measurement.counters,
+                loss,
+                passed: loss <= self.config.loss_tolerance,
+            });
+
+        Ok(Some(measurement))
+    }
+
+    /// Searches the highest rate without loss. Returns the rate in percent of the configured traffic rates.
+    async fn throughput(&self, frame_size: u32) -> Result<Option<f64>, String> {
+        let mut search = BinarySearch::new(self.config.resolution);
+        let mut trials = 0;
+
+        while let Some(rate) = search.next_value() {
+            let Some(measurement) = self
+                .trial(Rfc2544Test::Throughput, frame_size, rate, None)
+                .await?
+            else {
+                return Ok(None);
+            };
+
+            search.record(
+                rate,
+                measurement.counters.loss() <= self.config.loss_tolerance,
+            );
+            trials += 1;
+        }
+
+        let rate = search.result();
+        let (rate_l1, packet_rate) = offered_load(&self.config.trial(frame_size, rate));
+
+        info!("RFC 2544 throughput with {frame_size} byte frames: {rate}% ({rate_l1} Gbps).");
+
+        self.state
+            .rfc2544
+            .result
+            .lock()
+            .await
+            .throughput
+            .push(ThroughputResult {
+                frame_size,
+                rate,
+                rate_l1,
+                packet_rate,
+                trials,
+            });
+
+        Ok(Some(rate))
+    }
+
+    /// Measures the latency at the throughput `rate`.
+    async fn latency(&self, frame_size: u32, rate: f64) -> Result<Option<()>, String> {
+        if rate <= 0f64 {
+            info!("RFC 2544 latency with {frame_size} byte frames skipped. Throughput is 0.");
+            return Ok(Some(()));
+        }
+
+        let mut latencies = vec![];
+
+        for _ in 0..self.config.latency_trials {
+            let Some(measurement) = self
+                .trial(Rfc2544Test::Latency, frame_size, rate, None)
+                .await?
+            else {
+                return Ok(None);
+            };
+
+            latencies.push(measurement.latency);
+        }
+
+        self.state
+            .rfc2544
+            .result
+            .lock()
+            .await
+            .latency
+            .push(LatencyResult {
+                frame_size,
+                rate,
+                latency: average_latency(&latencies),
+                trials: latencies.len(),
+            });
+
+        Ok(Some(()))
+    }
+
+    /// Measures the frame loss at decreasing rates.
+    async fn frame_loss(&self, frame_size: u32) -> Result<Option<()>, String> {
+        let mut steps = FrameLossSteps::new(self.config.frame_loss_step);
+
+        while let Some(rate) = steps.next_rate() {
+            let Some(measurement) = self
+                .trial(Rfc2544Test::FrameLoss, frame_size, rate, None)
+                .await?
+            else {
+                return Ok(None);
+            };
+
+            steps.record(rate, measurement.counters.lost_frames);
+
+            self.state
+                .rfc2544
+                .result
+                .lock()
+                .await
+                .frame_loss
+                .push(FrameLossResult {
+                    frame_size,
+                    rate,
+                    rate_l1: offered_load(&self.config.trial(frame_size, rate)).0,
+                    counters: measurement.counters,
+                    loss: measurement.counters.loss(),
+                });
+        }
+
+        Ok(Some(()))
+    }
+
+    /// Searches the longest burst at the configured traffic rates without loss.
+    async fn back_to_back(&self, frame_size: u32) -> Result<Option<()>, String> {
+        let max_duration = self.config.back_to_back_duration as f64;
+        // Frames and duration (ms) of the longest burst without loss of each search
+        let mut bursts: Vec<(u64, f64)> = vec![];
+
+        for _ in 0..self.config.back_to_back_trials {
+            let mut search = BinarySearch::new(self.config.resolution);
+            let mut longest = (0u64, 0f64);
+
+            while let Some(value) = search.next_value() {
+                let duration = max_duration * value / 100f64;
+
+                let Some(measurement) = self
+                    .trial(Rfc2544Test::BackToBack, frame_size, 100f64, Some(duration))
+                    .await?
+                else {
+                    return Ok(None);
+                };
+
+                let passed = measurement.counters.loss() <= self.config.loss_tolerance;
+
+                if passed {
+                    longest = (measurement.counters.tx_frames, duration);
+                }
+
+                search.record(value, passed);
+            }
+
+            bursts.push(longest);
+        }
+
+        let n = bursts.len() as f64;
+
+        self.state
+            .rfc2544
+            .result
+            .lock()
+            .await
+            .back_to_back
+            .push(BackToBackResult {
+                frame_size,
+                burst_frames: bursts.iter().map(|b| b.0 as f64).sum::<f64>() / n,
+                burst_duration: bursts.iter().map(|b| b.1).sum::<f64>() / n,
+                min_burst_frames: bursts.iter().map(|b| b.0).min().unwrap_or(0),
+                max_burst_frames: bursts.iter().map(|b| b.0).max().unwrap_or(0),
+                trials: bursts.len(),
+            });
+
+        Ok(Some(()))
+    }
+}
+
+/// Averages the latencies of multiple trials. `min` and `max` are the extremes of all trials.
+/// Trials without RTT samples are ignored.
+fn average_latency(latencies: &[Latency]) -> Latency {
+    let measured: Vec<&Latency> = latencies.iter().filter(|l| l.n > 0).collect();
+
+    if measured.is_empty() {
+        return Latency::default();
+    }
+
+    let n = measured.len() as f64;
+    let mut percentiles: BTreeMap<u32, Vec<f64>> = BTreeMap::new();
+
+    for l in &measured {
+        for (p, value) in &l.percentiles {
+            percentiles.entry(*p).or_default().push(*value);
+        }
+    }
+
+    Latency {
+        mean: measured.iter().map(|l| l.mean).sum::<f64>() / n,
+        min: measured.iter().map(|l| l.min).min().unwrap_or(0),
+        max: measured.iter().map(|l| l.max).max().unwrap_or(0),
+        jitter: measured.iter().map(|l| l.jitter).sum::<f64>() / n,
+        n: measured.iter().map(|l| l.n).sum(),
+        percentiles: average_percentiles(percentiles),
+    }
+}
+
+fn average_percentiles(percentiles: BTreeMap<u32, Vec<f64>>) -> BTreeMap<u32, f64> {
+    percentiles
+        .into_iter()
+        .map(|(p, values)| (p, values.iter().sum::<f64>() / values.len() as f64))
+        .collect()
+}
+
+/// Offered L1 rate (Gbps) and packet rate (Mpps) of `payload`, summed over all active streams and TX ports.
+pub(crate) fn offered_load(payload: &TrafficGenData) -> (f64, f64) {
+    let mut rate_l1 = 0f64;
+    let mut packet_rate = 0f64;
+
+    for setting in payload.stream_settings.iter().filter(|s| s.active) {
+        if let Some(s) = payload
+            .streams
+            .iter()
+            .find(|s| s.stream_id == setting.stream_id)
+        {
+            let s = normalize_stream(s.clone(), payload.mode);
+            let l1_frame_size = s.frame_size + calculate_overhead(&s) + 20;
+            let rate = s.traffic_rate as f64;
+
+            rate_l1 += rate;
+            packet_rate += rate * 1000f64 / (l1_frame_size * 8) as f64;
+        }
+    }
+
+    (rate_l1, packet_rate)
+}
+
+/// Per-port and per-stream measurements of a trial.
+pub(crate) struct TrialData {
+    /// Active streams of the trial
+    pub(crate) streams: Vec<Stream>,
+    /// Generation mode of the trial
+    pub(crate) mode: GenerationMode,
+    /// Active stream settings of the trial. The ports are dev ports.
+    pub(crate) stream_settings: Vec<StreamSetting>,
+    /// Mapping of TX dev ports to RX dev ports
+    pub(crate) tx_rx_port_mapping: HashMap<String, u32>,
+    /// Number of sent frames per TX dev port
+    pub(crate) tx_frames: HashMap<u32, u64>,
+    /// Number of received frames per RX dev port
+    pub(crate) rx_frames: HashMap<u32, u64>,
+    /// Number of lost frames per RX dev port and app id
+    pub(crate) app_lost_frames: HashMap<u32, HashMap<u32, u64>>,
+    /// RTT samples per RX dev port
+    pub(crate) rtts: HashMap<u32, VecDeque<u64>>,
+    /// RTT samples per RX dev port and app id
+    pub(crate) app_rtts: HashMap<u32, HashMap<u32, VecDeque<u64>>>,
+    /// RTT histogram percentiles per RX dev port
+    pub(crate) percentiles: HashMap<u32, HashMap<u32, f64>>,
+    /// Mean L2 receive rate (Gbps) per app id, summed over all RX ports
+    pub(crate) app_rx_rates: HashMap<u32, f64>,
+}
+
+impl TrialData {
+    /// Measurements of all streams. The lost frames are the sent frames of the TX ports
+    /// that were not received on the RX ports.
+    pub(crate) fn measure(&self) -> TrialMeasurement {
+        let tx_ports = self.tx_ports(None);
+        let rx_ports = self.rx_ports(&tx_ports);
+
+        let tx_frames = tx_ports.iter().filter_map(|p| self.tx_frames.get(p)).sum();
+        let rx_frames = rx_ports.iter().filter_map(|p| self.rx_frames.get(p)).sum();
+
+        let counters = TrialCounters {
+            tx_frames,
+            rx_frames,
+            lost_frames: tx_frames.saturating_sub(rx_frames),
+        };
+
+        let rtts: VecDeque<u64> = rx_ports
+            .iter()
+            .filter_map(|p| self.rtts.get(p))
+            .flatten()
+            .copied()
+            .collect();
+
+        TrialMeasurement {
+            counters,
+            latency: self.latency(&rtts, &rx_ports),
+        }
+    }
+
+    /// Measurements of the stream `stream_id` from the lost frames and RTT samples of the stream on its RX ports.
+    /// The sent frames of a TX port are split among the streams of the port according to their packet rates.
+    /// RTT percentiles are only available per port and are taken from the RX ports of the stream.
+    pub(crate) fn measure_stream(&self, stream_id: u8) -> TrialMeasurement {
+        let Some(stream) = self.streams.iter().find(|s| s.stream_id == stream_id) else {
+            return TrialMeasurement::default();
+        };
+        let app_id = stream.app_id as u32;
+
+        let tx_ports = self.tx_ports(Some(stream_id));
+        let rx_ports = self.rx_ports(&tx_ports);
+
+        let tx_frames = tx_ports
+            .iter()
+            .map(|p| {
+                self.tx_frames.get(p).copied().unwrap_or_default() as f64
+                    * self.packet_rate_share(*p, stream_id)
+            })
+            .sum::<f64>()
+            .round() as u64;
+        let lost_frames: u64 = rx_ports
+            .iter()
+            .filter_map(|p| self.app_lost_frames.get(p)?.get(&app_id))
+            .sum();
+
+        let counters = TrialCounters {
+            tx_frames,
+            rx_frames: tx_frames.saturating_sub(lost_frames),
+            lost_frames,
+        };
+
+        let rtts: VecDeque<u64> = rx_ports
+            .iter()
+            .filter_map(|p| self.app_rtts.get(p)?.get(&app_id))
+            .flatten()
+            .copied()
+            .collect();
+
+        TrialMeasurement {
+            counters,
+            latency: self.latency(&rtts, &rx_ports),
+        }
+    }
+
+    /// TX ports of the stream `stream_id`, or of all streams if not set.
+    fn tx_ports(&self, stream_id: Option<u8>) -> Vec<u32> {
+        let mut tx_ports: Vec<u32> = self
+            .stream_settings
+            .iter()
+            .filter(|s| stream_id.is_none_or(|id| id == s.stream_id))
+            .map(|s| s.port)
+            .collect();
+        tx_ports.sort();
+        tx_ports.dedup();
+
+        tx_ports
+    }
+
+    fn rx_ports(&self, tx_ports: &[u32]) -> Vec<u32> {
+        let mut rx_ports: Vec<u32> = tx_ports
+            .iter()
+            .filter_map(|p| self.tx_rx_port_mapping.get(&p.to_string()).copied())
+            .collect();
+        rx_ports.sort();
+        rx_ports.dedup();
+
+        rx_ports
+    }
+
+    /// Share of the stream `stream_id` in the packet rate of all streams on TX port `port`.
+    fn packet_rate_share(&self, port: u32, stream_id: u8) -> f64 {
+        let packet_rate = |id: u8| {
+            self.streams
+                .iter()
+                .find(|s| s.stream_id == id)
+                .map(|s| {
+                    let s = normalize_stream(s.clone(), self.mode);
+                    s.traffic_rate as f64 / (s.frame_size + calculate_overhead(&s) + 20) as f64
+                })
+                .unwrap_or_default()
+        };
+
+        let total: f64 = self
+            .stream_settings
+            .iter()
+            .filter(|s| s.port == port)
+            .map(|s| packet_rate(s.stream_id))
+            .sum();
+
+        if total > 0f64 {
+            packet_rate(stream_id) / total
+        } else {
+            0f64
+        }
+    }
+
+    fn latency(&self, rtts: &VecDeque<u64>, rx_ports: &[u32]) -> Latency {
+        let mut percentiles: BTreeMap<u32, Vec<f64>> = BTreeMap::new();
+
+        for port in rx_ports {
+            for (p, value) in self.percentiles.get(port).into_iter().flatten() {
+                percentiles.entry(*p).or_default().push(*value);
+            }
+        }
+
+        Latency {
+            mean: simple_stats::average(rtts),
+            min: rtts.iter().min().copied().unwrap_or(0),
+            max: rtts.iter().max().copied().unwrap_or(0),
+            jitter: simple_stats::std(rtts),
+            n: rtts.len(),
+            percentiles: average_percentiles(percentiles),
+        }
+    }
+}
+
+/// Runs a single trial with the traffic generation `payload` until its duration elapsed.
+/// The counters are read `wait_time` seconds after the traffic generation stopped.
+/// The receive rates of the streams are sampled while the traffic generation is running,
+/// starting 2 seconds after the start of the trial when the first rates are available.
+///
+/// - `length`: Duration of the trial if the duration of `payload` is not set, e.g., for bursts shorter than a second
+///
+/// Returns `None` if the trial was cancelled.
+pub(crate) async fn run_trial(
+    state: &Arc<AppState>,
+    payload: TrafficGenData,
+    length: Option<Duration>,
+    wait_time: u32,
+    cancel_token: &CancellationToken,
+) -> Result<Option<TrialData>, String> {
+    let (streams, stream_settings, tx_rx_port_mapping) = active_test_configuration(state, &payload);
+    let mode = payload.mode;
+
+    start_single_test(state, payload)
+        .await
+        .map_err(|e| format!("Trial could not be started: {e}"))?;
+
+    if let Some(length) = length {
+        state
+            .monitor_task
+            .lock()
+            .await
+            .start_with_duration(state, length)
+            .await;
+    }
+
+    let start = Instant::now();
+    let mut interval = tokio::time::interval(Duration::from_millis(100));
+    // Sum of the sampled L2 receive rates (bits/s) per app id
+    let mut app_rx_rates: HashMap<u32, f64> = HashMap::new();
+    let mut rate_samples = 0;
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                if !state.experiment.lock().await.running {
+                    break;
+                }
+
+                if start.elapsed() >= Duration::from_secs(2) {
+                    let rate_monitor = state.rate_monitor.lock().await;
+
+                    for rates in rate_monitor.statistics.app_rx_l2.values() {
+                        for (app_id, rate) in rates {
+                            *app_rx_rates.entry(*app_id).or_default() += rate;
+                        }
+                    }
+
+                    rate_samples += 1;
+                }
+            }
+            _ = cancel_token.cancelled() => {
+                return Ok(None);
+            }
+        }
+    }
+
+    // wait for late frames and the next counter update
+    tokio::select! {
+        _ = tokio::time::sleep(Duration::from_secs(wait_time as u64)) => {}
+        _ = cancel_token.cancelled() => {
+            return Ok(None);
+        }
+    }
+
+    let mut data = TrialData {
+        streams,
+        mode,
+        stream_settings,
+        tx_rx_port_mapping,
+        tx_frames: HashMap::new(),
+        rx_frames: HashMap::new(),
+        app_lost_frames: HashMap::new(),
+        rtts: HashMap::new(),
+        app_rtts: HashMap::new(),
+        percentiles: HashMap::new(),
+        app_rx_rates: app_rx_rates
+            .into_iter()
+            .map(|(app_id, rate)| (app_id, rate / (rate_samples as f64 * 1e9)))
+            .collect(),
+    };
+
+    {
+        let frame_size_monitor = state.frame_size_monitor.lock().await;
+
+        for (port, count) in &frame_size_monitor.statistics.frame_size {
+            data.tx_frames
+                .insert(*port, count.tx.iter().map(|r| r.packets as u64).sum());
+            data.rx_frames
+                .insert(*port, count.rx.iter().map(|r| r.packets as u64).sum());
+        }
+    }
+
+    {
+        let rate_monitor = state.rate_monitor.lock().await;
+
+        data.app_lost_frames = rate_monitor.statistics.app_packet_loss.clone();
+        data.rtts = rate_monitor.rtt_storage.clone();
+        data.app_rtts = rate_monitor.app_rtt_storage.clone();
+    }
+
+    {
+        let histogram_monitor = state.rtt_histogram_monitor.lock().await;
+
+        for (port, histogram) in &histogram_monitor.histogram {
+            data.percentiles
+                .insert(*port, histogram.data.rx.percentiles.clone());
+        }
+    }
+
+    Ok(Some(data))
+}
+
+/// Stops the traffic generation of a cancelled trial.
+pub(crate) async fn stop_trial(state: &Arc<AppState>) {
+    state
+        .monitor_task
+        .lock()
+        .await
+        .cancel_existing_monitoring_task()
+        .await;
+
+    if state.experiment.lock().await.running {
+        match state
+            .traffic_generator
+            .lock()
+            .await
+            .stop_traffic_generation(state)
+            .await
+        {
+            Ok(_) => {
+                state.experiment.lock().await.running = false;
+                state.events.emit(StatisticsEvent::Stopped { name: None });
+            }
+            Err(e) => error!("Error while stopping traffic generation: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::docs::traffic_gen::EXAMPLE_POST_2_REQUEST;
+    use crate::core::backend::mock::tests::{call, mock_state, wait_for_traffic_gen};
+    use axum::http::{Method, StatusCode};
+
+    /// Runs the search against a device that forwards up to `capacity` percent without loss.
+    fn search(capacity: f64, resolution: f64) -> (f64, usize) {
+        let mut search = BinarySearch::new(resolution);
+        let mut trials = 0;
+
+        while let Some(rate) = search.next_value() {
+            search.record(rate, rate <= capacity);
+            trials += 1;
+        }
+
+        (search.result(), trials)
+    }
+
+    #[test]
+    fn throughput_search_finds_capacity() {
+        assert_eq!(search(100f64, 0.5), (100f64, 1));
+        assert_eq!(search(0f64, 0.5).0, 0f64);
+
+        for capacity in [0.3, 12.5, 42f64, 73.3, 99.9] {
+            let (throughput, trials) = search(capacity, 0.5);
+
+            assert!(throughput <= capacity);
+            assert!(capacity - throughput <= 0.5, "{capacity}: {throughput}");
+            assert!(trials <= 9);
+        }
+    }
+
+    #[test]
+    fn loss_without_tx_frames_fails() {
+        assert_eq!(TrialCounters::default().loss(), 100f64);
+        assert_eq!(
+            TrialCounters {
+                tx_frames: 1000,
+                rx_frames: 999,
+                lost_frames: 1
+            }
+            .loss(),
+            0.1
+        );
+    }
+
+    #[test]
+    fn trial_loss_is_sent_minus_received_frames() {
+        // two TX ports that send to the same RX port
+        let setting = EXAMPLE_POST_2_REQUEST.stream_settings[0].clone();
+        let data = TrialData {
+            streams: EXAMPLE_POST_2_REQUEST.streams.clone(),
+            mode: EXAMPLE_POST_2_REQUEST.mode,
+            stream_settings: vec![
+                StreamSetting {
+                    port: 10,
+                    ..setting.clone()
+                },
+                StreamSetting {
+                    port: 20,
+                    ..setting
+                },
+            ],
+            tx_rx_port_mapping: HashMap::from([("10".to_owned(), 30), ("20".to_owned(), 30)]),
+            tx_frames: HashMap::from([(10, 1000), (20, 500), (30, 0)]),
+            rx_frames: HashMap::from([(10, 0), (20, 0), (30, 1490)]),
+            app_lost_frames: HashMap::new(),
+            rtts: HashMap::new(),
+            app_rtts: HashMap::new(),
+            percentiles: HashMap::new(),
+            app_rx_rates: HashMap::new(),
+        };
+
+        let counters = data.measure().counters;
+        assert_eq!(counters.tx_frames, 1500);
+        assert_eq!(counters.rx_frames, 1490);
+        assert_eq!(counters.lost_frames, 10);
+    }
+
+    #[test]
+    fn offered_load_uses_the_generated_frame_sizes() {
+        // minimal sized IPv6 frames are generated with 77 bytes
+        let mut payload = EXAMPLE_POST_2_REQUEST.clone();
+        payload.mode = GenerationMode::Mpps;
+        payload.streams[0].ip_version = Some(6);
+        payload.streams[0].frame_size = 64;
+        payload.streams[0].traffic_rate = 1f32;
+
+        // 77 bytes + 4 bytes VLAN + 20 bytes preamble and IFG
+        let (rate_l1, packet_rate) = offered_load(&payload);
+        assert!((rate_l1 - 0.808).abs() < 1e-4, "{rate_l1}");
+        assert!((packet_rate - 1f64).abs() < 1e-4, "{packet_rate}");
+    }
+
+    #[test]
+    fn frame_loss_stops_after_two_trials_without_loss() {
+        // device that forwards up to 65% without loss
+        let mut steps = FrameLossSteps::new(10f64);
+        let mut rates = vec![];
+
+        while let Some(rate) = steps.next_rate() {
+            rates.push(rate);
+            steps.record(rate, if rate > 65f64 { 100 } else { 0 });
+        }
+
+        assert_eq!(rates, vec![100f64, 90f64, 80f64, 70f64, 60f64, 50f64]);
+
+        // the rate never drops to 0
+        let mut steps = FrameLossSteps::new(40f64);
+        let mut rates = vec![];
+
+        while let Some(rate) = steps.next_rate() {
+            rates.push(rate);
+            steps.record(rate, 1);
+        }
+
+        assert_eq!(rates, vec![100f64, 60f64, 20f64]);
+    }
+
+    #[test]
+    fn latencies_are_averaged() {
+        let latency = average_latency(&[
+            Latency {
+                mean: 1000f64,
+                min: 900,
+                max: 1200,
+                jitter: 10f64,
+                n: 100,
+                percentiles: BTreeMap::from([(5000, 1000f64)]),
+            },
+            Latency {
+                mean: 2000f64,
+                min: 800,
+                max: 2500,
+                jitter: 30f64,
+                n: 50,
+                percentiles: BTreeMap::from([(5000, 2000f64)]),
+            },
+            Latency::default(),
+        ]);
+
+        assert_eq!(
+            latency,
+            Latency {
+                mean: 1500f64,
+                min: 800,
+                max: 2500,
+                jitter: 20f64,
+                n: 150,
+                percentiles: BTreeMap::from([(5000, 1500f64)]),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn rfc2544_benchmark_is_started_and_aborted() {
+        let (_, state) = mock_state().await;
+
+        let mut config = serde_json::json!({
+            "traffic_gen": *EXAMPLE_POST_2_REQUEST,
+            "frame_sizes": [],
+        });
+        let (status, _) = call(&state, Method::POST, "/rfc2544", config.to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        config["frame_sizes"] = serde_json::json!([64, 1518]);
+        let (status, result) = call(&state, Method::POST, "/rfc2544", config.to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(result["running"], true);
+
+        // the first trial runs at the configured rate
+        wait_for_traffic_gen(&state).await;
+        assert_eq!(state.traffic_generator.lock().await.duration, Some(60));
+
+        let (status, result) = call(&state, Method::DELETE, "/rfc2544", String::new()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(result["running"], false);
+        assert_eq!(result["aborted"], true);
+        assert!(result["throughput"].as_array().unwrap().is_empty());
+        assert!(!state.traffic_generator.lock().await.running);
+    }
+}
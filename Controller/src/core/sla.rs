@@ -0,0 +1,713 @@
+/* Copyright 2022-present University of Tuebingen, Chair of Communication Networks
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/*
+ * Steffen Lindner (steffen.lindner@uni-tuebingen.de)
+ */
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::api::statistics::{get_statistics, StatisticsApi};
+use crate::api::traffic_gen::stop_test;
+use crate::core::traffic_gen_core::helper::{calculate_overhead, normalize_stream};
+use crate::core::traffic_gen_core::types::TrafficGenData;
+use crate::AppState;
+
+/// Interval in which the thresholds are evaluated while a test is running
+const EVALUATION_INTERVAL: Duration = Duration::from_secs(1);
+
+fn default_grace_period() -> u32 {
+    2
+}
+
+/// Acceptance criteria of a test
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct SlaConfig {
+    pub thresholds: Vec<SlaThreshold>,
+    /// Stops the test on the first violation. In multiple tests, the next test is started.
+    #[serde(default)]
+    pub stop_on_violation: bool,
+    /// Seconds after the start of the test in which the thresholds are not evaluated,
+    /// e.g., until the first rates are measured. Defaults to 2.
+    #[serde(default = "default_grace_period")]
+    pub grace_period: u32,
+}
+
+/// Thresholds of a port or a stream.
+/// Unset thresholds are not evaluated.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, ToSchema)]
+pub struct SlaThreshold {
+    /// RX front panel port to which the thresholds apply. Applies to all RX ports of the test if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u32>,
+    /// Channel of `port`. Defaults to 0.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<u8>,
+    /// Stream to which the thresholds apply. `max_iat_mae` cannot be evaluated per stream.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream_id: Option<u8>,
+    /// Maximal ratio of lost packets to received and lost packets, between 0 and 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_loss_ratio: Option<f64>,
+    /// Maximal RTT in ns per percentile (1 - 100) of the RTT histogram.
+    /// The percentiles are added to the RTT histogram configurations of the test.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_rtt_percentiles: Option<BTreeMap<u32, f64>>,
+    /// Minimal L2 receive rate in Gbps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_rx_rate: Option<f64>,
+    /// Maximal number of out of order packets
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_out_of_order: Option<u64>,
+    /// Maximal mean absolute error of the receive IAT in ns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_iat_mae: Option<f64>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SlaMetric {
+    LossRatio,
+    RttPercentile,
+    RxRate,
+    OutOfOrder,
+    IatMae,
+}
+
+/// A threshold that has been violated
+#[derive(Serialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct SlaViolation {
+    pub port: u32,
+    pub channel: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_id: Option<u8>,
+    pub metric: SlaMetric,
+    /// Percentile of a `rtt_percentile` violation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub percentile: Option<u32>,
+    /// Measured value. Rates are in Gbps, RTTs and IATs in ns.
+    pub value: f64,
+    pub threshold: f64,
+    /// Elapsed time of the test in seconds at which the violation was detected
+    pub time: u32,
+}
+
+impl SlaViolation {
+    fn key(&self) -> (u32, u8, Option<u8>, SlaMetric, Option<u32>) {
+        (
+            self.port,
+            self.channel,
+            self.stream_id,
+            self.metric,
+            self.percentile,
+        )
+    }
+}
+
+/// Verdict of the acceptance criteria of a test.
+/// Each threshold is reported with its first violation.
+#[derive(Serialize, Debug, Clone, Default, ToSchema)]
+pub struct SlaVerdict {
+    pub passed: bool,
+    pub violations: Vec<SlaViolation>,
+}
+
+impl SlaConfig {
+    /// Validates the thresholds against the streams and port mapping of `traffic_gen`.
+    pub fn validate(&self, traffic_gen: &TrafficGenData) -> Result<(), String> {
+        let rx_ports = rx_targets(traffic_gen, None);
+
+        for t in &self.thresholds {
+            if t.max_loss_ratio.is_none()
+                && t.max_rtt_percentiles.is_none()
+                && t.min_rx_rate.is_none()
+                && t.max_out_of_order.is_none()
+                && t.max_iat_mae.is_none()
+            {
+                return Err("SLA thresholds without any limit are not allowed.".to_owned());
+            }
+
+            if let Some(port) = t.port {
+                let channel = t.channel.unwrap_or(0);
+
+                if !rx_ports.contains(&(port, channel)) {
+                    return Err(format!(
+                        "SLA threshold for port {port}/{channel} that does not receive traffic."
+                    ));
+                }
+            }
+
+            if let Some(stream_id) = t.stream_id {
+                if !traffic_gen.streams.iter().any(|s| s.stream_id == stream_id) {
+                    return Err(format!(
+                        "SLA threshold for stream {stream_id} that does not exist."
+                    ));
+                }
+
+                if t.max_iat_mae.is_some() {
+                    return Err(format!(
+                        "max_iat_mae cannot be evaluated for stream {stream_id}."
+                    ));
+                }
+            }
+
+            if t.max_loss_ratio
+                .is_some_and(|r| !(0f64..=1f64).contains(&r))
+            {
+                return Err("max_loss_ratio has to be between 0 and 1.".to_owned());
+            }
+
+            let percentiles = t.max_rtt_percentiles.iter().flatten();
+
+            for (percentile, value) in percentiles {
+                if !(1..=100).contains(percentile) || *value < 0f64 {
+                    return Err(format!(
+                        "Invalid RTT percentile threshold {percentile}: {value}."
+                    ));
+                }
+            }
+
+            if t.min_rx_rate.is_some_and(|r| r < 0f64) || t.max_iat_mae.is_some_and(|m| m < 0f64) {
+                return Err("SLA thresholds have to be positive.".to_owned());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the RTT percentiles (0 - 1) that are required to evaluate the thresholds.
+    pub fn rtt_percentiles(&self) -> Vec<f64> {
+        let percentiles: BTreeSet<u32> = self
+            .thresholds
+            .iter()
+            .flat_map(|t| t.max_rtt_percentiles.iter().flatten().map(|(p, _)| *p))
+            .collect();
+
+        percentiles.into_iter().map(|p| p as f64 / 100f64).collect()
+    }
+}
+
+/// Returns the RX front panel ports and channels of the active TX ports of `traffic_gen`.
+/// If `stream_id` is set, only the RX ports of the TX ports that send the stream are returned.
+fn rx_targets(traffic_gen: &TrafficGenData, stream_id: Option<u8>) -> BTreeSet<(u32, u8)> {
+    traffic_gen
+        .stream_settings
+        .iter()
+        .filter(|s| s.active && stream_id.is_none_or(|id| id == s.stream_id))
+        .filter_map(|s| {
+            traffic_gen
+                .port_tx_rx_mapping
+                .get(&s.port.to_string())?
+                .get(&s.channel.unwrap_or(0).to_string())
+        })
+        .map(|target| (target.port, target.channel))
+        .collect()
+}
+
+/// Returns the statistic of `port` and `channel`.
+fn get<T>(map: &HashMap<u32, HashMap<u8, T>>, port: u32, channel: u8) -> Option<&T> {
+    map.get(&port).and_then(|c| c.get(&channel))
+}
+
+/// Returns the statistic of stream `app_id` on `port` and `channel` from `app_map` if `app_id` is set,
+/// otherwise the statistic of `port` and `channel` from `map`.
+fn get_stream<'a, T>(
+    map: &'a HashMap<u32, HashMap<u8, T>>,
+    app_map: &'a HashMap<u32, HashMap<u8, HashMap<u32, T>>>,
+    port: u32,
+    channel: u8,
+    app_id: Option<u32>,
+) -> Option<&'a T> {
+    match app_id {
+        Some(app_id) => get(app_map, port, channel).and_then(|apps| apps.get(&app_id)),
+        None => get(map, port, channel),
+    }
+}
+
+/// Returns the share of stream `app_id` in the packets received on `port` and `channel`.
+/// The share is estimated from the packet rates of the streams, i.e., their L2 receive rates
+/// divided by their frame sizes. Returns 1 if `app_id` is not set.
+fn rx_share(
+    traffic_gen: &TrafficGenData,
+    stats: &StatisticsApi,
+    port: u32,
+    channel: u8,
+    app_id: Option<u32>,
+) -> f64 {
+    let Some(app_id) = app_id else {
+        return 1f64;
+    };
+    let Some(rates) = get(&stats.app_rx_l2, port, channel) else {
+        return 0f64;
+    };
+
+    let packet_rate = |app: u32, rate: f64| {
+        traffic_gen
+            .streams
+            .iter()
+            .find(|s| s.app_id as u32 == app)
+            .map(|s| {
+                let s = normalize_stream(s.clone(), traffic_gen.mode);
+                rate / (8 * (s.frame_size + calculate_overhead(&s))) as f64
+            })
+            .unwrap_or(0f64)
+    };
+
+    let total: f64 = rates
+        .iter()
+        .map(|(app, rate)| packet_rate(*app, *rate))
+        .sum();
+
+    if total > 0f64 {
+        packet_rate(app_id, rates.get(&app_id).copied().unwrap_or(0f64)) / total
+    } else {
+        0f64
+    }
+}
+
+/// Evaluates the thresholds of `config` on the statistics of a test.
+///
+/// Returns all violated thresholds.
+pub(crate) fn evaluate(
+    config: &SlaConfig,
+    traffic_gen: &TrafficGenData,
+    stats: &StatisticsApi,
+) -> Vec<SlaViolation> {
+    let mut violations = vec![];
+
+    for t in &config.thresholds {
+        let targets: Vec<(u32, u8)> = rx_targets(traffic_gen, t.stream_id)
+            .into_iter()
+            .filter(|(port, channel)| {
+                t.port
+                    .is_none_or(|p| p == *port && t.channel.unwrap_or(0) == *channel)
+            })
+            .collect();
+
+        let app_id = t.stream_id.and_then(|id| {
+            traffic_gen
+                .streams
+                .iter()
+                .find(|s| s.stream_id == id)
+                .map(|s| s.app_id as u32)
+        });
+
+        for (port, channel) in targets {
+            let mut violation = |metric, percentile, value: f64, threshold: f64| {
+                violations.push(SlaViolation {
+                    port,
+                    channel,
+                    stream_id: t.stream_id,
+                    metric,
+                    percentile,
+                    value,
+                    threshold,
+                    time: stats.elapsed_time,
+                });
+            };
+
+            if let Some(min_rx_rate) = t.min_rx_rate {
+                let rate = get_stream(&stats.rx_rate_l2, &stats.app_rx_l2, port, channel, app_id)
+                    .copied()
+                    .unwrap_or(0f64)
+                    / 1e9;
+
+                if rate < min_rx_rate {
+                    violation(SlaMetric::RxRate, None, rate, min_rx_rate);
+                }
+            }
+
+            if let Some(max_loss_ratio) = t.max_loss_ratio {
+                let lost = get_stream(
+                    &stats.packet_loss,
+                    &stats.app_packet_loss,
+                    port,
+                    channel,
+                    app_id,
+                )
+                .copied()
+                .unwrap_or(0) as f64;
+                let received = get(&stats.frame_size, port, channel)
+                    .map(|r| r.rx.iter().map(|v| v.packets).sum::<u128>())
+                    .unwrap_or(0) as f64
+                    * rx_share(traffic_gen, stats, port, channel, app_id);
+                let ratio = if lost > 0f64 {
+                    lost / (received + lost)
+                } else {
+                    0f64
+                };
+
+                if ratio > max_loss_ratio {
+                    violation(SlaMetric::LossRatio, None, ratio, max_loss_ratio);
+                }
+            }
+
+            if let Some(max_out_of_order) = t.max_out_of_order {
+                let out_of_order = get_stream(
+                    &stats.out_of_order,
+                    &stats.app_out_of_order,
+                    port,
+                    channel,
+                    app_id,
+                )
+                .copied()
+                .unwrap_or(0);
+
+                if out_of_order > max_out_of_order {
+                    violation(
+                        SlaMetric::OutOfOrder,
+                        None,
+                        out_of_order as f64,
+                        max_out_of_order as f64,
+                    );
+                }
+            }
+
+            if let Some(max_iat_mae) = t.max_iat_mae {
+                if let Some(iat) = get(&stats.iats, port, channel) {
+                    if iat.rx.mae as f64 > max_iat_mae {
+                        violation(SlaMetric::IatMae, None, iat.rx.mae as f64, max_iat_mae);
+                    }
+                }
+            }
+
+            for (percentile, max_rtt) in t.max_rtt_percentiles.iter().flatten() {
+                // percentiles are only available once RTTs have been measured
+                let value = get_stream(
+                    &stats.rtt_histogram,
+                    &stats.app_rtt_histogram,
+                    port,
+                    channel,
+                    app_id,
+                )
+                .and_then(|h| h.data.rx.percentiles.get(percentile));
+
+                if let Some(value) = value {
+                    if value > max_rtt {
+                        violation(
+                            SlaMetric::RttPercentile,
+                            Some(*percentile),
+                            *value,
+                            *max_rtt,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    violations
+}
+
+/// Evaluates the acceptance criteria of the running test
+#[derive(Default)]
+pub struct SlaMonitor {
+    /// Verdict of the current test. `None` if the test has no acceptance criteria.
+    pub(crate) verdict: Option<SlaVerdict>,
+}
+
+impl SlaMonitor {
+    /// Resets the verdict for a new test with the acceptance criteria `config`.
+    pub fn start(&mut self, config: Option<&SlaConfig>) {
+        self.verdict = config.map(|_| SlaVerdict {
+            passed: true,
+            violations: vec![],
+        });
+    }
+
+    /// Adds the violations that have not been reported before.
+    /// Returns true if a new violation has been added.
+    fn record(&mut self, violations: Vec<SlaViolation>) -> bool {
+        let Some(verdict) = self.verdict.as_mut() else {
+            return false;
+        };
+
+        let mut added = false;
+
+        for violation in violations {
+            if !verdict
+                .violations
+                .iter()
+                .any(|v| v.key() == violation.key())
+            {
+                warn!("SLA violated: {violation:?}");
+                verdict.violations.push(violation);
+                added = true;
+            }
+        }
+
+        verdict.passed = verdict.violations.is_empty();
+
+        added
+    }
+
+    /// Evaluates the acceptance criteria of the running test on its current statistics.
+    /// Tests are not evaluated within their grace period.
+    ///
+    /// Returns the acceptance criteria if a new violation has been detected.
+    pub async fn evaluate(state: &Arc<AppState>) -> Option<SlaConfig> {
+        let (running, start) = {
+            let experiment = state.experiment.lock().await;
+            (experiment.running, experiment.start)
+        };
+
+        if !running || state.sla_monitor.lock().await.verdict.is_none() {
+            return None;
+        }
+
+        let traffic_gen = state.traffic_generator.lock().await.traffic_gen_data();
+        let config = traffic_gen.sla.clone()?;
+
+        let grace_period = Duration::from_secs(config.grace_period as u64);
+
+        if start.elapsed().unwrap_or_default() < grace_period {
+            return None;
+        }
+
+        let stats = get_statistics(state).await.into_iter().next()?;
+        let violations = evaluate(&config, &traffic_gen, &stats);
+
+        state
+            .sla_monitor
+            .lock()
+            .await
+            .record(violations)
+            .then_some(config)
+    }
+
+    /// Evaluates the acceptance criteria of the running test every second.
+    /// Stops the test on the first violation if requested.
+    pub async fn monitor_sla(state: Arc<AppState>) {
+        let mut interval = tokio::time::interval(EVALUATION_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let Some(config) = Self::evaluate(&state).await else {
+                continue;
+            };
+
+            if config.stop_on_violation {
+                info!("Stopping test because of an SLA violation.");
+
+                if let Err(e) = stop_test(&state, true).await {
+                    warn!("Error while stopping traffic generation: {e:#?}");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::docs::traffic_gen::EXAMPLE_POST_2_REQUEST;
+    use crate::core::backend::mock::tests::{call, mock_state};
+    use crate::core::statistics::{RangeCount, RangeCountValue};
+    use axum::http::{Method, StatusCode};
+
+    fn statistics() -> StatisticsApi {
+        let mut stats = crate::api::metrics::tests::statistics(None);
+
+        stats.rx_rate_l2 = HashMap::from([(2, HashMap::from([(0, 70e9)]))]);
+        stats.packet_loss = HashMap::from([(2, HashMap::from([(0, 10)]))]);
+        stats.frame_size = HashMap::from([(
+            2,
+            HashMap::from([(
+                0,
+                RangeCount {
+                    tx: vec![],
+                    rx: vec![RangeCountValue::new(64, 64, 990)],
+                },
+            )]),
+        )]);
+
+        stats
+    }
+
+    #[test]
+    fn violated_thresholds_are_reported() {
+        let traffic_gen = EXAMPLE_POST_2_REQUEST.clone();
+        let config = SlaConfig {
+            thresholds: vec![SlaThreshold {
+                max_loss_ratio: Some(0.001),
+                min_rx_rate: Some(60f64),
+                max_out_of_order: Some(0),
+                ..Default::default()
+            }],
+            stop_on_violation: false,
+            grace_period: 0,
+        };
+        assert!(config.validate(&traffic_gen).is_ok());
+
+        let violations = evaluate(&config, &traffic_gen, &statistics());
+
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].metric, SlaMetric::LossRatio);
+        assert_eq!((violations[0].port, violations[0].channel), (2, 0));
+        assert_eq!(violations[0].value, 0.01);
+    }
+
+    /// Returns the traffic generation of the example with a second stream of `frame_size` on the same ports.
+    fn two_streams(frame_size: u32) -> TrafficGenData {
+        let mut traffic_gen = EXAMPLE_POST_2_REQUEST.clone();
+        let mut stream = traffic_gen.streams[0].clone();
+        stream.stream_id = 2;
+        stream.app_id = 2;
+        stream.frame_size = frame_size;
+        traffic_gen.streams.push(stream);
+
+        let mut setting = traffic_gen.stream_settings[0].clone();
+        setting.stream_id = 2;
+        traffic_gen.stream_settings.push(setting);
+
+        traffic_gen
+    }
+
+    #[test]
+    fn stream_thresholds_are_evaluated_per_stream() {
+        let traffic_gen = two_streams(64);
+        let config = SlaConfig {
+            thresholds: vec![SlaThreshold {
+                stream_id: Some(1),
+                max_loss_ratio: Some(0.015),
+                max_out_of_order: Some(0),
+                max_rtt_percentiles: Some(BTreeMap::from([(99, 4000f64)])),
+                ..Default::default()
+            }],
+            stop_on_violation: false,
+            grace_period: 0,
+        };
+        assert!(config.validate(&traffic_gen).is_ok());
+
+        let mut stats = statistics();
+        // the port is shared with another stream that receives the same rate
+        stats.app_rx_l2 = HashMap::from([(
+            2,
+            HashMap::from([(0, HashMap::from([(1, 35e9), (2, 35e9)]))]),
+        )]);
+        stats.app_packet_loss =
+            HashMap::from([(2, HashMap::from([(0, HashMap::from([(1, 10)]))]))]);
+        stats.app_out_of_order =
+            HashMap::from([(2, HashMap::from([(0, HashMap::from([(1, 3)]))]))]);
+        let mut histogram = stats.rtt_histogram[&2][&0].clone();
+        histogram.data.rx.percentiles = HashMap::from([(99, 5000f64)]);
+        stats.app_rtt_histogram =
+            HashMap::from([(2, HashMap::from([(0, HashMap::from([(1, histogram)]))]))]);
+
+        let violations = evaluate(&config, &traffic_gen, &stats);
+        let metrics: Vec<SlaMetric> = violations.iter().map(|v| v.metric).collect();
+
+        // the stream received half of the 990 packets of the port
+        assert_eq!(
+            metrics,
+            vec![
+                SlaMetric::LossRatio,
+                SlaMetric::OutOfOrder,
+                SlaMetric::RttPercentile
+            ]
+        );
+        assert!(violations.iter().all(|v| v.stream_id == Some(1)));
+        assert_eq!(violations[0].value, 10f64 / 505f64);
+        assert_eq!(violations[1].value, 3f64);
+        assert_eq!(violations[2].value, 5000f64);
+    }
+
+    #[test]
+    fn stream_loss_ratio_is_weighted_by_packet_rate() {
+        let traffic_gen = two_streams(1518);
+        let config = SlaConfig {
+            thresholds: [1, 2]
+                .map(|stream_id| SlaThreshold {
+                    stream_id: Some(stream_id),
+                    max_loss_ratio: Some(0.015),
+                    ..Default::default()
+                })
+                .to_vec(),
+            stop_on_violation: false,
+            grace_period: 0,
+        };
+
+        let mut stats = statistics();
+        // both streams are received with the same bit rate
+        stats.app_rx_l2 = HashMap::from([(
+            2,
+            HashMap::from([(0, HashMap::from([(1, 35e9), (2, 35e9)]))]),
+        )]);
+        stats.app_packet_loss =
+            HashMap::from([(2, HashMap::from([(0, HashMap::from([(1, 10), (2, 10)]))]))]);
+
+        let violations = evaluate(&config, &traffic_gen, &stats);
+
+        // the 64 byte stream carries 1522 / 68 times as many packets as the 1518 byte stream,
+        // including their VLAN tags
+        let received = 990f64 * 68f64 / (68f64 + 1522f64);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].stream_id, Some(2));
+        assert!((violations[0].value - 10f64 / (received + 10f64)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn stream_thresholds_exclude_iats() {
+        let traffic_gen = EXAMPLE_POST_2_REQUEST.clone();
+        let config = SlaConfig {
+            thresholds: vec![SlaThreshold {
+                stream_id: Some(1),
+                max_iat_mae: Some(10f64),
+                ..Default::default()
+            }],
+            stop_on_violation: false,
+            grace_period: 0,
+        };
+
+        assert!(config.validate(&traffic_gen).is_err());
+    }
+
+    #[tokio::test]
+    async fn sla_verdicts_are_reported_in_statistics() {
+        let (_, state) = mock_state().await;
+
+        let mut request = serde_json::to_value(&*EXAMPLE_POST_2_REQUEST).unwrap();
+        request["sla"] = serde_json::json!({
+            "thresholds": [{"stream_id": 42, "min_rx_rate": 1.0}],
+            "grace_period": 0
+        });
+
+        let (status, _) = call(&state, Method::POST, "/trafficgen", request.to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        request["sla"]["thresholds"] = serde_json::json!([{"min_rx_rate": 1000.0}]);
+
+        let (status, _) = call(&state, Method::POST, "/trafficgen", request.to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, _) = call(&state, Method::DELETE, "/trafficgen", String::new()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, statistics) = call(&state, Method::GET, "/statistics", String::new()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let verdict = &statistics[0]["sla"];
+        assert_eq!(verdict["passed"], false);
+        assert_eq!(verdict["violations"][0]["metric"], "rx_rate");
+        assert_eq!(verdict["violations"][0]["threshold"], 1000.0);
+    }
+}
@@ -17,6 +17,9 @@
  * Steffen Lindner (steffen.lindner@uni-tuebingen.de)
  */
 
+use crate::core::disruption::DisruptionStatistics;
+use crate::core::loss_analysis::LossAnalysis;
+use crate::core::measurement_window::{MeanRates, MeasurementWindow};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashMap};
 use utoipa::ToSchema;
@@ -111,6 +114,8 @@ pub struct RateMonitorStatistics {
     pub(crate) app_rx_l2: HashMap<u32, HashMap<u32, f64>>,
     pub(crate) packet_loss: HashMap<u32, u64>,
     pub(crate) out_of_order: HashMap<u32, u64>,
+    pub(crate) app_packet_loss: HashMap<u32, HashMap<u32, u64>>,
+    pub(crate) app_out_of_order: HashMap<u32, HashMap<u32, u64>>,
 }
 
 impl RateMonitorStatistics {
@@ -126,6 +131,8 @@ impl RateMonitorStatistics {
             app_rx_l2: Default::default(),
             packet_loss: Default::default(),
             out_of_order: Default::default(),
+            app_packet_loss: Default::default(),
+            app_out_of_order: Default::default(),
         }
     }
 }
@@ -148,6 +155,25 @@ pub struct RTTStatistics {
     pub(crate) n: u32,
 }
 
+/// Quantiles over all samples of a test. Estimated with a relative error of at most 0.5%.
+#[derive(Serialize, Debug, Clone, Default, ToSchema)]
+pub struct Quantiles {
+    pub(crate) p50: f64,
+    pub(crate) p90: f64,
+    pub(crate) p99: f64,
+    pub(crate) p999: f64,
+    /// Number of samples
+    pub(crate) n: u64,
+}
+
+#[derive(Serialize, Debug, Clone, Default, ToSchema)]
+pub struct IATQuantiles {
+    /// TX path
+    pub(crate) tx: Quantiles,
+    /// RX path
+    pub(crate) rx: Quantiles,
+}
+
 impl IATStatistics {
     pub fn default() -> IATStatistics {
         IATStatistics {
@@ -182,19 +208,60 @@ impl IATValues {
     }
 }
 
+/// Elapsed time since the traffic generation has started in ms.
+/// Used as key of time series and serialized as (fractional) seconds, e.g., "1.5".
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, ToSchema)]
+#[schema(value_type = String)]
+pub struct ElapsedTime(pub(crate) u32);
+
+impl ElapsedTime {
+    /// Returns the start of the time slot of length `resolution` ms that contains this time.
+    pub fn slot(&self, resolution: u32) -> ElapsedTime {
+        let resolution = resolution.max(1);
+        ElapsedTime(self.0 / resolution * resolution)
+    }
+
+    pub fn as_secs_f64(&self) -> f64 {
+        self.0 as f64 / 1000f64
+    }
+}
+
+impl Serialize for ElapsedTime {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.as_secs_f64())
+    }
+}
+
 /// Common fields for time-based statistics
 #[derive(Serialize, Debug, Clone, ToSchema)]
 pub struct TimeStatistics {
     /// L1 send rates per test and port
-    pub(crate) tx_rate_l1: HashMap<u32, BTreeMap<u32, f64>>,
+    pub(crate) tx_rate_l1: HashMap<u32, BTreeMap<ElapsedTime, f64>>,
     /// L1 receive rates per test and port
-    pub(crate) rx_rate_l1: HashMap<u32, BTreeMap<u32, f64>>,
+    pub(crate) rx_rate_l1: HashMap<u32, BTreeMap<ElapsedTime, f64>>,
     /// Number of lost packets per test and port
-    pub(crate) packet_loss: HashMap<u32, BTreeMap<u32, u64>>,
+    pub(crate) packet_loss: HashMap<u32, BTreeMap<ElapsedTime, u64>>,
     /// Number of out-of-order packets per test and port
-    pub(crate) out_of_order: HashMap<u32, BTreeMap<u32, u64>>,
+    pub(crate) out_of_order: HashMap<u32, BTreeMap<ElapsedTime, u64>>,
     /// RTT values per test and port
-    pub(crate) rtt: HashMap<u32, BTreeMap<u32, u64>>,
+    pub(crate) rtt: HashMap<u32, BTreeMap<ElapsedTime, u64>>,
+    /// L2 send rates per test, port and stream
+    pub(crate) app_tx_l2: HashMap<u32, HashMap<u32, BTreeMap<ElapsedTime, f64>>>,
+    /// L2 receive rates per test, port and stream
+    pub(crate) app_rx_l2: HashMap<u32, HashMap<u32, BTreeMap<ElapsedTime, f64>>>,
+    /// Number of lost packets per test, port and stream
+    pub(crate) app_packet_loss: HashMap<u32, HashMap<u32, BTreeMap<ElapsedTime, u64>>>,
+    /// Number of out-of-order packets per test, port and stream
+    pub(crate) app_out_of_order: HashMap<u32, HashMap<u32, BTreeMap<ElapsedTime, u64>>>,
+    /// RTT values per test, port and stream
+    pub(crate) app_rtt: HashMap<u32, HashMap<u32, BTreeMap<ElapsedTime, u64>>>,
+    /// Cumulative disruption time in seconds per test and port
+    pub(crate) disruption_time: HashMap<u32, BTreeMap<ElapsedTime, f64>>,
+    /// Cumulative disruption time in seconds per test, port and stream
+    pub(crate) app_disruption_time: HashMap<u32, HashMap<u32, BTreeMap<ElapsedTime, f64>>>,
+    /// Measurement window of the test. Only present if a warm-up or cool-down phase is configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) measurement_window: Option<MeasurementWindow>,
     /// Name of the test those stats belong to
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) name: Option<String>,
@@ -208,27 +275,244 @@ impl TimeStatistics {
             packet_loss: Default::default(),
             out_of_order: Default::default(),
             rtt: Default::default(),
+            app_tx_l2: Default::default(),
+            app_rx_l2: Default::default(),
+            app_packet_loss: Default::default(),
+            app_out_of_order: Default::default(),
+            app_rtt: Default::default(),
+            disruption_time: Default::default(),
+            app_disruption_time: Default::default(),
+            measurement_window: None,
             name: None,
         }
     }
+
+    /// Returns the time statistics that only contain the time slots at or after `start`.
+    pub(crate) fn since(&self, start: ElapsedTime) -> TimeStatistics {
+        TimeStatistics {
+            tx_rate_l1: ports_since(&self.tx_rate_l1, start),
+            rx_rate_l1: ports_since(&self.rx_rate_l1, start),
+            packet_loss: ports_since(&self.packet_loss, start),
+            out_of_order: ports_since(&self.out_of_order, start),
+            rtt: ports_since(&self.rtt, start),
+            app_tx_l2: streams_since(&self.app_tx_l2, start),
+            app_rx_l2: streams_since(&self.app_rx_l2, start),
+            app_packet_loss: streams_since(&self.app_packet_loss, start),
+            app_out_of_order: streams_since(&self.app_out_of_order, start),
+            app_rtt: streams_since(&self.app_rtt, start),
+            disruption_time: ports_since(&self.disruption_time, start),
+            app_disruption_time: streams_since(&self.app_disruption_time, start),
+            measurement_window: self.measurement_window,
+            name: self.name.clone(),
+        }
+    }
+
+    /// Returns the oldest of the latest time slots of all time series,
+    /// i.e., the time slot up to which all time series are complete.
+    pub(crate) fn newest(&self) -> Option<ElapsedTime> {
+        [
+            ports_newest(&self.tx_rate_l1),
+            ports_newest(&self.rx_rate_l1),
+            ports_newest(&self.packet_loss),
+            ports_newest(&self.out_of_order),
+            ports_newest(&self.rtt),
+            streams_newest(&self.app_tx_l2),
+            streams_newest(&self.app_rx_l2),
+            streams_newest(&self.app_packet_loss),
+            streams_newest(&self.app_out_of_order),
+            streams_newest(&self.app_rtt),
+            ports_newest(&self.disruption_time),
+            streams_newest(&self.app_disruption_time),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+    }
+}
+
+fn ports_since<V: Copy>(
+    series: &HashMap<u32, BTreeMap<ElapsedTime, V>>,
+    start: ElapsedTime,
+) -> HashMap<u32, BTreeMap<ElapsedTime, V>> {
+    series
+        .iter()
+        .map(|(port, values)| {
+            (
+                *port,
+                values.range(start..).map(|(k, v)| (*k, *v)).collect(),
+            )
+        })
+        .collect()
+}
+
+fn streams_since<V: Copy>(
+    series: &HashMap<u32, HashMap<u32, BTreeMap<ElapsedTime, V>>>,
+    start: ElapsedTime,
+) -> HashMap<u32, HashMap<u32, BTreeMap<ElapsedTime, V>>> {
+    series
+        .iter()
+        .map(|(port, per_app)| (*port, ports_since(per_app, start)))
+        .collect()
+}
+
+fn ports_newest<V>(series: &HashMap<u32, BTreeMap<ElapsedTime, V>>) -> Option<ElapsedTime> {
+    series
+        .values()
+        .filter_map(|values| values.last_key_value().map(|(k, _)| *k))
+        .min()
+}
+
+fn streams_newest<V>(
+    series: &HashMap<u32, HashMap<u32, BTreeMap<ElapsedTime, V>>>,
+) -> Option<ElapsedTime> {
+    series.values().filter_map(ports_newest).min()
+}
+
+/// Layout of the bins of a histogram between its minimum and maximum.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BinLayout {
+    /// Bins of equal width
+    #[default]
+    Linear,
+    /// Bins whose width grows exponentially, i.e., equal width on a logarithmic scale.
+    /// The minimum has to be at least 1.
+    Logarithmic,
+}
+
+fn default_calibration_warmup() -> u32 {
+    2000
+}
+
+fn default_lower_percentile() -> f64 {
+    0.01
+}
+
+fn default_upper_percentile() -> f64 {
+    0.99
+}
+
+/// Automatic calibration of the histogram range.
+/// The samples of the RTT/IAT digests are observed during a warm-up phase at the start of the test.
+/// Afterwards, `min` and `max` are set to the percentiles of the samples and the histogram is restarted.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct HistogramCalibration {
+    /// Duration of the warm-up phase in ms. Defaults to 2000.
+    #[serde(default = "default_calibration_warmup")]
+    pub warmup: u32,
+    /// Percentile of the samples that is used as minimum of the range. Defaults to 0.01.
+    #[serde(default = "default_lower_percentile")]
+    pub lower_percentile: f64,
+    /// Percentile of the samples that is used as maximum of the range. Defaults to 0.99.
+    #[serde(default = "default_upper_percentile")]
+    pub upper_percentile: f64,
+}
+
+/// Range of a histogram that was chosen by the automatic calibration.
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct CalibratedRange {
+    pub min: u32,
+    pub max: u32,
+    /// Number of samples the range is based on
+    pub samples: u64,
 }
 
 #[derive(Serialize, Debug, Clone, ToSchema, Deserialize)]
 pub struct HistogramConfig {
     // Number of bins for histogram.
+    #[serde(default)]
     pub num_bins: u32,
     /// Minimum range for histogram.
+    #[serde(default)]
     pub min: u32,
     /// Maximum range for histogram.
+    #[serde(default)]
     pub max: u32,
+    /// Layout of the bins between minimum and maximum. Defaults to bins of equal width.
+    #[serde(default)]
+    pub layout: BinLayout,
+    /// Explicit bin edges in increasing order. Bin i contains the values in [bin_edges[i], bin_edges[i + 1]).
+    /// If set, `num_bins`, `min`, `max`, and `layout` are not used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bin_edges: Option<Vec<u32>>,
+    /// Calibrates `min` and `max` automatically from the samples of a warm-up phase.
+    /// `min` and `max` are used until the calibration is done.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto: Option<HistogramCalibration>,
     /// Percentiles to calculate from histogram data. Float values between 0 and 1.0
     #[serde(default)]
     pub percentiles: Option<Vec<f64>>,
 }
 
+/// Percentiles that are calculated if a histogram config does not specify any
+pub const DEFAULT_PERCENTILES: [f64; 4] = [0.25, 0.5, 0.75, 0.9];
+
 impl HistogramConfig {
-    pub fn get_bin_width(&self) -> u32 {
-        (self.max - self.min) / self.num_bins
+    /// Returns the edges of the bins, i.e., bin i contains the values in [edges[i], edges[i + 1]).
+    pub fn bin_edges(&self) -> Vec<u32> {
+        if let Some(edges) = &self.bin_edges {
+            return edges.clone();
+        }
+
+        if self.num_bins == 0 || self.max <= self.min {
+            return vec![];
+        }
+
+        match self.layout {
+            BinLayout::Linear => {
+                let bin_width = (self.max - self.min) / self.num_bins;
+                (0..=self.num_bins)
+                    .map(|i| self.min + i * bin_width)
+                    .collect()
+            }
+            BinLayout::Logarithmic => {
+                let min = self.min.max(1) as f64;
+                let ratio = self.max as f64 / min;
+
+                (0..=self.num_bins)
+                    .map(|i| {
+                        let edge = min * ratio.powf(i as f64 / self.num_bins as f64);
+                        (edge.round() as u32).clamp(self.min, self.max)
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Returns the value ranges [start, end] of the bins, e.g., to decompose them into ternary entries.
+    pub fn bin_ranges(&self) -> Vec<(u32, u32)> {
+        self.bin_edges()
+            .windows(2)
+            .map(|edges| (edges[0], edges[1].saturating_sub(1)))
+            .collect()
+    }
+
+    /// Returns the index of the bin that contains `value`.
+    pub fn bin_index(edges: &[u32], value: u64) -> Option<u32> {
+        let last = *edges.last()?;
+
+        if value < edges[0] as u64 || value >= last as u64 {
+            return None;
+        }
+
+        Some((edges.partition_point(|e| *e as u64 <= value) - 1) as u32)
+    }
+
+    /// Adds `percentiles` to the percentiles that are calculated from the histogram data.
+    /// Percentiles are identified by their rounded percent value.
+    pub fn add_percentiles(&mut self, percentiles: &[f64]) {
+        let configured = self
+            .percentiles
+            .get_or_insert_with(|| DEFAULT_PERCENTILES.to_vec());
+
+        for p in percentiles {
+            if !configured
+                .iter()
+                .any(|c| (c * 100f64).round() == (p * 100f64).round())
+            {
+                configured.push(*p);
+            }
+        }
     }
 }
 
@@ -238,7 +522,10 @@ impl Default for HistogramConfig {
             min: 1500,
             max: 2500,
             num_bins: 10,
-            percentiles: Some(vec![0.25, 0.5, 0.75, 0.9]),
+            layout: BinLayout::Linear,
+            bin_edges: None,
+            auto: None,
+            percentiles: Some(DEFAULT_PERCENTILES.to_vec()),
         }
     }
 }
@@ -271,6 +558,9 @@ pub struct HistogramBinEntry {
 pub struct Histogram {
     pub config: HistogramConfig,
     pub data: HistogramPacketPath,
+    /// Range chosen by the automatic calibration. Only present once the calibration is done.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub calibration: Option<CalibratedRange>,
 }
 
 #[derive(Serialize, Debug, Clone, ToSchema, Default)]
@@ -284,6 +574,7 @@ impl Histogram {
         Histogram {
             data: Default::default(),
             config: Default::default(),
+            calibration: None,
         }
     }
 }
@@ -320,10 +611,41 @@ pub struct Statistics {
     pub(crate) packet_loss: HashMap<u32, u64>,
     /// Number of out of order packets per port.
     pub(crate) out_of_order: HashMap<u32, u64>,
+    /// Number of lost packets per stream and port.
+    /// The number corresponds to the app_id in the Stream description.
+    pub(crate) app_packet_loss: HashMap<u32, HashMap<u32, u64>>,
+    /// Number of out of order packets per stream and port.
+    /// The number corresponds to the app_id in the Stream description.
+    pub(crate) app_out_of_order: HashMap<u32, HashMap<u32, u64>>,
+    /// Statistics of the round trip times per stream and port.
+    /// The number corresponds to the app_id in the Stream description.
+    pub(crate) app_rtts: HashMap<u32, HashMap<u32, RTTStatistics>>,
+    /// Quantiles of the sampled round trip times per port.
+    pub(crate) rtt_quantiles: HashMap<u32, Quantiles>,
+    /// Quantiles of the sampled round trip times per stream and port.
+    /// The number corresponds to the app_id in the Stream description.
+    pub(crate) app_rtt_quantiles: HashMap<u32, HashMap<u32, Quantiles>>,
+    /// Quantiles of the sampled inter arrival times per port. Only available in sample mode.
+    pub(crate) iat_quantiles: HashMap<u32, IATQuantiles>,
+    /// Disruption time and outage episodes per port, derived from the packet loss and the TX packet rate.
+    pub(crate) disruption: HashMap<u32, DisruptionStatistics>,
+    /// Disruption time and outage episodes per stream and port.
+    /// The number corresponds to the app_id in the Stream description.
+    pub(crate) app_disruption: HashMap<u32, HashMap<u32, DisruptionStatistics>>,
+    /// Characterization of the packet loss per port, e.g., burst lengths and Gilbert-Elliott parameters.
+    pub(crate) loss_analysis: HashMap<u32, LossAnalysis>,
+    /// Characterization of the packet loss per stream and port.
+    /// The number corresponds to the app_id in the Stream description.
+    pub(crate) app_loss_analysis: HashMap<u32, HashMap<u32, LossAnalysis>>,
+    /// Mean L1 and L2 rates per port inside the measurement window.
+    pub(crate) mean_rates: HashMap<u32, MeanRates>,
     /// Elapsed time since the traffic generation has started in seconds.
     pub(crate) elapsed_time: u32,
     /// RTT histogram data per port and per bin.
     pub(crate) rtt_histogram: HashMap<u32, Histogram>,
+    /// RTT histogram data per stream and port.
+    /// Estimated from the sampled RTTs using the RTT histogram config of the port.
+    pub(crate) app_rtt_histogram: HashMap<u32, HashMap<u32, Histogram>>,
     /// IAT histogram data per port and per bin.
     pub(crate) iat_histogram: HashMap<u32, Histogram>,
     // Name of the test for the statistics
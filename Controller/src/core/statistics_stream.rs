@@ -0,0 +1,507 @@
+/* Copyright 2022-present University of Tuebingen, Chair of Communication Networks
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/*
+ * Steffen Lindner (steffen.lindner@uni-tuebingen.de)
+ */
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tokio::sync::broadcast;
+use utoipa::ToSchema;
+
+use crate::api::statistics::{
+    current_time_statistics, get_statistics, get_time_statistics, Params,
+};
+use crate::core::statistics::ElapsedTime;
+use crate::AppState;
+
+/// Interval in which statistic updates are published
+const UPDATE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Number of events that are buffered for slow subscribers.
+/// Subscribers that fall behind receive a new snapshot.
+const EVENT_CAPACITY: usize = 128;
+
+/// Event of the statistics stream.
+#[derive(Serialize, Debug, Clone, ToSchema)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum StatisticsEvent {
+    /// Statistics and time statistics of all tests.
+    /// Sent when the stream is opened. Keys of `statistics` and `time_statistics` are the indices of the tests
+    /// as returned by `GET /api/statistics` and `GET /api/time_statistics`.
+    Snapshot { statistics: Value },
+    /// JSON merge patch (RFC 7386) of the snapshot since the last update
+    Update { patch: Value },
+    /// A traffic generation has been started
+    Started { name: Option<String> },
+    /// The traffic generation has been stopped
+    Stopped { name: Option<String> },
+    /// The traffic generation has been stopped because its duration elapsed
+    DurationElapsed { name: Option<String> },
+    /// A test of a multiple test run is started. `index` starts at 1.
+    NextTest {
+        index: usize,
+        total: usize,
+        name: Option<String>,
+    },
+}
+
+impl StatisticsEvent {
+    /// Name of the event, i.e., the `event` field of the serialized event
+    pub fn name(&self) -> &'static str {
+        match self {
+            StatisticsEvent::Snapshot { .. } => "snapshot",
+            StatisticsEvent::Update { .. } => "update",
+            StatisticsEvent::Started { .. } => "started",
+            StatisticsEvent::Stopped { .. } => "stopped",
+            StatisticsEvent::DurationElapsed { .. } => "duration_elapsed",
+            StatisticsEvent::NextTest { .. } => "next_test",
+        }
+    }
+}
+
+/// Distributes statistics events to all subscribers of the statistics stream
+pub struct EventBus {
+    sender: broadcast::Sender<StatisticsEvent>,
+    /// Snapshot that the published updates are based on.
+    /// Only present while statistics are published, i.e., while there are subscribers.
+    snapshot: std::sync::Mutex<Option<Value>>,
+}
+
+impl EventBus {
+    pub fn new() -> EventBus {
+        let (sender, _) = broadcast::channel(EVENT_CAPACITY);
+
+        EventBus {
+            sender,
+            snapshot: std::sync::Mutex::new(None),
+        }
+    }
+
+    /// Sends `event` to all subscribers. Events without subscribers are dropped.
+    pub fn emit(&self, event: StatisticsEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    /// Subscribes to the events. Returns the snapshot that the following updates are based on,
+    /// or `None` if no statistics have been published yet.
+    pub fn subscribe(&self) -> (broadcast::Receiver<StatisticsEvent>, Option<Value>) {
+        // the snapshot is locked such that no update is published in between
+        let snapshot = self.snapshot.lock().unwrap();
+
+        (self.sender.subscribe(), snapshot.clone())
+    }
+
+    /// Returns whether there are subscribers. The snapshot is dropped if there are none.
+    fn has_subscribers(&self) -> bool {
+        let mut snapshot = self.snapshot.lock().unwrap();
+
+        if self.sender.receiver_count() == 0 {
+            *snapshot = None;
+            return false;
+        }
+
+        true
+    }
+}
+
+/// Subscribes to the statistics stream.
+/// Returns the snapshot of the statistics that the following updates are based on.
+pub async fn subscribe(state: &Arc<AppState>) -> (broadcast::Receiver<StatisticsEvent>, Value) {
+    match state.events.subscribe() {
+        (receiver, Some(snapshot)) => (receiver, snapshot),
+        // the first update of the publisher contains the whole snapshot
+        (receiver, None) => (receiver, statistics_snapshot(state).await),
+    }
+}
+
+/// Returns the statistics and time statistics of all tests, keyed by the index of the test.
+pub async fn statistics_snapshot(state: &Arc<AppState>) -> Value {
+    let statistics = get_statistics(state).await;
+    let time_statistics = get_time_statistics(
+        state,
+        Params {
+            limit: None,
+            resolution: None,
+        },
+    )
+    .await;
+
+    serde_json::json!({
+        "statistics": indexed(serde_json::to_value(statistics).unwrap_or_default()),
+        "time_statistics": indexed(serde_json::to_value(time_statistics).unwrap_or_default()),
+    })
+}
+
+/// Converts a JSON array into an object keyed by the indices of the elements.
+fn indexed(values: Value) -> Value {
+    match values {
+        Value::Array(values) => Value::Object(
+            values
+                .into_iter()
+                .enumerate()
+                .map(|(i, v)| (i.to_string(), v))
+                .collect(),
+        ),
+        _ => Value::Object(Map::new()),
+    }
+}
+
+/// Returns the JSON merge patch (RFC 7386) that transforms `old` into `new`.
+/// Returns `None` if both are equal.
+pub(crate) fn merge_patch(old: &Value, new: &Value) -> Option<Value> {
+    diff(old, new, true)
+}
+
+/// Returns the JSON merge patch (RFC 7386) that updates `old` with the values of `new`.
+/// In contrast to [`merge_patch`], keys that are missing in `new` are kept.
+fn update_patch(old: &Value, new: &Value) -> Option<Value> {
+    diff(old, new, false)
+}
+
+fn diff(old: &Value, new: &Value, remove_missing: bool) -> Option<Value> {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            let mut patch = Map::new();
+
+            for (key, value) in new {
+                match old.get(key) {
+                    Some(old_value) => {
+                        if let Some(p) = diff(old_value, value, remove_missing) {
+                            patch.insert(key.clone(), p);
+                        }
+                    }
+                    None => {
+                        patch.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+
+            if remove_missing {
+                for key in old.keys().filter(|k| !new.contains_key(*k)) {
+                    patch.insert(key.clone(), Value::Null);
+                }
+            }
+
+            (!patch.is_empty()).then_some(Value::Object(patch))
+        }
+        _ => (old != new).then(|| new.clone()),
+    }
+}
+
+/// Applies the JSON merge patch (RFC 7386) `patch` to `target`.
+pub(crate) fn apply_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+
+    if let Value::Object(target) = target {
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(key);
+            } else {
+                apply_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+/// Time statistics of the current test that have been published
+struct Published {
+    /// Start of the test
+    start: SystemTime,
+    /// Number of previous tests
+    previous_tests: usize,
+    /// Time slot up to which all time series have been published.
+    /// It is published again as it may have been updated since.
+    since: Option<ElapsedTime>,
+}
+
+/// Publishes the changes of the statistics to the subscribers of the statistics stream.
+/// The statistics are only collected while there are subscribers, and only once per update for all subscribers.
+/// Only the newest time slots of the time statistics of the current test are collected.
+/// The time statistics of all tests are only collected again if a test is started or finished.
+pub async fn publish_statistics(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(UPDATE_INTERVAL);
+    let mut published: Option<Published> = None;
+
+    loop {
+        interval.tick().await;
+
+        if !state.events.has_subscribers() {
+            published = None;
+            continue;
+        }
+
+        let start = state.experiment.lock().await.start;
+        let previous_tests = state
+            .multiple_tests
+            .collected_time_statistics
+            .lock()
+            .await
+            .len();
+        // determined before the time statistics are collected such that no time slot is missed
+        let newest = state.rate_monitor.lock().await.time_statistics.newest();
+
+        let since = published.as_ref().and_then(|p| {
+            (p.start == start && p.previous_tests == previous_tests && newest >= p.since)
+                .then_some(p.since)
+        });
+
+        let update = match since {
+            Some(since) => {
+                let statistics =
+                    indexed(serde_json::to_value(get_statistics(&state).await).unwrap_or_default());
+                let params = Params {
+                    limit: None,
+                    resolution: None,
+                };
+                let current =
+                    serde_json::to_value(current_time_statistics(&state, &params, since).await)
+                        .unwrap_or_default();
+
+                Update::Newest {
+                    statistics,
+                    current,
+                }
+            }
+            None => Update::Snapshot(statistics_snapshot(&state).await),
+        };
+
+        let is_published = {
+            let mut snapshot = state.events.snapshot.lock().unwrap();
+
+            // `None` if the update cannot be applied to the snapshot
+            let patch = match (snapshot.as_mut(), update) {
+                (
+                    Some(last),
+                    Update::Newest {
+                        statistics,
+                        current,
+                    },
+                ) => {
+                    let mut patch = Map::new();
+
+                    if let Some(p) = merge_patch(&last["statistics"], &statistics) {
+                        patch.insert("statistics".to_owned(), p);
+                    }
+                    if let Some(p) = update_patch(&last["time_statistics"]["0"], &current) {
+                        patch.insert("time_statistics".to_owned(), serde_json::json!({"0": p}));
+                    }
+
+                    let patch = Value::Object(patch);
+                    apply_patch(last, &patch);
+
+                    Some((!patch.as_object().unwrap().is_empty()).then_some(patch))
+                }
+                (last, Update::Snapshot(new)) => {
+                    let patch = match last {
+                        Some(last) => merge_patch(last, &new),
+                        None => Some(new.clone()),
+                    };
+                    *snapshot = Some(new);
+
+                    Some(patch)
+                }
+                // the snapshot has been dropped in the meantime
+                (None, Update::Newest { .. }) => None,
+            };
+
+            if let Some(Some(patch)) = &patch {
+                state.events.emit(StatisticsEvent::Update {
+                    patch: patch.clone(),
+                });
+            }
+
+            patch.is_some()
+        };
+
+        if !is_published {
+            published = None;
+            continue;
+        }
+
+        published = Some(Published {
+            start,
+            previous_tests,
+            since: newest,
+        });
+    }
+}
+
+/// Collected statistics of an update
+enum Update {
+    /// Statistics of all tests and the newest time slots of the time statistics of the current test
+    Newest { statistics: Value, current: Value },
+    /// Statistics and time statistics of all tests
+    Snapshot(Value),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::docs::traffic_gen::EXAMPLE_POST_2_REQUEST;
+    use crate::core::backend::mock::tests::{call, mock_state, request};
+    use crate::core::backend::SwitchBackend;
+    use axum::http::{Method, StatusCode};
+    use serde_json::json;
+
+    #[test]
+    fn merge_patch_only_contains_changes() {
+        let old = json!({"0": {"tx_rate_l1": {"1": {"0": 10.0}, "2": {"0": 5.0}}, "elapsed_time": 1, "name": "A"}});
+        let new = json!({"0": {"tx_rate_l1": {"1": {"0": 12.0}, "2": {"0": 5.0}}, "elapsed_time": 1}, "1": {"elapsed_time": 3}});
+
+        assert_eq!(
+            merge_patch(&old, &new),
+            Some(
+                json!({"0": {"tx_rate_l1": {"1": {"0": 12.0}}, "name": null}, "1": {"elapsed_time": 3}})
+            )
+        );
+        assert_eq!(merge_patch(&new, &new), None);
+    }
+
+    #[test]
+    fn update_patch_keeps_missing_keys() {
+        let mut old = json!({"tx_rate_l1": {"1": {"0": 10.0, "0.5": 11.0}}, "name": "A"});
+        let new = json!({"tx_rate_l1": {"1": {"0.5": 12.0, "1": 13.0}}});
+
+        let patch = update_patch(&old, &new).unwrap();
+        assert_eq!(
+            patch,
+            json!({"tx_rate_l1": {"1": {"0.5": 12.0, "1": 13.0}}})
+        );
+
+        apply_patch(&mut old, &patch);
+        assert_eq!(
+            old,
+            json!({"tx_rate_l1": {"1": {"0": 10.0, "0.5": 12.0, "1": 13.0}}, "name": "A"})
+        );
+    }
+
+    #[tokio::test]
+    async fn statistics_stream_reports_state_transitions() {
+        use futures_util::StreamExt;
+
+        let (_, state) = mock_state().await;
+
+        let response = request(&state, Method::GET, "/statistics/stream", String::new()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let mut body = response.into_body().into_data_stream();
+        let mut next_event = async || {
+            let chunk = body.next().await.unwrap().unwrap();
+            String::from_utf8(chunk.to_vec()).unwrap()
+        };
+
+        let snapshot = next_event().await;
+        assert!(snapshot.starts_with("event: snapshot\n"));
+        assert!(snapshot.contains("\"time_statistics\""));
+
+        let (status, _) = call(
+            &state,
+            Method::POST,
+            "/trafficgen",
+            serde_json::to_string(&*EXAMPLE_POST_2_REQUEST).unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, _) = call(&state, Method::DELETE, "/trafficgen", String::new()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // updates of the statistics may be interleaved with the state transitions
+        let mut transitions = vec![];
+
+        while transitions.len() < 2 {
+            let event = next_event().await;
+
+            if !event.starts_with("event: update\n") {
+                transitions.push(event.lines().next().unwrap().to_owned());
+            }
+        }
+
+        assert_eq!(transitions, vec!["event: started", "event: stopped"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn updates_only_contain_the_newest_time_slots() {
+        let (switch, state) = mock_state().await;
+
+        let (status, _) = call(
+            &state,
+            Method::POST,
+            "/trafficgen",
+            serde_json::to_string(&*EXAMPLE_POST_2_REQUEST).unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let port = switch.dev_port(1, 0).unwrap();
+        let insert = async |time: u32| {
+            state
+                .rate_monitor
+                .lock()
+                .await
+                .time_statistics
+                .tx_rate_l1
+                .entry(port)
+                .or_default()
+                .insert(ElapsedTime(time), time as f64);
+        };
+
+        insert(0).await;
+        insert(1000).await;
+
+        let (mut receiver, _) = state.events.subscribe();
+        tokio::spawn(publish_statistics(Arc::clone(&state)));
+
+        let mut next_update = async || loop {
+            if let StatisticsEvent::Update { patch } = receiver.recv().await.unwrap() {
+                return patch;
+            }
+        };
+
+        // the first update contains the whole snapshot
+        let patch = next_update().await;
+        assert_eq!(
+            patch["time_statistics"]["0"]["tx_rate_l1"]["1"]["0"],
+            json!({"0": 0.0, "1": 1000.0})
+        );
+
+        insert(2000).await;
+
+        let patch = next_update().await;
+        assert_eq!(
+            patch["time_statistics"],
+            json!({"0": {"tx_rate_l1": {"1": {"0": {"2": 2000.0}}}}})
+        );
+
+        // late subscribers start with the snapshot that the updates are based on
+        let (_, snapshot) = state.events.subscribe();
+        assert_eq!(
+            snapshot.unwrap()["time_statistics"]["0"]["tx_rate_l1"]["1"]["0"],
+            json!({"0": 0.0, "1": 1000.0, "2": 2000.0})
+        );
+    }
+}
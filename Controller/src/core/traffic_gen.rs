@@ -22,6 +22,8 @@ use std::cmp;
 use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 
+use crate::core::backend::mock::SwitchOperation;
+use crate::core::backend::{MockSwitch, SwitchBackend};
 use crate::core::multicast::delete_simple_multicast_group;
 use crate::core::patterns::build_pattern_config_entry;
 use crate::core::traffic_gen_core::event::TrafficGenEvent;
@@ -32,16 +34,17 @@ use crate::{AppState, PortMapping};
 use log::info;
 use macaddr::MacAddr;
 use rbfrt::error::RBFRTError;
+use rbfrt::table;
 use rbfrt::table::{MatchValue, Request};
-use rbfrt::{table, SwitchConnection};
 
 use crate::core::traffic_gen_core::const_definitions::*;
 use crate::core::traffic_gen_core::helper::{
-    calculate_overhead, create_packet, get_num_pipes, mpps_to_gbps,
+    calculate_overhead, calculate_stream_send_behaviour, create_packet, get_num_pipes, mpps_to_gbps,
 };
-use crate::core::traffic_gen_core::optimization::calculate_send_behaviour;
+use crate::core::traffic_gen_core::optimization::{calculate_send_behaviour, SendBehaviour};
 use crate::core::traffic_gen_core::types::*;
 
+use super::sla::SlaConfig;
 use super::statistics::HistogramConfig;
 
 /// A Traffic Generator object.
@@ -80,6 +83,14 @@ pub struct TrafficGen {
     pub(crate) iat_histogram_config: HashMap<String, HashMap<String, HistogramConfig>>,
     /// Name of the current test
     pub(crate) name: Option<String>,
+    /// Acceptance criteria of the current test
+    pub(crate) sla: Option<SlaConfig>,
+    /// Resolution of the time statistics of the current test in ms
+    pub(crate) time_resolution: Option<u32>,
+    /// Warm-up duration of the current test in seconds
+    pub(crate) warmup: Option<u32>,
+    /// Cool-down duration of the current test in seconds
+    pub(crate) cooldown: Option<u32>,
 }
 
 impl TrafficGen {
@@ -97,6 +108,28 @@ impl TrafficGen {
             rtt_histogram_config: HashMap::new(),
             iat_histogram_config: HashMap::new(),
             name: None,
+            sla: None,
+            time_resolution: None,
+            warmup: None,
+            cooldown: None,
+        }
+    }
+
+    /// Returns the settings of the current traffic generation as received by the REST API.
+    pub fn traffic_gen_data(&self) -> TrafficGenData {
+        TrafficGenData {
+            mode: self.mode,
+            stream_settings: self.stream_settings.clone(),
+            streams: self.streams.clone(),
+            port_tx_rx_mapping: self.port_mapping.clone(),
+            duration: self.duration,
+            rtt_histogram_config: Some(self.rtt_histogram_config.clone()),
+            iat_histogram_config: Some(self.iat_histogram_config.clone()),
+            name: self.name.clone(),
+            sla: self.sla.clone(),
+            time_resolution: self.time_resolution,
+            warmup: self.warmup,
+            cooldown: self.cooldown,
         }
     }
 
@@ -117,7 +150,7 @@ impl TrafficGen {
     /// Returns a mapping between an index and the corresponding (port, app_id)
     pub async fn init_monitoring_packet(
         &mut self,
-        switch: &SwitchConnection,
+        switch: &dyn SwitchBackend,
         port_mapping: &HashMap<u32, PortMapping>,
     ) -> Result<HashMap<u32, MonitoringMapping>, RBFRTError> {
         // activate traffic gen capabilities on internal ports
@@ -230,7 +263,7 @@ impl TrafficGen {
     /// * `port_mapping`: Mapping of front panel port to TX / RX recirculation port
     async fn configure_monitoring_path(
         &mut self,
-        switch: &SwitchConnection,
+        switch: &dyn SwitchBackend,
         port_mapping: &HashMap<u32, PortMapping>,
     ) -> Result<HashMap<u32, MonitoringMapping>, RBFRTError> {
         // first clear all related tables
@@ -239,11 +272,13 @@ impl TrafficGen {
                 MONITORING_INIT_TABLE,
                 MONITORING_FORWARD_TABLE,
                 MONITORING_EGRESS_TABLE,
+                MONITORING_INGRESS_TABLE,
+                MONITORING_INGRESS_PORT_TABLE,
             ])
             .await?;
 
         // create a mapping between index and (port, app id)
-        // used to monitor L2 rates of individual streams
+        // used to monitor L2 rates, packet loss, out of order packets, and RTTs of individual streams
         let mut index = 1u32;
         let mut return_mapping = HashMap::new();
         let mut reverse_mapping = HashMap::new();
@@ -285,6 +320,7 @@ impl TrafficGen {
         let mut init_requests = vec![];
         let mut forward_requests = vec![];
         let mut egress_monitoring_requests = vec![];
+        let mut ingress_monitoring_requests = vec![];
 
         forward_requests.push(
             table::Request::new(MONITORING_FORWARD_TABLE)
@@ -319,6 +355,21 @@ impl TrafficGen {
 
             init_requests.push(req);
 
+            // received P4TG traffic of an unknown app id is tracked at the first index of the TX recirculation port
+            // no P4TG traffic is received there and the index is reported by the first monitoring packet of the port
+            let req = table::Request::new(MONITORING_INGRESS_PORT_TABLE)
+                .match_key(
+                    "ig_intr_md.ingress_port",
+                    MatchValue::exact(mapping.rx_recirculation),
+                )
+                .action("ingress.p4tg.set_stream_index")
+                .action_data(
+                    "idx",
+                    *reverse_mapping.get(&(mapping.tx_recirculation, 1)).unwrap(),
+                );
+
+            ingress_monitoring_requests.push(req);
+
             // configure forwarding in ingress
             let app_ids = if self.is_tofino2 { 1..16 } else { 1..8 };
             for app_id in app_ids {
@@ -409,12 +460,31 @@ impl TrafficGen {
                     );
 
                 egress_monitoring_requests.push(req);
+
+                // create mapping for received P4TG traffic in ingress to the index
+                // at which its sequence numbers, lost and out of order packets are tracked
+                let req = table::Request::new(MONITORING_INGRESS_TABLE)
+                    .match_key(
+                        "ig_intr_md.ingress_port",
+                        MatchValue::exact(mapping.rx_recirculation),
+                    )
+                    .match_key("hdr.path.app_id", MatchValue::exact(app_id))
+                    .action("ingress.p4tg.set_stream_index")
+                    .action_data(
+                        "idx",
+                        *reverse_mapping
+                            .get(&(mapping.rx_recirculation, app_id))
+                            .unwrap(),
+                    );
+
+                ingress_monitoring_requests.push(req);
             }
         }
 
         // write table entries
         init_requests.append(&mut forward_requests);
         init_requests.append(&mut egress_monitoring_requests);
+        init_requests.append(&mut ingress_monitoring_requests);
 
         switch.write_table_entries(init_requests).await?;
 
@@ -430,7 +500,7 @@ impl TrafficGen {
     /// * `port_mapping`: Mapping of front panel port to TX / RX recirculation port
     pub async fn configure_default_forwarding_path(
         &self,
-        switch: &SwitchConnection,
+        switch: &dyn SwitchBackend,
         port_mapping: &HashMap<u32, PortMapping>,
     ) -> Result<(), RBFRTError> {
         // clear previous state
@@ -475,7 +545,7 @@ impl TrafficGen {
     /// * `port_mapping`: Mapping of front panel port to TX / RX recirculation port
     pub async fn configure_egress_rules(
         &self,
-        switch: &SwitchConnection,
+        switch: &dyn SwitchBackend,
         port_mapping: &HashMap<u32, PortMapping>,
     ) -> Result<(), RBFRTError> {
         let mut is_egress_requests = vec![];
@@ -512,7 +582,7 @@ impl TrafficGen {
     }
 
     /// Deactivates all traffic gen applications except for the monitoring.
-    pub async fn stop(&mut self, switch: &SwitchConnection) -> Result<(), RBFRTError> {
+    pub async fn stop(&mut self, switch: &dyn SwitchBackend) -> Result<(), RBFRTError> {
         self.deactivate_traffic_gen_applications(switch).await?;
         self.reset_tables(switch).await?;
         self.running = false;
@@ -523,7 +593,7 @@ impl TrafficGen {
     /// Deactivates all traffic gen applications except for the monitoring.
     async fn deactivate_traffic_gen_applications(
         &self,
-        switch: &SwitchConnection,
+        switch: &dyn SwitchBackend,
     ) -> Result<(), RBFRTError> {
         // app id 0 is monitoring packet
         // keep monitoring running
@@ -559,13 +629,13 @@ impl TrafficGen {
     /// * `packets`: List of packets that should be generated. Index is the application id.
     pub async fn activate_traffic_gen_applications(
         &self,
-        switch: &SwitchConnection,
+        switch: &dyn SwitchBackend,
         packets: &HashMap<u8, StreamPacket>,
     ) -> Result<(), RBFRTError> {
         let update_requests: Result<Vec<Request>, P4TGError> = packets.values().map(|packet| {
                 if packet.n_packets == 0 {
-                    // The ILP did not find a solution for the configured parameters
-                    Err(P4TGError::Error { message: format!("The ILP did not find a valid solution for packet generation of app ID {:}. Try a different rate.", packet.app_id) })
+                    // No sending behaviour was found for the configured parameters
+                    Err(P4TGError::Error { message: format!("No valid sending behaviour for packet generation of app ID {:}. Try a different rate.", packet.app_id) })
                 } else {
                     let batch_factor: u32 = if packet.batches {BATCH_FACTOR} else {1};
                     Ok(table::Request::new(if self.is_tofino2 {APP_CFG_TF2} else {APP_CFG})
@@ -614,7 +684,7 @@ impl TrafficGen {
         stream_settings: Vec<StreamSetting>,
         tx_rx_mapping: &HashMap<String, u32>,
     ) -> Result<Vec<Stream>, RBFRTError> {
-        let switch = &state.switch;
+        let switch = state.switch.as_ref();
         let port_mapping = &state.port_mapping;
 
         // First stop possible existing generation
@@ -682,6 +752,155 @@ impl TrafficGen {
             .on_start(switch, &mode)
             .await?;
 
+        let (active_streams, _) = self
+            .configure_traffic_generation(
+                switch,
+                port_mapping,
+                streams,
+                mode,
+                &stream_settings,
+                tx_rx_mapping,
+            )
+            .await?;
+
+        self.running = true;
+
+        Ok(active_streams)
+    }
+
+    /// This method is called when a test stops and stops the traffic generation.
+    /// The on_stop routine is called on all relevant parts.
+    ///
+    /// # Arguments
+    ///
+    /// * `state`: App state that contains various other objects that configure parts of the switch
+    pub async fn stop_traffic_generation(&mut self, state: &AppState) -> Result<(), RBFRTError> {
+        let switch = state.switch.as_ref();
+
+        self.stop(switch).await?;
+
+        state
+            .frame_size_monitor
+            .lock()
+            .await
+            .on_stop(switch)
+            .await?;
+        state
+            .frame_type_monitor
+            .lock()
+            .await
+            .on_stop(switch)
+            .await?;
+        state.rate_monitor.lock().await.on_stop(switch).await?;
+        state
+            .rtt_histogram_monitor
+            .lock()
+            .await
+            .on_stop(switch)
+            .await?;
+        state
+            .iat_histogram_monitor
+            .lock()
+            .await
+            .on_stop(switch)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Computes the data plane configuration of a traffic generation without touching the switch.
+    /// The configuration is applied to an in-memory [MockSwitch] and the recorded operations are returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `port_mapping`: Mapping of front panel port to TX / RX recirculation port
+    /// * `streams`: List of streams that should be configured
+    /// * `mode`: Generation mode that should be used.
+    /// * `stream_settings`: List of stream settings that should be applied
+    /// * `tx_rx_mapping`: Mapping of TX port to expected RX port from the REST API. This is only relevant for the ANALYZE mode.
+    pub async fn plan_traffic_generation(
+        &self,
+        port_mapping: &HashMap<u32, PortMapping>,
+        streams: Vec<Stream>,
+        mode: GenerationMode,
+        stream_settings: &Vec<StreamSetting>,
+        tx_rx_mapping: &HashMap<String, u32>,
+    ) -> Result<TrafficGenPlan, RBFRTError> {
+        let switch = MockSwitch::new(0, self.num_pipes, self.is_tofino2);
+
+        let (streams, packet_mapping) = self
+            .configure_traffic_generation(
+                &switch,
+                port_mapping,
+                streams,
+                mode,
+                stream_settings,
+                tx_rx_mapping,
+            )
+            .await?;
+
+        let mut cleared_tables = vec![];
+        let mut entries = vec![];
+
+        for operation in switch.operations() {
+            match operation {
+                SwitchOperation::Clear(name) => cleared_tables.push(name),
+                SwitchOperation::Write(req) => {
+                    entries.push(planned_table_entry(EntryOperation::Write, &req))
+                }
+                SwitchOperation::Update(req) => {
+                    entries.push(planned_table_entry(EntryOperation::Update, &req))
+                }
+                // deletions of the previous multicast groups and operations are not part of the plan
+                SwitchOperation::Delete(_) | SwitchOperation::Execute(_) => {}
+            }
+        }
+
+        // sort entries to get a stable output that can be compared between configurations
+        entries.sort_by(|a, b| {
+            (&a.table, &a.match_keys, a.default_entry).cmp(&(
+                &b.table,
+                &b.match_keys,
+                b.default_entry,
+            ))
+        });
+
+        Ok(TrafficGenPlan {
+            streams,
+            buffer_offsets: packet_mapping
+                .values()
+                .filter_map(|p| Some((p.app_id, p.buffer_offset?)))
+                .collect(),
+            cleared_tables,
+            entries,
+        })
+    }
+
+    /// Configures the traffic generation related tables and multicast groups.
+    /// This includes the calculation of the sending behaviour of each stream, the packet buffer,
+    /// the forwarding and header rewrite rules, and the activation of the traffic gen applications.
+    ///
+    /// # Arguments
+    ///
+    /// * `port_mapping`: Mapping of front panel port to TX / RX recirculation port
+    /// * `streams`: List of streams that should be configured
+    /// * `mode`: Generation mode that should be used.
+    /// * `stream_settings`: List of stream settings that should be applied
+    /// * `tx_rx_mapping`: Mapping of TX port to expected RX port from the REST API. This is only relevant for the ANALYZE mode.
+    ///
+    /// # Returns
+    ///
+    /// Returns the streams with the calculated sending behaviour and a mapping of app_id to the
+    /// configured stream packet. The mapping is empty in [GenerationMode::Analyze].
+    async fn configure_traffic_generation(
+        &self,
+        switch: &dyn SwitchBackend,
+        port_mapping: &HashMap<u32, PortMapping>,
+        streams: Vec<Stream>,
+        mode: GenerationMode,
+        stream_settings: &Vec<StreamSetting>,
+        tx_rx_mapping: &HashMap<String, u32>,
+    ) -> Result<(Vec<Stream>, HashMap<u8, StreamPacket>), RBFRTError> {
         // configure tg mode
         self.configure_traffic_gen_mode_table(switch, &mode).await?;
 
@@ -703,47 +922,12 @@ impl TrafficGen {
 
         info!("Total Rate {total_rate} Gbps.");
 
-        // calculate sending behaviour via ILP optimization
+        // calculate sending behaviour via rational approximation
         // further adds number of packets per time to the stream
-        let mut active_streams: Vec<Stream> = streams.into_iter().map(|mut s| {
-            let encapsulation_overhead = calculate_overhead(&s);
-
-            // preamble + inter frame gap (IFG) = 20 bytes
-            let encapsulation_overhead = encapsulation_overhead + 20;
-
-            // For minimal sized IPv6 frames, the size is 73 bytes + 4 FCS
-            if s.ip_version == Some(6) && s.frame_size == 64 {
-                // TODO do we need FCS + IFG here again?
-                s.frame_size = 73 + 4;
-            }
-
-            // traffic rate has MPPS semantics
-            // rewrite traffic rate to reflect MPPS in Gbps
-            if s.unit == Some(GenerationUnit::Mpps) || mode == GenerationMode::Mpps {
-                // recompute "correct" traffic rate in Gbps
-                s.traffic_rate = mpps_to_gbps(s.frame_size + encapsulation_overhead, s.traffic_rate);
-            }
-
-            let num_pipes = get_num_pipes(&s, self.num_pipes);
-
-            // call solver
-            let (n_packets, mut timeout) = calculate_send_behaviour(s.frame_size + encapsulation_overhead, s.traffic_rate / num_pipes as f32, s.burst);
-            let rate = num_pipes as f64 * ((n_packets as u32) * (s.frame_size + encapsulation_overhead) * 8) as f64 / timeout as f64;
-            let rate_accuracy = 100f32 * (1f32 - ((s.traffic_rate - (rate as f32)).abs() / s.traffic_rate));
-
-            info!("Calculated traffic generation for stream #{}. #{} packets per {} ns. #Pipes: {}. Rate: {} Gbps. Accuracy: {:.2}%.", s.app_id, n_packets, timeout, num_pipes, rate, rate_accuracy);
-
-            // More bursty traffic desired. Activate batch mode
-            timeout = if s.batches.is_some_and(|b| b && s.burst != 1) {timeout * BATCH_FACTOR} else {timeout};
-
-            // add calculated values to the stream
-            s.n_packets = Some(n_packets);
-            s.timeout = Some(timeout);
-            s.generation_accuracy = Some(rate_accuracy);
-            s.n_pipes = Some(num_pipes as u8);
-
-            s
-        }).collect();
+        let mut active_streams: Vec<Stream> = streams
+            .into_iter()
+            .map(|s| calculate_stream_send_behaviour(s, mode, self.num_pipes))
+            .collect::<Result<_, _>>()?;
 
         // poisson mode
         // send with full capacity and then randomly drop in data plane to get geometric IAT distribution
@@ -756,7 +940,11 @@ impl TrafficGen {
 
             let num_pipes: u32 = get_num_pipes(stream, self.num_pipes);
 
-            let (n_packets, mut timeout) = calculate_send_behaviour(
+            let SendBehaviour {
+                n_packets,
+                mut timeout,
+                ..
+            } = calculate_send_behaviour(
                 stream.frame_size + encap_overhead,
                 if self.is_tofino2 {
                     TG_MAX_RATE_TF2
@@ -764,7 +952,7 @@ impl TrafficGen {
                     TG_MAX_RATE
                 } / num_pipes as f32,
                 25,
-            );
+            )?;
 
             // More bursty traffic desired. Activate batch mode
             timeout = if stream.batches.is_some_and(|b| b && stream.burst != 1) {
@@ -791,7 +979,7 @@ impl TrafficGen {
         // this mapping will contain StreamId -> Set of egress ports
         let mut stream_to_ports: HashMap<u8, HashSet<u32>> = HashMap::new();
 
-        for stream in &stream_settings {
+        for stream in stream_settings {
             let out_port = port_mapping.get(&stream.port).unwrap().tx_recirculation;
             stream_to_ports
                 .entry(stream.stream_id)
@@ -874,7 +1062,7 @@ impl TrafficGen {
                         .unwrap_or(&total_frame_size.saturating_sub(20))
                         + 6, // Size of the internal packet generation header
                     num_pipes as f64,
-                    state.tofino2,
+                    self.is_tofino2,
                 );
 
                 if pattern_entries.len() + entries.len() > max_pattern_table_entries {
@@ -914,22 +1102,22 @@ impl TrafficGen {
             self.configure_packet_header_rewrite(
                 switch,
                 &active_streams,
-                &stream_settings,
+                stream_settings,
                 port_mapping,
             )
             .await?;
             self.activate_traffic_gen_applications(switch, &packet_mapping)
                 .await?;
+
+            Ok((active_streams, packet_mapping))
         } else {
             // configure analyze forwarding rules
             // this installs the rules RX recirc -> TX recirc s.t. packets are forwarded
             self.configure_analyze_forwarding(switch, port_mapping, tx_rx_mapping)
                 .await?;
-        }
-
-        self.running = true;
 
-        Ok(active_streams)
+            Ok((active_streams, HashMap::new()))
+        }
     }
 
     /// This method configures the forwarding rules in the case of [GenerationMode::Analyze].
@@ -941,7 +1129,7 @@ impl TrafficGen {
     /// * `port_mapping`: Mapping of front panel port to TX / RX recirculation port
     async fn configure_analyze_forwarding(
         &self,
-        switch: &SwitchConnection,
+        switch: &dyn SwitchBackend,
         port_mapping: &HashMap<u32, PortMapping>,
         tx_rx_mapping: &HashMap<String, u32>,
     ) -> Result<(), RBFRTError> {
@@ -979,7 +1167,7 @@ impl TrafficGen {
     /// For [GenerationMode::Poisson], it also calculates the drop probability.
     async fn configure_traffic_gen_forwarding_table(
         &self,
-        switch: &SwitchConnection,
+        switch: &dyn SwitchBackend,
         streams: &Vec<Stream>,
         mode: GenerationMode,
     ) -> Result<(), RBFRTError> {
@@ -1043,7 +1231,7 @@ impl TrafficGen {
     /// * `port_mapping`: Mapping of front panel port to TX / RX recirculation port
     async fn configure_packet_header_rewrite(
         &self,
-        switch: &SwitchConnection,
+        switch: &dyn SwitchBackend,
         streams: &Vec<Stream>,
         stream_settings: &Vec<StreamSetting>,
         port_mapping: &HashMap<u32, PortMapping>,
@@ -1313,7 +1501,7 @@ impl TrafficGen {
     /// * `packets`: List of packets that should be configured.
     async fn configure_traffic_gen_table(
         &self,
-        switch: &SwitchConnection,
+        switch: &dyn SwitchBackend,
         packets: Vec<StreamPacket>,
     ) -> Result<HashMap<u8, StreamPacket>, RBFRTError> {
         let mut requests = vec![];
@@ -1356,7 +1544,7 @@ impl TrafficGen {
     /// that is used to detect the generation mode
     async fn configure_traffic_gen_mode_table(
         &self,
-        switch: &SwitchConnection,
+        switch: &dyn SwitchBackend,
         mode: &GenerationMode,
     ) -> Result<(), RBFRTError> {
         let req = Request::new(TRAFFIC_GEN_MODE)
@@ -1370,7 +1558,7 @@ impl TrafficGen {
     }
 
     /// Clears various tables that are refilled during traffic gen setup
-    async fn reset_tables(&self, switch: &SwitchConnection) -> Result<(), RBFRTError> {
+    async fn reset_tables(&self, switch: &dyn SwitchBackend) -> Result<(), RBFRTError> {
         if self.is_tofino2 {
             switch
                 .clear_tables(vec![
@@ -1407,3 +1595,58 @@ impl TrafficGen {
         Ok(())
     }
 }
+
+/// Encodes a byte vector as hex string.
+fn to_hex(bytes: &[u8]) -> String {
+    let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    format!("0x{hex}")
+}
+
+/// Converts a table request into its serializable representation.
+fn planned_table_entry(operation: EntryOperation, req: &Request) -> PlannedTableEntry {
+    let match_keys = req
+        .get_match_keys()
+        .iter()
+        .map(|(name, value)| {
+            let value = match value {
+                MatchValue::ExactValue { bytes } => to_hex(bytes),
+                MatchValue::RangeValue {
+                    lower_bytes,
+                    higher_bytes,
+                } => format!("{}..{}", to_hex(lower_bytes), to_hex(higher_bytes)),
+                MatchValue::LPM {
+                    bytes,
+                    prefix_length,
+                } => format!("{}/{}", to_hex(bytes), prefix_length),
+                MatchValue::Ternary { value, mask } => {
+                    format!("{}&&&{}", to_hex(value), to_hex(mask))
+                }
+            };
+
+            (name.clone(), value)
+        })
+        .collect();
+
+    PlannedTableEntry {
+        table: req.table_name.clone(),
+        operation,
+        match_keys,
+        action: req.has_action().then(|| req.get_action_name().to_owned()),
+        action_data: req
+            .get_action_data()
+            .iter()
+            .map(|a| (a.get_key().to_owned(), to_hex(a.get_data())))
+            .collect(),
+        action_data_repeated: req
+            .get_action_data_repeated()
+            .iter()
+            .map(|a| {
+                (
+                    a.get_key().to_owned(),
+                    a.get_data().iter().map(|d| to_hex(d)).collect(),
+                )
+            })
+            .collect(),
+        default_entry: req.is_default(),
+    }
+}
@@ -42,14 +42,14 @@ pub const P4TG_SOURCE_PORT: u16 = 50081;
 /// This is used in the data plane to detect P4TG traffic.
 pub const P4TG_DST_PORT: u16 = 50083;
 
-/// Max time for the ILP solver that calculates the sending behaviour.
-/// See [this method](TrafficGen::calculate_send_behaviour) for the solver.
-pub const SOLVER_TIME_LIMIT_IN_SECONDS: f64 = 2f64;
-
 /// Generation time in ns for the monitoring packet.
 /// Each [MONITORING_PACKET_INTERVAL] ns, a monitoring packet is created.
 pub const MONITORING_PACKET_INTERVAL: u32 = 500000000;
 
+/// Default resolution of the time statistics in ms.
+/// The resolution is limited by the [MONITORING_PACKET_INTERVAL].
+pub const DEFAULT_TIME_RESOLUTION: u32 = 1000;
+
 /// Multicast group ID for monitoring packet
 pub const MONITORING_PACKET_MID: u16 = 1000;
 
@@ -64,6 +64,14 @@ pub const MONITORING_FORWARD_TABLE: &str = "ingress.p4tg.monitor_forward";
 /// Triggers monitoring in egress for individual streams
 pub const MONITORING_EGRESS_TABLE: &str = "egress.monitor_stream";
 
+/// Maps received P4TG traffic in ingress to the index of its (port, app id).
+/// Sequence numbers, lost and out of order packets are tracked per index.
+pub const MONITORING_INGRESS_TABLE: &str = "ingress.p4tg.monitor_stream";
+
+/// Maps received P4TG traffic of an unknown app id in ingress to the fallback index of its RX port.
+/// The fallback index of an RX port is the first index of its TX recirculation port.
+pub const MONITORING_INGRESS_PORT_TABLE: &str = "ingress.p4tg.monitor_port";
+
 /// Indicates the current generation type
 pub const TRAFFIC_GEN_MODE: &str = "ingress.tg_mode";
 
@@ -17,8 +17,8 @@
  * Steffen Lindner (steffen.lindner@uni-tuebingen.de)
  */
 
+use crate::core::backend::SwitchBackend;
 use rbfrt::error::RBFRTError;
-use rbfrt::SwitchConnection;
 
 use crate::core::traffic_gen_core::types::GenerationMode;
 use async_trait::async_trait;
@@ -27,9 +27,9 @@ use async_trait::async_trait;
 pub trait TrafficGenEvent {
     async fn on_start(
         &mut self,
-        switch: &SwitchConnection,
+        switch: &dyn SwitchBackend,
         mode: &GenerationMode,
     ) -> Result<(), RBFRTError>;
-    async fn on_stop(&self, switch: &SwitchConnection) -> Result<(), RBFRTError>;
-    async fn on_reset(&mut self, switch: &SwitchConnection) -> Result<(), RBFRTError>;
+    async fn on_stop(&self, switch: &dyn SwitchBackend) -> Result<(), RBFRTError>;
+    async fn on_reset(&mut self, switch: &dyn SwitchBackend) -> Result<(), RBFRTError>;
 }
@@ -0,0 +1,246 @@
+/* Copyright 2022-present University of Tuebingen, Chair of Communication Networks
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/*
+ * Steffen Lindner (steffen.lindner@uni-tuebingen.de)
+ */
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::core::traffic_gen_core::const_definitions::{
+    BATCH_FACTOR, MAX_BUFFER_SIZE, TG_MAX_RATE, TG_MAX_RATE_TF2,
+};
+use crate::core::traffic_gen_core::helper::{
+    calculate_overhead, calculate_stream_send_behaviour, create_packet, get_num_pipes,
+    normalize_stream,
+};
+use crate::core::traffic_gen_core::types::*;
+
+/// Analyzes if the traffic generation in `payload` can be generated as configured.
+/// The payload is expected to be validated.
+///
+/// # Arguments
+///
+/// * `payload`: Traffic generation that should be analyzed
+/// * `port_speeds`: Configured speed (Gbps) of each front panel (port, channel)
+/// * `is_tofino2`: Indicates if the traffic generator runs on a Tofino 2
+/// * `max_pipes`: Number of pipes of the internal traffic generator
+pub fn analyze_feasibility(
+    payload: &TrafficGenData,
+    port_speeds: &HashMap<(u32, u8), f64>,
+    is_tofino2: bool,
+    max_pipes: u32,
+) -> FeasibilityReport {
+    let max_rate = if is_tofino2 {
+        TG_MAX_RATE_TF2
+    } else {
+        TG_MAX_RATE
+    } as f64;
+
+    let mut report = FeasibilityReport {
+        feasible: true,
+        total_rate: 0f64,
+        max_rate,
+        streams: vec![],
+        ports: vec![],
+        buffer: BufferUsage {
+            used: 0,
+            available: MAX_BUFFER_SIZE,
+        },
+        issues: vec![],
+    };
+
+    // no traffic is generated in analyze mode
+    if payload.mode == GenerationMode::Analyze {
+        return report;
+    }
+
+    let active_stream_settings: Vec<&StreamSetting> = payload
+        .stream_settings
+        .iter()
+        .filter(|s| s.active)
+        .collect();
+    let active_streams: Vec<&Stream> = payload
+        .streams
+        .iter()
+        .filter(|s| {
+            active_stream_settings
+                .iter()
+                .any(|setting| setting.stream_id == s.stream_id)
+        })
+        .collect();
+
+    // requested L1 rate of each stream id
+    let mut rates: HashMap<u8, f64> = HashMap::new();
+
+    for stream in &active_streams {
+        let (analysis, packet) =
+            match calculate_stream_send_behaviour((*stream).clone(), payload.mode, max_pipes) {
+                Ok(s) => {
+                    let l1_frame_size = s.frame_size + calculate_overhead(&s) + 20;
+                    let n_pipes = s.n_pipes.unwrap_or(1);
+                    let n_packets = s.n_packets.unwrap_or_default();
+                    let timeout = s.timeout.unwrap_or(1);
+                    let batch_factor = if s.batches.is_some_and(|b| b && s.burst != 1) {
+                        BATCH_FACTOR
+                    } else {
+                        1
+                    };
+
+                    // packets per ns on all pipes
+                    let packet_rate =
+                        (n_pipes as u32 * n_packets as u32 * batch_factor) as f64 / timeout as f64;
+
+                    let analysis = StreamFeasibility {
+                        stream_id: s.stream_id,
+                        app_id: s.app_id,
+                        l1_frame_size,
+                        traffic_rate: s.traffic_rate as f64,
+                        n_pipes,
+                        n_packets: s.n_packets,
+                        timeout: s.timeout,
+                        generated_rate: Some(packet_rate * (l1_frame_size * 8) as f64),
+                        packet_rate: Some(packet_rate * 1000f64),
+                        generation_accuracy: s.generation_accuracy,
+                        error: None,
+                    };
+
+                    (analysis, create_packet(&s, false))
+                }
+                Err(e) => {
+                    let stream = normalize_stream((*stream).clone(), payload.mode);
+                    let l1_frame_size = stream.frame_size + calculate_overhead(&stream) + 20;
+                    let message = e.to_string();
+
+                    report
+                        .issues
+                        .push(format!("Stream with ID #{}: {message}", stream.stream_id));
+
+                    let analysis = StreamFeasibility {
+                        stream_id: stream.stream_id,
+                        app_id: stream.app_id,
+                        l1_frame_size,
+                        traffic_rate: stream.traffic_rate as f64,
+                        n_pipes: get_num_pipes(&stream, max_pipes) as u8,
+                        n_packets: None,
+                        timeout: None,
+                        generated_rate: None,
+                        packet_rate: None,
+                        generation_accuracy: None,
+                        error: Some(message),
+                    };
+
+                    (analysis, create_packet(&stream, false))
+                }
+            };
+
+        // stream packets are stored 16 byte aligned in the packet buffer
+        report.buffer.used += (packet.len() as u32).next_multiple_of(16);
+        rates.insert(analysis.stream_id, analysis.traffic_rate);
+        report.streams.push(analysis);
+    }
+
+    report.total_rate = report.streams.iter().map(|s| s.traffic_rate).sum();
+
+    if report.total_rate > max_rate {
+        report.issues.push(format!(
+            "Total rate of {:.2} Gbps exceeds the maximal rate of {max_rate} Gbps of the traffic generator.",
+            report.total_rate
+        ));
+    }
+
+    // (port, channel) -> (load, stream ids)
+    let mut ports: BTreeMap<(u32, u8), (f64, Vec<u8>)> = BTreeMap::new();
+
+    for setting in &active_stream_settings {
+        if let Some(rate) = rates.get(&setting.stream_id) {
+            let entry = ports
+                .entry((setting.port, setting.channel.unwrap_or(0)))
+                .or_default();
+            entry.0 += rate;
+            entry.1.push(setting.stream_id);
+        }
+    }
+
+    for ((port, channel), (load, stream_ids)) in ports {
+        let capacity = *port_speeds.get(&(port, channel)).unwrap_or(&max_rate);
+        let oversubscribed = load > capacity;
+
+        if oversubscribed {
+            report.issues.push(format!(
+                "Port {port}/{channel} is oversubscribed. Load of {load:.2} Gbps exceeds the capacity of {capacity} Gbps."
+            ));
+        }
+
+        report.ports.push(PortFeasibility {
+            port,
+            channel,
+            capacity,
+            load,
+            utilization: 100f64 * load / capacity,
+            oversubscribed,
+            stream_ids,
+        });
+    }
+
+    if report.buffer.used > report.buffer.available {
+        report.issues.push(format!(
+            "Stream packets require {}B of the packet buffer. Maximal sum of packets size: {MAX_BUFFER_SIZE}B",
+            report.buffer.used
+        ));
+    }
+
+    report.feasible = report.issues.is_empty();
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::docs::traffic_gen::{EXAMPLE_POST_1_REQUEST, EXAMPLE_POST_2_REQUEST};
+
+    #[test]
+    fn oversubscribed_port_is_reported() {
+        let port_speeds = HashMap::from([((1, 0), 10f64)]);
+        let report = analyze_feasibility(&EXAMPLE_POST_2_REQUEST, &port_speeds, false, 2);
+
+        assert!(!report.feasible);
+        assert_eq!(report.issues.len(), 1);
+        assert!(report.ports[0].oversubscribed);
+        assert_eq!(report.ports[0].utilization, 800f64);
+
+        // the maximal rate of the traffic generator is used for unknown ports
+        let report = analyze_feasibility(&EXAMPLE_POST_2_REQUEST, &HashMap::new(), false, 2);
+        assert!(report.feasible);
+        assert_eq!(report.ports[0].capacity, TG_MAX_RATE as f64);
+    }
+
+    #[test]
+    fn mpps_streams_are_analyzed_as_l1_rate() {
+        let mut payload = EXAMPLE_POST_1_REQUEST.clone();
+        payload.streams[0].unit = Some(GenerationUnit::Mpps);
+        payload.streams[0].traffic_rate = 1f32;
+
+        let report = analyze_feasibility(&payload, &HashMap::new(), false, 2);
+        let stream = &report.streams[0];
+
+        // 1024 byte frames with 50 byte VxLAN overhead, preamble, and IFG
+        assert_eq!(stream.l1_frame_size, 1024 + 50 + 20);
+        assert!((stream.traffic_rate - 1094f64 * 8f64 / 1000f64).abs() < 1e-3);
+        assert!((stream.packet_rate.unwrap() - 1f64).abs() < 0.01);
+        assert_eq!(report.total_rate, stream.traffic_rate);
+    }
+}
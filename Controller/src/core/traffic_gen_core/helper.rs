@@ -2,13 +2,15 @@ use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use crate::core::traffic_gen_core::const_definitions::{
-    GTPU_UDP_PORT, P4TG_DST_PORT, P4TG_SOURCE_PORT, REMOVE_PORT_CHANNEL_MASK,
+    BATCH_FACTOR, GTPU_UDP_PORT, P4TG_DST_PORT, P4TG_SOURCE_PORT, REMOVE_PORT_CHANNEL_MASK,
     REMOVE_PORT_CHANNEL_MASK_TOFINO_2, VX_LAN_UDP_PORT,
 };
+use crate::core::traffic_gen_core::optimization::{calculate_send_behaviour, SendBehaviour};
 use crate::core::traffic_gen_core::types::*;
+use crate::error::P4TGError;
 use crate::{AppState, PortMapping};
 use etherparse::{IpHeader, Ipv6RawExtensionHeader, PacketBuilder};
-use log::error;
+use log::{error, info};
 use rbfrt::util::{Speed, FEC};
 
 // Create a HashMap of front_panel -> dev_port from the port_mapping
@@ -925,3 +927,73 @@ pub fn get_num_pipes(s: &Stream, max_pipes: u32) -> u32 {
         max_pipes
     }
 }
+
+/// Adjusts the frame size of minimal sized IPv6 frames of stream `s`
+/// and rewrites Mpps rates to the corresponding L1 rate in Gbps.
+pub(crate) fn normalize_stream(mut s: Stream, mode: GenerationMode) -> Stream {
+    // preamble + inter frame gap (IFG) = 20 bytes
+    let encapsulation_overhead = calculate_overhead(&s) + 20;
+
+    // For minimal sized IPv6 frames, the size is 73 bytes + 4 FCS
+    if s.ip_version == Some(6) && s.frame_size == 64 {
+        // TODO do we need FCS + IFG here again?
+        s.frame_size = 73 + 4;
+    }
+
+    // traffic rate has MPPS semantics
+    // rewrite traffic rate to reflect MPPS in Gbps
+    if s.unit == Some(GenerationUnit::Mpps) || mode == GenerationMode::Mpps {
+        // recompute "correct" traffic rate in Gbps
+        s.traffic_rate = mpps_to_gbps(s.frame_size + encapsulation_overhead, s.traffic_rate);
+    }
+
+    s
+}
+
+/// Calculates the sending behaviour of stream `s` on up to `max_pipes` pipes.
+/// Rewrites Mpps rates to the corresponding L1 rate in Gbps and adds the number of packets per timeout,
+/// the timeout, the generation accuracy, and the number of pipes to the stream.
+pub(crate) fn calculate_stream_send_behaviour(
+    s: Stream,
+    mode: GenerationMode,
+    max_pipes: u32,
+) -> Result<Stream, P4TGError> {
+    let mut s = normalize_stream(s, mode);
+
+    // preamble + inter frame gap (IFG) = 20 bytes
+    let encapsulation_overhead = calculate_overhead(&s) + 20;
+
+    let num_pipes = get_num_pipes(&s, max_pipes);
+
+    // call solver
+    let SendBehaviour {
+        n_packets,
+        mut timeout,
+        accuracy,
+    } = calculate_send_behaviour(
+        s.frame_size + encapsulation_overhead,
+        s.traffic_rate / num_pipes as f32,
+        s.burst,
+    )?;
+    let rate = num_pipes as f64
+        * ((n_packets as u32) * (s.frame_size + encapsulation_overhead) * 8) as f64
+        / timeout as f64;
+    let rate_accuracy = 100f32 * accuracy as f32;
+
+    info!("Calculated traffic generation for stream #{}. #{} packets per {} ns. #Pipes: {}. Rate: {} Gbps. Accuracy: {:.2}%.", s.app_id, n_packets, timeout, num_pipes, rate, rate_accuracy);
+
+    // More bursty traffic desired. Activate batch mode
+    timeout = if s.batches.is_some_and(|b| b && s.burst != 1) {
+        timeout * BATCH_FACTOR
+    } else {
+        timeout
+    };
+
+    // add calculated values to the stream
+    s.n_packets = Some(n_packets);
+    s.timeout = Some(timeout);
+    s.generation_accuracy = Some(rate_accuracy);
+    s.n_pipes = Some(num_pipes as u8);
+
+    Ok(s)
+}
@@ -18,6 +18,8 @@
  */
 pub mod const_definitions;
 pub mod event;
+pub mod feasibility;
 pub mod helper;
 pub mod optimization;
+pub mod pcap;
 pub mod types;
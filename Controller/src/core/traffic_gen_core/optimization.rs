@@ -17,86 +17,229 @@
  * Steffen Lindner (steffen.lindner@uni-tuebingen.de)
  */
 
-use crate::core::traffic_gen_core::const_definitions::SOLVER_TIME_LIMIT_IN_SECONDS;
-use highs::{HighsModelStatus, Sense};
-use log::warn;
+use crate::error::P4TGError;
+
+/// Relative accuracy that is targeted for the traffic rate.
+/// Determines the maximal number of packets per timeout.
+const TARGET_ACCURACY: f64 = 0.001;
+
+/// Minimal timeout in ns in rate mode, i.e., if a burst of more than one packet is allowed.
+const MIN_TIMEOUT: u32 = 30;
+
+/// Sending behaviour of a stream, i.e., `n_packets` are sent every `timeout` ns.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SendBehaviour {
+    pub n_packets: u16,
+    pub timeout: u32,
+    /// Achieved rate accuracy, i.e., 1 - |achieved rate - traffic rate| / traffic rate
+    pub accuracy: f64,
+}
 
 /// Calculates the number of packets (n) that should be sent per timeout ns limited by `max_burst`.
 /// Optimized for traffic rate accuracy and burst minimization.
-/// Uses highs ILP solver.
 ///
-/// It solves the ILP
+/// The inter-arrival time `frame_size * 8 / traffic_rate` is approximated by the fraction
+/// timeout / n with n <= max(d / 0.001 + 1, 30), where d is the relative fractional part of the inter-arrival time.
+/// The best fraction is found with continued fractions and scaled up to at least 2 packets and 30 ns.
+/// If `max_burst` is 1, a single packet is sent per timeout.
 ///
-/// min c + n
-/// s.t.
-/// 0 <= c - timeout * `traffic_rate` + (n * `frame_size` * 8) <= 0
-///
-/// Returns (number of packets, timeout)
-pub fn calculate_send_behaviour(frame_size: u32, traffic_rate: f32, max_burst: u16) -> (u16, u32) {
-    let mut problem = highs::RowProblem::default();
-
-    let accuracy = 0.001;
-    let real_iat = frame_size as f32 * 8f32 / traffic_rate;
-    let d = (real_iat - real_iat.floor()) / real_iat;
+/// Returns an error if the traffic rate is not positive or too low for the 32 bit timeout.
+pub fn calculate_send_behaviour(
+    frame_size: u32,
+    traffic_rate: f32,
+    max_burst: u16,
+) -> Result<SendBehaviour, P4TGError> {
+    let traffic_rate = traffic_rate as f64;
+    let bits = frame_size as f64 * 8f64;
+    let real_iat = bits / traffic_rate;
+    let no_solution = || {
+        P4TGError::Error {
+        message: format!(
+            "No sending behaviour available. Requested rate {traffic_rate} Gbps with frame size {frame_size}."
+        ),
+    }
+    };
 
-    let min_packets = if max_burst == 1 { 1 } else { 2 };
-    let max_packets = if max_burst == 1 {
-        1
+    let (min_packets, max_packets, min_timeout) = if max_burst == 1 {
+        (1u64, 1, 1)
     } else {
-        (((d / accuracy) as u32) + 1).max(30)
+        let d = (real_iat - real_iat.floor()) / real_iat;
+        (
+            2,
+            (((d / TARGET_ACCURACY) as u64) + 1).max(30),
+            MIN_TIMEOUT as u64,
+        )
     };
 
-    // calc + num packets for objective
-    let calculation = problem.add_column(1., 0..100);
-    let num_packets = problem.add_integer_column(1., min_packets..max_packets);
+    if !real_iat.is_finite() || real_iat <= 0f64 || min_packets as f64 * real_iat > u32::MAX as f64
+    {
+        return Err(no_solution());
+    }
+
+    let (timeout, n_packets) = best_fraction(real_iat, max_packets, bits, traffic_rate);
 
-    // Should be at least 30 ns for rate mode
-    let timeout = if max_burst == 1 {
-        problem.add_integer_column(0., 1..u32::MAX)
+    // equal fractions result in the same rate, use the smallest one that fulfills the bounds
+    let factor = min_packets
+        .div_ceil(n_packets)
+        .max(min_timeout.div_ceil(timeout.max(1)));
+
+    let (n_packets, timeout) = if timeout > 0 && n_packets * factor <= max_packets {
+        (n_packets * factor, timeout * factor)
     } else {
-        problem.add_integer_column(1., 30..u32::MAX)
-    }; // timeout in 32bit ns
-
-    // 0 <= calc - timeout * rate + (num_packets * frame_size * 8) <= 1
-    // Constraint is bound 0 <= ... <= 1 to overcome possible float problems.
-    // Problems could arise if the float 'calculation' does not match the float of the calculated difference.
-    // Therefore, 0 <= ... <= 1 should be suitable for a floating error after the comma.
-    // This problem wasn't experienced yet and this solution is a safety measurement.
-    problem.add_row(
-        0..1,
-        [
-            (calculation, 1.),
-            (timeout, -traffic_rate as f64),
-            (num_packets, (frame_size * 8) as f64),
-        ],
-    );
-    let mut solver = problem.optimise(Sense::Minimise);
-    solver.set_option("time_limit", SOLVER_TIME_LIMIT_IN_SECONDS);
-
-    let solved = solver.solve();
-
-    match solved.status() {
-        HighsModelStatus::Infeasible => {
-            warn!(
-                "No solution available. Requested rate {traffic_rate} with frame size {frame_size}"
-            );
-            (0, 100)
+        // the bounds exclude the best fraction, use the best timeout for each number of packets instead
+        (min_packets..=max_packets)
+            .map(|n| (n, ((n as f64 * real_iat).round() as u64).max(min_timeout)))
+            .fold((min_packets, u64::MAX), |best, candidate| {
+                if accuracy(candidate, bits, traffic_rate) > accuracy(best, bits, traffic_rate) {
+                    candidate
+                } else {
+                    best
+                }
+            })
+    };
+
+    if timeout > u32::MAX as u64 {
+        return Err(no_solution());
+    }
+
+    Ok(SendBehaviour {
+        n_packets: n_packets as u16,
+        timeout: timeout as u32,
+        accuracy: accuracy((n_packets, timeout), bits, traffic_rate),
+    })
+}
+
+/// Returns the rate accuracy if `n_packets` with `bits` are sent per `timeout` ns.
+fn accuracy((n_packets, timeout): (u64, u64), bits: f64, traffic_rate: f64) -> f64 {
+    let rate = n_packets as f64 * bits / timeout as f64;
+
+    1f64 - (rate - traffic_rate).abs() / traffic_rate
+}
+
+/// Finds the fraction numerator / denominator with denominator <= `max_denominator`
+/// that approximates `value` best, i.e., that results in the most accurate rate.
+///
+/// Uses the convergents of the continued fraction of `value` and the last semiconvergent.
+fn best_fraction(value: f64, max_denominator: u64, bits: f64, traffic_rate: f64) -> (u64, u64) {
+    // (p0 / q0) and (p1 / q1) are the last two convergents
+    let (mut p0, mut q0, mut p1, mut q1) = (0u64, 1u64, 1u64, 0u64);
+    let mut x = value;
+
+    loop {
+        let a = x.floor();
+        let q2 = q0 + a as u64 * q1;
+
+        if q2 > max_denominator {
+            break;
         }
-        _ => {
-            let solution = solved.get_solution().columns().to_vec();
-            (
-                solution.get(1).unwrap().round() as u16,
-                solution.get(2).unwrap().round() as u32,
-            )
+
+        (p0, q0, p1, q1) = (p1, q1, p0 + a as u64 * p1, q2);
+
+        let remainder = x - a;
+        if remainder < 1e-9 {
+            return (p1, q1);
         }
+
+        x = 1f64 / remainder;
+    }
+
+    let k = (max_denominator - q0) / q1;
+    let semiconvergent = (p0 + k * p1, q0 + k * q1);
+    let convergent = (p1, q1);
+
+    // the fractions are timeout / n_packets
+    if accuracy((semiconvergent.1, semiconvergent.0), bits, traffic_rate)
+        > accuracy((convergent.1, convergent.0), bits, traffic_rate)
+    {
+        semiconvergent
+    } else {
+        convergent
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use highs::{HighsModelStatus, Sense};
+    use log::warn;
     use test_case::test_matrix;
 
+    /// Max time for the ILP solver that calculates the sending behaviour.
+    const SOLVER_TIME_LIMIT_IN_SECONDS: f64 = 2f64;
+
+    /// ILP solver that was used before [calculate_send_behaviour].
+    ///
+    /// It solves the ILP
+    ///
+    /// min c + n
+    /// s.t.
+    /// 0 <= c - timeout * `traffic_rate` + (n * `frame_size` * 8) <= 0
+    ///
+    /// Returns (number of packets, timeout)
+    fn calculate_send_behaviour_ilp(
+        frame_size: u32,
+        traffic_rate: f32,
+        max_burst: u16,
+    ) -> (u16, u32) {
+        let mut problem = highs::RowProblem::default();
+
+        let accuracy = 0.001;
+        let real_iat = frame_size as f32 * 8f32 / traffic_rate;
+        let d = (real_iat - real_iat.floor()) / real_iat;
+
+        let min_packets = if max_burst == 1 { 1 } else { 2 };
+        let max_packets = if max_burst == 1 {
+            1
+        } else {
+            (((d / accuracy) as u32) + 1).max(30)
+        };
+
+        // calc + num packets for objective
+        let calculation = problem.add_column(1., 0..100);
+        let num_packets = problem.add_integer_column(1., min_packets..max_packets);
+
+        // Should be at least 30 ns for rate mode
+        let timeout = if max_burst == 1 {
+            problem.add_integer_column(0., 1..u32::MAX)
+        } else {
+            problem.add_integer_column(1., 30..u32::MAX)
+        }; // timeout in 32bit ns
+
+        // 0 <= calc - timeout * rate + (num_packets * frame_size * 8) <= 1
+        // Constraint is bound 0 <= ... <= 1 to overcome possible float problems.
+        // Problems could arise if the float 'calculation' does not match the float of the calculated difference.
+        // Therefore, 0 <= ... <= 1 should be suitable for a floating error after the comma.
+        // This problem wasn't experienced yet and this solution is a safety measurement.
+        problem.add_row(
+            0..1,
+            [
+                (calculation, 1.),
+                (timeout, -traffic_rate as f64),
+                (num_packets, (frame_size * 8) as f64),
+            ],
+        );
+        let mut solver = problem.optimise(Sense::Minimise);
+        solver.set_option("time_limit", SOLVER_TIME_LIMIT_IN_SECONDS);
+
+        let solved = solver.solve();
+
+        match solved.status() {
+            HighsModelStatus::Infeasible => {
+                warn!(
+                    "No solution available. Requested rate {traffic_rate} with frame size {frame_size}"
+                );
+                (0, 100)
+            }
+            _ => {
+                let solution = solved.get_solution().columns().to_vec();
+                (
+                    solution.get(1).unwrap().round() as u16,
+                    solution.get(2).unwrap().round() as u32,
+                )
+            }
+        }
+    }
+
     #[test_matrix(
         [64, 128, 256, 512, 1024, 1280, 1518, 9000],
         [0, 4, 8, 12, 16, 20, 24, 28, 32, 36, 40, 44, 48, 52, 56, 60, 64, 80, 96],
@@ -120,8 +263,10 @@ mod tests {
         let number_pipes = pipes_per_tofino;
         // let number_pipes = 1.0;
 
-        let (n_packets, timeout) =
-            calculate_send_behaviour(final_size, traffic_rate / number_pipes as f32, max_burst);
+        let SendBehaviour {
+            n_packets, timeout, ..
+        } = calculate_send_behaviour(final_size, traffic_rate / number_pipes as f32, max_burst)
+            .unwrap();
         assert_ne!(0, n_packets);
 
         let rate_l1 =
@@ -134,4 +279,59 @@ mod tests {
         //    _ => assert!(accuracy >= 0.98),
         //}
     }
+
+    /// Compares the solver with the ILP solver on the matrix of `send_behavior_calculated_successful`.
+    #[test]
+    fn send_behavior_at_least_as_accurate_as_ilp() {
+        for frame_size in [64, 128, 256, 512, 1024, 1280, 1518, 9000] {
+            for encapsulation_size in [
+                0, 4, 8, 12, 16, 20, 24, 28, 32, 36, 40, 44, 48, 52, 56, 60, 64, 80, 96,
+            ] {
+                for traffic_rate in [1, 4, 10, 40, 100] {
+                    for max_burst in [1, 100] {
+                        for number_pipes in [2, 4] {
+                            let final_size = frame_size + encapsulation_size + 20;
+                            let traffic_rate = traffic_rate as f32 / number_pipes as f32;
+
+                            let behaviour =
+                                calculate_send_behaviour(final_size, traffic_rate, max_burst)
+                                    .unwrap();
+                            let (n_packets, timeout) =
+                                calculate_send_behaviour_ilp(final_size, traffic_rate, max_burst);
+                            let ilp_accuracy = accuracy(
+                                (n_packets as u64, timeout as u64),
+                                final_size as f64 * 8f64,
+                                traffic_rate as f64,
+                            );
+
+                            assert!(
+                                behaviour.accuracy >= ilp_accuracy - 1e-9,
+                                "{behaviour:?} less accurate than ILP ({n_packets} packets per {timeout} ns) for {final_size} byte @ {traffic_rate} Gbps"
+                            );
+
+                            let real_iat = final_size as f32 * 8f32 / traffic_rate;
+                            let d = (real_iat - real_iat.floor()) / real_iat;
+
+                            if max_burst == 1 {
+                                assert_eq!(behaviour.n_packets, 1);
+                            } else {
+                                assert!(behaviour.n_packets >= 2);
+                                assert!(
+                                    behaviour.n_packets as u32 <= ((d / 0.001) as u32 + 1).max(30)
+                                );
+                                assert!(behaviour.timeout >= 30);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn send_behavior_rejects_invalid_rate() {
+        assert!(calculate_send_behaviour(84, 0f32, 100).is_err());
+        assert!(calculate_send_behaviour(84, -1f32, 1).is_err());
+        assert!(calculate_send_behaviour(84, 1e-12, 100).is_err());
+    }
 }
@@ -0,0 +1,1384 @@
+use etherparse::{
+    ether_type, ip_number, Ethernet2HeaderSlice, Ipv4HeaderSlice, Ipv6Header, Ipv6HeaderSlice,
+    Ipv6RawExtensionHeaderSlice, SingleVlanHeaderSlice, UdpHeaderSlice,
+};
+use macaddr::{MacAddr, MacAddr6};
+use std::cmp;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+use crate::core::traffic_gen_core::const_definitions::{
+    GTPU_UDP_PORT, MAX_BUFFER_SIZE, MAX_NUM_MPLS_LABEL, MAX_NUM_SRV6_SIDS, P4TG_DST_PORT,
+    P4TG_SOURCE_PORT, VX_LAN_UDP_PORT,
+};
+use crate::core::traffic_gen_core::helper::{calculate_overhead, create_packet};
+use crate::core::traffic_gen_core::types::*;
+use crate::error::P4TGError;
+
+/// Ethernet + outer IPv4 + UDP + VxLAN/GTP-U header size of tunneled packets.
+const TUNNEL_HEADER_SIZE: usize = 50;
+
+/// Magic numbers of pcap files with microsecond and nanosecond timestamps.
+const PCAP_MAGIC: [u32; 2] = [0xa1b2c3d4, 0xa1b23c4d];
+
+/// Magic number of the section header block of pcapng files.
+const PCAPNG_MAGIC: u32 = 0x0a0d0d0a;
+
+/// App ids 1-7 can be used for streams.
+const MAX_NUM_STREAMS: usize = 7;
+
+/// An example frame of a stream as it is sent on a port.
+pub struct ExampleFrame {
+    pub port: u32,
+    pub channel: u8,
+    pub stream_id: u8,
+    /// Frame without FCS.
+    pub bytes: Vec<u8>,
+}
+
+/// Creates one example frame for each active (stream, port) pair, ordered by port and stream.
+///
+/// The frames are created from the packet template of the stream, i.e., the content of the packet buffer,
+/// and the headers are rewritten in software as done by the header replace tables in the data plane.
+/// Randomized IP address bits are left unset, i.e., the configured addresses are used.
+pub fn create_example_frames(
+    streams: &[Stream],
+    stream_settings: &[StreamSetting],
+) -> Result<Vec<ExampleFrame>, P4TGError> {
+    let mut frames = vec![];
+
+    for s in streams {
+        for setting in stream_settings {
+            if setting.stream_id != s.stream_id || !setting.active {
+                continue;
+            }
+
+            let mut bytes = create_packet(s, false);
+            rewrite_headers(s, setting, &mut bytes)?;
+
+            frames.push(ExampleFrame {
+                port: setting.port,
+                channel: setting.channel.unwrap_or(0),
+                stream_id: s.stream_id,
+                bytes,
+            });
+        }
+    }
+
+    frames.sort_by_key(|f| (f.port, f.channel, f.stream_id));
+
+    Ok(frames)
+}
+
+/// Serializes the `frames` into the pcap file format with Ethernet link type.
+/// Frames are 1 µs apart.
+pub fn write_pcap(frames: &[ExampleFrame]) -> Vec<u8> {
+    let mut pcap = vec![];
+
+    // global header: magic number, version 2.4, timezone, accuracy, snap length, Ethernet link type
+    pcap.extend_from_slice(&0xa1b2c3d4u32.to_le_bytes());
+    pcap.extend_from_slice(&2u16.to_le_bytes());
+    pcap.extend_from_slice(&4u16.to_le_bytes());
+    pcap.extend_from_slice(&0i32.to_le_bytes());
+    pcap.extend_from_slice(&0u32.to_le_bytes());
+    pcap.extend_from_slice(&65535u32.to_le_bytes());
+    pcap.extend_from_slice(&1u32.to_le_bytes());
+
+    for (i, frame) in frames.iter().enumerate() {
+        let len = frame.bytes.len() as u32;
+
+        pcap.extend_from_slice(&0u32.to_le_bytes());
+        pcap.extend_from_slice(&(i as u32).to_le_bytes());
+        pcap.extend_from_slice(&len.to_le_bytes());
+        pcap.extend_from_slice(&len.to_le_bytes());
+        pcap.extend_from_slice(&frame.bytes);
+    }
+
+    pcap
+}
+
+/// Creates a traffic generation from the frames of the pcap file `data`.
+///
+/// Each distinct frame, i.e., each combination of header values and frame size, results in a stream
+/// that is sent on `options.port`. Duplicates of a frame are ignored.
+/// The L4 header and payload of the frames are replaced by the UDP and P4TG header.
+/// Headers that cannot be represented are reported once per distinct message.
+pub fn import_pcap(data: &[u8], options: &PcapImportOptions) -> Result<PcapImport, P4TGError> {
+    let mut streams: Vec<Stream> = vec![];
+    let mut stream_settings: Vec<StreamSetting> = vec![];
+    let mut imported_frames: Vec<String> = vec![];
+    let mut issues: Vec<PcapImportIssue> = vec![];
+
+    for (i, captured) in read_pcap(data)?.into_iter().enumerate() {
+        let mut report = |skipped: bool, message: String| {
+            if !issues
+                .iter()
+                .any(|issue| issue.skipped == skipped && issue.message == message)
+            {
+                issues.push(PcapImportIssue {
+                    frame: i + 1,
+                    skipped,
+                    message,
+                });
+            }
+        };
+
+        let mut frame = match ImportedFrame::parse(captured.bytes, captured.len) {
+            Ok(frame) => frame,
+            Err(message) => {
+                report(true, message);
+                continue;
+            }
+        };
+
+        // identical frames result in the same stream
+        let key = serde_json::to_string(&(&frame.stream, &frame.setting)).unwrap_or_default();
+        if imported_frames.contains(&key) {
+            continue;
+        }
+
+        if streams.len() == MAX_NUM_STREAMS {
+            report(
+                true,
+                format!("Maximal number of {MAX_NUM_STREAMS} streams is reached."),
+            );
+            continue;
+        }
+
+        if streams.iter().map(|s| s.frame_size).sum::<u32>() + frame.stream.frame_size
+            > MAX_BUFFER_SIZE
+        {
+            report(
+                true,
+                format!("Maximal sum of packet sizes of {MAX_BUFFER_SIZE}B is reached."),
+            );
+            continue;
+        }
+
+        for message in frame.issues.drain(..) {
+            report(false, message);
+        }
+
+        let stream_id = streams.len() as u8 + 1;
+
+        frame.stream.stream_id = stream_id;
+        frame.stream.app_id = stream_id;
+        frame.setting.stream_id = stream_id;
+        frame.setting.port = options.port;
+
+        imported_frames.push(key);
+        streams.push(frame.stream);
+        stream_settings.push(frame.setting);
+    }
+
+    let rx_target = RxTarget {
+        port: options.rx_port.unwrap_or(options.port),
+        channel: 0,
+    };
+
+    for stream in streams.iter_mut() {
+        stream.traffic_rate = options.traffic_rate;
+    }
+
+    Ok(PcapImport {
+        traffic_gen: TrafficGenData {
+            mode: GenerationMode::Cbr,
+            stream_settings,
+            streams,
+            port_tx_rx_mapping: HashMap::from([(
+                options.port.to_string(),
+                HashMap::from([("0".to_string(), rx_target)]),
+            )]),
+            duration: None,
+            rtt_histogram_config: None,
+            iat_histogram_config: None,
+            name: None,
+            sla: None,
+            time_resolution: None,
+            warmup: None,
+            cooldown: None,
+        },
+        issues,
+    })
+}
+
+/// A frame of a pcap file.
+struct CapturedFrame<'a> {
+    /// Captured bytes of the frame without FCS. May be truncated.
+    bytes: &'a [u8],
+    /// Length of the frame on the wire without FCS.
+    len: usize,
+}
+
+/// Reads the frames of the pcap file `data`. Only the Ethernet link type is supported.
+fn read_pcap(data: &[u8]) -> Result<Vec<CapturedFrame<'_>>, P4TGError> {
+    let truncated = || P4TGError::Error {
+        message: String::from("Pcap file is truncated."),
+    };
+
+    let header = data.get(..24).ok_or_else(truncated)?;
+    let magic = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+
+    let from_bytes: fn([u8; 4]) -> u32 = if PCAP_MAGIC.contains(&magic) {
+        u32::from_le_bytes
+    } else if PCAP_MAGIC.contains(&magic.swap_bytes()) {
+        u32::from_be_bytes
+    } else if magic == PCAPNG_MAGIC {
+        return Err(P4TGError::Error {
+            message: String::from(
+                "Pcapng files are not supported. Please convert the file to pcap.",
+            ),
+        });
+    } else {
+        return Err(P4TGError::Error {
+            message: String::from("File is not a pcap file."),
+        });
+    };
+    let field = |bytes: &[u8], offset: usize| {
+        from_bytes([
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ]) as usize
+    };
+
+    // the upper bits of the link type indicate if the frames contain an FCS and its length in 16 bit words
+    let link_type = field(header, 20);
+    let fcs_len = if link_type & (1 << 26) != 0 {
+        (link_type >> 28) * 2
+    } else {
+        0
+    };
+
+    if link_type & 0xffff != 1 {
+        return Err(P4TGError::Error {
+            message: format!(
+                "Link type {} of pcap file is not supported. Only Ethernet is supported.",
+                link_type & 0xffff
+            ),
+        });
+    }
+
+    let mut frames = vec![];
+    let mut offset = 24;
+
+    while offset < data.len() {
+        let record = data.get(offset..offset + 16).ok_or_else(truncated)?;
+        let captured_len = field(record, 8);
+        let len = field(record, 12).saturating_sub(fcs_len);
+
+        let bytes = data
+            .get(offset + 16..offset + 16 + captured_len)
+            .ok_or_else(truncated)?;
+
+        frames.push(CapturedFrame {
+            bytes: &bytes[..cmp::min(captured_len, len)],
+            len,
+        });
+
+        offset += 16 + captured_len;
+    }
+
+    Ok(frames)
+}
+
+/// Stream and stream setting that reproduce the headers of a captured frame.
+struct ImportedFrame {
+    stream: Stream,
+    setting: StreamSetting,
+    /// Headers that are not represented by the stream
+    issues: Vec<String>,
+}
+
+impl ImportedFrame {
+    /// Parses the captured `bytes` of a frame with length `len`.
+    /// Returns an error message if the frame cannot be represented by a stream.
+    fn parse(bytes: &[u8], len: usize) -> Result<ImportedFrame, String> {
+        let mut frame = ImportedFrame {
+            stream: Stream {
+                stream_id: 0,
+                app_id: 0,
+                frame_size: 0,
+                encapsulation: Encapsulation::None,
+                number_of_lse: None,
+                traffic_rate: 0f32,
+                burst: 100,
+                batches: Some(true),
+                n_packets: None,
+                timeout: None,
+                generation_accuracy: None,
+                n_pipes: None,
+                vxlan: false,
+                gtpu: false,
+                ip_version: None,
+                number_of_srv6_sids: None,
+                srv6_ip_tunneling: None,
+                unit: Some(GenerationUnit::Gbps),
+                pattern: None,
+            },
+            setting: StreamSetting {
+                port: 0,
+                channel: None,
+                stream_id: 0,
+                vlan: None,
+                mpls_stack: None,
+                srv6_base_header: None,
+                sid_list: None,
+                ethernet: Ethernet {
+                    eth_src: String::new(),
+                    eth_dst: String::new(),
+                },
+                ip: None,
+                ipv6: None,
+                active: true,
+                vxlan: None,
+                gtpu: None,
+            },
+            issues: vec![],
+        };
+
+        match frame.parse_tunnel(bytes)? {
+            Some(offset) if frame.stream.gtpu => frame.parse_ipv4(bytes, offset)?,
+            Some(offset) => frame.parse_ethernet(bytes, offset)?,
+            None => frame.parse_ethernet(bytes, 0)?,
+        }
+
+        frame.set_frame_size(len);
+
+        Ok(frame)
+    }
+
+    /// Parses the VxLAN or GTP-U tunnel of the frame, if any.
+    /// Returns the offset of the tunneled Ethernet frame (VxLAN) or IP packet (GTP-U).
+    fn parse_tunnel(&mut self, bytes: &[u8]) -> Result<Option<usize>, String> {
+        let eth = Ethernet2HeaderSlice::from_slice(bytes).map_err(truncated)?;
+
+        if eth.ether_type() != ether_type::IPV4 {
+            return Ok(None);
+        }
+
+        let ip = Ipv4HeaderSlice::from_slice(rest(bytes, 14)).map_err(truncated)?;
+
+        if ip.protocol() != ip_number::UDP || ip.is_fragmenting_payload() {
+            return Ok(None);
+        }
+
+        let udp_offset = 14 + ip.slice().len();
+        let udp = UdpHeaderSlice::from_slice(rest(bytes, udp_offset)).map_err(truncated)?;
+        let tunnel_offset = udp_offset + 8;
+
+        let offset = match udp.destination_port() {
+            VX_LAN_UDP_PORT => {
+                let vxlan = bytes
+                    .get(tunnel_offset..tunnel_offset + 8)
+                    .ok_or_else(|| truncated("VxLAN"))?;
+
+                self.stream.vxlan = true;
+                self.setting.vxlan = Some(VxLAN {
+                    eth_src: format_mac(eth.source()),
+                    eth_dst: format_mac(eth.destination()),
+                    ip_src: ip.source_addr(),
+                    ip_dst: ip.destination_addr(),
+                    ip_tos: ip.slice()[1],
+                    udp_source: udp.source_port(),
+                    vni: u32::from_be_bytes([0, vxlan[4], vxlan[5], vxlan[6]]),
+                });
+
+                tunnel_offset + 8
+            }
+            GTPU_UDP_PORT => {
+                let gtpu = bytes
+                    .get(tunnel_offset..tunnel_offset + 8)
+                    .ok_or_else(|| truncated("GTP-U"))?;
+
+                if gtpu[1] != 0xff {
+                    return Err(format!(
+                        "GTP-U message type {} is not supported. Only user data (G-PDU) is supported.",
+                        gtpu[1]
+                    ));
+                }
+
+                let mut header_len = 8;
+
+                // sequence number, N-PDU number, and next extension header type follow if any flag is set
+                if gtpu[0] & 0x07 != 0 {
+                    self.issues
+                        .push(String::from("Optional GTP-U header fields are dropped."));
+                    header_len += 4;
+
+                    if gtpu[0] & 0x04 != 0 {
+                        // extension headers start with their length in 4 byte units and end with the next type
+                        let mut next_type = *bytes
+                            .get(tunnel_offset + 11)
+                            .ok_or_else(|| truncated("GTP-U"))?;
+
+                        while next_type != 0 {
+                            let extension_len = *bytes
+                                .get(tunnel_offset + header_len)
+                                .ok_or_else(|| truncated("GTP-U"))?
+                                as usize
+                                * 4;
+
+                            if extension_len == 0 {
+                                return Err(String::from("GTP-U extension header is invalid."));
+                            }
+
+                            header_len += extension_len;
+                            next_type = *bytes
+                                .get(tunnel_offset + header_len - 1)
+                                .ok_or_else(|| truncated("GTP-U"))?;
+                        }
+
+                        self.issues
+                            .push(String::from("GTP-U extension headers are dropped."));
+                    }
+                }
+
+                if bytes.get(tunnel_offset + header_len).map(|b| b >> 4) != Some(4) {
+                    return Err(String::from(
+                        "GTP-U is only supported with an inner IPv4 packet.",
+                    ));
+                }
+
+                self.stream.gtpu = true;
+                self.setting.ethernet = Ethernet {
+                    eth_src: format_mac(eth.source()),
+                    eth_dst: format_mac(eth.destination()),
+                };
+                self.setting.gtpu = Some(GtpU {
+                    ip_src: ip.source_addr(),
+                    ip_dst: ip.destination_addr(),
+                    ip_tos: ip.slice()[1],
+                    udp_source: udp.source_port(),
+                    teid: u32::from_be_bytes([gtpu[4], gtpu[5], gtpu[6], gtpu[7]]),
+                });
+
+                tunnel_offset + header_len
+            }
+            _ => return Ok(None),
+        };
+
+        if ip.ihl() > 5 {
+            self.issues.push(String::from(
+                "IPv4 options of the outer IPv4 header are dropped.",
+            ));
+        }
+
+        Ok(Some(offset))
+    }
+
+    /// Parses the Ethernet frame at `offset` including VLAN tags and MPLS stack.
+    fn parse_ethernet(&mut self, bytes: &[u8], offset: usize) -> Result<(), String> {
+        let eth = Ethernet2HeaderSlice::from_slice(rest(bytes, offset)).map_err(truncated)?;
+
+        self.setting.ethernet = Ethernet {
+            eth_src: format_mac(eth.source()),
+            eth_dst: format_mac(eth.destination()),
+        };
+
+        let mut ether_type = eth.ether_type();
+        let mut offset = offset + 14;
+
+        if is_vlan(ether_type) {
+            let outer =
+                SingleVlanHeaderSlice::from_slice(rest(bytes, offset)).map_err(truncated)?;
+            let mut vlan = Vlan {
+                vlan_id: outer.vlan_identifier(),
+                pcp: outer.priority_code_point(),
+                dei: u8::from(outer.drop_eligible_indicator()),
+                inner_vlan_id: 0,
+                inner_pcp: 0,
+                inner_dei: 0,
+            };
+
+            ether_type = outer.ether_type();
+            offset += 4;
+            self.stream.encapsulation = Encapsulation::Vlan;
+
+            if is_vlan(ether_type) {
+                let inner =
+                    SingleVlanHeaderSlice::from_slice(rest(bytes, offset)).map_err(truncated)?;
+
+                vlan.inner_vlan_id = inner.vlan_identifier();
+                vlan.inner_pcp = inner.priority_code_point();
+                vlan.inner_dei = u8::from(inner.drop_eligible_indicator());
+
+                ether_type = inner.ether_type();
+                offset += 4;
+                self.stream.encapsulation = Encapsulation::QinQ;
+
+                if is_vlan(ether_type) {
+                    return Err(String::from("More than two VLAN tags are not supported."));
+                }
+            }
+
+            self.setting.vlan = Some(vlan);
+        }
+
+        match ether_type {
+            // MPLS unicast and multicast
+            0x8847 | 0x8848 => self.parse_mpls(bytes, offset),
+            ether_type::IPV4 => self.parse_ipv4(bytes, offset),
+            ether_type::IPV6 => self.parse_ipv6(bytes, offset),
+            _ => Err(format!("EtherType {ether_type:#06x} is not supported.")),
+        }
+    }
+
+    /// Parses the MPLS stack at `offset` and the following IP packet.
+    fn parse_mpls(&mut self, bytes: &[u8], mut offset: usize) -> Result<(), String> {
+        if self.stream.encapsulation != Encapsulation::None {
+            return Err(String::from(
+                "Combination of VLAN and MPLS is not supported.",
+            ));
+        }
+
+        let mut mpls_stack = vec![];
+
+        loop {
+            let lse = bytes
+                .get(offset..offset + 4)
+                .ok_or_else(|| truncated("MPLS"))?;
+            let lse = u32::from_be_bytes([lse[0], lse[1], lse[2], lse[3]]);
+
+            mpls_stack.push(MPLSHeader {
+                label: lse >> 12,
+                tc: (lse >> 9) & 0x7,
+                ttl: lse & 0xff,
+            });
+            offset += 4;
+
+            // bottom of stack
+            if lse & 0x100 != 0 {
+                break;
+            }
+        }
+
+        if mpls_stack.len() > MAX_NUM_MPLS_LABEL as usize {
+            return Err(format!(
+                "MPLS stack with {} LSEs exceeds the maximum of {MAX_NUM_MPLS_LABEL} LSEs.",
+                mpls_stack.len()
+            ));
+        }
+
+        self.stream.encapsulation = Encapsulation::Mpls;
+        self.stream.number_of_lse = Some(mpls_stack.len() as u8);
+        self.setting.mpls_stack = Some(mpls_stack);
+
+        match bytes.get(offset).map(|b| b >> 4) {
+            Some(4) => self.parse_ipv4(bytes, offset),
+            Some(6) => self.parse_ipv6(bytes, offset),
+            _ => Err(String::from("MPLS payload is not an IP packet.")),
+        }
+    }
+
+    /// Parses the IPv4 header at `offset`.
+    fn parse_ipv4(&mut self, bytes: &[u8], offset: usize) -> Result<(), String> {
+        let ip = Ipv4HeaderSlice::from_slice(rest(bytes, offset)).map_err(truncated)?;
+
+        self.stream.ip_version = Some(4);
+        self.setting.ip = Some(IPv4 {
+            ip_src: ip.source_addr(),
+            ip_dst: ip.destination_addr(),
+            ip_tos: ip.slice()[1],
+            ip_src_mask: Ipv4Addr::UNSPECIFIED,
+            ip_dst_mask: Ipv4Addr::UNSPECIFIED,
+        });
+
+        if ip.ihl() > 5 {
+            self.issues.push(String::from("IPv4 options are dropped."));
+        }
+
+        if ip.is_fragmenting_payload() {
+            self.issues.push(String::from(
+                "IPv4 fragments are imported as unfragmented packets.",
+            ));
+            return Ok(());
+        }
+
+        self.check_transport(ip.protocol(), rest(bytes, offset + ip.slice().len()))
+    }
+
+    /// Parses the IPv6 header at `offset`, including a segment routing header.
+    fn parse_ipv6(&mut self, bytes: &[u8], offset: usize) -> Result<(), String> {
+        let ip = Ipv6HeaderSlice::from_slice(rest(bytes, offset)).map_err(truncated)?;
+        let payload = rest(bytes, offset + 40);
+
+        // routing header with routing type 4
+        if ip.next_header() == ip_number::IPV6_ROUTE && payload.get(2) == Some(&4) {
+            return self.parse_srv6(bytes, offset, &ip);
+        }
+
+        if self.stream.vxlan {
+            return Err(String::from("VxLAN with inner IPv6 is not supported."));
+        }
+
+        self.stream.ip_version = Some(6);
+        self.setting.ipv6 = Some(ipv6_settings(&ip));
+
+        let (protocol, transport) =
+            Ipv6Header::skip_all_header_extensions_in_slice(payload, ip.next_header())
+                .map_err(truncated)?;
+
+        if transport.len() != payload.len() {
+            self.issues
+                .push(String::from("IPv6 extension headers are dropped."));
+        }
+
+        self.check_transport(protocol, transport)
+    }
+
+    /// Parses the SRv6 base header at `offset`, the segment routing header, and the following IP packet.
+    fn parse_srv6(
+        &mut self,
+        bytes: &[u8],
+        offset: usize,
+        ip: &Ipv6HeaderSlice,
+    ) -> Result<(), String> {
+        if self.stream.vxlan {
+            return Err(String::from(
+                "Combination of VxLAN and SRv6 is not supported.",
+            ));
+        }
+
+        if self.stream.encapsulation != Encapsulation::None {
+            return Err(format!(
+                "Combination of {:?} and SRv6 is not supported.",
+                self.stream.encapsulation
+            ));
+        }
+
+        let srh =
+            Ipv6RawExtensionHeaderSlice::from_slice(rest(bytes, offset + 40)).map_err(truncated)?;
+
+        // routing type, segments left, last entry, flags, tag, and SID list
+        let payload = srh.payload();
+        let number_of_sids = payload[2] as usize + 1;
+        let sid_list: Vec<Ipv6Addr> = payload[6..]
+            .chunks_exact(16)
+            .take(number_of_sids)
+            .map(|sid| Ipv6Addr::from(<[u8; 16]>::try_from(sid).unwrap_or_default()))
+            .collect();
+
+        if sid_list.len() != number_of_sids {
+            return Err(String::from("Segment routing header is invalid."));
+        }
+
+        if number_of_sids > MAX_NUM_SRV6_SIDS as usize {
+            return Err(format!(
+                "Segment routing header with {number_of_sids} SIDs exceeds the maximum of {MAX_NUM_SRV6_SIDS} SIDs."
+            ));
+        }
+
+        if payload.len() > 6 + 16 * number_of_sids {
+            self.issues.push(String::from(
+                "TLVs of the segment routing header are dropped.",
+            ));
+        }
+
+        if payload[1] as usize != number_of_sids - 1 {
+            self.issues.push(format!(
+                "Segments left of the segment routing header is set to {}.",
+                number_of_sids - 1
+            ));
+        }
+
+        self.stream.encapsulation = Encapsulation::SRv6;
+        self.stream.number_of_srv6_sids = Some(number_of_sids as u8);
+        self.setting.srv6_base_header = Some(ipv6_settings(ip));
+        self.setting.sid_list = Some(sid_list);
+
+        let inner_offset = offset + 40 + srh.slice().len();
+
+        match srh.next_header() {
+            ip_number::IPV4 => {
+                self.stream.srv6_ip_tunneling = Some(true);
+                self.parse_ipv4(bytes, inner_offset)
+            }
+            ip_number::IPV6 => {
+                self.stream.srv6_ip_tunneling = Some(true);
+                self.parse_ipv6(bytes, inner_offset)
+            }
+            protocol => {
+                self.stream.srv6_ip_tunneling = Some(false);
+                self.check_transport(protocol, rest(bytes, inner_offset))
+            }
+        }
+    }
+
+    /// Checks if the transport header, which is replaced by the UDP and P4TG header, can be represented.
+    fn check_transport(&mut self, protocol: u8, transport: &[u8]) -> Result<(), String> {
+        if protocol != ip_number::UDP {
+            self.issues
+                .push(format!("IP protocol {protocol} is replaced by UDP."));
+            return Ok(());
+        }
+
+        let udp = UdpHeaderSlice::from_slice(transport).map_err(truncated)?;
+
+        if (udp.source_port(), udp.destination_port()) != (P4TG_SOURCE_PORT, P4TG_DST_PORT) {
+            self.issues.push(format!(
+                "UDP ports {} and {} are replaced by the P4TG ports.",
+                udp.source_port(),
+                udp.destination_port()
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Sets the frame size of the stream such that the generated frames have the captured length `len`.
+    fn set_frame_size(&mut self, len: usize) {
+        // the frame size includes the FCS
+        let overhead = calculate_overhead(&self.stream) as usize;
+        let frame_size = (len + 4).saturating_sub(overhead);
+
+        let ip_header_len = match (self.stream.srv6_ip_tunneling, self.stream.ip_version) {
+            (Some(false), _) => 0,
+            (_, Some(6)) => 40,
+            _ => 20,
+        };
+        // GTP-U tunnels the IP packet without Ethernet header
+        let ethernet_header_len = if self.stream.gtpu { 0 } else { 14 };
+        // Ethernet, IP, UDP, P4TG header, and FCS
+        let min_frame_size = ethernet_header_len + ip_header_len + 8 + 11 + 4;
+
+        if frame_size < min_frame_size {
+            self.issues.push(format!(
+                "Frame of {len} bytes is too short for the P4TG header and is extended to {} bytes.",
+                min_frame_size + overhead - 4
+            ));
+        }
+
+        self.stream.frame_size = cmp::max(frame_size, min_frame_size) as u32;
+    }
+}
+
+fn is_vlan(ether_type: u16) -> bool {
+    matches!(
+        ether_type,
+        ether_type::VLAN_TAGGED_FRAME
+            | ether_type::PROVIDER_BRIDGING
+            | ether_type::VLAN_DOUBLE_TAGGED_FRAME
+    )
+}
+
+fn ipv6_settings(ip: &Ipv6HeaderSlice) -> IPv6 {
+    IPv6 {
+        ipv6_src: ip.source_addr(),
+        ipv6_dst: ip.destination_addr(),
+        ipv6_traffic_class: ip.traffic_class(),
+        ipv6_src_mask: Ipv6Addr::UNSPECIFIED,
+        ipv6_dst_mask: Ipv6Addr::UNSPECIFIED,
+        ipv6_flow_label: ip.flow_label(),
+    }
+}
+
+fn format_mac(mac: [u8; 6]) -> String {
+    MacAddr6::from(mac).to_string()
+}
+
+/// Returns the bytes from `offset` on, or an empty slice if the frame is shorter.
+fn rest(bytes: &[u8], offset: usize) -> &[u8] {
+    bytes.get(offset..).unwrap_or_default()
+}
+
+fn truncated(header: impl std::fmt::Display) -> String {
+    format!("Frame is truncated ({header}).")
+}
+
+/// Applies the header rewrites of `setting` on the template `packet` of stream `s`.
+///
+/// IPv4 header checksums and the checksum of the P4TG UDP header are recalculated afterwards.
+pub fn rewrite_headers(
+    s: &Stream,
+    setting: &StreamSetting,
+    packet: &mut [u8],
+) -> Result<(), P4TGError> {
+    let src_mac = parse_mac(&setting.ethernet.eth_src)?;
+    let dst_mac = parse_mac(&setting.ethernet.eth_dst)?;
+    let ip_version = s.ip_version.unwrap_or(4);
+
+    if s.gtpu {
+        let gtpu = setting.gtpu.as_ref().ok_or(missing("GTP-U"))?;
+        let ip = setting.ip.as_ref().ok_or(missing("IPv4"))?;
+
+        write(packet, 0, &dst_mac)?;
+        write(packet, 6, &src_mac)?;
+        rewrite_ipv4(
+            packet,
+            14,
+            gtpu.ip_src.octets(),
+            gtpu.ip_dst.octets(),
+            gtpu.ip_tos,
+        )?;
+        write(packet, 34, &gtpu.udp_source.to_be_bytes())?;
+        write(packet, 46, &gtpu.teid.to_be_bytes())?;
+        rewrite_ipv4(
+            packet,
+            TUNNEL_HEADER_SIZE,
+            ip.ip_src.octets(),
+            ip.ip_dst.octets(),
+            ip.ip_tos,
+        )?;
+
+        return update_udp_checksum(packet, TUNNEL_HEADER_SIZE, TUNNEL_HEADER_SIZE + 20, None);
+    }
+
+    // start of the frame that contains the encapsulation
+    let frame = if s.vxlan {
+        let vxlan = setting.vxlan.as_ref().ok_or(missing("VxLAN"))?;
+
+        write(packet, 0, &parse_mac(&vxlan.eth_dst)?)?;
+        write(packet, 6, &parse_mac(&vxlan.eth_src)?)?;
+        rewrite_ipv4(
+            packet,
+            14,
+            vxlan.ip_src.octets(),
+            vxlan.ip_dst.octets(),
+            vxlan.ip_tos,
+        )?;
+        write(packet, 34, &vxlan.udp_source.to_be_bytes())?;
+        write(packet, 46, &vxlan.vni.to_be_bytes()[1..])?;
+
+        TUNNEL_HEADER_SIZE
+    } else {
+        0
+    };
+
+    write(packet, frame, &dst_mac)?;
+    write(packet, frame + 6, &src_mac)?;
+
+    let mut ip_offset = frame + 14;
+
+    match s.encapsulation {
+        Encapsulation::None => {}
+        Encapsulation::Vlan => {
+            let vlan = setting.vlan.as_ref().ok_or(missing("VLAN"))?;
+            write(
+                packet,
+                ip_offset,
+                &vlan_tci(vlan.pcp, vlan.dei, vlan.vlan_id),
+            )?;
+            ip_offset += 4;
+        }
+        Encapsulation::QinQ => {
+            let vlan = setting.vlan.as_ref().ok_or(missing("VLAN"))?;
+            write(
+                packet,
+                ip_offset,
+                &vlan_tci(vlan.pcp, vlan.dei, vlan.vlan_id),
+            )?;
+            write(
+                packet,
+                ip_offset + 4,
+                &vlan_tci(vlan.inner_pcp, vlan.inner_dei, vlan.inner_vlan_id),
+            )?;
+            ip_offset += 8;
+        }
+        Encapsulation::Mpls => {
+            let mpls_stack = setting.mpls_stack.as_ref().ok_or(missing("MPLS stack"))?;
+            let number_of_lse = s.number_of_lse.unwrap_or(0);
+            let rewritten = cmp::min(number_of_lse, MAX_NUM_MPLS_LABEL) as usize;
+
+            for (i, lse) in mpls_stack.iter().take(rewritten).enumerate() {
+                let bos = u32::from(i + 1 == rewritten);
+                let value = ((lse.label & 0xfffff) << 12)
+                    | ((lse.tc & 0x7) << 9)
+                    | (bos << 8)
+                    | (lse.ttl & 0xff);
+                write(packet, ip_offset + 4 * i, &value.to_be_bytes())?;
+            }
+
+            ip_offset += 4 * number_of_lse as usize;
+        }
+        Encapsulation::SRv6 => {
+            let base = setting
+                .srv6_base_header
+                .as_ref()
+                .ok_or(missing("SRv6 base header"))?;
+            let sid_list = setting.sid_list.as_ref().ok_or(missing("SID list"))?;
+            let number_of_sids = s.number_of_srv6_sids.unwrap_or(0) as usize;
+
+            rewrite_ipv6(
+                packet,
+                ip_offset,
+                base.ipv6_src,
+                base.ipv6_dst,
+                base.ipv6_traffic_class,
+                base.ipv6_flow_label,
+            )?;
+
+            for (i, sid) in sid_list
+                .iter()
+                .take(cmp::min(number_of_sids, MAX_NUM_SRV6_SIDS as usize))
+                .enumerate()
+            {
+                write(packet, ip_offset + 48 + 16 * i, &sid.octets())?;
+            }
+
+            let srv6_offset = ip_offset;
+            ip_offset += 48 + 16 * number_of_sids;
+
+            if s.srv6_ip_tunneling == Some(false) {
+                // UDP follows the SRH directly, the checksum uses the final segment as destination
+                let destination = match (number_of_sids, sid_list.first()) {
+                    (n, Some(sid)) if n > 1 => *sid,
+                    _ => base.ipv6_dst,
+                };
+
+                return update_udp_checksum(
+                    packet,
+                    srv6_offset,
+                    ip_offset,
+                    Some((base.ipv6_src, destination)),
+                );
+            }
+        }
+    }
+
+    if ip_version == 6 {
+        let ipv6 = setting.ipv6.as_ref().ok_or(missing("IPv6"))?;
+
+        rewrite_ipv6(
+            packet,
+            ip_offset,
+            ipv6.ipv6_src,
+            ipv6.ipv6_dst,
+            ipv6.ipv6_traffic_class,
+            ipv6.ipv6_flow_label,
+        )?;
+
+        update_udp_checksum(packet, ip_offset, ip_offset + 40, None)
+    } else {
+        let ip = setting.ip.as_ref().ok_or(missing("IPv4"))?;
+
+        rewrite_ipv4(
+            packet,
+            ip_offset,
+            ip.ip_src.octets(),
+            ip.ip_dst.octets(),
+            ip.ip_tos,
+        )?;
+
+        update_udp_checksum(packet, ip_offset, ip_offset + 20, None)
+    }
+}
+
+fn parse_mac(mac: &str) -> Result<[u8; 6], P4TGError> {
+    let mac = MacAddr::from_str(mac).map_err(|_| P4TGError::Error {
+        message: format!("Mac address {mac} in stream settings not valid."),
+    })?;
+
+    Ok(mac.as_bytes().try_into().unwrap_or_default())
+}
+
+fn missing(header: &str) -> P4TGError {
+    P4TGError::Error {
+        message: format!("{header} settings are missing in stream settings."),
+    }
+}
+
+fn write(packet: &mut [u8], offset: usize, bytes: &[u8]) -> Result<(), P4TGError> {
+    packet
+        .get_mut(offset..offset + bytes.len())
+        .ok_or(P4TGError::Error {
+            message: String::from("Packet is too short for the configured encapsulation."),
+        })?
+        .copy_from_slice(bytes);
+
+    Ok(())
+}
+
+fn vlan_tci(pcp: u8, dei: u8, vlan_id: u16) -> [u8; 2] {
+    ((((pcp & 0x7) as u16) << 13) | (((dei & 0x1) as u16) << 12) | (vlan_id & 0xfff)).to_be_bytes()
+}
+
+/// Sets addresses and TOS of the IPv4 header at `offset` and recalculates its checksum.
+fn rewrite_ipv4(
+    packet: &mut [u8],
+    offset: usize,
+    src: [u8; 4],
+    dst: [u8; 4],
+    tos: u8,
+) -> Result<(), P4TGError> {
+    write(packet, offset + 1, &[tos])?;
+    write(packet, offset + 12, &src)?;
+    write(packet, offset + 16, &dst)?;
+    write(packet, offset + 10, &[0, 0])?;
+
+    let checksum = !fold(sum(&packet[offset..offset + 20]));
+    write(packet, offset + 10, &checksum.to_be_bytes())
+}
+
+/// Sets addresses, traffic class, and flow label of the IPv6 header at `offset`.
+fn rewrite_ipv6(
+    packet: &mut [u8],
+    offset: usize,
+    src: Ipv6Addr,
+    dst: Ipv6Addr,
+    traffic_class: u8,
+    flow_label: u32,
+) -> Result<(), P4TGError> {
+    let first_word = (6u32 << 28) | ((traffic_class as u32) << 20) | (flow_label & 0xfffff);
+
+    write(packet, offset, &first_word.to_be_bytes())?;
+    write(packet, offset + 8, &src.octets())?;
+    write(packet, offset + 24, &dst.octets())
+}
+
+/// Recalculates the checksum of the UDP header at `udp_offset` that is carried in the IP header at `ip_offset`.
+/// `addresses` overwrites the IPv6 source and destination of the pseudo header.
+fn update_udp_checksum(
+    packet: &mut [u8],
+    ip_offset: usize,
+    udp_offset: usize,
+    addresses: Option<(Ipv6Addr, Ipv6Addr)>,
+) -> Result<(), P4TGError> {
+    write(packet, udp_offset + 6, &[0, 0])?;
+
+    let udp_len = u16::from_be_bytes([packet[udp_offset + 4], packet[udp_offset + 5]]) as usize;
+    let end = cmp::min(udp_offset + udp_len.max(8), packet.len());
+
+    let pseudo_header = match (packet[ip_offset] >> 4, addresses) {
+        (4, _) => sum(&packet[ip_offset + 12..ip_offset + 20]),
+        (_, Some((src, dst))) => sum(&src.octets()) + sum(&dst.octets()),
+        _ => sum(&packet[ip_offset + 8..ip_offset + 40]),
+    } + 17
+        + (end - udp_offset) as u64;
+
+    let checksum = match !fold(pseudo_header + sum(&packet[udp_offset..end])) {
+        0 => 0xffff,
+        checksum => checksum,
+    };
+
+    write(packet, udp_offset + 6, &checksum.to_be_bytes())
+}
+
+/// Sums the 16 bit words of `bytes`.
+fn sum(bytes: &[u8]) -> u64 {
+    bytes
+        .chunks(2)
+        .map(|c| u16::from_be_bytes([c[0], *c.get(1).unwrap_or(&0)]) as u64)
+        .sum()
+}
+
+/// Folds `sum` into a 16 bit one's complement sum.
+fn fold(mut sum: u64) -> u16 {
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+
+    sum as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::docs::traffic_gen::{EXAMPLE_POST_1_REQUEST, EXAMPLE_POST_2_REQUEST};
+    use crate::core::backend::mock::tests::{call, mock_state, request};
+    use axum::body::to_bytes;
+    use axum::http::{header, Method, StatusCode};
+    use etherparse::{InternetSlice, SlicedPacket, TransportSlice, VlanSlice};
+
+    #[test]
+    fn vlan_frame_is_rewritten() {
+        let request = &*EXAMPLE_POST_2_REQUEST;
+        let frames = create_example_frames(&request.streams, &request.stream_settings).unwrap();
+        assert_eq!(frames.len(), 1);
+
+        let frame = &frames[0];
+        let packet = SlicedPacket::from_ethernet(&frame.bytes).unwrap();
+
+        let Some(etherparse::LinkSlice::Ethernet2(eth)) = packet.link else {
+            panic!("No Ethernet header");
+        };
+        assert_eq!(eth.source(), [0x32, 0xd5, 0x42, 0x2a, 0xf6, 0x92]);
+
+        let Some(VlanSlice::SingleVlan(vlan)) = packet.vlan else {
+            panic!("No VLAN header");
+        };
+        assert_eq!(vlan.vlan_identifier(), 5);
+        assert_eq!(vlan.priority_code_point(), 1);
+
+        let Some(InternetSlice::Ipv4(ip, _)) = packet.ip else {
+            panic!("No IPv4 header");
+        };
+        assert_eq!(ip.source_addr().to_string(), "192.168.178.10");
+        assert_eq!(ip.destination_addr().to_string(), "192.168.178.11");
+        assert_eq!(
+            ip.to_header().calc_header_checksum().unwrap(),
+            ip.header_checksum()
+        );
+
+        let Some(TransportSlice::Udp(udp)) = packet.transport else {
+            panic!("No UDP header");
+        };
+        assert_eq!(
+            udp.to_header()
+                .calc_checksum_ipv4(&ip.to_header(), packet.payload)
+                .unwrap(),
+            udp.checksum()
+        );
+
+        // 64 byte frame without FCS and with the VLAN header
+        assert_eq!(frame.bytes.len(), 64);
+
+        let pcap = write_pcap(&frames);
+        assert_eq!(pcap.len(), 24 + 16 + 64);
+        assert_eq!(&pcap[24 + 16..], &frame.bytes[..]);
+    }
+
+    #[test]
+    fn vxlan_frame_is_rewritten() {
+        let request = &*EXAMPLE_POST_1_REQUEST;
+        let frames = create_example_frames(&request.streams, &request.stream_settings).unwrap();
+        assert!(!frames.is_empty());
+
+        for frame in frames {
+            let setting = request
+                .stream_settings
+                .iter()
+                .find(|s| s.stream_id == frame.stream_id && s.port == frame.port)
+                .unwrap();
+            let vxlan = setting.vxlan.as_ref().unwrap();
+
+            let outer = SlicedPacket::from_ethernet(&frame.bytes).unwrap();
+            let Some(InternetSlice::Ipv4(ip, _)) = outer.ip else {
+                panic!("No outer IPv4 header");
+            };
+            assert_eq!(ip.source_addr(), vxlan.ip_src);
+            assert_eq!(
+                ip.to_header().calc_header_checksum().unwrap(),
+                ip.header_checksum()
+            );
+            assert_eq!(&frame.bytes[46..49], &vxlan.vni.to_be_bytes()[1..]);
+
+            let inner = SlicedPacket::from_ethernet(&frame.bytes[TUNNEL_HEADER_SIZE..]).unwrap();
+            let Some(InternetSlice::Ipv4(ip, _)) = inner.ip else {
+                panic!("No inner IPv4 header");
+            };
+            assert_eq!(ip.source_addr(), setting.ip.as_ref().unwrap().ip_src);
+        }
+    }
+
+    #[test]
+    fn exported_frames_are_imported() {
+        let vlan = &*EXAMPLE_POST_2_REQUEST;
+        let vxlan = &*EXAMPLE_POST_1_REQUEST;
+
+        let mut streams = vec![vlan.streams[0].clone(), vxlan.streams[0].clone()];
+        let mut settings = vec![
+            vlan.stream_settings[0].clone(),
+            vxlan.stream_settings[0].clone(),
+        ];
+
+        let ipv6 = IPv6 {
+            ipv6_src: Ipv6Addr::from_str("fe80::1").unwrap(),
+            ipv6_dst: Ipv6Addr::from_str("fe80::2").unwrap(),
+            ipv6_traffic_class: 4,
+            ipv6_src_mask: Ipv6Addr::UNSPECIFIED,
+            ipv6_dst_mask: Ipv6Addr::UNSPECIFIED,
+            ipv6_flow_label: 17,
+        };
+
+        // QinQ with IPv6
+        let mut stream = vlan.streams[0].clone();
+        let mut setting = vlan.stream_settings[0].clone();
+        stream.encapsulation = Encapsulation::QinQ;
+        stream.ip_version = Some(6);
+        setting.vlan.as_mut().unwrap().inner_vlan_id = 7;
+        setting.ip = None;
+        setting.ipv6 = Some(ipv6.clone());
+        streams.push(stream);
+        settings.push(setting);
+
+        // MPLS
+        let mut stream = vlan.streams[0].clone();
+        let mut setting = vlan.stream_settings[0].clone();
+        stream.encapsulation = Encapsulation::Mpls;
+        stream.number_of_lse = Some(3);
+        setting.vlan = None;
+        setting.mpls_stack = Some(
+            (1..4)
+                .map(|i| MPLSHeader {
+                    label: 1000 * i,
+                    tc: i,
+                    ttl: 64,
+                })
+                .collect(),
+        );
+        streams.push(stream);
+        settings.push(setting);
+
+        // SRv6 with inner IPv4
+        let mut stream = vlan.streams[0].clone();
+        let mut setting = vlan.stream_settings[0].clone();
+        stream.encapsulation = Encapsulation::SRv6;
+        stream.number_of_srv6_sids = Some(2);
+        stream.srv6_ip_tunneling = Some(true);
+        setting.vlan = None;
+        setting.srv6_base_header = Some(ipv6);
+        setting.sid_list = Some(vec![
+            Ipv6Addr::from_str("ff80::1").unwrap(),
+            Ipv6Addr::from_str("ff80::2").unwrap(),
+        ]);
+        streams.push(stream);
+        settings.push(setting);
+
+        // GTP-U
+        let mut stream = vlan.streams[0].clone();
+        let mut setting = vlan.stream_settings[0].clone();
+        stream.encapsulation = Encapsulation::None;
+        stream.gtpu = true;
+        setting.vlan = None;
+        setting.gtpu = Some(GtpU {
+            ip_src: Ipv4Addr::new(10, 0, 0, 1),
+            ip_dst: Ipv4Addr::new(10, 0, 0, 2),
+            ip_tos: 8,
+            udp_source: 1234,
+            teid: 42,
+        });
+        streams.push(stream);
+        settings.push(setting);
+
+        for (i, (stream, setting)) in streams.iter_mut().zip(settings.iter_mut()).enumerate() {
+            stream.stream_id = i as u8 + 1;
+            stream.app_id = i as u8 + 1;
+            stream.frame_size = 128;
+            stream.traffic_rate = 1f32;
+            setting.stream_id = i as u8 + 1;
+        }
+
+        let frames = create_example_frames(&streams, &settings).unwrap();
+        let import = import_pcap(&write_pcap(&frames), &PcapImportOptions::default()).unwrap();
+
+        assert_eq!(import.issues, vec![]);
+        assert_eq!(
+            serde_json::to_value(&import.traffic_gen.streams).unwrap(),
+            serde_json::to_value(&streams).unwrap()
+        );
+        assert_eq!(
+            serde_json::to_value(&import.traffic_gen.stream_settings).unwrap(),
+            serde_json::to_value(&settings).unwrap()
+        );
+    }
+
+    #[test]
+    fn unsupported_headers_are_reported() {
+        let mut tcp = vec![];
+        etherparse::PacketBuilder::ethernet2([1, 2, 3, 4, 5, 6], [6, 5, 4, 3, 2, 1])
+            .ipv4([10, 0, 0, 1], [10, 0, 0, 2], 64)
+            .tcp(80, 8080, 0, 1024)
+            .write(&mut tcp, &[0; 100])
+            .unwrap();
+
+        let mut arp = vec![0xff; 6];
+        arp.extend_from_slice(&[1, 2, 3, 4, 5, 6, 0x08, 0x06]);
+        arp.extend_from_slice(&[0; 46]);
+
+        let frames: Vec<ExampleFrame> = [&tcp, &arp, &tcp]
+            .into_iter()
+            .map(|bytes| ExampleFrame {
+                port: 1,
+                channel: 0,
+                stream_id: 1,
+                bytes: bytes.clone(),
+            })
+            .collect();
+
+        let options = PcapImportOptions {
+            port: 5,
+            rx_port: Some(6),
+            traffic_rate: 10f32,
+        };
+        let import = import_pcap(&write_pcap(&frames), &options).unwrap();
+
+        assert_eq!(
+            import.issues,
+            vec![
+                PcapImportIssue {
+                    frame: 1,
+                    skipped: false,
+                    message: String::from("IP protocol 6 is replaced by UDP."),
+                },
+                PcapImportIssue {
+                    frame: 2,
+                    skipped: true,
+                    message: String::from("EtherType 0x0806 is not supported."),
+                },
+            ]
+        );
+
+        let traffic_gen = import.traffic_gen;
+        assert_eq!(traffic_gen.streams.len(), 1);
+        assert_eq!(traffic_gen.streams[0].frame_size, tcp.len() as u32 + 4);
+        assert_eq!(traffic_gen.streams[0].traffic_rate, 10f32);
+        assert_eq!(traffic_gen.stream_settings[0].port, 5);
+        assert_eq!(
+            traffic_gen.stream_settings[0].ip.as_ref().unwrap().ip_dst,
+            Ipv4Addr::new(10, 0, 0, 2)
+        );
+        assert_eq!(traffic_gen.port_tx_rx_mapping["5"]["0"].port, 6);
+
+        assert!(import_pcap(&[0; 24], &options).is_err());
+    }
+
+    #[tokio::test]
+    async fn pcap_contains_configured_streams() {
+        let (_, state) = mock_state().await;
+
+        let (status, _) = call(&state, Method::GET, "/trafficgen/pcap", String::new()).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+
+        let traffic_gen = &*EXAMPLE_POST_2_REQUEST;
+        let body = serde_json::to_string(traffic_gen).unwrap();
+        let (status, _) = call(&state, Method::POST, "/trafficgen", body).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let response = request(&state, Method::GET, "/trafficgen/pcap", String::new()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/vnd.tcpdump.pcap"
+        );
+
+        let pcap = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let frames = read_pcap(&pcap).unwrap();
+        assert_eq!(frames.len(), traffic_gen.stream_settings.len());
+
+        for (frame, setting) in frames.iter().zip(&traffic_gen.stream_settings) {
+            let stream = traffic_gen
+                .streams
+                .iter()
+                .find(|s| s.stream_id == setting.stream_id)
+                .unwrap();
+            assert_eq!(frame.len, stream.frame_size as usize);
+
+            let packet = SlicedPacket::from_ethernet(frame.bytes).unwrap();
+
+            let Some(etherparse::LinkSlice::Ethernet2(eth)) = packet.link else {
+                panic!("No Ethernet header");
+            };
+            let eth_src: MacAddr6 = setting.ethernet.eth_src.parse().unwrap();
+            let eth_dst: MacAddr6 = setting.ethernet.eth_dst.parse().unwrap();
+            assert_eq!(eth.source(), eth_src.into_array());
+            assert_eq!(eth.destination(), eth_dst.into_array());
+
+            let Some(VlanSlice::SingleVlan(vlan)) = packet.vlan else {
+                panic!("No VLAN header");
+            };
+            let expected_vlan = setting.vlan.as_ref().unwrap();
+            assert_eq!(vlan.vlan_identifier(), expected_vlan.vlan_id);
+            assert_eq!(vlan.priority_code_point(), expected_vlan.pcp);
+
+            let Some(InternetSlice::Ipv4(ip, _)) = packet.ip else {
+                panic!("No IPv4 header");
+            };
+            let expected_ip = setting.ip.as_ref().unwrap();
+            assert_eq!(ip.source_addr(), expected_ip.ip_src);
+            assert_eq!(ip.destination_addr(), expected_ip.ip_dst);
+        }
+    }
+}
@@ -20,11 +20,12 @@
 
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::net::Ipv4Addr;
 use std::net::Ipv6Addr;
 use utoipa::ToSchema;
 
+use crate::core::sla::SlaConfig;
 use crate::core::statistics::HistogramConfig;
 
 /// Describes the supported encapsulations of P4TG.
@@ -85,6 +86,7 @@ pub struct StreamPacket {
 /// This is used to monitor stream-based traffic rates in the data plane.
 /// There is a unique index per (`port`, `app_id`) combination.
 #[derive(Debug, Clone)]
+#[allow(dead_code)]
 pub struct MonitoringMapping {
     /// index that is used in the data plane to access the app rate register
     pub index: u32,
@@ -151,6 +153,7 @@ pub struct MPLSHeader {
 
 #[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 #[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
 pub enum TrafficGenTests {
     SingleTest(TrafficGenData),
     MultipleTest(Vec<TrafficGenData>),
@@ -181,6 +184,20 @@ pub struct TrafficGenData {
     /// The name of the test. This is used to identify the test in the UI.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub(crate) name: Option<String>,
+    /// Acceptance criteria of this test. The verdict is reported in the statistics.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) sla: Option<SlaConfig>,
+    /// Resolution of the time statistics of this test in ms. Defaults to 1000 ms.
+    /// Must be at least the interval of the monitoring packet, i.e., 500 ms.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) time_resolution: Option<u32>,
+    /// Duration in seconds at the start of the test that is excluded from the statistics, e.g., for MAC learning of the DUT.
+    /// Defaults to 0. In sample mode, IAT samples are excluded for at least 3 seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) warmup: Option<u32>,
+    /// Duration in seconds at the end of the test that is excluded from the statistics. Requires a duration.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) cooldown: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, ToSchema)]
@@ -411,3 +428,181 @@ pub enum HistogramType {
     Rtt,
     Iat,
 }
+
+/// Describes the data plane configuration of a traffic generation without applying it.
+/// Returned by the dry run on POST /trafficgen/plan.
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct TrafficGenPlan {
+    /// Streams including the calculated sending behaviour, i.e., `n_packets`, `timeout`, and `generation_accuracy`.
+    pub streams: Vec<Stream>,
+    /// Offset (bytes) of each stream packet in the packet buffer of the internal traffic generator.
+    /// The key is the app_id. Empty in analyze mode.
+    pub buffer_offsets: BTreeMap<u8, u32>,
+    /// Tables that are cleared before the entries are written.
+    pub cleared_tables: Vec<String>,
+    /// Table entries that are written or updated, including the multicast groups (`$pre.node`, `$pre.mgid`).
+    /// Sorted by table name and match keys.
+    pub entries: Vec<PlannedTableEntry>,
+}
+
+/// Operation of a [PlannedTableEntry] on the switch.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryOperation {
+    Write,
+    Update,
+}
+
+/// A single table entry of a [TrafficGenPlan].
+/// All values are encoded as hex strings of their byte representation.
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct PlannedTableEntry {
+    /// Name of the table
+    pub table: String,
+    /// Operation that is applied to the entry
+    pub operation: EntryOperation,
+    /// Match keys of the entry. Range matches are encoded as `lower..higher`,
+    /// LPM matches as `value/prefix_length`, and ternary matches as `value&&&mask`.
+    pub match_keys: BTreeMap<String, String>,
+    /// Name of the action, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    /// Action data of the entry
+    pub action_data: BTreeMap<String, String>,
+    /// Repeated action data of the entry, e.g., the dev ports of a multicast node
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub action_data_repeated: BTreeMap<String, Vec<String>>,
+    /// Indicates if this is the default entry of the table
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub default_entry: bool,
+}
+
+/// Options of the stream import from a pcap file on POST /trafficgen/import.
+#[derive(Deserialize, Debug, Clone)]
+pub struct PcapImportOptions {
+    /// Front panel port on which the imported streams are sent.
+    #[serde(default = "PcapImportOptions::default_port")]
+    pub port: u32,
+    /// Front panel port on which the imported streams are received. Defaults to `port`.
+    #[serde(default)]
+    pub rx_port: Option<u32>,
+    /// Traffic rate in Gbps of each imported stream.
+    #[serde(default = "PcapImportOptions::default_traffic_rate")]
+    pub traffic_rate: f32,
+}
+
+impl PcapImportOptions {
+    fn default_port() -> u32 {
+        1
+    }
+
+    fn default_traffic_rate() -> f32 {
+        1f32
+    }
+}
+
+impl Default for PcapImportOptions {
+    fn default() -> Self {
+        PcapImportOptions {
+            port: Self::default_port(),
+            rx_port: None,
+            traffic_rate: Self::default_traffic_rate(),
+        }
+    }
+}
+
+/// Result of the stream import from a pcap file.
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct PcapImport {
+    /// Traffic generation with one stream per distinct frame of the pcap file.
+    /// Can be sent as is to POST /trafficgen.
+    pub traffic_gen: TrafficGenData,
+    /// Headers of the captured frames that could not be represented by the imported streams.
+    pub issues: Vec<PcapImportIssue>,
+}
+
+/// A header of a captured frame that could not be represented.
+#[derive(Serialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct PcapImportIssue {
+    /// Number of the frame in the pcap file, starting at 1.
+    pub frame: usize,
+    /// Indicates if the frame was skipped. Otherwise, the frame is imported without the header.
+    pub skipped: bool,
+    /// Description of the header
+    pub message: String,
+}
+
+/// Feasibility analysis of a traffic generation.
+/// Returned on POST /trafficgen/feasibility and as part of the response on POST /trafficgen.
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct FeasibilityReport {
+    /// Indicates if the traffic generation can be generated as configured, i.e., a sending behaviour exists for each stream,
+    /// the total rate and the load of each port are within their capacity, and the stream packets fit into the packet buffer.
+    pub feasible: bool,
+    /// Sum of the L1 rates (Gbps) of all streams that are generated by the internal traffic generator.
+    pub total_rate: f64,
+    /// Maximal rate (Gbps) of the internal traffic generator.
+    pub max_rate: f64,
+    /// Analysis of each active stream.
+    pub streams: Vec<StreamFeasibility>,
+    /// Analysis of each (port, channel) to which streams are sent. Sorted by port and channel.
+    pub ports: Vec<PortFeasibility>,
+    /// Usage of the packet buffer of the internal traffic generator.
+    pub buffer: BufferUsage,
+    /// Description of each problem that makes the traffic generation infeasible.
+    pub issues: Vec<String>,
+}
+
+/// Feasibility analysis of a single stream.
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct StreamFeasibility {
+    pub stream_id: u8,
+    pub app_id: u8,
+    /// Size (bytes) of a frame on the wire, i.e., including encapsulation, preamble, and inter frame gap.
+    pub l1_frame_size: u32,
+    /// Requested L1 rate in Gbps. Rates in Mpps are converted to Gbps.
+    pub traffic_rate: f64,
+    /// Number of pipes that generate the stream. Each pipe generates `traffic_rate / n_pipes`.
+    pub n_pipes: u8,
+    /// Number of packets that are sent per timeout on each pipe.
+    pub n_packets: Option<u16>,
+    /// Timeout in ns
+    pub timeout: Option<u32>,
+    /// Predicted L1 rate (Gbps) of the calculated sending behaviour.
+    pub generated_rate: Option<f64>,
+    /// Predicted packet rate in Mpps
+    pub packet_rate: Option<f64>,
+    /// Predicted accuracy (percent) of the generated rate.
+    pub generation_accuracy: Option<f32>,
+    /// Reason why no sending behaviour exists for the stream.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Load of a (port, channel) to which streams are sent.
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct PortFeasibility {
+    /// Front panel port
+    pub port: u32,
+    pub channel: u8,
+    /// Capacity (Gbps) of the port, i.e., the configured port speed.
+    /// The maximal rate of the internal traffic generator is used if the speed of the port is unknown.
+    pub capacity: f64,
+    /// Sum of the requested L1 rates (Gbps) of all streams that are sent on the port.
+    pub load: f64,
+    /// Load relative to the capacity in percent
+    pub utilization: f64,
+    /// Indicates if the load exceeds the capacity
+    pub oversubscribed: bool,
+    /// IDs of the streams that are sent on the port
+    pub stream_ids: Vec<u8>,
+}
+
+/// Usage of the packet buffer of the internal traffic generator.
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct BufferUsage {
+    /// Bytes used by the stream packets, including the 16 byte alignment of each packet.
+    pub used: u32,
+    /// Bytes usable by the stream packets
+    pub available: u32,
+}
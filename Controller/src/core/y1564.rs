@@ -0,0 +1,837 @@
+/* Copyright 2022-present University of Tuebingen, Chair of Communication Networks
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *   http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/*
+ * Steffen Lindner (steffen.lindner@uni-tuebingen.de)
+ */
+
+use std::sync::Arc;
+
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use utoipa::ToSchema;
+
+use crate::core::rfc2544::{run_trial, stop_trial, TrialCounters, TrialData};
+use crate::core::traffic_gen_core::helper::{calculate_overhead, normalize_stream};
+use crate::core::traffic_gen_core::types::{GenerationMode, GenerationUnit, TrafficGenData};
+use crate::AppState;
+
+/// Acceptance criteria of a Y.1564 service.
+/// The frame delay is measured as RTT.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct Y1564Criteria {
+    /// Maximal mean frame delay in ns
+    #[serde(default)]
+    pub max_frame_delay: Option<f64>,
+    /// Maximal frame delay variation, i.e., the standard deviation of the frame delay, in ns
+    #[serde(default)]
+    pub max_delay_variation: Option<f64>,
+    /// Maximal frame loss in percent
+    #[serde(default)]
+    pub max_loss: f64,
+    /// Accepted deviation of the information rate in percent
+    #[serde(default = "Y1564Criteria::default_ir_tolerance")]
+    pub ir_tolerance: f64,
+}
+
+impl Y1564Criteria {
+    fn default_ir_tolerance() -> f64 {
+        1f64
+    }
+}
+
+impl Default for Y1564Criteria {
+    fn default() -> Self {
+        Y1564Criteria {
+            max_frame_delay: None,
+            max_delay_variation: None,
+            max_loss: 0f64,
+            ir_tolerance: Y1564Criteria::default_ir_tolerance(),
+        }
+    }
+}
+
+/// Bandwidth profile and acceptance criteria of a Y.1564 service.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct Y1564Service {
+    /// Stream that carries the service
+    pub stream_id: u8,
+    /// Name of the service
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Committed information rate (L2) in Gbps
+    pub cir: f64,
+    /// Excess information rate (L2) in Gbps
+    #[serde(default)]
+    pub eir: f64,
+    /// Indicates if the device under test polices the service to CIR + EIR.
+    /// Adds an overshoot step to the service configuration test.
+    #[serde(default)]
+    pub policing: bool,
+    #[serde(default)]
+    pub criteria: Y1564Criteria,
+}
+
+/// Configuration of an ITU-T Y.1564 service activation test.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct Y1564Config {
+    /// Traffic generation that carries the services.
+    /// The traffic rates of the service streams are set to the rates of the test steps.
+    /// Streams that are not mapped to a service are not generated.
+    pub traffic_gen: TrafficGenData,
+    pub services: Vec<Y1564Service>,
+    /// Steps of the service configuration test up to the CIR in percent of the CIR.
+    #[serde(default = "Y1564Config::default_cir_steps")]
+    pub cir_steps: Vec<f64>,
+    /// Rate of the overshoot step in percent above CIR + EIR.
+    #[serde(default = "Y1564Config::default_overshoot")]
+    pub overshoot: f64,
+    /// Duration of a step of the service configuration test in seconds.
+    #[serde(default = "Y1564Config::default_step_duration")]
+    pub step_duration: u32,
+    /// Duration of the service performance test in seconds.
+    #[serde(default = "Y1564Config::default_performance_duration")]
+    pub performance_duration: u32,
+    /// Time in seconds after a step before the loss counters are read.
+    #[serde(default = "Y1564Config::default_wait_time")]
+    pub wait_time: u32,
+    /// Indicates if the service configuration test is run.
+    #[serde(default = "Y1564Config::default_enabled")]
+    pub configuration_test: bool,
+    /// Indicates if the service performance test is run.
+    #[serde(default = "Y1564Config::default_enabled")]
+    pub performance_test: bool,
+}
+
+/// Step of a Y.1564 service activation test.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, ToSchema)]
+pub enum Y1564Step {
+    /// Configuration test step up to the CIR
+    Cir,
+    /// Configuration test step at CIR + EIR
+    Eir,
+    /// Configuration test step above CIR + EIR
+    Overshoot,
+    /// Service performance test at the CIR
+    Performance,
+}
+
+impl Y1564Config {
+    fn default_cir_steps() -> Vec<f64> {
+        vec![25f64, 50f64, 75f64, 100f64]
+    }
+
+    fn default_overshoot() -> f64 {
+        25f64
+    }
+
+    fn default_step_duration() -> u32 {
+        60
+    }
+
+    fn default_performance_duration() -> u32 {
+        900
+    }
+
+    fn default_wait_time() -> u32 {
+        2
+    }
+
+    fn default_enabled() -> bool {
+        true
+    }
+
+    /// Validates the services and test parameters.
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.configuration_test && !self.performance_test {
+            return Err("No test selected.".to_owned());
+        }
+
+        if self.services.is_empty() {
+            return Err("No services provided.".to_owned());
+        }
+
+        if self.traffic_gen.mode != GenerationMode::Cbr {
+            return Err("Y.1564 tests require CBR generation mode.".to_owned());
+        }
+
+        let mut app_ids = vec![];
+
+        for service in &self.services {
+            let Some(stream) = self
+                .traffic_gen
+                .streams
+                .iter()
+                .find(|s| s.stream_id == service.stream_id)
+            else {
+                return Err(format!("Stream {} does not exist.", service.stream_id));
+            };
+
+            if !self
+                .traffic_gen
+                .stream_settings
+                .iter()
+                .any(|s| s.stream_id == service.stream_id && s.active)
+            {
+                return Err(format!(
+                    "Stream {} is not active on any port.",
+                    service.stream_id
+                ));
+            }
+
+            if app_ids.contains(&stream.app_id) {
+                return Err("Services require streams with distinct app ids.".to_owned());
+            }
+
+            app_ids.push(stream.app_id);
+
+            if stream.unit == Some(GenerationUnit::Mpps) {
+                return Err(format!(
+                    "Stream {} must be generated in Gbps.",
+                    service.stream_id
+                ));
+            }
+
+            if !(service.cir > 0f64 && service.eir >= 0f64) {
+                return Err(format!(
+                    "CIR of service {} must be positive and EIR must not be negative.",
+                    service.stream_id
+                ));
+            }
+
+            let criteria = &service.criteria;
+
+            if !(0f64..100f64).contains(&criteria.max_loss)
+                || !(0f64..100f64).contains(&criteria.ir_tolerance)
+                || criteria.max_frame_delay.is_some_and(|d| d < 0f64)
+                || criteria.max_delay_variation.is_some_and(|d| d < 0f64)
+            {
+                return Err(format!(
+                    "Invalid acceptance criteria of service {}.",
+                    service.stream_id
+                ));
+            }
+        }
+
+        if self.cir_steps.is_empty() || self.cir_steps.iter().any(|s| !(*s > 0f64 && *s <= 100f64))
+        {
+            return Err("CIR steps must be in (0, 100].".to_owned());
+        }
+
+        if self.overshoot < 0f64 {
+            return Err("Overshoot must not be negative.".to_owned());
+        }
+
+        // the information rate is sampled starting 2 seconds after the start of a step
+        if self.step_duration < 3 || self.performance_duration < 3 {
+            return Err(
+                "Step and performance test durations must be at least 3 seconds.".to_owned(),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Steps of the service configuration test of `service`, i.e., the step and the information rate in Gbps.
+    pub(crate) fn ramp(&self, service: &Y1564Service) -> Vec<(Y1564Step, f64)> {
+        let mut steps: Vec<(Y1564Step, f64)> = self
+            .cir_steps
+            .iter()
+            .map(|s| (Y1564Step::Cir, service.cir * s / 100f64))
+            .collect();
+
+        if service.eir > 0f64 {
+            steps.push((Y1564Step::Eir, service.cir + service.eir));
+        }
+
+        if service.policing {
+            steps.push((
+                Y1564Step::Overshoot,
+                (service.cir + service.eir) * (1f64 + self.overshoot / 100f64),
+            ));
+        }
+
+        steps
+    }
+
+    /// Returns the traffic generation in which only the services `rates` are generated.
+    ///
+    /// - `rates`: Stream ids of the services and their information rates (L2) in Gbps
+    /// - `duration`: Duration of the traffic generation in seconds
+    pub fn trial(&self, rates: &[(u8, f64)], duration: u32) -> TrafficGenData {
+        let mut traffic_gen = self.traffic_gen.clone();
+
+        for s in traffic_gen.streams.iter_mut() {
+            if let Some((_, rate)) = rates.iter().find(|(id, _)| *id == s.stream_id) {
+                let normalized = normalize_stream(s.clone(), traffic_gen.mode);
+                let l2_frame_size =
+                    (normalized.frame_size + calculate_overhead(&normalized)) as f64;
+
+                s.traffic_rate = (rate * (l2_frame_size + 20f64) / l2_frame_size) as f32;
+                s.unit = Some(GenerationUnit::Gbps);
+            }
+        }
+
+        for s in traffic_gen.stream_settings.iter_mut() {
+            s.active = s.active && rates.iter().any(|(id, _)| *id == s.stream_id);
+        }
+
+        traffic_gen.duration = Some(duration);
+        // trials are evaluated by the benchmark itself
+        traffic_gen.sla = None;
+        traffic_gen.warmup = None;
+        traffic_gen.cooldown = None;
+
+        traffic_gen
+    }
+
+    /// Traffic generation of the service performance test, i.e., all services at their CIR.
+    pub fn performance_trial(&self) -> TrafficGenData {
+        let rates: Vec<(u8, f64)> = self.services.iter().map(|s| (s.stream_id, s.cir)).collect();
+
+        self.trial(&rates, self.performance_duration)
+    }
+}
+
+/// Measurement of a service during a step.
+#[derive(Serialize, Debug, Clone, Default, ToSchema)]
+pub struct ServiceMeasurement {
+    /// Mean received information rate (L2) in Gbps
+    pub information_rate: f64,
+    /// Mean frame delay (RTT) in ns
+    pub frame_delay: f64,
+    /// Maximal frame delay (RTT) in ns
+    pub max_frame_delay: u64,
+    /// Frame delay variation, i.e., the standard deviation of the frame delay, in ns
+    pub delay_variation: f64,
+    #[serde(flatten)]
+    pub counters: TrialCounters,
+    /// Frame loss in percent
+    pub loss: f64,
+}
+
+impl ServiceMeasurement {
+    /// Measurement of `service` in the trial `data`.
+    /// Frame delay and loss are measured per stream, so that services that share a port are judged independently.
+    fn new(config: &Y1564Config, service: &Y1564Service, data: &TrialData) -> ServiceMeasurement {
+        let measurement = data.measure_stream(service.stream_id);
+        let information_rate = config
+            .traffic_gen
+            .streams
+            .iter()
+            .find(|s| s.stream_id == service.stream_id)
+            .and_then(|s| data.app_rx_rates.get(&(s.app_id as u32)))
+            .copied()
+            .unwrap_or(0f64);
+
+        ServiceMeasurement {
+            information_rate,
+            frame_delay: measurement.latency.mean,
+            max_frame_delay: measurement.latency.max,
+            delay_variation: measurement.latency.jitter,
+            counters: measurement.counters,
+            loss: measurement.counters.loss(),
+        }
+    }
+}
+
+/// Evaluates the measurement of a step against the acceptance criteria of `service`.
+/// Returns the violated criteria.
+///
+/// Frame delay, delay variation, and loss are only evaluated up to the CIR.
+/// Above the CIR, the information rate has to be at least the CIR and at most CIR + EIR.
+pub(crate) fn evaluate(
+    service: &Y1564Service,
+    step: Y1564Step,
+    rate: f64,
+    measurement: &ServiceMeasurement,
+) -> Vec<String> {
+    let criteria = &service.criteria;
+    let lower = 1f64 - criteria.ir_tolerance / 100f64;
+    let upper = 1f64 + criteria.ir_tolerance / 100f64;
+    let ir = measurement.information_rate;
+    let mut failures = vec![];
+
+    match step {
+        Y1564Step::Cir | Y1564Step::Performance => {
+            if ir < rate * lower {
+                failures.push(format!(
+                    "Information rate of {ir:.3} Gbps is below {rate:.3} Gbps."
+                ));
+            }
+
+            if measurement.loss > criteria.max_loss {
+                failures.push(format!(
+                    "Frame loss of {}% exceeds {}%.",
+                    measurement.loss, criteria.max_loss
+                ));
+            }
+
+            if let Some(max) = criteria.max_frame_delay {
+                if measurement.frame_delay > max {
+                    failures.push(format!(
+                        "Frame delay of {:.0} ns exceeds {max} ns.",
+                        measurement.frame_delay
+                    ));
+                }
+            }
+
+            if let Some(max) = criteria.max_delay_variation {
+                if measurement.delay_variation > max {
+                    failures.push(format!(
+                        "Frame delay variation of {:.0} ns exceeds {max} ns.",
+                        measurement.delay_variation
+                    ));
+                }
+            }
+        }
+        Y1564Step::Eir | Y1564Step::Overshoot => {
+            if ir < service.cir * lower {
+                failures.push(format!(
+                    "Information rate of {ir:.3} Gbps is below the CIR of {:.3} Gbps.",
+                    service.cir
+                ));
+            }
+
+            if ir > (service.cir + service.eir) * upper {
+                failures.push(format!(
+                    "Information rate of {ir:.3} Gbps exceeds CIR + EIR of {:.3} Gbps.",
+                    service.cir + service.eir
+                ));
+            }
+        }
+    }
+
+    failures
+}
+
+/// Result of a step of a Y.1564 service activation test.
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct Y1564StepResult {
+    pub step: Y1564Step,
+    /// Offered information rate (L2) in Gbps
+    pub rate: f64,
+    #[serde(flatten)]
+    pub measurement: ServiceMeasurement,
+    /// Indicates if all acceptance criteria are met
+    pub passed: bool,
+    /// Violated acceptance criteria
+    pub failures: Vec<String>,
+}
+
+/// Report of a Y.1564 service.
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct Y1564ServiceReport {
+    pub stream_id: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Steps of the service configuration test
+    pub configuration: Vec<Y1564StepResult>,
+    /// Service performance test
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub performance: Option<Y1564StepResult>,
+    /// Indicates if all completed steps passed
+    pub passed: bool,
+}
+
+/// State and report of the Y.1564 service activation test.
+#[derive(Serialize, Debug, Clone, Default, ToSchema)]
+pub struct Y1564Result {
+    /// Indicates if the test is running
+    pub running: bool,
+    /// Indicates if the test was aborted
+    pub aborted: bool,
+    /// Error that aborted the test
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Indicates if all services passed. Only set once the test is completed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub passed: Option<bool>,
+    /// Report per service
+    pub services: Vec<Y1564ServiceReport>,
+}
+
+/// Stores the Y.1564 test task and its report
+pub struct Y1564 {
+    pub(crate) task: Mutex<Y1564Task>,
+    pub(crate) result: Mutex<Y1564Result>,
+}
+
+#[derive(Default)]
+pub struct Y1564Task {
+    pub handle: Option<JoinHandle<()>>,
+    pub cancel_token: Option<CancellationToken>,
+}
+
+impl Y1564Task {
+    /// Starts a task that runs the Y.1564 service activation test `config`.
+    /// The report is stored in the app state.
+    ///
+    /// - `state`: App state that holds the report
+    /// - `config`: Validated test configuration
+    pub async fn start(&mut self, state: &Arc<AppState>, config: Y1564Config) {
+        let cancel_token = CancellationToken::new();
+        let cancel_token_clone = cancel_token.clone();
+        let state_clone = state.clone();
+
+        *state.y1564.result.lock().await = Y1564Result {
+            running: true,
+            services: config
+                .services
+                .iter()
+                .map(|s| Y1564ServiceReport {
+                    stream_id: s.stream_id,
+                    name: s.name.clone(),
+                    configuration: vec![],
+                    performance: None,
+                    passed: true,
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let handle = tokio::spawn(async move {
+            let result = Self::run(&state_clone, &config, &cancel_token_clone).await;
+
+            if cancel_token_clone.is_cancelled() {
+                stop_trial(&state_clone).await;
+            }
+
+            let mut state_result = state_clone.y1564.result.lock().await;
+            state_result.running = false;
+            state_result.aborted = cancel_token_clone.is_cancelled();
+
+            match result {
+                Ok(Some(_)) => {
+                    state_result.passed = Some(state_result.services.iter().all(|s| s.passed))
+                }
+                Ok(None) => {}
+                Err(message) => {
+                    error!("Y.1564 test failed: {message}");
+                    state_result.error = Some(message);
+                }
+            }
+        });
+
+        self.handle = Some(handle);
+        self.cancel_token = Some(cancel_token);
+    }
+
+    /// Runs the service configuration test of each service and the service performance test.
+    /// Returns `Ok(None)` if the test was cancelled.
+    async fn run(
+        state: &Arc<AppState>,
+        config: &Y1564Config,
+        cancel_token: &CancellationToken,
+    ) -> Result<Option<()>, String> {
+        if config.configuration_test {
+            for (index, service) in config.services.iter().enumerate() {
+                for (step, rate) in config.ramp(service) {
+                    info!(
+                        "Y.1564 {step:?} step of service {} at {rate} Gbps.",
+                        service.stream_id
+                    );
+
+                    let payload = config.trial(&[(service.stream_id, rate)], config.step_duration);
+
+                    let Some(data) =
+                        run_trial(state, payload, None, config.wait_time, cancel_token).await?
+                    else {
+                        return Ok(None);
+                    };
+
+                    let result = Self::step_result(config, service, step, rate, &data);
+                    let report = &mut state.y1564.result.lock().await.services[index];
+
+                    report.passed &= result.passed;
+                    report.configuration.push(result);
+                }
+            }
+        }
+
+        if config.performance_test {
+            info!("Y.1564 service performance test.");
+
+            let Some(data) = run_trial(
+                state,
+                config.performance_trial(),
+                None,
+                config.wait_time,
+                cancel_token,
+            )
+            .await?
+            else {
+                return Ok(None);
+            };
+
+            let mut result = state.y1564.result.lock().await;
+
+            for (index, service) in config.services.iter().enumerate() {
+                let step_result =
+                    Self::step_result(config, service, Y1564Step::Performance, service.cir, &data);
+                let report = &mut result.services[index];
+
+                report.passed &= step_result.passed;
+                report.performance = Some(step_result);
+            }
+        }
+
+        Ok(Some(()))
+    }
+
+    fn step_result(
+        config: &Y1564Config,
+        service: &Y1564Service,
+        step: Y1564Step,
+        rate: f64,
+        data: &TrialData,
+    ) -> Y1564StepResult {
+        let measurement = ServiceMeasurement::new(config, service, data);
+        let failures = evaluate(service, step, rate, &measurement);
+
+        Y1564StepResult {
+            step,
+            rate,
+            measurement,
+            passed: failures.is_empty(),
+            failures,
+        }
+    }
+
+    /// Cancels the test task if it is running and waits until it is stopped.
+    pub async fn cancel_existing_task(&mut self) {
+        if let Some(token) = self.cancel_token.take() {
+            token.cancel();
+            info!("Y.1564 task cancelled.")
+        }
+
+        if let Some(handle) = self.handle.take() {
+            if let Err(e) = handle.await {
+                error!("Y.1564 task join error: {e}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::docs::traffic_gen::EXAMPLE_POST_2_REQUEST;
+    use crate::core::backend::mock::tests::{call, mock_state, wait_for_traffic_gen};
+    use crate::core::traffic_gen_core::types::{Stream, StreamSetting};
+    use axum::http::{Method, StatusCode};
+    use std::collections::{HashMap, VecDeque};
+
+    fn service(eir: f64, policing: bool) -> Y1564Service {
+        Y1564Service {
+            stream_id: 1,
+            name: None,
+            cir: 10f64,
+            eir,
+            policing,
+            criteria: Y1564Criteria {
+                max_frame_delay: Some(2000f64),
+                max_delay_variation: None,
+                max_loss: 0f64,
+                ir_tolerance: 1f64,
+            },
+        }
+    }
+
+    fn measurement(
+        information_rate: f64,
+        lost_frames: u64,
+        frame_delay: f64,
+    ) -> ServiceMeasurement {
+        let counters = TrialCounters {
+            tx_frames: 1000,
+            rx_frames: 1000 - lost_frames,
+            lost_frames,
+        };
+
+        ServiceMeasurement {
+            information_rate,
+            frame_delay,
+            counters,
+            loss: counters.loss(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn ramp_steps_up_to_overshoot() {
+        let config: Y1564Config = serde_json::from_value(serde_json::json!({
+            "traffic_gen": *EXAMPLE_POST_2_REQUEST,
+            "services": [],
+        }))
+        .unwrap();
+
+        let steps: Vec<Y1564Step> = config
+            .ramp(&service(0f64, false))
+            .iter()
+            .map(|s| s.0)
+            .collect();
+        assert_eq!(steps, vec![Y1564Step::Cir; 4]);
+
+        assert_eq!(
+            config.ramp(&service(2f64, true)),
+            vec![
+                (Y1564Step::Cir, 2.5),
+                (Y1564Step::Cir, 5f64),
+                (Y1564Step::Cir, 7.5),
+                (Y1564Step::Cir, 10f64),
+                (Y1564Step::Eir, 12f64),
+                (Y1564Step::Overshoot, 15f64),
+            ]
+        );
+    }
+
+    #[test]
+    fn steps_are_evaluated_against_criteria() {
+        let s = service(2f64, true);
+
+        assert!(evaluate(&s, Y1564Step::Cir, 10f64, &measurement(9.95, 0, 1000f64)).is_empty());
+        assert_eq!(
+            evaluate(&s, Y1564Step::Cir, 10f64, &measurement(9f64, 1, 3000f64)).len(),
+            3
+        );
+
+        // loss and delay above the CIR are accepted
+        assert!(evaluate(&s, Y1564Step::Eir, 12f64, &measurement(11f64, 100, 5000f64)).is_empty());
+
+        // the overshoot is not policed
+        assert_eq!(
+            evaluate(
+                &s,
+                Y1564Step::Overshoot,
+                15f64,
+                &measurement(15f64, 0, 0f64)
+            )
+            .len(),
+            1
+        );
+        assert!(evaluate(
+            &s,
+            Y1564Step::Overshoot,
+            15f64,
+            &measurement(12f64, 0, 0f64)
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn services_on_a_shared_port_are_measured_per_stream() {
+        let traffic_gen = &*EXAMPLE_POST_2_REQUEST;
+        let streams: Vec<Stream> = [1, 2]
+            .into_iter()
+            .map(|id| Stream {
+                stream_id: id,
+                app_id: id,
+                ..traffic_gen.streams[0].clone()
+            })
+            .collect();
+        let stream_settings: Vec<StreamSetting> = [1, 2]
+            .into_iter()
+            .map(|id| StreamSetting {
+                stream_id: id,
+                port: 128,
+                ..traffic_gen.stream_settings[0].clone()
+            })
+            .collect();
+
+        let config: Y1564Config = serde_json::from_value(serde_json::json!({
+            "traffic_gen": TrafficGenData {
+                streams: streams.clone(),
+                stream_settings: stream_settings.clone(),
+                ..traffic_gen.clone()
+            },
+            "services": [{"stream_id": 1, "cir": 10}, {"stream_id": 2, "cir": 10}],
+        }))
+        .unwrap();
+
+        // only the stream of the second service loses frames on the shared RX port 136
+        let data = TrialData {
+            streams,
+            mode: traffic_gen.mode,
+            stream_settings,
+            tx_rx_port_mapping: HashMap::from([("128".to_owned(), 136)]),
+            tx_frames: HashMap::from([(128, 2000)]),
+            rx_frames: HashMap::from([(136, 1950)]),
+            app_lost_frames: HashMap::from([(136, HashMap::from([(1, 0), (2, 50)]))]),
+            rtts: HashMap::from([(136, VecDeque::from([1000, 1000, 5000, 5000]))]),
+            app_rtts: HashMap::from([(
+                136,
+                HashMap::from([
+                    (1, VecDeque::from([1000, 1000])),
+                    (2, VecDeque::from([5000, 5000])),
+                ]),
+            )]),
+            percentiles: HashMap::new(),
+            app_rx_rates: HashMap::from([(1, 10f64), (2, 10f64)]),
+        };
+
+        let first = ServiceMeasurement::new(&config, &config.services[0], &data);
+        assert_eq!(first.counters.tx_frames, 1000);
+        assert_eq!(first.counters.lost_frames, 0);
+        assert_eq!(first.frame_delay, 1000f64);
+        assert!(evaluate(&config.services[0], Y1564Step::Cir, 10f64, &first).is_empty());
+
+        let second = ServiceMeasurement::new(&config, &config.services[1], &data);
+        assert_eq!(second.counters.tx_frames, 1000);
+        assert_eq!(second.counters.lost_frames, 50);
+        assert_eq!(second.loss, 5f64);
+        assert_eq!(second.frame_delay, 5000f64);
+        assert_eq!(
+            evaluate(&config.services[1], Y1564Step::Cir, 10f64, &second).len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn y1564_test_is_started_and_aborted() {
+        let (_, state) = mock_state().await;
+
+        let mut config = serde_json::json!({
+            "traffic_gen": *EXAMPLE_POST_2_REQUEST,
+            "services": [{"stream_id": 2, "cir": 10}],
+        });
+        let (status, _) = call(&state, Method::POST, "/y1564", config.to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        config["services"] = serde_json::json!([{"stream_id": 1, "cir": 10, "eir": 2}]);
+        let (status, result) = call(&state, Method::POST, "/y1564", config.to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(result["running"], true);
+        assert_eq!(result["services"][0]["stream_id"], 1);
+
+        // the first step runs at 25% of the CIR
+        wait_for_traffic_gen(&state).await;
+        assert_eq!(state.traffic_generator.lock().await.duration, Some(60));
+
+        let (status, result) = call(&state, Method::DELETE, "/y1564", String::new()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(result["running"], false);
+        assert_eq!(result["aborted"], true);
+        assert!(result.get("passed").is_none());
+        assert!(result["services"][0]["configuration"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+        assert!(!state.traffic_generator.lock().await.running);
+    }
+}
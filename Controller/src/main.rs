@@ -18,8 +18,8 @@
  */
 use log::{info, warn};
 use macaddr::MacAddr;
+use rbfrt::error::RBFRTError;
 use rbfrt::table::ActionData;
-use rbfrt::util::PortManager;
 use rbfrt::{table, SwitchConnection};
 use std::collections::HashMap;
 use std::env;
@@ -33,11 +33,21 @@ mod error;
 
 use crate::api::statistics::StatisticsApi;
 use crate::api::statistics::TimeStatisticsApi;
+use crate::core::archive::Archive;
+use crate::core::backend::{
+    DataPlaneEmulator, EmulatorConfig, MockSwitch, RbfrtBackend, RecordingBackend, ReplayBackend,
+    SwitchBackend,
+};
+use crate::core::measurement_window;
+use crate::core::rfc2544::Rfc2544;
+use crate::core::sla::SlaMonitor;
+use crate::core::statistics_stream::{publish_statistics, EventBus};
 use crate::core::traffic_gen_core::const_definitions::{
     DEVICE_CONFIGURATION, DEVICE_CONFIGURATION_TF2, PORT_CFG_TF2,
 };
 use crate::core::traffic_gen_core::event::TrafficGenEvent;
-use crate::core::traffic_gen_core::types::HistogramType;
+use crate::core::traffic_gen_core::types::{HistogramType, MonitoringMapping, TrafficGenData};
+use crate::core::y1564::Y1564;
 use crate::core::{
     configure_ports, Arp, Config, DurationMonitorTask, FrameSizeMonitor, FrameTypeMonitor,
     HistogramMonitor, RateMonitor, TrafficGen,
@@ -76,6 +86,7 @@ pub struct Experiment {
 pub struct MultiTest {
     pub(crate) collected_statistics: Mutex<Vec<StatisticsApi>>,
     pub(crate) collected_time_statistics: Mutex<Vec<TimeStatisticsApi>>,
+    pub(crate) collected_traffic_gen: Mutex<Vec<TrafficGenData>>,
     pub(crate) multiple_test_monitor_task: Mutex<DurationMonitorTask>,
 }
 
@@ -88,8 +99,7 @@ pub struct AppState {
     pub(crate) rate_monitor: Mutex<RateMonitor>,
     pub(crate) rtt_histogram_monitor: Mutex<HistogramMonitor>,
     pub(crate) iat_histogram_monitor: Mutex<HistogramMonitor>,
-    pub(crate) switch: SwitchConnection,
-    pub(crate) pm: PortManager,
+    pub(crate) switch: Arc<dyn SwitchBackend>,
     pub(crate) experiment: Mutex<Experiment>,
     pub(crate) sample_mode: bool,
     pub(crate) config: Mutex<Config>,
@@ -98,51 +108,28 @@ pub struct AppState {
     pub(crate) loopback_mode: bool,
     pub(crate) monitor_task: Mutex<DurationMonitorTask>,
     pub(crate) multiple_tests: MultiTest,
+    pub(crate) rfc2544: Rfc2544,
+    pub(crate) y1564: Y1564,
+    pub(crate) events: EventBus,
+    pub(crate) archive: std::sync::Mutex<Archive>,
+    pub(crate) sla_monitor: Mutex<SlaMonitor>,
 }
 
-async fn run() -> Result<(), Box<dyn std::error::Error>> {
-    //console_subscriber::init();
-
-    let sample_mode = env::var("SAMPLE")
-        .unwrap_or("0".to_owned())
-        .parse()
-        .unwrap_or(0);
-    let sample_mode = sample_mode == 1;
-    let p4_name = env::var("P4_NAME").unwrap_or("traffic_gen".to_owned());
-    let loopback_mode = env::var("LOOPBACK")
-        .unwrap_or("0".to_owned())
-        .parse()
-        .unwrap_or(false);
-
-    // Automatically set to true by GitHub CI/CD. Used to deploy gh-pages
-    let ci_docs = env::var("CI")
-        .unwrap_or("0".to_owned())
-        .parse()
-        .unwrap_or(false);
-    if ci_docs {
-        info!("Building OpenAPI json file.");
-        api::server::generate_api_json();
-    }
-
-    info!("Start controller...");
-
-    let mut switch = SwitchConnection::builder("localhost", 50052)
-        .device_id(0)
-        .client_id(1)
-        .p4_name(&p4_name)
-        .connect()
-        .await?;
-
-    // check if its tofino 1 or tofino 2
-    // this could be done more intelligent
-    // we simply check if a table in tf2 scope exists
-    let is_tofino2 = switch.has_table(PORT_CFG_TF2);
-
+/// Performs the initial configuration of the switch and creates the app state.
+///
+/// Returns the app state and the monitoring index mapping that is required by [RateMonitor::monitor_digests].
+pub(crate) async fn init_state(
+    switch: Arc<dyn SwitchBackend>,
+    mut config: Config,
+    num_ports: u32,
+    is_tofino2: bool,
+    sample_mode: bool,
+    loopback_mode: bool,
+    archive: Archive,
+) -> Result<(Arc<AppState>, HashMap<u32, MonitoringMapping>), RBFRTError> {
     let req = if is_tofino2 {
-        info!("ASIC: Tofino2");
         table::Request::new(DEVICE_CONFIGURATION_TF2).default(true)
     } else {
-        info!("ASIC: Tofino1");
         table::Request::new(DEVICE_CONFIGURATION).default(true)
     };
 
@@ -153,46 +140,10 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
         .as_u32();
     info!("#Pipes: {num_pipes:?}");
 
-    // TODO find a way to derive this from device configuration
-    let num_ports = env::var("NUM_PORTS")
-        .unwrap_or("32".to_owned())
-        .parse()
-        .unwrap_or(32);
-
-    if loopback_mode {
-        info!("Loopback mode activated.");
-    }
-
     // Front panel ports that can be used for traffic generation.
     // At default, the first 10 ports are used for traffic generation.
     let all_ports: Vec<u32> = (1..=num_ports).collect();
 
-    // TG ports either from config or default
-    let mut config = match File::open("config.json") {
-        Ok(file) => {
-            let mut config: Config = serde_json::from_reader(file).unwrap_or_else(|_| {
-                warn!("Config file not valid. Using default config.");
-                Config::default_tofino(is_tofino2)
-            });
-
-            let config = if let Err(err) = config
-                .normalize(is_tofino2)
-                .and_then(|_| config.validate(num_ports, is_tofino2))
-            {
-                warn!("{err} Using default config.");
-                Config::default_tofino(is_tofino2)
-            } else {
-                config
-            };
-
-            config
-        }
-        Err(_) => {
-            warn!("No config file (/app/config.json) for controller found. Using default config.");
-            Config::default_tofino(is_tofino2)
-        }
-    };
-
     if config.tg_ports.is_empty() {
         panic!("No traffic generation ports should be configured.");
     }
@@ -206,11 +157,8 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut port_mapping: HashMap<u32, PortMapping> = HashMap::new();
 
-    let pm = PortManager::new(&switch).await;
-
     configure_ports(
-        &mut switch,
-        &pm,
+        switch.as_ref(),
         &mut config,
         &recirculation_ports,
         &mut port_mapping,
@@ -227,22 +175,24 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
 
     // configures rate monitoring and monitoring packets related tables
     let mut rate_monitor = RateMonitor::new(port_mapping.clone());
-    rate_monitor.init_rtt_meter(&switch).await?;
-    rate_monitor.init_iat_meter(&switch, sample_mode).await?;
-    rate_monitor.on_reset(&switch).await?;
+    rate_monitor.init_rtt_meter(switch.as_ref()).await?;
+    rate_monitor
+        .init_iat_meter(switch.as_ref(), sample_mode)
+        .await?;
+    rate_monitor.on_reset(switch.as_ref()).await?;
 
     let rtt_histogram_monitor = HistogramMonitor::new(port_mapping.clone(), HistogramType::Rtt);
     let iat_histogram_monitor = HistogramMonitor::new(port_mapping.clone(), HistogramType::Iat);
 
     let mut traffic_generator = TrafficGen::new(is_tofino2, num_pipes);
-    traffic_generator.stop(&switch).await?;
+    traffic_generator.stop(switch.as_ref()).await?;
 
     let index_mapping = traffic_generator
-        .init_monitoring_packet(&switch, &port_mapping)
+        .init_monitoring_packet(switch.as_ref(), &port_mapping)
         .await?;
 
     let arp_handler = Arp::new();
-    arp_handler.init(&switch, &port_mapping).await?;
+    arp_handler.init(switch.as_ref(), &port_mapping).await?;
 
     let state = Arc::new(AppState {
         frame_size_monitor: Mutex::new(frame_size_monitor),
@@ -253,7 +203,6 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
         rtt_histogram_monitor: Mutex::new(rtt_histogram_monitor),
         iat_histogram_monitor: Mutex::new(iat_histogram_monitor),
         switch,
-        pm,
         sample_mode,
         experiment: Mutex::new(Experiment {
             start: std::time::SystemTime::now(),
@@ -270,26 +219,187 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
         multiple_tests: MultiTest {
             collected_statistics: Default::default(),
             collected_time_statistics: Default::default(),
+            collected_traffic_gen: Default::default(),
             multiple_test_monitor_task: Mutex::new(DurationMonitorTask {
                 handle: None,
                 cancel_token: None,
             }),
         },
+        rfc2544: Rfc2544 {
+            task: Default::default(),
+            result: Default::default(),
+        },
+        y1564: Y1564 {
+            task: Default::default(),
+            result: Default::default(),
+        },
+        events: EventBus::new(),
+        archive: std::sync::Mutex::new(archive),
+        sla_monitor: Mutex::new(SlaMonitor::default()),
     });
 
     state
         .frame_size_monitor
         .lock()
         .await
-        .configure(&state.switch)
+        .configure(state.switch.as_ref())
         .await?;
     state
         .frame_type_monitor
         .lock()
         .await
-        .configure(&state.switch)
+        .configure(state.switch.as_ref())
         .await?;
 
+    Ok((state, index_mapping))
+}
+
+async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    //console_subscriber::init();
+
+    let sample_mode = env::var("SAMPLE")
+        .unwrap_or("0".to_owned())
+        .parse()
+        .unwrap_or(0);
+    let sample_mode = sample_mode == 1;
+    let p4_name = env::var("P4_NAME").unwrap_or("traffic_gen".to_owned());
+    let loopback_mode = env::var("LOOPBACK")
+        .unwrap_or("0".to_owned())
+        .parse()
+        .unwrap_or(false);
+
+    // Runs the controller against an in-memory switch instead of a Tofino.
+    // 1 emulates a Tofino1, 2 emulates a Tofino2.
+    let mock_switch = env::var("MOCK_SWITCH")
+        .unwrap_or("0".to_owned())
+        .parse()
+        .unwrap_or(0);
+
+    // Writes all switch interactions into a trace file, or replays them from a trace file instead of connecting to a switch.
+    let record_trace = env::var("RECORD_TRACE").ok();
+    let replay_trace = env::var("REPLAY_TRACE").ok();
+
+    // Directory in which finished tests are archived
+    let archive_dir = env::var("ARCHIVE_DIR").unwrap_or("archive".to_owned());
+
+    // Automatically set to true by GitHub CI/CD. Used to deploy gh-pages
+    let ci_docs = env::var("CI")
+        .unwrap_or("0".to_owned())
+        .parse()
+        .unwrap_or(false);
+    if ci_docs {
+        info!("Building OpenAPI json file.");
+        api::server::generate_api_json();
+    }
+
+    info!("Start controller...");
+
+    // TODO find a way to derive this from device configuration
+    let num_ports = env::var("NUM_PORTS")
+        .unwrap_or("32".to_owned())
+        .parse()
+        .unwrap_or(32);
+
+    // The mock switch is driven by the data plane emulator
+    let mut emulator = None;
+
+    let switch: Arc<dyn SwitchBackend> = match (replay_trace, mock_switch) {
+        (Some(path), _) => {
+            info!("Replaying switch trace {path}.");
+            Arc::new(ReplayBackend::open(path)?)
+        }
+        (None, 1 | 2) => {
+            info!("Using mock switch.");
+            let is_tofino2 = mock_switch == 2;
+            let mock = Arc::new(MockSwitch::new(
+                num_ports,
+                if is_tofino2 { 4 } else { 2 },
+                is_tofino2,
+            ));
+
+            let config = EmulatorConfig::from_env(&mock)?;
+            info!("Emulating the data plane with {config:?}.");
+
+            emulator = Some(Arc::new(DataPlaneEmulator::new(Arc::clone(&mock), config)));
+
+            mock
+        }
+        (None, _) => {
+            let switch = SwitchConnection::builder("localhost", 50052)
+                .device_id(0)
+                .client_id(1)
+                .p4_name(&p4_name)
+                .connect()
+                .await?;
+
+            Arc::new(RbfrtBackend::new(switch).await)
+        }
+    };
+
+    let switch: Arc<dyn SwitchBackend> = match record_trace {
+        Some(path) => {
+            info!("Recording switch trace to {path}.");
+            Arc::new(RecordingBackend::create(switch, path)?)
+        }
+        None => switch,
+    };
+
+    // check if its tofino 1 or tofino 2
+    // this could be done more intelligent
+    // we simply check if a table in tf2 scope exists
+    let is_tofino2 = switch.has_table(PORT_CFG_TF2);
+
+    if is_tofino2 {
+        info!("ASIC: Tofino2");
+    } else {
+        info!("ASIC: Tofino1");
+    }
+
+    if loopback_mode {
+        info!("Loopback mode activated.");
+    }
+
+    // TG ports either from config or default
+    let config = match File::open("config.json") {
+        Ok(file) => {
+            let mut config: Config = serde_json::from_reader(file).unwrap_or_else(|_| {
+                warn!("Config file not valid. Using default config.");
+                Config::default_tofino(is_tofino2)
+            });
+
+            let config = if let Err(err) = config
+                .normalize(is_tofino2)
+                .and_then(|_| config.validate(num_ports, is_tofino2))
+            {
+                warn!("{err} Using default config.");
+                Config::default_tofino(is_tofino2)
+            } else {
+                config
+            };
+
+            config
+        }
+        Err(_) => {
+            warn!("No config file (/app/config.json) for controller found. Using default config.");
+            Config::default_tofino(is_tofino2)
+        }
+    };
+
+    let (state, index_mapping) = init_state(
+        switch,
+        config,
+        num_ports,
+        is_tofino2,
+        sample_mode,
+        loopback_mode,
+        Archive::new(archive_dir),
+    )
+    .await?;
+
+    if let Some(emulator) = emulator {
+        tokio::spawn(emulator.run());
+    }
+
     let monitoring_state = Arc::clone(&state);
 
     // start iat monitoring
@@ -337,12 +447,37 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
 
     let monitoring_state = Arc::clone(&state);
 
+    // start monitoring the measurement windows of the tests
+    tokio::spawn(async move {
+        let local_state = monitoring_state;
+
+        measurement_window::monitor_window(local_state).await;
+    });
+
+    let monitoring_state = Arc::clone(&state);
+
     // start digest monitoring
     tokio::spawn(async move {
         let local_state = monitoring_state;
         RateMonitor::monitor_digests(local_state, &index_mapping, sample_mode).await;
     });
 
+    let monitoring_state = Arc::clone(&state);
+
+    // start publishing statistics to the statistics stream
+    tokio::spawn(async move {
+        let local_state = monitoring_state;
+        publish_statistics(local_state).await;
+    });
+
+    let monitoring_state = Arc::clone(&state);
+
+    // start evaluating the acceptance criteria of running tests
+    tokio::spawn(async move {
+        let local_state = monitoring_state;
+        SlaMonitor::monitor_sla(local_state).await;
+    });
+
     // start rest API
     api::server::start_api_server(Arc::clone(&state)).await;
 